@@ -5,11 +5,17 @@ use salvo::prelude::*;
 use std::sync::Arc;
 use tracing::info;
 
-use beam_auth::config::ServerConfig;
-use beam_auth::server::auth_routes;
+use beam_auth::config::{AuthBackendKind, ServerConfig, SessionBackend};
+use beam_auth::server::{TrustedProxies, auth_routes, jwks_routes};
+use beam_auth::utils::admin_log::SqlAdminLogSink;
+use beam_auth::utils::backend::{AuthenticationBackend, LocalPasswordBackend};
+use beam_auth::utils::keys::TokenKeys;
+use beam_auth::utils::ldap::LdapBackend;
+use beam_auth::utils::oidc::OidcService;
+use beam_auth::utils::rate_limit::{LoginRateLimiter, RedisLoginRateLimiter, SqlLoginRateLimiter};
 use beam_auth::utils::repository::SqlUserRepository;
 use beam_auth::utils::service::{AuthService, LocalAuthService};
-use beam_auth::utils::session_store::RedisSessionStore;
+use beam_auth::utils::session_store::{RedisSessionStore, SessionStore, SqlSessionStore};
 
 #[handler]
 async fn health_check(res: &mut Response) {
@@ -40,22 +46,74 @@ async fn main() -> Result<()> {
         .map_err(|e| eyre!("Failed to connect to database: {}", e))?;
     info!("Connected to database");
 
-    // Connect to Redis
-    info!("Connecting to Redis at {}", config.redis_url);
-    let session_store = Arc::new(
-        RedisSessionStore::new(&config.redis_url)
-            .await
-            .map_err(|e| eyre!("Failed to connect to Redis: {}", e))?,
-    );
-    info!("Connected to Redis");
+    let (session_store, login_limiter): (Arc<dyn SessionStore>, Arc<dyn LoginRateLimiter>) =
+        match config.session_backend {
+            SessionBackend::Redis => {
+                info!("Connecting to Redis at {}", config.redis_url);
+                let session_store = RedisSessionStore::new(&config.redis_url)
+                    .await
+                    .map_err(|e| eyre!("Failed to connect to Redis: {}", e))?;
+                info!("Connected to Redis");
+                let login_limiter = RedisLoginRateLimiter::new(session_store.pool());
+                (Arc::new(session_store), Arc::new(login_limiter))
+            }
+            SessionBackend::Postgres => {
+                info!("Keeping sessions in the database");
+                let session_store = Arc::new(SqlSessionStore::new(db.clone()));
+                session_store.spawn_sweeper();
+                let login_limiter = Arc::new(SqlLoginRateLimiter::new(db.clone()));
+                login_limiter.spawn_sweeper();
+                (session_store, login_limiter)
+            }
+        };
 
     // Build services
-    let user_repo = Arc::new(SqlUserRepository::new(db));
-    let auth_service: Arc<dyn AuthService> = Arc::new(LocalAuthService::new(
-        user_repo,
-        session_store,
-        config.jwt_secret.clone(),
-    ));
+    let user_repo = Arc::new(SqlUserRepository::new(db.clone()));
+    let mut backends: Vec<Arc<dyn AuthenticationBackend>> = Vec::new();
+    for kind in &config.auth_backends {
+        backends.push(match kind {
+            AuthBackendKind::Local => Arc::new(LocalPasswordBackend::new(user_repo.clone())),
+            AuthBackendKind::Ldap => {
+                let ldap = LdapBackend::new(config.ldap.clone())
+                    .map_err(|e| eyre!("Failed to set up LDAP: {}", e))?;
+                info!("Checking passwords against LDAP at {:?}", config.ldap.url);
+                Arc::new(ldap)
+            }
+        });
+    }
+    let mut auth_service =
+        LocalAuthService::new(user_repo, session_store, config.jwt_secret.clone())
+            .with_authentication_backends(backends)
+            .with_registration_mode(config.registration_mode)
+            .with_require_admin_mfa(config.require_admin_mfa)
+            .with_login_rate_limiter(login_limiter)
+            .with_admin_log(Arc::new(SqlAdminLogSink::new(db)));
+    if let Some(key) = &config.totp_encryption_key {
+        auth_service = auth_service.with_totp_encryption_key(key);
+    }
+    if let Some(paths) = &config.jwt_signing_keys {
+        let token_keys = TokenKeys::from_private_key_files(paths)
+            .map_err(|e| eyre!("Failed to load JWT signing keys: {}", e))?;
+        info!("Signing access tokens with {} key(s)", paths.len());
+        auth_service = auth_service.with_token_keys(Arc::new(token_keys));
+    }
+    let auth_service: Arc<dyn AuthService> = Arc::new(auth_service);
+
+    let oidc_service = (!config.oidc_providers.is_empty()).then(|| {
+        let oidc = OidcService::new(
+            config.oidc_providers.clone(),
+            &config.jwt_secret,
+            &format!("{}/v1/auth/oidc", config.server_url.trim_end_matches('/')),
+        );
+        for provider in oidc.providers() {
+            info!(
+                "Single sign-on with {} enabled; redirect URI {}",
+                provider.config().name,
+                provider.redirect_uri()
+            );
+        }
+        Arc::new(oidc)
+    });
 
     // Build CORS handler
     let cors = Cors::new()
@@ -77,8 +135,16 @@ async fn main() -> Result<()> {
         .max_age(3600)
         .into_handler();
 
+    let trusted_proxies = TrustedProxies::parse(&config.trusted_proxies).map_err(|e| eyre!(e))?;
+
     // Build API router
-    let router = Router::new().hoop(affix_state::inject(auth_service)).push(
+    let mut router = Router::new()
+        .hoop(affix_state::inject(auth_service))
+        .hoop(affix_state::inject(trusted_proxies));
+    if let Some(oidc_service) = oidc_service {
+        router = router.hoop(affix_state::inject(oidc_service));
+    }
+    let router = router.push(jwks_routes()).push(
         Router::with_path("v1")
             .push(Router::with_path("health").get(health_check))
             .push(Router::with_path("auth").push(auth_routes())),
@@ -0,0 +1,375 @@
+#[cfg(test)]
+#[path = "account_tests.rs"]
+mod account_tests;
+
+use crate::server::require_user;
+use crate::utils::models::ApiKeyScope;
+use crate::utils::service::{
+    ApiKeySummary, AuthError, AuthService, AuthUserResponse, CreatedApiKey, TotpEnrollment,
+};
+use salvo::oapi::{ToResponses, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ChangePasswordResponse {
+    /// Number of other sessions that were signed out
+    pub revoked_sessions: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ChangeEmailRequest {
+    pub password: String,
+    pub email: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct UpdateProfileRequest {
+    /// Name shown instead of the username. Cleared when omitted.
+    pub display_name: Option<String>,
+    /// URL of the avatar image. Cleared when omitted.
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeleteAccountRequest {
+    pub password: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct TotpCodeRequest {
+    /// Current code from the authenticator app
+    pub code: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DisableTotpRequest {
+    pub password: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RecoveryCodesResponse {
+    /// One-time codes for logging in without the authenticator; shown only once
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    /// What the key is for, e.g. the device it is used on
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+// ── Error enum ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, ToResponses)]
+pub enum AccountError {
+    /// Invalid request body
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// Invalid or missing JWT
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// Password or two-factor code is incorrect
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// No such API key
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// Email already in use, the account is the last admin, or 2FA is already enabled
+    #[salvo(response(status_code = 409))]
+    Conflict(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+impl From<AuthError> for AccountError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::InvalidCredentials => Self::Forbidden("Incorrect password".into()),
+            AuthError::InvalidMfaCode => Self::Forbidden(err.to_string()),
+            AuthError::UserNotFound => Self::Unauthorized(err.to_string()),
+            AuthError::MfaNotEnrolled | AuthError::InvalidApiKey(_) => {
+                Self::BadRequest(err.to_string())
+            }
+            AuthError::ApiKeyNotFound => Self::NotFound(err.to_string()),
+            AuthError::UserAlreadyExists | AuthError::LastAdmin | AuthError::MfaAlreadyEnabled => {
+                Self::Conflict(err.to_string())
+            }
+            _ => Self::InternalError(err.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for AccountError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        let (status, msg) = match self {
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+        res.status_code(status);
+        res.render(Text::Plain(msg));
+    }
+}
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+/// Get the signed-in user's account
+#[endpoint(tags("account"))]
+pub async fn get_account(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<AuthUserResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    Ok(Json(auth.get_account(&user.user_id).await?))
+}
+
+/// Change the password and sign out all other sessions
+#[endpoint(
+    tags("account"),
+    request_body = ChangePasswordRequest,
+)]
+pub async fn change_password(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<ChangePasswordResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: ChangePasswordRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    let revoked_sessions = auth
+        .change_password(
+            &user.user_id,
+            &user.session_id,
+            &body.current_password,
+            &body.new_password,
+        )
+        .await?;
+    Ok(Json(ChangePasswordResponse { revoked_sessions }))
+}
+
+/// Change the email address
+#[endpoint(
+    tags("account"),
+    request_body = ChangeEmailRequest,
+)]
+pub async fn change_email(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<AuthUserResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: ChangeEmailRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    Ok(Json(
+        auth.change_email(&user.user_id, &body.password, &body.email)
+            .await?,
+    ))
+}
+
+/// Update the display name and avatar
+#[endpoint(
+    tags("account"),
+    request_body = UpdateProfileRequest,
+)]
+pub async fn update_profile(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<AuthUserResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: UpdateProfileRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    Ok(Json(
+        auth.update_profile(&user.user_id, body.display_name, body.avatar_url)
+            .await?,
+    ))
+}
+
+/// Delete the signed-in user's account
+#[endpoint(
+    tags("account"),
+    request_body = DeleteAccountRequest,
+)]
+pub async fn delete_account(req: &mut Request, depot: &mut Depot) -> Result<(), AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: DeleteAccountRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    auth.delete_account(&user.user_id, &body.password).await?;
+    Ok(())
+}
+
+/// Start setting up TOTP two-factor authentication
+#[endpoint(tags("account"))]
+pub async fn begin_totp_enrollment(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<TotpEnrollment>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    Ok(Json(auth.begin_totp_enrollment(&user.user_id).await?))
+}
+
+/// Enable TOTP two-factor authentication with a code from the new secret
+#[endpoint(
+    tags("account"),
+    request_body = TotpCodeRequest,
+)]
+pub async fn confirm_totp_enrollment(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<RecoveryCodesResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: TotpCodeRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    let recovery_codes = auth
+        .confirm_totp_enrollment(&user.user_id, &body.code)
+        .await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// Disable two-factor authentication
+#[endpoint(
+    tags("account"),
+    request_body = DisableTotpRequest,
+)]
+pub async fn disable_totp(req: &mut Request, depot: &mut Depot) -> Result<(), AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: DisableTotpRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    auth.disable_totp(&user.user_id, &body.password).await?;
+    Ok(())
+}
+
+/// Replace the two-factor recovery codes
+#[endpoint(
+    tags("account"),
+    request_body = TotpCodeRequest,
+)]
+pub async fn regenerate_recovery_codes(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<RecoveryCodesResponse>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: TotpCodeRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    let recovery_codes = auth
+        .regenerate_recovery_codes(&user.user_id, &body.code)
+        .await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+/// List the signed-in user's API keys
+#[endpoint(tags("account"))]
+pub async fn list_api_keys(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<Vec<ApiKeySummary>>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    Ok(Json(auth.list_api_keys(&user.user_id).await?))
+}
+
+/// Create an API key for scripts and devices that cannot log in interactively
+#[endpoint(
+    tags("account"),
+    request_body = CreateApiKeyRequest,
+)]
+pub async fn create_api_key(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<CreatedApiKey>, AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let body: CreateApiKeyRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| AccountError::BadRequest("Invalid request body".into()))?;
+    Ok(Json(
+        auth.create_api_key(&user.user_id, &body.name, &body.scopes)
+            .await?,
+    ))
+}
+
+/// Revoke one of the signed-in user's API keys
+#[endpoint(
+    tags("account"),
+    parameters(("id" = String, description = "API key ID")),
+)]
+pub async fn revoke_api_key(req: &mut Request, depot: &mut Depot) -> Result<(), AccountError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, AccountError::Unauthorized).await?;
+
+    let key_id: String = req.param::<String>("id").unwrap_or_default();
+    auth.revoke_api_key(&user.user_id, &key_id).await?;
+    Ok(())
+}
+
+/// Routes for managing the signed-in user's own account
+pub fn account_routes() -> Router {
+    Router::with_path("me")
+        .get(get_account)
+        .delete(delete_account)
+        .push(Router::with_path("password").put(change_password))
+        .push(Router::with_path("email").put(change_email))
+        .push(Router::with_path("profile").put(update_profile))
+        .push(
+            Router::with_path("mfa")
+                .push(
+                    Router::with_path("totp")
+                        .post(begin_totp_enrollment)
+                        .delete(disable_totp)
+                        .push(Router::with_path("confirm").post(confirm_totp_enrollment)),
+                )
+                .push(Router::with_path("recovery-codes").post(regenerate_recovery_codes)),
+        )
+        .push(
+            Router::with_path("api-keys")
+                .get(list_api_keys)
+                .post(create_api_key)
+                .push(Router::with_path("{id}").delete(revoke_api_key)),
+        )
+}
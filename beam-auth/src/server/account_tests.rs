@@ -0,0 +1,286 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::prelude::*;
+    use salvo::test::{ResponseExt, TestClient};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::server::routes::auth_routes;
+    use crate::utils::repository::in_memory::InMemoryUserRepository;
+    use crate::utils::service::{AuthService, LocalAuthService};
+    use crate::utils::session_store::in_memory::InMemorySessionStore;
+    use crate::utils::totp;
+
+    const TEST_JWT_SECRET: &str = "test-secret";
+
+    #[derive(Debug, Deserialize)]
+    struct TestAuthResponse {
+        token: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestUser {
+        username: String,
+        email: String,
+        display_name: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestEnrollment {
+        secret: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestRecoveryCodes {
+        recovery_codes: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestChallenge {
+        mfa_required: bool,
+        challenge_token: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestApiKey {
+        id: String,
+        name: String,
+        scopes: Vec<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestCreatedApiKey {
+        key: String,
+        api_key: TestApiKey,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestChangePassword {
+        revoked_sessions: u64,
+    }
+
+    fn make_test_service() -> Service {
+        let auth: Arc<dyn AuthService> = Arc::new(LocalAuthService::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        ));
+        let router = Router::new()
+            .hoop(affix_state::inject(auth))
+            .push(auth_routes());
+        Service::new(router)
+    }
+
+    async fn register(service: &Service, username: &str) -> TestAuthResponse {
+        TestClient::post("http://0.0.0.0/register")
+            .json(&json!({
+                "username": username,
+                "email": format!("{username}@example.com"),
+                "password": "password123",
+            }))
+            .send(service)
+            .await
+            .take_json()
+            .await
+            .unwrap()
+    }
+
+    async fn login(service: &Service, username: &str, password: &str) -> salvo::Response {
+        TestClient::post("http://0.0.0.0/login")
+            .json(&json!({ "username_or_email": username, "password": password }))
+            .send(service)
+            .await
+    }
+
+    #[tokio::test]
+    async fn get_and_update_own_profile() {
+        let service = make_test_service();
+        let alice = register(&service, "alice").await;
+
+        let res = TestClient::get("http://0.0.0.0/me").send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::put("http://0.0.0.0/me/profile")
+            .bearer_auth(&alice.token)
+            .json(&json!({ "display_name": "Alice A." }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        let mut res = TestClient::get("http://0.0.0.0/me")
+            .bearer_auth(&alice.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let user: TestUser = res.take_json().await.unwrap();
+        assert_eq!(user.username, "alice");
+        assert_eq!(user.display_name.as_deref(), Some("Alice A."));
+
+        let mut res = TestClient::put("http://0.0.0.0/me/email")
+            .bearer_auth(&alice.token)
+            .json(&json!({ "password": "password123", "email": "alice@new.example.com" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let user: TestUser = res.take_json().await.unwrap();
+        assert_eq!(user.email, "alice@new.example.com");
+    }
+
+    #[tokio::test]
+    async fn change_password_signs_out_other_sessions() {
+        let service = make_test_service();
+        let bob = register(&service, "bob").await;
+        let other: TestAuthResponse = login(&service, "bob", "password123")
+            .await
+            .take_json()
+            .await
+            .unwrap();
+
+        let res = TestClient::put("http://0.0.0.0/me/password")
+            .bearer_auth(&bob.token)
+            .json(&json!({ "current_password": "wrong", "new_password": "newpass456" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let mut res = TestClient::put("http://0.0.0.0/me/password")
+            .bearer_auth(&bob.token)
+            .json(&json!({ "current_password": "password123", "new_password": "newpass456" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let body: TestChangePassword = res.take_json().await.unwrap();
+        assert_eq!(body.revoked_sessions, 1);
+
+        let res = TestClient::get("http://0.0.0.0/me")
+            .bearer_auth(&other.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = login(&service, "bob", "newpass456").await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn delete_own_account() {
+        let service = make_test_service();
+        register(&service, "owner").await;
+        let carol = register(&service, "carol").await;
+
+        let res = TestClient::delete("http://0.0.0.0/me")
+            .bearer_auth(&carol.token)
+            .json(&json!({ "password": "wrong" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let res = TestClient::delete("http://0.0.0.0/me")
+            .bearer_auth(&carol.token)
+            .json(&json!({ "password": "password123" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        let res = login(&service, "carol", "password123").await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn totp_enrollment_and_two_step_login() {
+        let service = make_test_service();
+        let dora = register(&service, "dora").await;
+
+        let mut res = TestClient::post("http://0.0.0.0/me/mfa/totp")
+            .bearer_auth(&dora.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let enrollment: TestEnrollment = res.take_json().await.unwrap();
+        let secret = data_encoding::BASE32_NOPAD
+            .decode(enrollment.secret.as_bytes())
+            .unwrap();
+        let code = totp::code_at(&secret, chrono::Utc::now().timestamp());
+
+        let mut res = TestClient::post("http://0.0.0.0/me/mfa/totp/confirm")
+            .bearer_auth(&dora.token)
+            .json(&json!({ "code": code }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let codes: TestRecoveryCodes = res.take_json().await.unwrap();
+        assert_eq!(codes.recovery_codes.len(), totp::RECOVERY_CODE_COUNT);
+
+        let mut res = login(&service, "dora", "password123").await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        let challenge: TestChallenge = res.take_json().await.unwrap();
+        assert!(challenge.mfa_required);
+
+        let res = TestClient::post("http://0.0.0.0/login/mfa")
+            .json(&json!({ "challenge_token": challenge.challenge_token, "code": "000000" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::post("http://0.0.0.0/login/mfa")
+            .json(&json!({
+                "challenge_token": challenge.challenge_token,
+                "code": codes.recovery_codes[0],
+            }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+    }
+
+    #[tokio::test]
+    async fn create_list_and_revoke_api_keys() {
+        let service = make_test_service();
+        let dana = register(&service, "dana").await;
+
+        let res = TestClient::post("http://0.0.0.0/me/api-keys")
+            .bearer_auth(&dana.token)
+            .json(&json!({ "name": "Kodi", "scopes": [] }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+
+        let mut res = TestClient::post("http://0.0.0.0/me/api-keys")
+            .bearer_auth(&dana.token)
+            .json(&json!({ "name": "Kodi", "scopes": ["read_only", "stream"] }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let created: TestCreatedApiKey = res.take_json().await.unwrap();
+        assert_eq!(created.api_key.scopes, ["read_only", "stream"]);
+
+        let mut res = TestClient::get("http://0.0.0.0/me/api-keys")
+            .bearer_auth(&dana.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let keys: Vec<TestApiKey> = res.take_json().await.unwrap();
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].name, "Kodi");
+
+        // A key cannot manage the account it belongs to
+        let res = TestClient::get("http://0.0.0.0/me/api-keys")
+            .bearer_auth(&created.key)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let url = format!("http://0.0.0.0/me/api-keys/{}", created.api_key.id);
+        let res = TestClient::delete(&url)
+            .bearer_auth(&dana.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let res = TestClient::delete(&url)
+            .bearer_auth(&dana.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+    }
+}
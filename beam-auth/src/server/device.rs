@@ -0,0 +1,269 @@
+#[cfg(test)]
+#[path = "device_tests.rs"]
+mod device_tests;
+
+use crate::server::require_user;
+use crate::server::routes::{extract_client_ip, user_agent_from_request};
+use crate::utils::service::{AuthError, AuthResponse, AuthService, DeviceCode, PendingDevice};
+use salvo::oapi::{ToResponses, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeviceCodeRequest {
+    /// Shown to the user approving the device and in the session list, e.g. "Living room TV"
+    #[serde(default)]
+    pub device_name: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DeviceTokenRequest {
+    /// Device code from `/device/code`
+    pub device_code: String,
+}
+
+/// Why a device has no session yet, as an RFC 8628 error code
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DevicePollResponse {
+    /// `authorization_pending` or `slow_down` to keep polling; `access_denied` or
+    /// `expired_token` to give up
+    pub error: String,
+}
+
+// ── Error enums ───────────────────────────────────────────────────────────────
+
+#[derive(ToResponses)]
+pub enum DeviceCodeError {
+    /// Invalid request body
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+#[async_trait]
+impl Writer for DeviceCodeError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        match self {
+            Self::BadRequest(msg) => {
+                res.status_code(StatusCode::BAD_REQUEST);
+                res.render(Text::Plain(msg));
+            }
+            Self::InternalError(msg) => {
+                res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render(Text::Plain(msg));
+            }
+        }
+    }
+}
+
+#[derive(ToResponses)]
+pub enum DeviceTokenError {
+    /// Invalid request body
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// Not approved yet, denied or expired
+    #[salvo(response(status_code = 400))]
+    Pending(DevicePollResponse),
+    /// The account that approved the device is disabled
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+#[async_trait]
+impl Writer for DeviceTokenError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        match self {
+            Self::BadRequest(msg) => {
+                res.status_code(StatusCode::BAD_REQUEST);
+                res.render(Text::Plain(msg));
+            }
+            Self::Pending(body) => {
+                res.status_code(StatusCode::BAD_REQUEST);
+                res.render(Json(body));
+            }
+            Self::Forbidden(msg) => {
+                res.status_code(StatusCode::FORBIDDEN);
+                res.render(Text::Plain(msg));
+            }
+            Self::InternalError(msg) => {
+                res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render(Text::Plain(msg));
+            }
+        }
+    }
+}
+
+#[derive(Debug, ToResponses)]
+pub enum DeviceApprovalError {
+    /// Missing or invalid session
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// Account disabled
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// No device is waiting with this code
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+impl From<AuthError> for DeviceApprovalError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::PairingCodeNotFound => Self::NotFound(err.to_string()),
+            AuthError::AccountDisabled => Self::Forbidden(err.to_string()),
+            AuthError::InvalidCredentials | AuthError::UserNotFound => {
+                Self::Unauthorized("Invalid or expired token".into())
+            }
+            _ => Self::InternalError(err.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for DeviceApprovalError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        match self {
+            Self::Unauthorized(msg) => {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                res.render(Text::Plain(msg));
+            }
+            Self::Forbidden(msg) => {
+                res.status_code(StatusCode::FORBIDDEN);
+                res.render(Text::Plain(msg));
+            }
+            Self::NotFound(msg) => {
+                res.status_code(StatusCode::NOT_FOUND);
+                res.render(Text::Plain(msg));
+            }
+            Self::InternalError(msg) => {
+                res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render(Text::Plain(msg));
+            }
+        }
+    }
+}
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+/// Start pairing a device such as a TV; show the user code and poll `/device/token`
+#[endpoint(
+    tags("device"),
+    request_body = DeviceCodeRequest,
+)]
+pub async fn request_device_code(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<DeviceCode>, DeviceCodeError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let body: DeviceCodeRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| DeviceCodeError::BadRequest("Invalid request body".into()))?;
+
+    let user_agent = user_agent_from_request(req);
+    let ip = extract_client_ip(req, depot);
+
+    let code = auth
+        .start_device_authorization(&body.device_name, &user_agent, &ip)
+        .await
+        .map_err(|err| DeviceCodeError::InternalError(err.to_string()))?;
+    Ok(Json(code))
+}
+
+/// Poll for the session of a device once a user has approved it
+#[endpoint(
+    tags("device"),
+    request_body = DeviceTokenRequest,
+)]
+pub async fn poll_device_token(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<AuthResponse>, DeviceTokenError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let body: DeviceTokenRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| DeviceTokenError::BadRequest("Invalid request body".into()))?;
+
+    let ip = extract_client_ip(req, depot);
+
+    let auth_response = auth
+        .poll_device_authorization(&body.device_code, &ip)
+        .await
+        .map_err(|err| match err {
+            AuthError::DevicePairing(error) => DeviceTokenError::Pending(DevicePollResponse {
+                error: error.to_string(),
+            }),
+            AuthError::AccountDisabled => DeviceTokenError::Forbidden(err.to_string()),
+            _ => DeviceTokenError::InternalError(err.to_string()),
+        })?;
+    Ok(Json(auth_response))
+}
+
+/// Show which device is waiting with a code before approving it
+#[endpoint(
+    tags("device"),
+    parameters(("user_code" = String, description = "Code shown on the device")),
+)]
+pub async fn get_pending_device(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<PendingDevice>, DeviceApprovalError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user(req, &auth, DeviceApprovalError::Unauthorized).await?;
+
+    let user_code: String = req.param::<String>("user_code").unwrap_or_default();
+    Ok(Json(auth.get_pending_device(&user_code).await?))
+}
+
+/// Sign a waiting device in to the current user's account
+#[endpoint(
+    tags("device"),
+    parameters(("user_code" = String, description = "Code shown on the device")),
+)]
+pub async fn approve_device(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<PendingDevice>, DeviceApprovalError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, DeviceApprovalError::Unauthorized).await?;
+
+    let user_code: String = req.param::<String>("user_code").unwrap_or_default();
+    Ok(Json(auth.approve_device(&user.user_id, &user_code).await?))
+}
+
+/// Refuse a waiting device
+#[endpoint(
+    tags("device"),
+    parameters(("user_code" = String, description = "Code shown on the device")),
+)]
+pub async fn deny_device(req: &mut Request, depot: &mut Depot) -> Result<(), DeviceApprovalError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let user = require_user(req, &auth, DeviceApprovalError::Unauthorized).await?;
+
+    let user_code: String = req.param::<String>("user_code").unwrap_or_default();
+    auth.deny_device(&user.user_id, &user_code).await?;
+    Ok(())
+}
+
+/// Routes for pairing devices with a code instead of a password
+pub fn device_routes() -> Router {
+    Router::with_path("device")
+        .push(Router::with_path("code").post(request_device_code))
+        .push(Router::with_path("token").post(poll_device_token))
+        .push(
+            Router::with_path("verify/{user_code}")
+                .get(get_pending_device)
+                .push(Router::with_path("approve").post(approve_device))
+                .push(Router::with_path("deny").post(deny_device)),
+        )
+}
@@ -0,0 +1,170 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::prelude::*;
+    use salvo::test::{ResponseExt, TestClient};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::server::routes::auth_routes;
+    use crate::utils::models::ApiKeyScope;
+    use crate::utils::repository::in_memory::InMemoryUserRepository;
+    use crate::utils::service::{AuthService, LocalAuthService};
+    use crate::utils::session_store::in_memory::InMemorySessionStore;
+
+    const TEST_JWT_SECRET: &str = "test-secret";
+
+    #[derive(Debug, Deserialize)]
+    struct TestAuthResponse {
+        token: String,
+        user: TestUser,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestUser {
+        id: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestDeviceCode {
+        device_code: String,
+        user_code: String,
+        interval: u64,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestPendingDevice {
+        device_name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestPollError {
+        error: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestSession {
+        device_name: String,
+    }
+
+    fn make_test_service() -> (Service, Arc<dyn AuthService>) {
+        let auth: Arc<dyn AuthService> = Arc::new(LocalAuthService::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        ));
+        let router = Router::new()
+            .hoop(affix_state::inject(auth.clone()))
+            .push(auth_routes());
+        (Service::new(router), auth)
+    }
+
+    async fn register(service: &Service, username: &str) -> TestAuthResponse {
+        TestClient::post("http://0.0.0.0/register")
+            .json(&json!({
+                "username": username,
+                "email": format!("{username}@example.com"),
+                "password": "password123",
+            }))
+            .send(service)
+            .await
+            .take_json()
+            .await
+            .unwrap()
+    }
+
+    async fn request_code(service: &Service) -> TestDeviceCode {
+        TestClient::post("http://0.0.0.0/device/code")
+            .json(&json!({ "device_name": "Living room TV" }))
+            .send(service)
+            .await
+            .take_json()
+            .await
+            .unwrap()
+    }
+
+    async fn poll(service: &Service, device_code: &str) -> salvo::Response {
+        TestClient::post("http://0.0.0.0/device/token")
+            .json(&json!({ "device_code": device_code }))
+            .send(service)
+            .await
+    }
+
+    #[tokio::test]
+    async fn tv_is_paired_after_approval_in_the_web_ui() {
+        let (service, _) = make_test_service();
+        let owner = register(&service, "owner").await;
+        let code = request_code(&service).await;
+        assert!(code.interval > 0);
+
+        let mut res = poll(&service, &code.device_code).await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+        let body: TestPollError = res.take_json().await.unwrap();
+        assert_eq!(body.error, "authorization_pending");
+
+        let url = format!("http://0.0.0.0/device/verify/{}", code.user_code);
+        let res = TestClient::get(&url).send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        let mut res = TestClient::get(&url)
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let pending: TestPendingDevice = res.take_json().await.unwrap();
+        assert_eq!(pending.device_name, "Living room TV");
+
+        let res = TestClient::post(format!("{url}/approve"))
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        let mut res = poll(&service, &code.device_code).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let tv: TestAuthResponse = res.take_json().await.unwrap();
+        assert_eq!(tv.user.id, owner.user.id);
+
+        let mut res = TestClient::get("http://0.0.0.0/sessions")
+            .bearer_auth(&tv.token)
+            .send(&service)
+            .await;
+        let sessions: Vec<TestSession> = res.take_json().await.unwrap();
+        assert!(sessions.iter().any(|s| s.device_name == "Living room TV"));
+    }
+
+    #[tokio::test]
+    async fn denied_and_unknown_codes() {
+        let (service, auth) = make_test_service();
+        let owner = register(&service, "owner").await;
+        let code = request_code(&service).await;
+
+        let res = TestClient::post("http://0.0.0.0/device/verify/AEIO-UAEI/approve")
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+
+        // API keys cannot pair devices
+        let key = auth
+            .create_api_key(&owner.user.id, "script", &[ApiKeyScope::Admin])
+            .await
+            .unwrap();
+        let url = format!("http://0.0.0.0/device/verify/{}", code.user_code);
+        let res = TestClient::post(format!("{url}/approve"))
+            .bearer_auth(&key.key)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::post(format!("{url}/deny"))
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        let mut res = poll(&service, &code.device_code).await;
+        let body: TestPollError = res.take_json().await.unwrap();
+        assert_eq!(body.error, "access_denied");
+    }
+}
@@ -1,3 +1,27 @@
+pub mod account;
+pub mod device;
+pub mod oidc;
 pub mod routes;
+pub mod users;
 
-pub use routes::auth_routes;
+pub use routes::{TrustedProxies, auth_routes, jwks_routes};
+
+use crate::utils::service::{AuthService, AuthenticatedUser};
+use salvo::Request;
+use std::sync::Arc;
+
+/// Verifies the bearer token of the signed-in user's session, never an API key.
+///
+/// Failures are reported through `unauthorized`, so each route keeps its own error type.
+pub(crate) async fn require_user<E>(
+    req: &Request,
+    auth: &Arc<dyn AuthService>,
+    unauthorized: impl Fn(String) -> E,
+) -> Result<AuthenticatedUser, E> {
+    let token = routes::extract_bearer_token(req)
+        .ok_or_else(|| unauthorized("Missing Authorization header".into()))?;
+
+    routes::verify_session_token(auth, &token)
+        .await
+        .map_err(|_| unauthorized("Invalid or expired token".into()))
+}
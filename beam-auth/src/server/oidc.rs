@@ -0,0 +1,228 @@
+#[cfg(test)]
+#[path = "oidc_tests.rs"]
+mod oidc_tests;
+
+#[cfg(test)]
+#[path = "oidc_mock_idp.rs"]
+mod oidc_mock_idp;
+
+use crate::server::routes::{REFRESH_COOKIE, extract_client_ip, user_agent_from_request};
+use crate::utils::oidc::{OidcError, OidcService};
+use crate::utils::service::{AuthError, AuthService};
+use salvo::http::cookie::{Cookie, SameSite, time::Duration};
+use salvo::oapi::{ToResponses, ToSchema};
+use salvo::prelude::*;
+use serde::Serialize;
+use std::sync::Arc;
+
+/// HttpOnly cookie holding the state of a login in progress at an identity provider
+const LOGIN_STATE_COOKIE: &str = "oidc_login";
+
+/// An identity provider users can log in with
+#[derive(Debug, Serialize, ToSchema)]
+pub struct OidcProviderSummary {
+    pub id: String,
+    /// Label for the login button
+    pub name: String,
+}
+
+// ── Error enum ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, ToResponses)]
+pub enum OidcLoginError {
+    /// Missing parameters, or the provider refused the login
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// The login expired or the provider's ID token is invalid
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// Account disabled, or no account exists and none may be created
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// No such provider, or single sign-on is not configured
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// Another account already uses the provider's email address
+    #[salvo(response(status_code = 409))]
+    Conflict(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+    /// The identity provider could not be reached or answered nonsense
+    #[salvo(response(status_code = 502))]
+    BadGateway(String),
+}
+
+impl From<OidcError> for OidcLoginError {
+    fn from(err: OidcError) -> Self {
+        match err {
+            OidcError::UnknownProvider => Self::NotFound(err.to_string()),
+            OidcError::Denied(_) => Self::BadRequest(err.to_string()),
+            OidcError::InvalidState | OidcError::InvalidIdToken(_) => {
+                Self::Unauthorized(err.to_string())
+            }
+            OidcError::Http(_) | OidcError::InvalidResponse(_) => Self::BadGateway(err.to_string()),
+            OidcError::Keys(_) => Self::InternalError(err.to_string()),
+        }
+    }
+}
+
+impl From<AuthError> for OidcLoginError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::AccountDisabled | AuthError::RegistrationClosed => {
+                Self::Forbidden(err.to_string())
+            }
+            AuthError::UserAlreadyExists => Self::Conflict(
+                "An account with this email already exists; log in with a password instead".into(),
+            ),
+            AuthError::MissingEmail => Self::BadRequest(err.to_string()),
+            _ => Self::InternalError(err.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for OidcLoginError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        let (status, msg) = match self {
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+            Self::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
+        };
+        res.status_code(status);
+        res.render(Text::Plain(msg));
+    }
+}
+
+/// The OIDC service, which is only in the depot when providers are configured.
+fn oidc_service(depot: &Depot) -> Result<Arc<OidcService>, OidcLoginError> {
+    depot
+        .obtain::<Arc<OidcService>>()
+        .cloned()
+        .map_err(|_| OidcLoginError::NotFound("Single sign-on is not configured".into()))
+}
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+/// List the identity providers users can log in with
+#[endpoint(tags("auth"))]
+pub async fn list_oidc_providers(depot: &mut Depot) -> Json<Vec<OidcProviderSummary>> {
+    let providers = match depot.obtain::<Arc<OidcService>>() {
+        Ok(oidc) => oidc
+            .providers()
+            .iter()
+            .map(|p| OidcProviderSummary {
+                id: p.config().id.clone(),
+                name: p.config().name.clone(),
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    Json(providers)
+}
+
+/// Start logging in with an identity provider by redirecting to it
+#[endpoint(
+    tags("auth"),
+    parameters(
+        ("provider" = String, description = "Provider ID"),
+        ("redirect_to" = Option<String>, Query, description = "Local path to return to after logging in"),
+    ),
+)]
+pub async fn oidc_login(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), OidcLoginError> {
+    let oidc = oidc_service(depot)?;
+    let provider: String = req.param::<String>("provider").unwrap_or_default();
+    let redirect_to = req.query::<String>("redirect_to").unwrap_or_default();
+
+    let start = oidc.begin_login(&provider, &redirect_to).await?;
+
+    res.add_cookie(
+        Cookie::build((LOGIN_STATE_COOKIE, start.login_state))
+            .path("/")
+            .http_only(true)
+            // Lax so the cookie comes along when the provider redirects back
+            .same_site(SameSite::Lax)
+            .max_age(Duration::minutes(10))
+            .build(),
+    );
+    res.render(Redirect::found(start.authorization_url));
+    Ok(())
+}
+
+/// Where the identity provider sends the browser back to; logs in and redirects to the
+/// page the login was started from
+#[endpoint(
+    tags("auth"),
+    parameters(
+        ("provider" = String, description = "Provider ID"),
+        ("code" = Option<String>, Query, description = "Authorization code"),
+        ("state" = Option<String>, Query, description = "State passed to the provider"),
+        ("error" = Option<String>, Query, description = "Why the provider refused the login"),
+    ),
+)]
+pub async fn oidc_callback(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), OidcLoginError> {
+    let oidc = oidc_service(depot)?;
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let provider: String = req.param::<String>("provider").unwrap_or_default();
+
+    // The state is single-use whatever happens next
+    let login_state = req
+        .cookie(LOGIN_STATE_COOKIE)
+        .map(|c| c.value().to_string());
+    res.remove_cookie(LOGIN_STATE_COOKIE);
+
+    if let Some(error) = req.query::<String>("error") {
+        let description = req.query::<String>("error_description").unwrap_or_default();
+        return Err(OidcError::Denied(format!("{error} {description}").trim_end().into()).into());
+    }
+    let (Some(code), Some(state)) = (req.query::<String>("code"), req.query::<String>("state"))
+    else {
+        return Err(OidcLoginError::BadRequest("Missing code or state".into()));
+    };
+    let login_state = login_state.ok_or(OidcError::InvalidState)?;
+
+    let login = oidc
+        .complete_login(&provider, &login_state, &code, &state)
+        .await?;
+
+    let user_agent = user_agent_from_request(req);
+    let ip = extract_client_ip(req, depot);
+    let auth_response = auth
+        .login_with_identity(&login.identity, &user_agent, &ip)
+        .await?;
+
+    // The page picks the session up by refreshing with this cookie
+    res.add_cookie(
+        Cookie::build((REFRESH_COOKIE, auth_response.refresh_token))
+            .path("/")
+            .http_only(true)
+            .same_site(SameSite::Lax)
+            .max_age(Duration::days(7))
+            .build(),
+    );
+    res.render(Redirect::found(login.redirect_to));
+    Ok(())
+}
+
+pub fn oidc_routes() -> Router {
+    Router::with_path("oidc")
+        .push(Router::with_path("providers").get(list_oidc_providers))
+        .push(
+            Router::with_path("{provider}")
+                .push(Router::with_path("login").get(oidc_login))
+                .push(Router::with_path("callback").get(oidc_callback)),
+        )
+}
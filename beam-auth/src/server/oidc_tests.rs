@@ -0,0 +1,369 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::http::header;
+    use salvo::prelude::*;
+    use salvo::test::{ResponseExt, TestClient};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::config::OidcProviderConfig;
+    use crate::server::oidc::oidc_mock_idp::MockIdp;
+    use crate::server::routes::auth_routes;
+    use crate::utils::models::Role;
+    use crate::utils::oidc::OidcService;
+    use crate::utils::repository::{UserRepository, in_memory::InMemoryUserRepository};
+    use crate::utils::service::{AuthService, LocalAuthService};
+    use crate::utils::session_store::in_memory::InMemorySessionStore;
+
+    const TEST_JWT_SECRET: &str = "test-secret";
+    const BASE: &str = "http://beam.test";
+
+    #[derive(Debug, Deserialize)]
+    struct TestProvider {
+        id: String,
+        name: String,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestAuthResponse {
+        user: TestUser,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestUser {
+        username: String,
+        email: String,
+        is_admin: bool,
+    }
+
+    /// Serves the auth routes with one provider, `mock`, backed by a fresh mock IdP.
+    async fn make_test_service(
+        configure: impl FnOnce(&mut OidcProviderConfig),
+    ) -> (
+        Service,
+        MockIdp,
+        Arc<LocalAuthService>,
+        Arc<InMemoryUserRepository>,
+    ) {
+        let idp = MockIdp::start().await;
+        let mut config = idp.provider_config("mock");
+        configure(&mut config);
+
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let auth = Arc::new(LocalAuthService::new(
+            user_repo.clone(),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        ));
+        let oidc = Arc::new(OidcService::new(
+            vec![config],
+            TEST_JWT_SECRET,
+            &format!("{BASE}/v1/auth/oidc"),
+        ));
+        let auth_dyn: Arc<dyn AuthService> = auth.clone();
+        let router = Router::new()
+            .hoop(affix_state::inject(auth_dyn))
+            .hoop(affix_state::inject(oidc))
+            .push(auth_routes());
+        (Service::new(router), idp, auth, user_repo)
+    }
+
+    /// The value a response sets the cookie `name` to, if any. Read from the cookie jar
+    /// since the test client keeps only the last `Set-Cookie` header.
+    fn set_cookie(res: &Response, name: &str) -> Option<String> {
+        res.cookie(name).map(|c| c.value().to_string())
+    }
+
+    fn location(res: &Response) -> String {
+        res.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Starts a login, returning where the browser is sent and the login state cookie.
+    async fn start_login(service: &Service, redirect_to: &str) -> (String, String) {
+        let res = TestClient::get(format!("{BASE}/oidc/mock/login?redirect_to={redirect_to}"))
+            .send(service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FOUND));
+        (
+            location(&res),
+            set_cookie(&res, "oidc_login").expect("login state cookie"),
+        )
+    }
+
+    async fn callback(service: &Service, code: &str, state: &str, login_state: &str) -> Response {
+        TestClient::get(format!(
+            "{BASE}/oidc/mock/callback?code={code}&state={state}"
+        ))
+        .add_header(header::COOKIE, format!("oidc_login={login_state}"), true)
+        .send(service)
+        .await
+    }
+
+    /// Logs in through the IdP as whoever it is set up for and returns the callback
+    /// response.
+    async fn log_in(service: &Service, idp: &MockIdp) -> Response {
+        let (authorization_url, login_state) = start_login(service, "/library").await;
+        let (code, state) = idp.authorize(&authorization_url).await;
+        callback(service, &code, &state, &login_state).await
+    }
+
+    /// Exchanges the refresh cookie of a finished login for the signed-in user.
+    async fn session_user(service: &Service, res: &Response) -> TestUser {
+        let refresh_token = set_cookie(res, "refresh_token").expect("refresh cookie");
+        let mut res = TestClient::post(format!("{BASE}/refresh"))
+            .add_header(
+                header::COOKIE,
+                format!("refresh_token={refresh_token}"),
+                true,
+            )
+            .send(service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        res.take_json::<TestAuthResponse>().await.unwrap().user
+    }
+
+    fn alice() -> serde_json::Value {
+        json!({
+            "sub": "alice-at-idp",
+            "email": "alice@example.com",
+            "email_verified": true,
+            "preferred_username": "alice",
+            "name": "Alice Liddell",
+        })
+    }
+
+    // ─── GET /oidc/providers ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn providers_lists_configured_providers() {
+        let (service, _, _, _) = make_test_service(|_| {}).await;
+
+        let mut res = TestClient::get(format!("{BASE}/oidc/providers"))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let providers: Vec<TestProvider> = res.take_json().await.unwrap();
+        assert_eq!(providers.len(), 1);
+        assert_eq!(providers[0].id, "mock");
+        assert_eq!(providers[0].name, "Mock IdP");
+    }
+
+    #[tokio::test]
+    async fn sso_is_absent_without_providers() {
+        let auth: Arc<dyn AuthService> = Arc::new(LocalAuthService::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        ));
+        let service = Service::new(
+            Router::new()
+                .hoop(affix_state::inject(auth))
+                .push(auth_routes()),
+        );
+
+        let mut res = TestClient::get(format!("{BASE}/oidc/providers"))
+            .send(&service)
+            .await;
+        let providers: Vec<TestProvider> = res.take_json().await.unwrap();
+        assert!(providers.is_empty());
+
+        let res = TestClient::get(format!("{BASE}/oidc/mock/login"))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+    }
+
+    // ─── GET /oidc/{provider}/login ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn login_redirects_to_provider_with_pkce() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+
+        let (authorization_url, _) = start_login(&service, "/").await;
+        let url = reqwest::Url::parse(&authorization_url).unwrap();
+        assert_eq!(
+            url.as_str().split('?').next().unwrap(),
+            format!("{}/authorize", idp.issuer())
+        );
+        let param = |name: &str| {
+            url.query_pairs()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.into_owned())
+        };
+        assert_eq!(param("client_id").as_deref(), Some("beam"));
+        assert_eq!(
+            param("redirect_uri").as_deref(),
+            Some("http://beam.test/v1/auth/oidc/mock/callback")
+        );
+        assert_eq!(param("code_challenge_method").as_deref(), Some("S256"));
+        assert_eq!(param("scope").as_deref(), Some("openid email profile"));
+        assert!(param("code_challenge").is_some());
+        assert!(param("nonce").is_some());
+        assert!(param("state").is_some());
+    }
+
+    #[tokio::test]
+    async fn login_with_unknown_provider_returns_404() {
+        let (service, _, _, _) = make_test_service(|_| {}).await;
+
+        let res = TestClient::get(format!("{BASE}/oidc/other/login"))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+    }
+
+    // ─── GET /oidc/{provider}/callback ────────────────────────────────────────
+
+    #[tokio::test]
+    async fn callback_creates_user_signs_in_and_redirects_back() {
+        let (service, idp, _, user_repo) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+
+        let res = log_in(&service, &idp).await;
+        assert_eq!(res.status_code, Some(StatusCode::FOUND));
+        assert_eq!(location(&res), "/library");
+
+        let user = session_user(&service, &res).await;
+        assert_eq!(user.username, "alice");
+        assert_eq!(user.email, "alice@example.com");
+
+        // Logging in again signs in to the same account
+        let res = log_in(&service, &idp).await;
+        assert_eq!(session_user(&service, &res).await.username, "alice");
+        assert_eq!(user_repo.count().await.unwrap(), 1);
+        let linked = user_repo
+            .find_by_identity("mock", "alice-at-idp")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(linked.display_name.as_deref(), Some("Alice Liddell"));
+    }
+
+    #[tokio::test]
+    async fn callback_maps_admin_group_from_userinfo() {
+        let (service, idp, auth, _) = make_test_service(|config| {
+            config.admin_group = Some("admins".into());
+        })
+        .await;
+        // Someone else is the first user, and with that the bootstrap admin
+        auth.register("owner", "owner@example.com", "password123", "", "")
+            .await
+            .unwrap();
+
+        idp.set_user(alice());
+        idp.set_userinfo(json!({ "groups": ["users", "admins"] }));
+        let res = log_in(&service, &idp).await;
+        assert!(session_user(&service, &res).await.is_admin);
+
+        idp.set_userinfo(json!({ "groups": ["users"] }));
+        let res = log_in(&service, &idp).await;
+        assert!(!session_user(&service, &res).await.is_admin);
+    }
+
+    #[tokio::test]
+    async fn callback_links_existing_account_by_verified_email() {
+        let (service, idp, auth, user_repo) = make_test_service(|_| {}).await;
+        let existing = auth
+            .register("alice.local", "alice@example.com", "password123", "", "")
+            .await
+            .unwrap();
+
+        idp.set_user(alice());
+        let res = log_in(&service, &idp).await;
+        assert_eq!(session_user(&service, &res).await.username, "alice.local");
+        let linked = user_repo
+            .find_by_identity("mock", "alice-at-idp")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(linked.id.to_string(), existing.user.id);
+        assert_eq!(linked.role, Role::Admin);
+    }
+
+    #[tokio::test]
+    async fn callback_with_mismatched_state_returns_401() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+
+        let (authorization_url, login_state) = start_login(&service, "/").await;
+        let (code, _) = idp.authorize(&authorization_url).await;
+        let res = callback(&service, &code, "forged", &login_state).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        assert!(set_cookie(&res, "refresh_token").is_none());
+    }
+
+    #[tokio::test]
+    async fn callback_without_login_cookie_returns_401() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+
+        let (authorization_url, _) = start_login(&service, "/").await;
+        let (code, state) = idp.authorize(&authorization_url).await;
+        let res = TestClient::get(format!(
+            "{BASE}/oidc/mock/callback?code={code}&state={state}"
+        ))
+        .send(&service)
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn callback_rejects_id_token_for_another_client() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+        idp.override_id_token(json!({ "aud": "someone-else" }));
+
+        let res = log_in(&service, &idp).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn callback_rejects_id_token_with_wrong_nonce() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+        idp.override_id_token(json!({ "nonce": "replayed" }));
+
+        let res = log_in(&service, &idp).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn callback_rejects_id_token_from_another_issuer() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+        idp.override_id_token(json!({ "iss": "https://evil.example.com" }));
+
+        let res = log_in(&service, &idp).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn callback_with_provider_error_returns_400() {
+        let (service, _, _, _) = make_test_service(|_| {}).await;
+
+        let res = TestClient::get(format!(
+            "{BASE}/oidc/mock/callback?error=access_denied&state=x"
+        ))
+        .send(&service)
+        .await;
+        assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
+    }
+
+    #[tokio::test]
+    async fn callback_never_redirects_off_site() {
+        let (service, idp, _, _) = make_test_service(|_| {}).await;
+        idp.set_user(alice());
+
+        let (authorization_url, login_state) =
+            start_login(&service, "//evil.example.com/phish").await;
+        let (code, state) = idp.authorize(&authorization_url).await;
+        let res = callback(&service, &code, &state, &login_state).await;
+        assert_eq!(res.status_code, Some(StatusCode::FOUND));
+        assert_eq!(location(&res), "/");
+    }
+}
@@ -2,35 +2,94 @@
 #[path = "routes_tests.rs"]
 mod routes_tests;
 
-use crate::utils::service::AuthService;
+use crate::server::account::account_routes;
+use crate::server::device::device_routes;
+use crate::server::oidc::oidc_routes;
+use crate::server::users::user_routes;
+use crate::utils::service::{AuthError, AuthService, AuthenticatedUser};
+use ipnet::IpNet;
 use salvo::oapi::{ToResponses, ToSchema};
 use salvo::prelude::*;
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use std::net::IpAddr;
 use std::sync::Arc;
 
-fn device_hash_from_request(req: &Request) -> String {
-    let user_agent = req
-        .headers()
+pub(crate) fn user_agent_from_request(req: &Request) -> String {
+    req.headers()
         .get("user-agent")
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    format!("{:x}", Sha256::digest(user_agent.as_bytes()))
+        .unwrap_or("")
+        .to_string()
+}
+
+/// Reverse proxies whose forwarding headers name the real client
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<IpNet>);
+
+impl TrustedProxies {
+    /// Parses addresses like `10.0.0.1` and ranges like `10.0.0.0/8`
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Self, String> {
+        entries
+            .iter()
+            .map(|entry| {
+                let entry = entry.as_ref().trim();
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| format!("Invalid trusted proxy address: {entry}"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|net| net.contains(&ip))
+    }
 }
 
-fn extract_client_ip(req: &Request) -> String {
+/// The address of the client behind the request.
+///
+/// Forwarding headers are only believed when the connecting peer is one of the
+/// [`TrustedProxies`] in the depot. `X-Forwarded-For` is then read from the right,
+/// skipping further trusted hops, so a client can't choose its address by sending
+/// the header itself.
+pub(crate) fn extract_client_ip(req: &Request, depot: &Depot) -> String {
+    let Some(peer) = req.remote_addr().ip() else {
+        return "unknown".to_string();
+    };
+    let Ok(proxies) = depot.obtain::<TrustedProxies>() else {
+        return peer.to_string();
+    };
+    if !proxies.contains(peer) {
+        return peer.to_string();
+    }
+
     if let Some(forwarded_for) = req
         .headers()
         .get("x-forwarded-for")
         .and_then(|v| v.to_str().ok())
-        && let Some(first) = forwarded_for.split(',').next()
     {
-        return first.trim().to_string();
+        let mut client = None;
+        for hop in forwarded_for.split(',').rev() {
+            match hop.trim().parse::<IpAddr>() {
+                Ok(ip) if proxies.contains(ip) => client = Some(ip),
+                Ok(ip) => return ip.to_string(),
+                Err(_) => break,
+            }
+        }
+        if let Some(ip) = client {
+            return ip.to_string();
+        }
     }
-    if let Some(real_ip) = req.headers().get("x-real-ip").and_then(|v| v.to_str().ok()) {
+    if let Some(real_ip) = req
+        .headers()
+        .get("x-real-ip")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<IpAddr>().ok())
+    {
         return real_ip.to_string();
     }
-    "unknown".to_string()
+    peer.to_string()
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -38,6 +97,8 @@ pub struct RegisterRequest {
     pub username: String,
     pub email: String,
     pub password: String,
+    /// Required unless registration is open
+    pub invite_code: Option<String>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -46,9 +107,28 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct MfaLoginRequest {
+    /// Token from the `mfa_required` login response
+    pub challenge_token: String,
+    /// Code from the authenticator app, or an unused recovery code
+    pub code: String,
+}
+
+/// Returned instead of a session when the account has two-factor authentication
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallengeResponse {
+    pub mfa_required: bool,
+    /// Pass to `/login/mfa` with the code; expires after five minutes
+    pub challenge_token: String,
+}
+
+/// HttpOnly cookie carrying the refresh token for browser clients
+pub(crate) const REFRESH_COOKIE: &str = "refresh_token";
+
 #[derive(Deserialize, ToSchema)]
 pub struct RefreshRequest {
-    pub session_id: String,
+    pub refresh_token: String,
 }
 
 // ── Error enums ───────────────────────────────────────────────────────────────
@@ -58,6 +138,9 @@ pub enum RegisterError {
     /// Invalid request body
     #[salvo(response(status_code = 400))]
     BadRequest(String),
+    /// Registration closed or invalid invite
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
     /// User already exists
     #[salvo(response(status_code = 409))]
     Conflict(String),
@@ -71,6 +154,10 @@ impl Writer for RegisterError {
                 res.status_code(StatusCode::BAD_REQUEST);
                 res.render(Text::Plain(msg));
             }
+            Self::Forbidden(msg) => {
+                res.status_code(StatusCode::FORBIDDEN);
+                res.render(Text::Plain(msg));
+            }
             Self::Conflict(msg) => {
                 res.status_code(StatusCode::CONFLICT);
                 res.render(Text::Plain(msg));
@@ -87,12 +174,28 @@ pub enum LoginError {
     /// Invalid credentials
     #[salvo(response(status_code = 401))]
     Unauthorized(String),
+    /// Account disabled, or a directory account has no Beam account and none may be created
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// Password accepted; a second factor is required
+    #[salvo(response(status_code = 401))]
+    MfaRequired(MfaChallengeResponse),
+    /// Too many failed attempts; retry after the number of seconds in `Retry-After`
+    #[salvo(response(status_code = 429))]
+    TooManyRequests(u64),
+    /// The directory passwords are checked against could not be reached
+    #[salvo(response(status_code = 503))]
+    ServiceUnavailable(String),
 }
 
 #[async_trait]
 impl Writer for LoginError {
     async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
         match self {
+            Self::MfaRequired(challenge) => {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                res.render(Json(challenge));
+            }
             Self::BadRequest(msg) => {
                 res.status_code(StatusCode::BAD_REQUEST);
                 res.render(Text::Plain(msg));
@@ -101,6 +204,20 @@ impl Writer for LoginError {
                 res.status_code(StatusCode::UNAUTHORIZED);
                 res.render(Text::Plain(msg));
             }
+            Self::Forbidden(msg) => {
+                res.status_code(StatusCode::FORBIDDEN);
+                res.render(Text::Plain(msg));
+            }
+            Self::TooManyRequests(retry_after_secs) => {
+                res.status_code(StatusCode::TOO_MANY_REQUESTS);
+                res.headers_mut()
+                    .insert("Retry-After", retry_after_secs.to_string().parse().unwrap());
+                res.render(Text::Plain("Too many failed login attempts"));
+            }
+            Self::ServiceUnavailable(msg) => {
+                res.status_code(StatusCode::SERVICE_UNAVAILABLE);
+                res.render(Text::Plain(msg));
+            }
         }
     }
 }
@@ -195,6 +312,39 @@ impl Writer for ListSessionsError {
     }
 }
 
+#[derive(ToResponses)]
+pub enum RevokeSessionError {
+    /// Invalid or missing JWT
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// No such session for the current user
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+#[async_trait]
+impl Writer for RevokeSessionError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        match self {
+            Self::Unauthorized(msg) => {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                res.render(Text::Plain(msg));
+            }
+            Self::NotFound(msg) => {
+                res.status_code(StatusCode::NOT_FOUND);
+                res.render(Text::Plain(msg));
+            }
+            Self::InternalError(msg) => {
+                res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render(Text::Plain(msg));
+            }
+        }
+    }
+}
+
 // ── Endpoints ─────────────────────────────────────────────────────────────────
 
 /// Register a new user account
@@ -213,22 +363,28 @@ pub async fn register(
         .await
         .map_err(|_| RegisterError::BadRequest("Invalid request body".into()))?;
 
-    let device_hash = device_hash_from_request(req);
-    let ip = extract_client_ip(req);
+    let user_agent = user_agent_from_request(req);
+    let ip = extract_client_ip(req, depot);
 
     let auth_response = auth
-        .register(
+        .register_with_invite(
             &body.username,
             &body.email,
             &body.password,
-            &device_hash,
+            body.invite_code.as_deref(),
+            &user_agent,
             &ip,
         )
         .await
-        .map_err(|err| RegisterError::Conflict(err.to_string()))?;
+        .map_err(|err| match err {
+            AuthError::RegistrationClosed | AuthError::InvalidInvite => {
+                RegisterError::Forbidden(err.to_string())
+            }
+            _ => RegisterError::Conflict(err.to_string()),
+        })?;
 
     let cookie =
-        salvo::http::cookie::Cookie::build(("session_id", auth_response.session_id.clone()))
+        salvo::http::cookie::Cookie::build((REFRESH_COOKIE, auth_response.refresh_token.clone()))
             .path("/")
             .http_only(true)
             .same_site(salvo::http::cookie::SameSite::Lax)
@@ -255,16 +411,33 @@ pub async fn login(
         .await
         .map_err(|_| LoginError::BadRequest("Invalid request body".into()))?;
 
-    let device_hash = device_hash_from_request(req);
-    let ip = extract_client_ip(req);
+    let user_agent = user_agent_from_request(req);
+    let ip = extract_client_ip(req, depot);
 
     let auth_response = auth
-        .login(&body.username_or_email, &body.password, &device_hash, &ip)
+        .login(&body.username_or_email, &body.password, &user_agent, &ip)
         .await
-        .map_err(|_| LoginError::Unauthorized("Invalid username or password".into()))?;
+        .map_err(|err| match err {
+            AuthError::AccountDisabled | AuthError::RegistrationClosed => {
+                LoginError::Forbidden(err.to_string())
+            }
+            AuthError::TooManyAttempts { retry_after_secs } => {
+                LoginError::TooManyRequests(retry_after_secs)
+            }
+            AuthError::Backend(_) => {
+                LoginError::ServiceUnavailable("Login is temporarily unavailable".into())
+            }
+            AuthError::MfaRequired { challenge_token } => {
+                LoginError::MfaRequired(MfaChallengeResponse {
+                    mfa_required: true,
+                    challenge_token,
+                })
+            }
+            _ => LoginError::Unauthorized("Invalid username or password".into()),
+        })?;
 
     let cookie =
-        salvo::http::cookie::Cookie::build(("session_id", auth_response.session_id.clone()))
+        salvo::http::cookie::Cookie::build((REFRESH_COOKIE, auth_response.refresh_token.clone()))
             .path("/")
             .http_only(true)
             .same_site(salvo::http::cookie::SameSite::Lax)
@@ -275,10 +448,53 @@ pub async fn login(
     Ok(Json(auth_response))
 }
 
-/// Refresh an existing session using a session cookie or request body
+/// Finish logging in to an account with two-factor authentication
 #[endpoint(
     tags("auth"),
-    request_body(content = RefreshRequest, description = "Session ID (alternative to session cookie)"),
+    request_body = MfaLoginRequest,
+)]
+pub async fn login_mfa(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<Json<crate::utils::service::AuthResponse>, LoginError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let body: MfaLoginRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| LoginError::BadRequest("Invalid request body".into()))?;
+
+    let user_agent = user_agent_from_request(req);
+    let ip = extract_client_ip(req, depot);
+
+    let auth_response = auth
+        .complete_mfa_login(&body.challenge_token, &body.code, &user_agent, &ip)
+        .await
+        .map_err(|err| match err {
+            AuthError::AccountDisabled => LoginError::Forbidden(err.to_string()),
+            AuthError::TooManyAttempts { retry_after_secs } => {
+                LoginError::TooManyRequests(retry_after_secs)
+            }
+            AuthError::InvalidMfaCode => LoginError::Unauthorized(err.to_string()),
+            _ => LoginError::Unauthorized("Invalid or expired challenge".into()),
+        })?;
+
+    let cookie =
+        salvo::http::cookie::Cookie::build((REFRESH_COOKIE, auth_response.refresh_token.clone()))
+            .path("/")
+            .http_only(true)
+            .same_site(salvo::http::cookie::SameSite::Lax)
+            .max_age(salvo::http::cookie::time::Duration::days(7))
+            .build();
+    res.add_cookie(cookie);
+
+    Ok(Json(auth_response))
+}
+
+/// Exchange a refresh token from the cookie or request body for new tokens
+#[endpoint(
+    tags("auth"),
+    request_body(content = RefreshRequest, description = "Refresh token (alternative to the refresh_token cookie)"),
 )]
 pub async fn refresh(
     req: &mut Request,
@@ -287,23 +503,30 @@ pub async fn refresh(
 ) -> Result<Json<crate::utils::service::AuthResponse>, RefreshError> {
     let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
 
-    let session_id = if let Some(c) = req.cookie("session_id") {
+    let refresh_token = if let Some(c) = req.cookie(REFRESH_COOKIE) {
         c.value().to_string()
     } else if let Ok(body) = req.parse_json::<RefreshRequest>().await {
-        body.session_id
+        body.refresh_token
     } else {
         return Err(RefreshError::Unauthorized(
-            "Missing session cookie or body".into(),
+            "Missing refresh token cookie or body".into(),
         ));
     };
 
-    let auth_response = auth
-        .refresh(&session_id)
-        .await
-        .map_err(|_| RefreshError::Unauthorized("Invalid or expired session".into()))?;
+    let ip = extract_client_ip(req, depot);
+    let auth_response = match auth.refresh(&refresh_token, &ip).await {
+        Ok(auth_response) => auth_response,
+        Err(err) => {
+            res.remove_cookie(REFRESH_COOKIE);
+            return Err(match err {
+                AuthError::RefreshTokenReused => RefreshError::Unauthorized(err.to_string()),
+                _ => RefreshError::Unauthorized("Invalid or expired session".into()),
+            });
+        }
+    };
 
     let cookie =
-        salvo::http::cookie::Cookie::build(("session_id", auth_response.session_id.clone()))
+        salvo::http::cookie::Cookie::build((REFRESH_COOKIE, auth_response.refresh_token.clone()))
             .path("/")
             .http_only(true)
             .same_site(salvo::http::cookie::SameSite::Lax)
@@ -323,19 +546,19 @@ pub async fn logout(
 ) -> Result<(), LogoutError> {
     let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
 
-    let session_id = if let Some(c) = req.cookie("session_id") {
+    let refresh_token = if let Some(c) = req.cookie(REFRESH_COOKIE) {
         c.value().to_string()
     } else if let Ok(body) = req.parse_json::<RefreshRequest>().await {
-        body.session_id
+        body.refresh_token
     } else {
         // Already logged out or no session — idempotent 200
         return Ok(());
     };
 
     // Remove cookie
-    res.remove_cookie("session_id");
+    res.remove_cookie(REFRESH_COOKIE);
 
-    auth.logout(&session_id)
+    auth.logout_refresh_token(&refresh_token)
         .await
         .map_err(|err| LogoutError::InternalError(err.to_string()))?;
 
@@ -352,12 +575,33 @@ pub struct LogoutAllResponse {
 pub struct SessionSummary {
     pub session_id: String,
     pub device_hash: String,
+    /// Readable device name, e.g. "Firefox on Windows"
+    pub device_name: String,
+    /// IP the session was created from
     pub ip: String,
+    /// IP the session was last refreshed from
+    pub last_ip: String,
+    /// Whether this is the session making the request
+    pub current: bool,
     pub created_at: i64,
     pub last_active: i64,
 }
 
-fn extract_bearer_token(req: &Request) -> Option<String> {
+/// Verifies the bearer token of a session. API keys are refused: they are for clients
+/// using the API, not for managing the account they belong to. Users who must change
+/// their password are let through so they can.
+pub(crate) async fn verify_session_token(
+    auth: &Arc<dyn AuthService>,
+    token: &str,
+) -> Result<AuthenticatedUser, AuthError> {
+    let user = auth.verify_account_token(token).await?;
+    if user.api_key_id.is_some() {
+        return Err(AuthError::InvalidCredentials);
+    }
+    Ok(user)
+}
+
+pub(crate) fn extract_bearer_token(req: &Request) -> Option<String> {
     req.headers()
         .get("Authorization")
         .and_then(|v| v.to_str().ok())
@@ -376,8 +620,7 @@ pub async fn logout_all(
     let token = extract_bearer_token(req)
         .ok_or_else(|| LogoutAllError::Unauthorized("Missing Authorization header".into()))?;
 
-    let user = auth
-        .verify_token(&token)
+    let user = verify_session_token(&auth, &token)
         .await
         .map_err(|_| LogoutAllError::Unauthorized("Invalid or expired token".into()))?;
 
@@ -400,8 +643,7 @@ pub async fn list_sessions(
     let token = extract_bearer_token(req)
         .ok_or_else(|| ListSessionsError::Unauthorized("Missing Authorization header".into()))?;
 
-    let user = auth
-        .verify_token(&token)
+    let user = verify_session_token(&auth, &token)
         .await
         .map_err(|_| ListSessionsError::Unauthorized("Invalid or expired token".into()))?;
 
@@ -413,9 +655,12 @@ pub async fn list_sessions(
     let summaries: Vec<SessionSummary> = sessions
         .into_iter()
         .map(|(session_id, data)| SessionSummary {
+            current: session_id == user.session_id,
             session_id,
             device_hash: data.device_hash,
+            device_name: data.device_name,
             ip: data.ip,
+            last_ip: data.last_ip,
             created_at: data.created_at,
             last_active: data.last_active,
         })
@@ -424,12 +669,64 @@ pub async fn list_sessions(
     Ok(Json(summaries))
 }
 
+/// Revoke one of the current user's sessions, e.g. on a lost device
+#[endpoint(
+    tags("auth"),
+    parameters(("id" = String, description = "Session ID")),
+)]
+pub async fn revoke_session(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<(), RevokeSessionError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+
+    let token = extract_bearer_token(req)
+        .ok_or_else(|| RevokeSessionError::Unauthorized("Missing Authorization header".into()))?;
+
+    let user = verify_session_token(&auth, &token)
+        .await
+        .map_err(|_| RevokeSessionError::Unauthorized("Invalid or expired token".into()))?;
+
+    let session_id: String = req.param::<String>("id").unwrap_or_default();
+    auth.revoke_session(&user.user_id, &session_id)
+        .await
+        .map_err(|err| match err {
+            AuthError::SessionNotFound => RevokeSessionError::NotFound(err.to_string()),
+            _ => RevokeSessionError::InternalError(err.to_string()),
+        })
+}
+
+/// Public keys that verify access tokens, for services that accept them without being
+/// able to issue them.
+#[handler]
+pub async fn jwks(depot: &mut Depot, res: &mut Response) {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    res.render(Json(auth.jwks()));
+}
+
+/// Serves [`jwks`] at `/.well-known/jwks.json`; mount at the root of the server.
+pub fn jwks_routes() -> Router {
+    Router::with_path(".well-known/jwks.json").get(jwks)
+}
+
 pub fn auth_routes() -> Router {
     Router::new()
         .push(Router::with_path("register").post(register))
-        .push(Router::with_path("login").post(login))
+        .push(
+            Router::with_path("login")
+                .post(login)
+                .push(Router::with_path("mfa").post(login_mfa)),
+        )
         .push(Router::with_path("refresh").post(refresh))
         .push(Router::with_path("logout").post(logout))
         .push(Router::with_path("logout-all").post(logout_all))
-        .push(Router::with_path("sessions").get(list_sessions))
+        .push(
+            Router::with_path("sessions")
+                .get(list_sessions)
+                .push(Router::with_path("{id}").delete(revoke_session)),
+        )
+        .push(oidc_routes())
+        .push(device_routes())
+        .push(account_routes())
+        .push(user_routes())
 }
@@ -8,7 +8,9 @@ mod tests {
     use serde::Deserialize;
     use serde_json::json;
 
-    use crate::server::routes::auth_routes;
+    use crate::server::routes::{TrustedProxies, auth_routes, extract_client_ip, jwks_routes};
+    use crate::utils::keys::{PrivateKey, TokenKeys, TokenKind};
+    use crate::utils::rate_limit::{LockoutPolicy, in_memory::InMemoryLoginRateLimiter};
     use crate::utils::repository::in_memory::InMemoryUserRepository;
     use crate::utils::service::{AuthService, LocalAuthService};
     use crate::utils::session_store::{SessionStore, in_memory::InMemorySessionStore};
@@ -21,6 +23,7 @@ mod tests {
     struct TestAuthResponse {
         token: String,
         session_id: String,
+        refresh_token: String,
     }
 
     /// Build a `Service` backed entirely by in-memory implementations.
@@ -72,8 +75,11 @@ mod tests {
 
         let set_cookie_val = set_cookie.expect("Set-Cookie header should be present");
         assert!(
-            set_cookie_val.to_str().unwrap().starts_with("session_id="),
-            "Set-Cookie should set session_id"
+            set_cookie_val
+                .to_str()
+                .unwrap()
+                .starts_with("refresh_token="),
+            "Set-Cookie should set refresh_token"
         );
     }
 
@@ -163,7 +169,12 @@ mod tests {
         assert!(!auth.session_id.is_empty());
 
         let set_cookie_val = set_cookie.expect("Set-Cookie should be set on login");
-        assert!(set_cookie_val.to_str().unwrap().starts_with("session_id="));
+        assert!(
+            set_cookie_val
+                .to_str()
+                .unwrap()
+                .starts_with("refresh_token=")
+        );
     }
 
     #[tokio::test]
@@ -243,6 +254,44 @@ mod tests {
         assert_eq!(res.status_code, Some(StatusCode::BAD_REQUEST));
     }
 
+    #[tokio::test]
+    async fn login_after_repeated_failures_returns_429_with_retry_after() {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let auth: Arc<dyn AuthService> = Arc::new(
+            LocalAuthService::new(
+                user_repo,
+                Arc::new(InMemorySessionStore::default()),
+                TEST_JWT_SECRET.to_string(),
+            )
+            .with_login_rate_limiter(Arc::new(InMemoryLoginRateLimiter::default())),
+        );
+        let service = Service::new(
+            Router::new()
+                .hoop(affix_state::inject(auth))
+                .push(auth_routes()),
+        );
+
+        for _ in 0..=LockoutPolicy::ACCOUNT.free_attempts {
+            let res = TestClient::post("http://0.0.0.0/login")
+                .json(&json!({ "username_or_email": "ghost", "password": "guess" }))
+                .send(&service)
+                .await;
+            assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        }
+
+        let res = TestClient::post("http://0.0.0.0/login")
+            .json(&json!({ "username_or_email": "ghost", "password": "guess" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::TOO_MANY_REQUESTS));
+        let retry_after: u64 = res.headers()["retry-after"]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after >= 1);
+    }
+
     // ─── POST /refresh ────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -263,7 +312,7 @@ mod tests {
         let mut res = TestClient::post("http://0.0.0.0/refresh")
             .add_header(
                 header::COOKIE,
-                format!("session_id={}", auth.session_id),
+                format!("refresh_token={}", auth.refresh_token),
                 true,
             )
             .send(&service)
@@ -273,10 +322,11 @@ mod tests {
         let refreshed: TestAuthResponse = res.take_json().await.unwrap();
         assert!(!refreshed.token.is_empty());
         assert_eq!(refreshed.session_id, auth.session_id);
+        assert_ne!(refreshed.refresh_token, auth.refresh_token);
     }
 
     #[tokio::test]
-    async fn refresh_with_session_id_in_body_returns_200() {
+    async fn refresh_with_refresh_token_in_body_returns_200() {
         let (service, _, _) = make_test_service();
 
         let mut reg_res = TestClient::post("http://0.0.0.0/register")
@@ -290,7 +340,7 @@ mod tests {
         let auth: TestAuthResponse = reg_res.take_json().await.unwrap();
 
         let mut res = TestClient::post("http://0.0.0.0/refresh")
-            .json(&json!({ "session_id": auth.session_id }))
+            .json(&json!({ "refresh_token": auth.refresh_token }))
             .send(&service)
             .await;
 
@@ -300,11 +350,61 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn refresh_invalid_session_id_returns_401() {
+    async fn refresh_with_session_id_alone_returns_401() {
+        let (service, _, _) = make_test_service();
+
+        let mut reg_res = TestClient::post("http://0.0.0.0/register")
+            .json(&json!({
+                "username": "hana",
+                "email": "hana@example.com",
+                "password": "password123"
+            }))
+            .send(&service)
+            .await;
+        let auth: TestAuthResponse = reg_res.take_json().await.unwrap();
+
+        let res = TestClient::post("http://0.0.0.0/refresh")
+            .json(&json!({ "refresh_token": auth.session_id }))
+            .send(&service)
+            .await;
+
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+    }
+
+    #[tokio::test]
+    async fn refresh_with_reused_token_revokes_session() {
+        let (service, _, session_store) = make_test_service();
+
+        let mut reg_res = TestClient::post("http://0.0.0.0/register")
+            .json(&json!({
+                "username": "hugo",
+                "email": "hugo@example.com",
+                "password": "password123"
+            }))
+            .send(&service)
+            .await;
+        let auth: TestAuthResponse = reg_res.take_json().await.unwrap();
+
+        let res = TestClient::post("http://0.0.0.0/refresh")
+            .json(&json!({ "refresh_token": auth.refresh_token }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        let res = TestClient::post("http://0.0.0.0/refresh")
+            .json(&json!({ "refresh_token": auth.refresh_token }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+        assert!(session_store.get(&auth.session_id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_invalid_token_returns_401() {
         let (service, _, _) = make_test_service();
 
         let res = TestClient::post("http://0.0.0.0/refresh")
-            .json(&json!({ "session_id": "00000000-0000-0000-0000-000000000000" }))
+            .json(&json!({ "refresh_token": "00000000-0000-0000-0000-000000000000.bogus" }))
             .send(&service)
             .await;
 
@@ -326,8 +426,8 @@ mod tests {
     // ─── POST /logout ─────────────────────────────────────────────────────────
 
     #[tokio::test]
-    async fn logout_with_valid_session_cookie_returns_200_and_clears_cookie() {
-        let (service, _, _) = make_test_service();
+    async fn logout_with_valid_refresh_cookie_returns_200_and_clears_cookie() {
+        let (service, _, session_store) = make_test_service();
 
         let mut reg_res = TestClient::post("http://0.0.0.0/register")
             .json(&json!({
@@ -342,22 +442,23 @@ mod tests {
         let res = TestClient::post("http://0.0.0.0/logout")
             .add_header(
                 header::COOKIE,
-                format!("session_id={}", auth.session_id),
+                format!("refresh_token={}", auth.refresh_token),
                 true,
             )
             .send(&service)
             .await;
 
         assert_eq!(res.status_code, Some(StatusCode::OK));
+        assert!(session_store.get(&auth.session_id).await.unwrap().is_none());
 
-        // The removal Set-Cookie should have session_id with an empty value /
+        // The removal Set-Cookie should have refresh_token with an empty value /
         // Max-Age=0 to instruct the browser to delete the cookie.
         let set_cookie = res.headers().get(header::SET_COOKIE);
         if let Some(hv) = set_cookie {
             let s = hv.to_str().unwrap();
             assert!(
-                s.starts_with("session_id="),
-                "Set-Cookie should reference session_id, got: {s}"
+                s.starts_with("refresh_token="),
+                "Set-Cookie should reference refresh_token, got: {s}"
             );
         }
         // Note: Salvo only emits Set-Cookie when the cookie jar has delta entries.
@@ -539,4 +640,218 @@ mod tests {
 
         assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
     }
+
+    // ─── DELETE /sessions/{id} ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn revoke_session_removes_only_that_session() {
+        let (service, auth, session_store) = make_test_service();
+
+        let reg = auth
+            .register(
+                "dana2",
+                "dana2@example.com",
+                "password123",
+                "device-1",
+                "192.168.1.1",
+            )
+            .await
+            .unwrap();
+        let tablet = auth
+            .login(
+                "dana2",
+                "password123",
+                "Mozilla/5.0 (iPad; CPU OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+                "192.168.1.2",
+            )
+            .await
+            .unwrap();
+
+        let mut res = TestClient::get("http://0.0.0.0/sessions")
+            .bearer_auth(&reg.token)
+            .send(&service)
+            .await;
+        let sessions: Vec<serde_json::Value> = res.take_json().await.unwrap();
+        let listed = sessions
+            .iter()
+            .find(|s| s["session_id"] == tablet.session_id.as_str())
+            .expect("tablet session should be listed");
+        assert_eq!(listed["device_name"], "Safari on iPad");
+        assert_eq!(listed["current"], false);
+
+        let res = TestClient::delete(format!("http://0.0.0.0/sessions/{}", tablet.session_id))
+            .bearer_auth(&reg.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        assert!(
+            session_store
+                .get(&tablet.session_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(session_store.get(&reg.session_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn revoke_session_of_another_user_returns_404() {
+        let (service, auth, session_store) = make_test_service();
+
+        let erin = auth
+            .register(
+                "erin2",
+                "erin2@example.com",
+                "password123",
+                "device-1",
+                "10.0.0.1",
+            )
+            .await
+            .unwrap();
+        let finn = auth
+            .register(
+                "finn2",
+                "finn2@example.com",
+                "password123",
+                "device-2",
+                "10.0.0.2",
+            )
+            .await
+            .unwrap();
+
+        let res = TestClient::delete(format!("http://0.0.0.0/sessions/{}", erin.session_id))
+            .bearer_auth(&finn.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::NOT_FOUND));
+        assert!(session_store.get(&erin.session_id).await.unwrap().is_some());
+    }
+
+    // ─── GET /.well-known/jwks.json ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn jwks_publishes_public_keys_that_verify_issued_tokens() {
+        let token_keys = Arc::new(
+            TokenKeys::from_private_keys(&[
+                PrivateKey::generate_ed25519(),
+                PrivateKey::generate_ed25519(),
+            ])
+            .unwrap(),
+        );
+        let auth = Arc::new(
+            LocalAuthService::new(
+                Arc::new(InMemoryUserRepository::default()),
+                Arc::new(InMemorySessionStore::default()),
+                TEST_JWT_SECRET.to_string(),
+            )
+            .with_token_keys(token_keys),
+        );
+        let auth_dyn: Arc<dyn AuthService> = auth.clone();
+        let service = Service::new(
+            Router::new()
+                .hoop(affix_state::inject(auth_dyn))
+                .push(jwks_routes()),
+        );
+
+        let mut res = TestClient::get("http://0.0.0.0/.well-known/jwks.json")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let jwks: jsonwebtoken::jwk::JwkSet = res.take_json().await.unwrap();
+        assert_eq!(jwks.keys.len(), 2);
+
+        let registered = auth
+            .register(
+                "gail",
+                "gail@example.com",
+                "password123",
+                "device-1",
+                "10.0.0.1",
+            )
+            .await
+            .unwrap();
+        let verifier = TokenKeys::from_jwks(jwks).unwrap();
+        assert!(
+            verifier
+                .verify::<crate::utils::service::Claims>(TokenKind::Access, &registered.token)
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn jwks_is_empty_with_a_shared_secret() {
+        let (_, auth, _) = make_test_service();
+        let auth_dyn: Arc<dyn AuthService> = auth;
+        let service = Service::new(
+            Router::new()
+                .hoop(affix_state::inject(auth_dyn))
+                .push(jwks_routes()),
+        );
+
+        let mut res = TestClient::get("http://0.0.0.0/.well-known/jwks.json")
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let body: serde_json::Value = res.take_json().await.unwrap();
+        assert_eq!(body, json!({ "keys": [] }));
+    }
+
+    fn request_from(peer: &str, headers: &[(&'static str, &str)]) -> Request {
+        let mut req = Request::new();
+        *req.remote_addr_mut() = peer.parse::<std::net::SocketAddr>().unwrap().into();
+        for (name, value) in headers {
+            req.headers_mut().insert(*name, value.parse().unwrap());
+        }
+        req
+    }
+
+    fn depot_trusting(proxies: &[&str]) -> Depot {
+        let mut depot = Depot::new();
+        depot.inject(TrustedProxies::parse(proxies).unwrap());
+        depot
+    }
+
+    #[test]
+    fn client_ip_ignores_forwarding_headers_from_untrusted_peers() {
+        let req = request_from(
+            "203.0.113.7:5000",
+            &[("x-forwarded-for", "10.9.9.9"), ("x-real-ip", "10.9.9.9")],
+        );
+
+        assert_eq!(extract_client_ip(&req, &Depot::new()), "203.0.113.7");
+        assert_eq!(
+            extract_client_ip(&req, &depot_trusting(&["10.0.0.0/8"])),
+            "203.0.113.7"
+        );
+    }
+
+    #[test]
+    fn client_ip_skips_trusted_hops_in_forwarded_for() {
+        let depot = depot_trusting(&["10.0.0.1", "172.16.0.0/12"]);
+        // The client made up the leftmost entry; the proxies appended the rest.
+        let req = request_from(
+            "10.0.0.1:5000",
+            &[("x-forwarded-for", "1.2.3.4, 198.51.100.20, 172.16.0.5")],
+        );
+
+        assert_eq!(extract_client_ip(&req, &depot), "198.51.100.20");
+    }
+
+    #[test]
+    fn client_ip_falls_back_to_real_ip_then_peer() {
+        let depot = depot_trusting(&["10.0.0.1"]);
+
+        let req = request_from("10.0.0.1:5000", &[("x-real-ip", "198.51.100.20")]);
+        assert_eq!(extract_client_ip(&req, &depot), "198.51.100.20");
+
+        let req = request_from("10.0.0.1:5000", &[]);
+        assert_eq!(extract_client_ip(&req, &depot), "10.0.0.1");
+    }
+
+    #[test]
+    fn trusted_proxies_reject_garbage() {
+        assert!(TrustedProxies::parse(&["10.0.0.0/8", "::1"]).is_ok());
+        assert!(TrustedProxies::parse(&["proxy.local"]).is_err());
+    }
 }
@@ -0,0 +1,278 @@
+#[cfg(test)]
+#[path = "users_tests.rs"]
+mod users_tests;
+
+use crate::server::routes::extract_bearer_token;
+use crate::utils::models::{Permission, Role};
+use crate::utils::service::{
+    AuthError, AuthService, AuthenticatedUser, InviteSummary, UserAccount,
+};
+use salvo::oapi::{ToResponses, ToSchema};
+use salvo::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetRoleRequest {
+    pub role: Role,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateInviteRequest {
+    /// Role given to the account registered with the invite. Defaults to `viewer`.
+    #[serde(default)]
+    pub role: Role,
+    /// Hours until the invite expires. Never expires when omitted.
+    pub expires_in_hours: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ResetPasswordResponse {
+    /// One-time password to hand to the user; they must change it after logging in
+    pub temporary_password: String,
+}
+
+// ── Error enum ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, ToResponses)]
+pub enum UserAdminError {
+    /// Invalid request body
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// Invalid or missing JWT
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// Missing `manage_users` permission, or the password must be changed first
+    #[salvo(response(status_code = 403))]
+    Forbidden(String),
+    /// User or invite not found
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// The change would leave no active admin
+    #[salvo(response(status_code = 409))]
+    Conflict(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+impl From<AuthError> for UserAdminError {
+    fn from(err: AuthError) -> Self {
+        match err {
+            AuthError::UserNotFound => Self::NotFound(err.to_string()),
+            AuthError::LastAdmin => Self::Conflict(err.to_string()),
+            _ => Self::InternalError(err.to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Writer for UserAdminError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        let (status, msg) = match self {
+            Self::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+            Self::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            Self::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            Self::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            Self::Conflict(msg) => (StatusCode::CONFLICT, msg),
+            Self::InternalError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        };
+        res.status_code(status);
+        res.render(Text::Plain(msg));
+    }
+}
+
+/// Verifies the bearer token and requires the `manage_users` permission.
+async fn require_user_admin(
+    req: &Request,
+    auth: &Arc<dyn AuthService>,
+) -> Result<AuthenticatedUser, UserAdminError> {
+    let token = extract_bearer_token(req)
+        .ok_or_else(|| UserAdminError::Unauthorized("Missing Authorization header".into()))?;
+
+    let user = auth.verify_token(&token).await.map_err(|err| match err {
+        AuthError::PasswordResetRequired => UserAdminError::Forbidden(err.to_string()),
+        _ => UserAdminError::Unauthorized("Invalid or expired token".into()),
+    })?;
+
+    if user.has_permission(Permission::ManageUsers) {
+        Ok(user)
+    } else {
+        Err(UserAdminError::Forbidden(
+            "Forbidden: manage_users permission required".into(),
+        ))
+    }
+}
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+/// List all user accounts
+#[endpoint(tags("users"))]
+pub async fn list_users(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<Vec<UserAccount>>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    Ok(Json(auth.list_users().await?))
+}
+
+/// Delete a user account and revoke its sessions
+#[endpoint(
+    tags("users"),
+    parameters(("id" = String, description = "User ID")),
+)]
+pub async fn delete_user(req: &mut Request, depot: &mut Depot) -> Result<(), UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let id: String = req.param::<String>("id").unwrap_or_default();
+    auth.delete_user(&id).await?;
+    Ok(())
+}
+
+/// Disable a user account and revoke its sessions
+#[endpoint(
+    tags("users"),
+    parameters(("id" = String, description = "User ID")),
+)]
+pub async fn disable_user(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<UserAccount>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let id: String = req.param::<String>("id").unwrap_or_default();
+    Ok(Json(auth.set_user_disabled(&id, true).await?))
+}
+
+/// Re-enable a disabled user account
+#[endpoint(
+    tags("users"),
+    parameters(("id" = String, description = "User ID")),
+)]
+pub async fn enable_user(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<UserAccount>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let id: String = req.param::<String>("id").unwrap_or_default();
+    Ok(Json(auth.set_user_disabled(&id, false).await?))
+}
+
+/// Change a user's role, e.g. to promote them to admin
+#[endpoint(
+    tags("users"),
+    parameters(("id" = String, description = "User ID")),
+    request_body = SetRoleRequest,
+)]
+pub async fn set_user_role(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<UserAccount>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let body: SetRoleRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| UserAdminError::BadRequest("Invalid request body".into()))?;
+    let id: String = req.param::<String>("id").unwrap_or_default();
+    Ok(Json(auth.set_user_role(&id, body.role).await?))
+}
+
+/// Replace a user's password with a temporary one they must change at next login
+#[endpoint(
+    tags("users"),
+    parameters(("id" = String, description = "User ID")),
+)]
+pub async fn reset_user_password(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<ResetPasswordResponse>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let id: String = req.param::<String>("id").unwrap_or_default();
+    let temporary_password = auth.reset_user_password(&id).await?;
+    Ok(Json(ResetPasswordResponse { temporary_password }))
+}
+
+/// List all invite codes
+#[endpoint(tags("users"))]
+pub async fn list_invites(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<Vec<InviteSummary>>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    Ok(Json(auth.list_invites().await?))
+}
+
+/// Create an invite code for registering a new account
+#[endpoint(
+    tags("users"),
+    request_body = CreateInviteRequest,
+)]
+pub async fn create_invite(
+    req: &mut Request,
+    depot: &mut Depot,
+) -> Result<Json<InviteSummary>, UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    let admin = require_user_admin(req, &auth).await?;
+
+    let body: CreateInviteRequest = req
+        .parse_json()
+        .await
+        .map_err(|_| UserAdminError::BadRequest("Invalid request body".into()))?;
+    let expires_in = body
+        .expires_in_hours
+        .map(|hours| chrono::Duration::hours(hours.into()));
+    Ok(Json(
+        auth.create_invite(&admin.user_id, body.role, expires_in)
+            .await?,
+    ))
+}
+
+/// Delete an invite code
+#[endpoint(
+    tags("users"),
+    parameters(("code" = String, description = "Invite code")),
+)]
+pub async fn delete_invite(req: &mut Request, depot: &mut Depot) -> Result<(), UserAdminError> {
+    let auth = depot.obtain::<Arc<dyn AuthService>>().unwrap().clone();
+    require_user_admin(req, &auth).await?;
+
+    let code: String = req.param::<String>("code").unwrap_or_default();
+    if auth.delete_invite(&code).await? {
+        Ok(())
+    } else {
+        Err(UserAdminError::NotFound("Invite not found".into()))
+    }
+}
+
+/// Admin routes for managing accounts and invites
+pub fn user_routes() -> Router {
+    Router::new()
+        .push(
+            Router::with_path("users").get(list_users).push(
+                Router::with_path("{id}")
+                    .delete(delete_user)
+                    .push(Router::with_path("disable").post(disable_user))
+                    .push(Router::with_path("enable").post(enable_user))
+                    .push(Router::with_path("role").put(set_user_role))
+                    .push(Router::with_path("reset-password").post(reset_user_password)),
+            ),
+        )
+        .push(
+            Router::with_path("invites")
+                .get(list_invites)
+                .post(create_invite)
+                .push(Router::with_path("{code}").delete(delete_invite)),
+        )
+}
@@ -0,0 +1,236 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use salvo::prelude::*;
+    use salvo::test::{ResponseExt, TestClient};
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use crate::config::RegistrationMode;
+    use crate::server::routes::auth_routes;
+    use crate::utils::repository::in_memory::InMemoryUserRepository;
+    use crate::utils::service::{AuthService, LocalAuthService};
+    use crate::utils::session_store::in_memory::InMemorySessionStore;
+
+    const TEST_JWT_SECRET: &str = "test-secret";
+
+    #[derive(Debug, Deserialize)]
+    struct TestAuthResponse {
+        token: String,
+        user: TestUser,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestUser {
+        id: String,
+        username: String,
+        role: String,
+        #[serde(default)]
+        disabled: bool,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct TestInvite {
+        code: String,
+    }
+
+    fn make_test_service(mode: RegistrationMode) -> Service {
+        let auth: Arc<dyn AuthService> = Arc::new(
+            LocalAuthService::new(
+                Arc::new(InMemoryUserRepository::default()),
+                Arc::new(InMemorySessionStore::default()),
+                TEST_JWT_SECRET.to_string(),
+            )
+            .with_registration_mode(mode),
+        );
+        let router = Router::new()
+            .hoop(affix_state::inject(auth))
+            .push(auth_routes());
+        Service::new(router)
+    }
+
+    async fn register(
+        service: &Service,
+        username: &str,
+        invite_code: Option<&str>,
+    ) -> salvo::Response {
+        TestClient::post("http://0.0.0.0/register")
+            .json(&json!({
+                "username": username,
+                "email": format!("{username}@example.com"),
+                "password": "password123",
+                "invite_code": invite_code,
+            }))
+            .send(service)
+            .await
+    }
+
+    #[tokio::test]
+    async fn list_users_requires_manage_users() {
+        let service = make_test_service(RegistrationMode::Open);
+        let mut res = register(&service, "owner", None).await;
+        let owner: TestAuthResponse = res.take_json().await.unwrap();
+        let mut res = register(&service, "alice", None).await;
+        let alice: TestAuthResponse = res.take_json().await.unwrap();
+
+        let res = TestClient::get("http://0.0.0.0/users").send(&service).await;
+        assert_eq!(res.status_code, Some(StatusCode::UNAUTHORIZED));
+
+        let res = TestClient::get("http://0.0.0.0/users")
+            .bearer_auth(&alice.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let mut res = TestClient::get("http://0.0.0.0/users")
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let users: Vec<TestUser> = res.take_json().await.unwrap();
+        let names: Vec<&str> = users.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(names, ["owner", "alice"]);
+        assert_eq!(users[0].role, "admin");
+        assert_eq!(users[1].role, "viewer");
+    }
+
+    #[tokio::test]
+    async fn invite_only_registration_flow() {
+        let service = make_test_service(RegistrationMode::Invite);
+        let mut res = register(&service, "owner", None).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let owner: TestAuthResponse = res.take_json().await.unwrap();
+
+        let res = register(&service, "bob", None).await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let mut res = TestClient::post("http://0.0.0.0/invites")
+            .bearer_auth(&owner.token)
+            .json(&json!({ "role": "library_manager", "expires_in_hours": 24 }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let invite: TestInvite = res.take_json().await.unwrap();
+
+        let mut res = register(&service, "bob", Some(&invite.code)).await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let bob: TestAuthResponse = res.take_json().await.unwrap();
+        assert_eq!(bob.user.role, "library_manager");
+
+        let res = register(&service, "carol", Some(&invite.code)).await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+    }
+
+    #[tokio::test]
+    async fn disable_promote_and_delete_user() {
+        let service = make_test_service(RegistrationMode::Open);
+        let mut res = register(&service, "owner", None).await;
+        let owner: TestAuthResponse = res.take_json().await.unwrap();
+        let mut res = register(&service, "dave", None).await;
+        let dave: TestAuthResponse = res.take_json().await.unwrap();
+
+        let mut res = TestClient::post(format!("http://0.0.0.0/users/{}/disable", dave.user.id))
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let account: TestUser = res.take_json().await.unwrap();
+        assert!(account.disabled);
+
+        let res = TestClient::post("http://0.0.0.0/login")
+            .json(&json!({ "username_or_email": "dave", "password": "password123" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let mut res = TestClient::put(format!("http://0.0.0.0/users/{}/role", dave.user.id))
+            .bearer_auth(&owner.token)
+            .json(&json!({ "role": "admin" }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+        let account: TestUser = res.take_json().await.unwrap();
+        assert_eq!(account.role, "admin");
+
+        let res = TestClient::delete(format!("http://0.0.0.0/users/{}", dave.user.id))
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        // The owner is now the only admin left
+        let res = TestClient::delete(format!("http://0.0.0.0/users/{}", owner.user.id))
+            .bearer_auth(&owner.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::CONFLICT));
+    }
+
+    #[tokio::test]
+    async fn reset_admin_can_only_change_password() {
+        #[derive(Debug, Deserialize)]
+        struct TestResetResponse {
+            temporary_password: String,
+        }
+
+        #[derive(Debug, Deserialize)]
+        struct TestLoginResponse {
+            token: String,
+            refresh_token: String,
+        }
+
+        let service = make_test_service(RegistrationMode::Open);
+        let mut res = register(&service, "owner", None).await;
+        let owner: TestAuthResponse = res.take_json().await.unwrap();
+        let mut res = register(&service, "erin", None).await;
+        let erin: TestAuthResponse = res.take_json().await.unwrap();
+        TestClient::put(format!("http://0.0.0.0/users/{}/role", erin.user.id))
+            .bearer_auth(&owner.token)
+            .json(&json!({ "role": "admin" }))
+            .send(&service)
+            .await;
+
+        let mut res = TestClient::post(format!(
+            "http://0.0.0.0/users/{}/reset-password",
+            erin.user.id
+        ))
+        .bearer_auth(&owner.token)
+        .send(&service)
+        .await;
+        let reset: TestResetResponse = res.take_json().await.unwrap();
+        let mut res = TestClient::post("http://0.0.0.0/login")
+            .json(&json!({ "username_or_email": "erin", "password": reset.temporary_password }))
+            .send(&service)
+            .await;
+        let erin: TestLoginResponse = res.take_json().await.unwrap();
+
+        let res = TestClient::get("http://0.0.0.0/users")
+            .bearer_auth(&erin.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::FORBIDDEN));
+
+        let res = TestClient::put("http://0.0.0.0/me/password")
+            .bearer_auth(&erin.token)
+            .json(&json!({
+                "current_password": reset.temporary_password,
+                "new_password": "new-password123",
+            }))
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+
+        // The next access token no longer carries the restriction
+        let mut res = TestClient::post("http://0.0.0.0/refresh")
+            .json(&json!({ "refresh_token": erin.refresh_token }))
+            .send(&service)
+            .await;
+        let erin: TestLoginResponse = res.take_json().await.unwrap();
+        let res = TestClient::get("http://0.0.0.0/users")
+            .bearer_auth(&erin.token)
+            .send(&service)
+            .await;
+        assert_eq!(res.status_code, Some(StatusCode::OK));
+    }
+}
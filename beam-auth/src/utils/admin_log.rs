@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use beam_entity::admin_log::{AdminLogCategory, AdminLogLevel};
+use sea_orm::*;
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Destination for authentication events shown in the admin log.
+///
+/// Every entry is written in the `auth` category.
+#[async_trait]
+pub trait AdminLogSink: Send + Sync + std::fmt::Debug {
+    /// Records an event with optional structured `details`.
+    async fn log(
+        &self,
+        level: AdminLogLevel,
+        message: String,
+        details: Option<Value>,
+    ) -> Result<(), DbErr>;
+}
+
+/// Writes entries to the `admin_logs` table shared with the rest of Beam.
+#[derive(Debug)]
+pub struct SqlAdminLogSink {
+    db: DatabaseConnection,
+}
+
+impl SqlAdminLogSink {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl AdminLogSink for SqlAdminLogSink {
+    async fn log(
+        &self,
+        level: AdminLogLevel,
+        message: String,
+        details: Option<Value>,
+    ) -> Result<(), DbErr> {
+        use beam_entity::admin_log;
+
+        let entry = admin_log::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            level: Set(level),
+            category: Set(AdminLogCategory::Auth),
+            message: Set(message),
+            details: Set(details),
+            created_at: Set(chrono::Utc::now().into()),
+        };
+        entry.insert(&self.db).await?;
+        Ok(())
+    }
+}
+
+/// Discards all entries. Used when no database is available for the admin log.
+#[derive(Debug)]
+pub struct NoOpAdminLogSink;
+
+#[async_trait]
+impl AdminLogSink for NoOpAdminLogSink {
+    async fn log(
+        &self,
+        _level: AdminLogLevel,
+        _message: String,
+        _details: Option<Value>,
+    ) -> Result<(), DbErr> {
+        Ok(())
+    }
+}
+
+/// In-memory admin log for use in tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod in_memory {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone)]
+    pub struct AdminLogEntry {
+        pub level: AdminLogLevel,
+        pub message: String,
+        pub details: Option<Value>,
+    }
+
+    #[derive(Debug, Default)]
+    pub struct InMemoryAdminLogSink {
+        entries: Mutex<Vec<AdminLogEntry>>,
+    }
+
+    impl InMemoryAdminLogSink {
+        /// All entries recorded so far, oldest first.
+        pub fn entries(&self) -> Vec<AdminLogEntry> {
+            self.entries.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl AdminLogSink for InMemoryAdminLogSink {
+        async fn log(
+            &self,
+            level: AdminLogLevel,
+            message: String,
+            details: Option<Value>,
+        ) -> Result<(), DbErr> {
+            self.entries.lock().unwrap().push(AdminLogEntry {
+                level,
+                message,
+                details,
+            });
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::utils::models::User;
+use crate::utils::repository::UserRepository;
+use crate::utils::service::{AuthError, ExternalIdentity};
+use argon2::{
+    Argon2,
+    password_hash::{PasswordHash, PasswordVerifier},
+};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+type Result<T> = std::result::Result<T, AuthError>;
+
+/// Who a backend found behind a username and password
+#[derive(Debug, Clone)]
+pub enum Authenticated {
+    /// A local user whose own password matched
+    Local(User),
+    /// An account elsewhere, signed in to its linked local user like any external identity
+    External(ExternalIdentity),
+}
+
+/// Something `login` can check a username and password against
+#[async_trait]
+pub trait AuthenticationBackend: Send + Sync + std::fmt::Debug {
+    /// `Ok(None)` when the backend does not know the account or the password is wrong, so
+    /// the next backend can be tried. Errors mean the backend could not give an answer.
+    async fn authenticate(
+        &self,
+        username_or_email: &str,
+        password: &str,
+    ) -> Result<Option<Authenticated>>;
+}
+
+/// Checks a password against its argon2 hash.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let parsed_hash = match PasswordHash::new(password_hash) {
+        Ok(hash) => hash,
+        Err(_) => return false,
+    };
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .is_ok()
+}
+
+/// The password hashes stored with the users
+#[derive(Debug)]
+pub struct LocalPasswordBackend {
+    user_repo: Arc<dyn UserRepository>,
+}
+
+impl LocalPasswordBackend {
+    pub fn new(user_repo: Arc<dyn UserRepository>) -> Self {
+        Self { user_repo }
+    }
+}
+
+#[async_trait]
+impl AuthenticationBackend for LocalPasswordBackend {
+    async fn authenticate(
+        &self,
+        username_or_email: &str,
+        password: &str,
+    ) -> Result<Option<Authenticated>> {
+        // Try to find by username first, then email
+        let user = if let Some(u) = self
+            .user_repo
+            .find_by_username(username_or_email)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+        {
+            Some(u)
+        } else {
+            self.user_repo
+                .find_by_email(username_or_email)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+        };
+
+        Ok(user
+            .filter(|u| verify_password(password, &u.password_hash))
+            .map(Authenticated::Local))
+    }
+}
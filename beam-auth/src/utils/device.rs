@@ -0,0 +1,105 @@
+#[cfg(test)]
+#[path = "device_tests.rs"]
+mod device_tests;
+
+use rand::RngExt;
+use sha2::{Digest, Sha256};
+
+/// Letters a pairing code is made of: no vowels, so codes never spell words, and
+/// nothing that looks like a digit
+const USER_CODE_ALPHABET: &[u8] = b"BCDFGHJKLMNPQRSTVWXZ";
+
+/// Letters in a pairing code
+const USER_CODE_LEN: usize = 8;
+
+/// Stable fingerprint of a client's user-agent, stored with each session.
+pub fn device_hash(user_agent: &str) -> String {
+    format!("{:x}", Sha256::digest(user_agent.as_bytes()))
+}
+
+/// Human-readable device name such as "Firefox on Windows", derived from a user-agent.
+///
+/// Falls back to the first product token (e.g. "curl") for non-browser clients and to
+/// "Unknown device" when the user-agent is empty.
+pub fn device_name(user_agent: &str) -> String {
+    let user_agent = user_agent.trim();
+    if user_agent.is_empty() {
+        return "Unknown device".to_string();
+    }
+
+    let browser = browser_name(user_agent);
+    let os = os_name(user_agent);
+
+    match (browser, os) {
+        (Some(browser), Some(os)) => format!("{browser} on {os}"),
+        (Some(browser), None) => browser.to_string(),
+        (None, Some(os)) => os.to_string(),
+        (None, None) => user_agent
+            .split(['/', ' '])
+            .next()
+            .unwrap_or(user_agent)
+            .to_string(),
+    }
+}
+
+fn browser_name(user_agent: &str) -> Option<&'static str> {
+    // Order matters: most browsers also advertise the engines they are built on
+    const BROWSERS: [(&str, &str); 7] = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("SamsungBrowser/", "Samsung Internet"),
+        ("Firefox/", "Firefox"),
+        ("FxiOS/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("CriOS/", "Chrome"),
+    ];
+
+    BROWSERS
+        .iter()
+        .find(|(token, _)| user_agent.contains(token))
+        .map(|(_, name)| *name)
+        .or_else(|| {
+            (user_agent.contains("Safari/") && user_agent.contains("Version/")).then_some("Safari")
+        })
+}
+
+fn os_name(user_agent: &str) -> Option<&'static str> {
+    const SYSTEMS: [(&str, &str); 8] = [
+        ("iPhone", "iPhone"),
+        ("iPad", "iPad"),
+        ("Android", "Android"),
+        ("CrOS", "ChromeOS"),
+        ("Windows", "Windows"),
+        ("Macintosh", "macOS"),
+        ("Mac OS X", "macOS"),
+        ("Linux", "Linux"),
+    ];
+
+    SYSTEMS
+        .iter()
+        .find(|(token, _)| user_agent.contains(token))
+        .map(|(_, name)| *name)
+}
+
+/// Random code a TV shows for the user to type in elsewhere, without the dash.
+pub fn generate_user_code() -> String {
+    let mut rng = rand::rng();
+    (0..USER_CODE_LEN)
+        .map(|_| USER_CODE_ALPHABET[rng.random_range(0..USER_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Undoes what users do to a pairing code when typing it: lower case, spaces, the dash.
+pub fn normalize_user_code(user_code: &str) -> String {
+    user_code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Pairing code as it is shown, e.g. `BCDF-GHJK`.
+pub fn format_user_code(user_code: &str) -> String {
+    let (first, second) = user_code.split_at(user_code.len() / 2);
+    format!("{first}-{second}")
+}
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::device::{
+        device_hash, device_name, format_user_code, generate_user_code, normalize_user_code,
+    };
+
+    #[test]
+    fn device_name_recognises_common_browsers() {
+        let cases = [
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:128.0) Gecko/20100101 Firefox/128.0",
+                "Firefox on Windows",
+            ),
+            (
+                "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15",
+                "Safari on macOS",
+            ),
+            (
+                "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Mobile Safari/537.36",
+                "Chrome on Android",
+            ),
+            (
+                "Mozilla/5.0 (iPad; CPU OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1",
+                "Safari on iPad",
+            ),
+            (
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0",
+                "Edge on Windows",
+            ),
+        ];
+
+        for (user_agent, expected) in cases {
+            assert_eq!(device_name(user_agent), expected, "{user_agent}");
+        }
+    }
+
+    #[test]
+    fn device_name_falls_back_to_product_token() {
+        assert_eq!(device_name("curl/8.5.0"), "curl");
+        assert_eq!(device_name(""), "Unknown device");
+    }
+
+    #[test]
+    fn device_hash_is_stable_per_user_agent() {
+        assert_eq!(device_hash("curl/8.5.0"), device_hash("curl/8.5.0"));
+        assert_ne!(device_hash("curl/8.5.0"), device_hash("curl/8.6.0"));
+    }
+
+    #[test]
+    fn user_codes_survive_being_typed_sloppily() {
+        let code = generate_user_code();
+        assert_eq!(code.len(), 8);
+        assert!(code.chars().all(|c| "BCDFGHJKLMNPQRSTVWXZ".contains(c)));
+
+        let shown = format_user_code(&code);
+        assert_eq!(shown.len(), 9);
+        assert_eq!(normalize_user_code(&shown), code);
+        assert_eq!(normalize_user_code(" bcdf-ghjk "), "BCDFGHJK");
+    }
+}
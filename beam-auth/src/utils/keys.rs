@@ -0,0 +1,419 @@
+#[cfg(test)]
+#[path = "keys_tests.rs"]
+mod keys_tests;
+
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use aws_lc_rs::rand::SystemRandom;
+use aws_lc_rs::signature::{Ed25519KeyPair, KeyPair};
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, EllipticCurve, Jwk, KeyAlgorithm, OctetKeyPairParameters,
+    OctetKeyPairType, PublicKeyUse, ThumbprintHash,
+};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header, Validation, decode, decode_header, encode,
+};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+pub use jsonwebtoken::jwk::JwkSet;
+
+#[derive(Debug, Error)]
+pub enum KeyError {
+    #[error("Failed to read key file {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+    #[error("No signing key configured; this server can only verify tokens")]
+    NoSigningKey,
+    #[error("Token error: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+}
+
+type Result<T> = std::result::Result<T, KeyError>;
+
+/// What a token is for. Each kind has its own `typ` header, issuer and audience, all of
+/// which are checked on verification, so a token of one kind is never accepted as another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Short-lived bearer token for the API
+    Access,
+    /// Grants playback of a single stream
+    Stream,
+    /// Proves the password step of a two-factor login
+    MfaChallenge,
+    /// Carries the state of an OpenID Connect login through the provider's redirect
+    OidcLogin,
+}
+
+impl TokenKind {
+    pub fn typ(self) -> &'static str {
+        match self {
+            Self::Access => "at+jwt",
+            Self::Stream => "stream+jwt",
+            Self::MfaChallenge => "mfa-challenge+jwt",
+            Self::OidcLogin => "oidc-login+jwt",
+        }
+    }
+
+    pub fn issuer(self) -> &'static str {
+        match self {
+            Self::Access | Self::MfaChallenge | Self::OidcLogin => "beam-auth",
+            Self::Stream => "beam-stream",
+        }
+    }
+
+    pub fn audience(self) -> &'static str {
+        match self {
+            Self::Access => "beam-api",
+            Self::Stream => "beam-stream-media",
+            Self::MfaChallenge => "beam-auth-mfa",
+            Self::OidcLogin => "beam-auth-oidc",
+        }
+    }
+}
+
+/// Claims of a given kind with the issuer and audience added
+#[derive(Serialize)]
+struct Registered<'a, T> {
+    iss: &'static str,
+    aud: &'static str,
+    #[serde(flatten)]
+    claims: &'a T,
+}
+
+/// An Ed25519 (EdDSA) or RSA (RS256) private key for signing tokens.
+#[derive(Clone)]
+pub struct PrivateKey {
+    key: EncodingKey,
+    algorithm: Algorithm,
+}
+
+impl std::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("algorithm", &self.algorithm)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PrivateKey {
+    /// Parses a PKCS#8 or PKCS#1 PEM key, e.g. from `openssl genpkey -algorithm ed25519`.
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        if let Ok(key) = EncodingKey::from_ed_pem(pem) {
+            return Ok(Self {
+                key,
+                algorithm: Algorithm::EdDSA,
+            });
+        }
+        EncodingKey::from_rsa_pem(pem)
+            .map(|key| Self {
+                key,
+                algorithm: Algorithm::RS256,
+            })
+            .map_err(|_| KeyError::InvalidKey("expected an Ed25519 or RSA private key".into()))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let pem = std::fs::read(path).map_err(|e| KeyError::Io(path.display().to_string(), e))?;
+        Self::from_pem(&pem)
+    }
+
+    /// Generates a random Ed25519 key.
+    pub fn generate_ed25519() -> Self {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .expect("Ed25519 key generation cannot fail");
+        Self {
+            key: EncodingKey::from_ed_der(pkcs8.as_ref()),
+            algorithm: Algorithm::EdDSA,
+        }
+    }
+
+    /// The public half as a JWK, with its RFC 7638 thumbprint as `kid`.
+    pub fn public_jwk(&self) -> Result<Jwk> {
+        let mut jwk = match self.algorithm {
+            Algorithm::EdDSA => {
+                let pair = Ed25519KeyPair::from_pkcs8(self.key.inner())
+                    .map_err(|e| KeyError::InvalidKey(e.to_string()))?;
+                Jwk {
+                    common: Default::default(),
+                    algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                        key_type: OctetKeyPairType::OctetKeyPair,
+                        curve: EllipticCurve::Ed25519,
+                        x: URL_SAFE_NO_PAD.encode(pair.public_key().as_ref()),
+                    }),
+                }
+            }
+            algorithm => Jwk::from_encoding_key(&self.key, algorithm)?,
+        };
+
+        jwk.common.key_algorithm = Some(match self.algorithm {
+            Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+            _ => KeyAlgorithm::RS256,
+        });
+        jwk.common.public_key_use = Some(PublicKeyUse::Signature);
+        jwk.common.key_id = Some(jwk.thumbprint(ThumbprintHash::SHA256));
+        Ok(jwk)
+    }
+}
+
+struct SigningKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+#[derive(Default)]
+struct Verification {
+    keys: Vec<VerificationKey>,
+    /// Public keys published at `/.well-known/jwks.json`
+    jwks: Vec<Jwk>,
+}
+
+/// Where verify-only keys come from, asked again when a token names a key not yet known,
+/// e.g. one the issuer rotated in since the last fetch.
+#[async_trait::async_trait]
+pub trait KeySource: Send + Sync + std::fmt::Debug {
+    /// Replace `keys` with the issuer's current set. Implementations rate-limit this, as
+    /// anyone can send a token with a made-up `kid`.
+    async fn unknown_key(&self, keys: &TokenKeys);
+}
+
+/// Keys that sign and verify access tokens.
+///
+/// Either a shared HS256 secret, with which every holder can also mint tokens, or
+/// asymmetric keys identified by the `kid` header: the issuer signs with its current
+/// private key and other services verify with the public keys from its JWKS. Keys
+/// rotated out stay in the set so tokens they signed remain valid until they expire.
+pub struct TokenKeys {
+    signing: Option<SigningKey>,
+    verification: RwLock<Verification>,
+    source: Option<Arc<dyn KeySource>>,
+}
+
+impl std::fmt::Debug for TokenKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenKeys")
+            .field("can_sign", &self.can_sign())
+            .finish_non_exhaustive()
+    }
+}
+
+impl TokenKeys {
+    /// Signs and verifies with a shared secret. Nothing is published in the JWKS.
+    pub fn hmac(secret: &str) -> Self {
+        Self {
+            signing: Some(SigningKey {
+                kid: None,
+                algorithm: Algorithm::HS256,
+                key: EncodingKey::from_secret(secret.as_bytes()),
+            }),
+            verification: RwLock::new(Verification {
+                keys: vec![VerificationKey {
+                    kid: None,
+                    algorithm: Algorithm::HS256,
+                    key: DecodingKey::from_secret(secret.as_bytes()),
+                }],
+                jwks: Vec::new(),
+            }),
+            source: None,
+        }
+    }
+
+    /// Signs with the first key and accepts tokens signed by any of them.
+    pub fn from_private_keys(private_keys: &[PrivateKey]) -> Result<Self> {
+        let current = private_keys
+            .first()
+            .ok_or_else(|| KeyError::InvalidKey("at least one signing key is required".into()))?;
+
+        let jwks = private_keys
+            .iter()
+            .map(PrivateKey::public_jwk)
+            .collect::<Result<Vec<_>>>()?;
+        let keys = Self {
+            signing: Some(SigningKey {
+                kid: jwks[0].common.key_id.clone(),
+                algorithm: current.algorithm,
+                key: current.key.clone(),
+            }),
+            verification: RwLock::default(),
+            source: None,
+        };
+        keys.set_jwks(JwkSet { keys: jwks })?;
+        Ok(keys)
+    }
+
+    /// Reads PEM private keys, the first of which signs.
+    pub fn from_private_key_files<P: AsRef<Path>>(paths: &[P]) -> Result<Self> {
+        let private_keys = paths
+            .iter()
+            .map(PrivateKey::from_file)
+            .collect::<Result<Vec<_>>>()?;
+        Self::from_private_keys(&private_keys)
+    }
+
+    /// Verifies with the public keys an issuer publishes, without being able to sign.
+    pub fn from_jwks(jwks: JwkSet) -> Result<Self> {
+        let keys = Self {
+            signing: None,
+            verification: RwLock::default(),
+            source: None,
+        };
+        keys.set_jwks(jwks)?;
+        Ok(keys)
+    }
+
+    /// Ask `source` for the keys again whenever a token names an unknown one.
+    pub fn with_source(mut self, source: Arc<dyn KeySource>) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Fetches the keys again from their source if `token` names a key that isn't known.
+    pub async fn refresh_for(&self, token: &str) {
+        let Some(source) = &self.source else {
+            return;
+        };
+        let unknown = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .is_some_and(|kid| !self.has_key(&kid));
+        if unknown {
+            source.unknown_key(self).await;
+        }
+    }
+
+    /// Replaces the accepted public keys, e.g. after fetching the issuer's JWKS again.
+    ///
+    /// Keys without a `kid`, encryption keys and symmetric keys are ignored. Keys without
+    /// an `alg` are assumed to use the usual algorithm for their type.
+    pub fn set_jwks(&self, jwks: JwkSet) -> Result<()> {
+        let mut verification = Verification::default();
+        for jwk in jwks.keys {
+            if matches!(jwk.algorithm, AlgorithmParameters::OctetKey(_))
+                || jwk.common.public_key_use == Some(PublicKeyUse::Encryption)
+            {
+                continue;
+            }
+            let Some(kid) = &jwk.common.key_id else {
+                continue;
+            };
+            let algorithm = match jwk.common.key_algorithm {
+                Some(key_algorithm) => Algorithm::from_str(&key_algorithm.to_string())?,
+                None => match &jwk.algorithm {
+                    AlgorithmParameters::RSA(_) => Algorithm::RS256,
+                    AlgorithmParameters::EllipticCurve(ec) if ec.curve == EllipticCurve::P384 => {
+                        Algorithm::ES384
+                    }
+                    AlgorithmParameters::EllipticCurve(_) => Algorithm::ES256,
+                    _ => Algorithm::EdDSA,
+                },
+            };
+            verification.keys.push(VerificationKey {
+                kid: Some(kid.clone()),
+                algorithm,
+                key: DecodingKey::from_jwk(&jwk)?,
+            });
+            verification.jwks.push(jwk);
+        }
+
+        *self.verification.write().unwrap() = verification;
+        Ok(())
+    }
+
+    /// Whether a key with this `kid` is known.
+    pub fn has_key(&self, kid: &str) -> bool {
+        self.verification
+            .read()
+            .unwrap()
+            .keys
+            .iter()
+            .any(|key| key.kid.as_deref() == Some(kid))
+    }
+
+    /// Public keys for verifying tokens, empty when a shared secret is used.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.verification.read().unwrap().jwks.clone(),
+        }
+    }
+
+    pub fn can_sign(&self) -> bool {
+        self.signing.is_some()
+    }
+
+    /// Signs `claims` as a token of `kind`, adding its `typ`, issuer and audience.
+    pub fn sign<T: Serialize>(&self, kind: TokenKind, claims: &T) -> Result<String> {
+        let signing = self.signing.as_ref().ok_or(KeyError::NoSigningKey)?;
+        let mut header = Header::new(signing.algorithm);
+        header.typ = Some(kind.typ().to_string());
+        header.kid = signing.kid.clone();
+        let claims = Registered {
+            iss: kind.issuer(),
+            aud: kind.audience(),
+            claims,
+        };
+        Ok(encode(&header, &claims, &signing.key)?)
+    }
+
+    /// Checks that `token` is a token of `kind` signed by the key named by its `kid`, and
+    /// returns its claims.
+    ///
+    /// `exp`, `sub`, `iss` and `aud` are required.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        kind: TokenKind,
+        token: &str,
+    ) -> jsonwebtoken::errors::Result<T> {
+        let header = decode_header(token)?;
+        if header.typ.as_deref() != Some(kind.typ()) {
+            return Err(ErrorKind::InvalidToken.into());
+        }
+        let verification = self.verification.read().unwrap();
+        let key = verification
+            .keys
+            .iter()
+            .find(|key| key.kid == header.kid && key.algorithm == header.alg)
+            .ok_or(ErrorKind::InvalidSignature)?;
+
+        let mut validation = Validation::new(key.algorithm);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+        validation.set_issuer(&[kind.issuer()]);
+        validation.set_audience(&[kind.audience()]);
+        Ok(decode::<T>(token, &key.key, &validation)?.claims)
+    }
+
+    /// Checks a token from another issuer, such as an OpenID Connect ID token, against the
+    /// key named by its `kid` and returns its claims.
+    ///
+    /// `validation` decides which claims are checked; its algorithms are replaced with the
+    /// key's.
+    pub fn verify_external<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> jsonwebtoken::errors::Result<T> {
+        let header = decode_header(token)?;
+        let verification = self.verification.read().unwrap();
+        let key = verification
+            .keys
+            .iter()
+            .find(|key| key.kid == header.kid && key.algorithm == header.alg)
+            .ok_or(ErrorKind::InvalidSignature)?;
+
+        validation.algorithms = vec![key.algorithm];
+        Ok(decode::<T>(token, &key.key, &validation)?.claims)
+    }
+}
@@ -0,0 +1,437 @@
+#[cfg(test)]
+#[path = "ldap_tests.rs"]
+mod ldap_tests;
+
+use crate::config::LdapConfig;
+use crate::utils::backend::{Authenticated, AuthenticationBackend};
+use crate::utils::service::{AuthError, ExternalIdentity};
+use async_trait::async_trait;
+use ldap3::{LdapConnAsync, LdapConnSettings, Scope, SearchEntry, ldap_escape};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Provider name LDAP accounts are linked under
+pub const PROVIDER: &str = "ldap";
+
+/// Result code of a bind with the wrong password
+const INVALID_CREDENTIALS: u32 = 49;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+#[error("LDAP error: {0}")]
+pub struct DirectoryError(pub String);
+
+impl From<ldap3::LdapError> for DirectoryError {
+    fn from(err: ldap3::LdapError) -> Self {
+        Self(err.to_string())
+    }
+}
+
+/// The operations logging in needs from a directory server
+#[async_trait]
+pub trait Directory: Send + Sync + std::fmt::Debug {
+    /// Searches as the service account.
+    async fn search(
+        &self,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: &[String],
+    ) -> Result<Vec<SearchEntry>, DirectoryError>;
+
+    /// Whether the directory accepts `password` for `dn`.
+    async fn bind(&self, dn: &str, password: &str) -> Result<bool, DirectoryError>;
+}
+
+/// A directory server reached over the network, with a connection per operation
+#[derive(Debug)]
+pub struct LdapDirectory {
+    url: String,
+    starttls: bool,
+    bind_dn: Option<String>,
+    bind_password: Option<String>,
+}
+
+impl LdapDirectory {
+    pub fn new(config: &LdapConfig) -> Result<Self, DirectoryError> {
+        let url = config
+            .url
+            .clone()
+            .ok_or_else(|| DirectoryError("LDAP_URL is not set".into()))?;
+        Ok(Self {
+            url,
+            starttls: config.starttls,
+            bind_dn: config.bind_dn.clone(),
+            bind_password: config.bind_password.clone(),
+        })
+    }
+
+    async fn connect(&self) -> Result<ldap3::Ldap, DirectoryError> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(TIMEOUT)
+            .set_starttls(self.starttls);
+        let (conn, ldap) = LdapConnAsync::with_settings(settings, &self.url).await?;
+        ldap3::drive!(conn);
+        Ok(ldap)
+    }
+}
+
+#[async_trait]
+impl Directory for LdapDirectory {
+    async fn search(
+        &self,
+        base: &str,
+        scope: Scope,
+        filter: &str,
+        attrs: &[String],
+    ) -> Result<Vec<SearchEntry>, DirectoryError> {
+        let mut ldap = self.connect().await?;
+        if let Some(bind_dn) = &self.bind_dn {
+            ldap.with_timeout(TIMEOUT)
+                .simple_bind(bind_dn, self.bind_password.as_deref().unwrap_or_default())
+                .await?
+                .success()?;
+        }
+        let (entries, _) = ldap
+            .with_timeout(TIMEOUT)
+            .search(base, scope, filter, attrs)
+            .await?
+            .success()?;
+        let _ = ldap.unbind().await;
+        Ok(entries.into_iter().map(SearchEntry::construct).collect())
+    }
+
+    async fn bind(&self, dn: &str, password: &str) -> Result<bool, DirectoryError> {
+        let mut ldap = self.connect().await?;
+        let result = ldap.with_timeout(TIMEOUT).simple_bind(dn, password).await?;
+        let _ = ldap.unbind().await;
+        match result.rc {
+            INVALID_CREDENTIALS => Ok(false),
+            _ => result.success().map(|_| true).map_err(Into::into),
+        }
+    }
+}
+
+/// Checks passwords by binding to an LDAP directory as the user, the way most directory
+/// servers expect: find the user's entry, then bind with its DN and the password.
+#[derive(Debug)]
+pub struct LdapBackend {
+    config: LdapConfig,
+    directory: Arc<dyn Directory>,
+}
+
+impl LdapBackend {
+    pub fn new(config: LdapConfig) -> Result<Self, DirectoryError> {
+        let directory = Arc::new(LdapDirectory::new(&config)?);
+        Ok(Self::with_directory(config, directory))
+    }
+
+    pub fn with_directory(config: LdapConfig, directory: Arc<dyn Directory>) -> Self {
+        Self { config, directory }
+    }
+
+    fn attributes(&self) -> Vec<String> {
+        let config = &self.config;
+        [
+            Some(&config.username_attribute),
+            Some(&config.email_attribute),
+            Some(&config.display_name_attribute),
+            config.id_attribute.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
+
+    /// Whether the entry matches the admin filter, or `None` without one.
+    async fn is_admin(
+        &self,
+        entry: &SearchEntry,
+        username: &str,
+    ) -> Result<Option<bool>, DirectoryError> {
+        let Some(template) = &self.config.admin_filter else {
+            return Ok(None);
+        };
+        let filter = fill_filter(template, &[("username", username), ("dn", &entry.dn)]);
+        let matches = match &self.config.admin_group_base_dn {
+            Some(base) => {
+                let attrs = ["1.1".to_string()];
+                self.directory
+                    .search(base, Scope::Subtree, &filter, &attrs)
+                    .await?
+            }
+            None => {
+                self.directory
+                    .search(&entry.dn, Scope::Base, &filter, &[])
+                    .await?
+            }
+        };
+        Ok(Some(!matches.is_empty()))
+    }
+
+    fn subject(&self, entry: &SearchEntry) -> Result<String, DirectoryError> {
+        let Some(id_attribute) = &self.config.id_attribute else {
+            return Ok(entry.dn.clone());
+        };
+        if let Some(id) = attribute(entry, id_attribute) {
+            return Ok(id.to_string());
+        }
+        // Active Directory's objectGUID is binary
+        entry
+            .bin_attrs
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(id_attribute))
+            .and_then(|(_, values)| values.first())
+            .map(|id| data_encoding::HEXLOWER.encode(id))
+            .ok_or_else(|| DirectoryError(format!("{} has no {id_attribute}", entry.dn)))
+    }
+}
+
+#[async_trait]
+impl AuthenticationBackend for LdapBackend {
+    async fn authenticate(
+        &self,
+        username_or_email: &str,
+        password: &str,
+    ) -> Result<Option<Authenticated>, AuthError> {
+        // Directories treat a bind without a password as an anonymous bind, which succeeds
+        if password.is_empty() || username_or_email.is_empty() {
+            return Ok(None);
+        }
+        let backend_error = |e: DirectoryError| AuthError::Backend(e.to_string());
+
+        let filter = fill_filter(&self.config.user_filter, &[("username", username_or_email)]);
+        let entries = self
+            .directory
+            .search(
+                &self.config.user_base_dn,
+                Scope::Subtree,
+                &filter,
+                &self.attributes(),
+            )
+            .await
+            .map_err(backend_error)?;
+        let entry = match entries.as_slice() {
+            [entry] => entry,
+            [] => return Ok(None),
+            _ => {
+                tracing::warn!(
+                    "{} LDAP entries match {filter}; refusing to guess which one is logging in",
+                    entries.len()
+                );
+                return Ok(None);
+            }
+        };
+
+        if !self
+            .directory
+            .bind(&entry.dn, password)
+            .await
+            .map_err(backend_error)?
+        {
+            return Ok(None);
+        }
+
+        let username = attribute(entry, &self.config.username_attribute);
+        let is_admin = self
+            .is_admin(entry, username.unwrap_or(username_or_email))
+            .await
+            .map_err(backend_error)?;
+
+        Ok(Some(Authenticated::External(ExternalIdentity {
+            provider: PROVIDER.to_string(),
+            subject: self.subject(entry).map_err(backend_error)?,
+            email: attribute(entry, &self.config.email_attribute).map(str::to_string),
+            // Addresses in the directory are managed by its admins
+            email_verified: true,
+            username: username.map(str::to_string),
+            display_name: attribute(entry, &self.config.display_name_attribute).map(str::to_string),
+            is_admin,
+            link_by_email: self.config.link_by_email,
+            create_user: self.config.create_users,
+        })))
+    }
+}
+
+/// First value of an attribute; directories do not all return names in the case asked for.
+fn attribute<'a>(entry: &'a SearchEntry, name: &str) -> Option<&'a str> {
+    entry
+        .attrs
+        .iter()
+        .find(|(attr, _)| attr.eq_ignore_ascii_case(name))
+        .and_then(|(_, values)| values.first())
+        .map(String::as_str)
+        .filter(|value| !value.is_empty())
+}
+
+/// Replaces `{name}` placeholders with escaped values in one pass, so a value that
+/// happens to contain a placeholder is not expanded again.
+pub(crate) fn fill_filter(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filter = String::with_capacity(template.len());
+    let mut rest = template;
+    'outer: while let Some(start) = rest.find('{') {
+        filter.push_str(&rest[..start]);
+        rest = &rest[start..];
+        for (name, value) in values {
+            if let Some(after) = rest
+                .strip_prefix('{')
+                .and_then(|r| r.strip_prefix(name))
+                .and_then(|r| r.strip_prefix('}'))
+            {
+                filter.push_str(&ldap_escape(*value));
+                rest = after;
+                continue 'outer;
+            }
+        }
+        filter.push('{');
+        rest = &rest[1..];
+    }
+    filter.push_str(rest);
+    filter
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod in_memory {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// A directory of entries held in memory, understanding equality, presence, `&`, `|`
+    /// and `!` filters.
+    #[derive(Debug, Default)]
+    pub struct InMemoryDirectory {
+        entries: Mutex<Vec<(SearchEntry, String)>>,
+        unavailable: AtomicBool,
+    }
+
+    impl InMemoryDirectory {
+        pub fn add_entry(&self, dn: &str, password: &str, attrs: &[(&str, &[&str])]) {
+            let entry = SearchEntry {
+                dn: dn.to_string(),
+                attrs: attrs
+                    .iter()
+                    .map(|(name, values)| {
+                        (
+                            name.to_string(),
+                            values.iter().map(|v| v.to_string()).collect(),
+                        )
+                    })
+                    .collect(),
+                bin_attrs: HashMap::new(),
+            };
+            self.entries
+                .lock()
+                .unwrap()
+                .push((entry, password.to_string()));
+        }
+
+        /// Fail every operation as if the server were down.
+        pub fn set_unavailable(&self, unavailable: bool) {
+            self.unavailable.store(unavailable, Ordering::SeqCst);
+        }
+
+        fn check_available(&self) -> Result<(), DirectoryError> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err(DirectoryError("Connection refused".into()));
+            }
+            Ok(())
+        }
+    }
+
+    fn in_scope(dn: &str, base: &str, scope: Scope) -> bool {
+        let (dn, base) = (dn.to_lowercase(), base.to_lowercase());
+        match scope {
+            Scope::Base => dn == base,
+            Scope::OneLevel => dn.split_once(',').is_some_and(|(_, parent)| parent == base),
+            Scope::Subtree => base.is_empty() || dn == base || dn.ends_with(&format!(",{base}")),
+        }
+    }
+
+    /// Splits `(a)(b)(c)` into its parenthesised parts.
+    fn split_filters(list: &str) -> Vec<&str> {
+        let mut filters = Vec::new();
+        let (mut depth, mut start) = (0, 0);
+        for (i, c) in list.char_indices() {
+            match c {
+                '(' => {
+                    if depth == 0 {
+                        start = i;
+                    }
+                    depth += 1;
+                }
+                ')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        filters.push(&list[start..=i]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        filters
+    }
+
+    fn matches(entry: &SearchEntry, filter: &str) -> bool {
+        let filter = filter
+            .strip_prefix('(')
+            .and_then(|f| f.strip_suffix(')'))
+            .unwrap_or(filter);
+        if let Some(list) = filter.strip_prefix('&') {
+            return split_filters(list).iter().all(|f| matches(entry, f));
+        }
+        if let Some(list) = filter.strip_prefix('|') {
+            return split_filters(list).iter().any(|f| matches(entry, f));
+        }
+        if let Some(inner) = filter.strip_prefix('!') {
+            return !matches(entry, inner);
+        }
+        let Some((name, value)) = filter.split_once('=') else {
+            return false;
+        };
+        let values = entry
+            .attrs
+            .iter()
+            .filter(|(attr, _)| attr.eq_ignore_ascii_case(name))
+            .flat_map(|(_, values)| values);
+        match value {
+            "*" => values.count() > 0,
+            _ => values.into_iter().any(|v| v.eq_ignore_ascii_case(value)),
+        }
+    }
+
+    #[async_trait]
+    impl Directory for InMemoryDirectory {
+        async fn search(
+            &self,
+            base: &str,
+            scope: Scope,
+            filter: &str,
+            _attrs: &[String],
+        ) -> Result<Vec<SearchEntry>, DirectoryError> {
+            self.check_available()?;
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(entry, _)| entry)
+                .filter(|entry| in_scope(&entry.dn, base, scope) && matches(entry, filter))
+                .cloned()
+                .collect())
+        }
+
+        async fn bind(&self, dn: &str, password: &str) -> Result<bool, DirectoryError> {
+            self.check_available()?;
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .iter()
+                .any(|(entry, pw)| entry.dn.eq_ignore_ascii_case(dn) && pw == password))
+        }
+    }
+}
@@ -0,0 +1,163 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use confique::Config;
+
+    use crate::config::LdapConfig;
+    use crate::utils::backend::{Authenticated, AuthenticationBackend};
+    use crate::utils::ldap::{LdapBackend, fill_filter, in_memory::InMemoryDirectory};
+    use crate::utils::service::{AuthError, ExternalIdentity};
+
+    const ADMINS: &str = "cn=beam-admins,ou=groups,dc=example,dc=com";
+
+    fn config() -> LdapConfig {
+        let mut config = LdapConfig::builder().load().unwrap();
+        config.user_base_dn = "ou=people,dc=example,dc=com".into();
+        config
+    }
+
+    fn directory() -> Arc<InMemoryDirectory> {
+        let directory = Arc::new(InMemoryDirectory::default());
+        directory.add_entry(
+            "uid=alice,ou=people,dc=example,dc=com",
+            "directory-pw",
+            &[
+                ("uid", &["alice"]),
+                ("mail", &["alice@example.com"]),
+                ("cn", &["Alice Liddell"]),
+                ("entryUUID", &["6f1c-alice"]),
+                ("memberOf", &[ADMINS]),
+            ],
+        );
+        directory.add_entry(
+            "uid=bob,ou=people,dc=example,dc=com",
+            "bobs-pw",
+            &[("uid", &["bob"]), ("mail", &["bob@example.com"])],
+        );
+        directory
+    }
+
+    async fn identity(
+        backend: &LdapBackend,
+        username: &str,
+        password: &str,
+    ) -> Option<ExternalIdentity> {
+        match backend.authenticate(username, password).await.unwrap() {
+            Some(Authenticated::External(identity)) => Some(identity),
+            Some(Authenticated::Local(_)) => panic!("LDAP returned a local user"),
+            None => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn binds_as_the_user_found_by_username_or_email() {
+        let backend = LdapBackend::with_directory(config(), directory());
+
+        let alice = identity(&backend, "alice", "directory-pw").await.unwrap();
+        assert_eq!(alice.provider, "ldap");
+        assert_eq!(alice.subject, "uid=alice,ou=people,dc=example,dc=com");
+        assert_eq!(alice.email.as_deref(), Some("alice@example.com"));
+        assert!(alice.email_verified);
+        assert_eq!(alice.username.as_deref(), Some("alice"));
+        assert_eq!(alice.display_name.as_deref(), Some("Alice Liddell"));
+        assert_eq!(alice.is_admin, None);
+        assert!(alice.create_user && alice.link_by_email);
+
+        let by_email = identity(&backend, "alice@example.com", "directory-pw").await;
+        assert_eq!(by_email.unwrap().subject, alice.subject);
+    }
+
+    #[tokio::test]
+    async fn wrong_unknown_and_empty_passwords_are_not_accepted() {
+        let backend = LdapBackend::with_directory(config(), directory());
+
+        assert!(identity(&backend, "alice", "bobs-pw").await.is_none());
+        assert!(identity(&backend, "carol", "directory-pw").await.is_none());
+        // An empty password would be an anonymous bind, which directories allow
+        assert!(identity(&backend, "alice", "").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn usernames_cannot_widen_the_filter() {
+        let backend = LdapBackend::with_directory(config(), directory());
+
+        assert!(identity(&backend, "*", "directory-pw").await.is_none());
+        assert!(identity(&backend, "al*", "directory-pw").await.is_none());
+        assert_eq!(
+            fill_filter("(uid={username})", &[("username", "*)(uid=*")]),
+            "(uid=\\2a\\29\\28uid=\\2a)"
+        );
+    }
+
+    #[tokio::test]
+    async fn ambiguous_filters_match_nobody() {
+        let mut config = config();
+        config.user_filter = "(uid=*)".into();
+        let directory = directory();
+        let backend = LdapBackend::with_directory(config, directory);
+
+        assert!(identity(&backend, "alice", "directory-pw").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn admin_filter_is_checked_against_the_entry() {
+        let mut config = config();
+        config.admin_filter = Some(format!("(memberOf={ADMINS})"));
+        let backend = LdapBackend::with_directory(config, directory());
+
+        let alice = identity(&backend, "alice", "directory-pw").await.unwrap();
+        assert_eq!(alice.is_admin, Some(true));
+        let bob = identity(&backend, "bob", "bobs-pw").await.unwrap();
+        assert_eq!(bob.is_admin, Some(false));
+    }
+
+    #[tokio::test]
+    async fn admin_filter_can_search_groups_for_members() {
+        let directory = directory();
+        directory.add_entry(
+            ADMINS,
+            "",
+            &[
+                ("cn", &["beam-admins"]),
+                ("member", &["uid=bob,ou=people,dc=example,dc=com"]),
+            ],
+        );
+        let mut config = config();
+        config.admin_filter = Some("(&(cn=beam-admins)(member={dn}))".into());
+        config.admin_group_base_dn = Some("ou=groups,dc=example,dc=com".into());
+        let backend = LdapBackend::with_directory(config, directory);
+
+        let bob = identity(&backend, "bob", "bobs-pw").await.unwrap();
+        assert_eq!(bob.is_admin, Some(true));
+        let alice = identity(&backend, "alice", "directory-pw").await.unwrap();
+        assert_eq!(alice.is_admin, Some(false));
+    }
+
+    #[tokio::test]
+    async fn id_attribute_is_the_subject() {
+        let mut config = config();
+        config.id_attribute = Some("entryuuid".into());
+        let backend = LdapBackend::with_directory(config, directory());
+
+        let alice = identity(&backend, "alice", "directory-pw").await.unwrap();
+        assert_eq!(alice.subject, "6f1c-alice");
+        // Without the attribute there is nothing stable to link the account by
+        assert!(matches!(
+            backend.authenticate("bob", "bobs-pw").await,
+            Err(AuthError::Backend(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn unreachable_directory_is_an_error() {
+        let directory = directory();
+        directory.set_unavailable(true);
+        let backend = LdapBackend::with_directory(config(), directory);
+
+        assert!(matches!(
+            backend.authenticate("alice", "directory-pw").await,
+            Err(AuthError::Backend(_))
+        ));
+    }
+}
@@ -1,4 +1,12 @@
+pub mod admin_log;
+pub mod backend;
+pub mod device;
+pub mod keys;
+pub mod ldap;
 pub mod models;
+pub mod oidc;
+pub mod rate_limit;
 pub mod repository;
 pub mod service;
 pub mod session_store;
+pub mod totp;
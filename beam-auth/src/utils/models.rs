@@ -1,6 +1,127 @@
+use std::fmt;
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+/// Something a user is allowed to do. Roles grant sets of permissions.
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Permission {
+    /// Manage user accounts, roles and library access
+    ManageUsers,
+    /// Create, delete and edit libraries and their metadata
+    ManageLibraries,
+    /// Start library scans
+    TriggerScans,
+    /// Read the admin log
+    ViewLogs,
+    /// Download original media files
+    DownloadOriginals,
+    /// Stream media, remuxing or transcoding as needed
+    Transcode,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 6] = [
+        Permission::ManageUsers,
+        Permission::ManageLibraries,
+        Permission::TriggerScans,
+        Permission::ViewLogs,
+        Permission::DownloadOriginals,
+        Permission::Transcode,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::ManageUsers => "manage_users",
+            Permission::ManageLibraries => "manage_libraries",
+            Permission::TriggerScans => "trigger_scans",
+            Permission::ViewLogs => "view_logs",
+            Permission::DownloadOriginals => "download_originals",
+            Permission::Transcode => "transcode",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::ALL
+            .into_iter()
+            .find(|p| p.as_str() == s)
+            .ok_or_else(|| format!("unknown permission: {s}"))
+    }
+}
+
+/// Role assigned to a user. The permissions of each role are stored in the `roles` table.
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    LibraryManager,
+    #[default]
+    Viewer,
+    Guest,
+}
+
+impl Role {
+    pub const ALL: [Role; 4] = [Role::Admin, Role::LibraryManager, Role::Viewer, Role::Guest];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::LibraryManager => "library_manager",
+            Role::Viewer => "viewer",
+            Role::Guest => "guest",
+        }
+    }
+
+    /// Permissions the role is seeded with. The `roles` table is authoritative.
+    pub fn default_permissions(&self) -> Vec<Permission> {
+        use Permission::*;
+        match self {
+            Role::Admin => Permission::ALL.to_vec(),
+            Role::LibraryManager => vec![
+                ManageLibraries,
+                TriggerScans,
+                ViewLogs,
+                DownloadOriginals,
+                Transcode,
+            ],
+            Role::Viewer => vec![DownloadOriginals, Transcode],
+            Role::Guest => vec![Transcode],
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|r| r.as_str() == s)
+            .ok_or_else(|| format!("unknown role: {s}"))
+    }
+}
+
 /// Represents a user in the system.
 #[derive(Debug, Clone)]
 pub struct User {
@@ -8,18 +129,58 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password_hash: String,
-    pub is_admin: bool,
+    pub role: Role,
+    /// Set while an admin has disabled the account
+    pub disabled_at: Option<DateTime<Utc>>,
+    /// The user must choose a new password after their next login
+    pub password_reset_required: bool,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    /// Encrypted TOTP secret; pending until `totp_enabled_at` is set
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTime<Utc>>,
+    /// SHA-256 hashes of the unused recovery codes
+    pub totp_recovery_codes: Vec<String>,
+    /// Time step of the last accepted TOTP code; codes from it or earlier are refused
+    pub totp_last_step: Option<i64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl User {
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    /// Whether logging in requires a second factor.
+    pub fn mfa_enabled(&self) -> bool {
+        self.totp_enabled_at.is_some()
+    }
+}
+
+/// Changes to apply to a user. `None` leaves a field as it is.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateUser {
+    pub email: Option<String>,
+    pub password_hash: Option<String>,
+    pub password_reset_required: Option<bool>,
+    /// `Some(None)` clears the display name
+    pub display_name: Option<Option<String>>,
+    /// `Some(None)` clears the avatar
+    pub avatar_url: Option<Option<String>>,
+    /// `Some(None)` removes the TOTP secret
+    pub totp_secret: Option<Option<String>>,
+    pub totp_enabled_at: Option<Option<DateTime<Utc>>>,
+    pub totp_recovery_codes: Option<Vec<String>>,
+}
+
 /// Parameters for creating a new user.
 #[derive(Debug, Clone)]
 pub struct CreateUser {
     pub username: String,
     pub email: String,
     pub password_hash: String,
-    pub is_admin: bool,
+    pub role: Role,
 }
 
 impl From<beam_entity::user::Model> for User {
@@ -29,9 +190,149 @@ impl From<beam_entity::user::Model> for User {
             username: model.username,
             email: model.email,
             password_hash: model.password_hash,
-            is_admin: model.is_admin,
+            // Unknown roles get the least privileged one
+            role: model.role.parse().unwrap_or(Role::Guest),
+            disabled_at: model.disabled_at.map(Into::into),
+            password_reset_required: model.password_reset_required,
+            display_name: model.display_name,
+            avatar_url: model.avatar_url,
+            totp_secret: model.totp_secret,
+            totp_enabled_at: model.totp_enabled_at.map(Into::into),
+            totp_recovery_codes: model.totp_recovery_codes,
+            totp_last_step: model.totp_last_step,
             created_at: model.created_at.into(),
             updated_at: model.updated_at.into(),
         }
     }
 }
+
+/// A single-use code that lets someone register while open registration is off.
+#[derive(Debug, Clone)]
+pub struct Invite {
+    pub code: String,
+    /// Role given to the account registered with this invite
+    pub role: Role,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub used_by: Option<Uuid>,
+    pub used_at: Option<DateTime<Utc>>,
+}
+
+impl Invite {
+    /// Whether the invite can still be used to register.
+    pub fn is_redeemable(&self, now: DateTime<Utc>) -> bool {
+        self.used_at.is_none() && self.expires_at.is_none_or(|expires| expires > now)
+    }
+}
+
+impl From<beam_entity::invite::Model> for Invite {
+    fn from(model: beam_entity::invite::Model) -> Self {
+        Self {
+            code: model.code,
+            role: model.role.parse().unwrap_or(Role::Guest),
+            created_by: model.created_by,
+            created_at: model.created_at.into(),
+            expires_at: model.expires_at.map(Into::into),
+            used_by: model.used_by,
+            used_at: model.used_at.map(Into::into),
+        }
+    }
+}
+
+/// What an API key may be used for. A key never grants more than its user's role does.
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyScope {
+    /// Browse the libraries the user can see, without changing anything
+    ReadOnly,
+    /// Play and download media
+    Stream,
+    /// Manage users, libraries and scans and read the admin log
+    Admin,
+}
+
+impl ApiKeyScope {
+    pub const ALL: [ApiKeyScope; 3] = [
+        ApiKeyScope::ReadOnly,
+        ApiKeyScope::Stream,
+        ApiKeyScope::Admin,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::ReadOnly => "read_only",
+            ApiKeyScope::Stream => "stream",
+            ApiKeyScope::Admin => "admin",
+        }
+    }
+
+    /// Permissions the scope allows the key to use, if the user has them.
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            ApiKeyScope::ReadOnly => &[],
+            ApiKeyScope::Stream => &[Transcode, DownloadOriginals],
+            ApiKeyScope::Admin => &[ManageUsers, ManageLibraries, TriggerScans, ViewLogs],
+        }
+    }
+}
+
+impl fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ApiKeyScope::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("unknown API key scope: {s}"))
+    }
+}
+
+/// A long-lived key a user's scripts and devices authenticate with instead of logging in.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// First characters of the key, shown to tell keys apart
+    pub prefix: String,
+    /// SHA-256 hash of the key; the key itself is only shown when it is created
+    pub key_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+/// Parameters for creating an API key.
+#[derive(Debug, Clone)]
+pub struct CreateApiKey {
+    pub user_id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<ApiKeyScope>,
+}
+
+impl From<beam_entity::api_key::Model> for ApiKey {
+    fn from(model: beam_entity::api_key::Model) -> Self {
+        Self {
+            id: model.id,
+            user_id: model.user_id,
+            name: model.name,
+            prefix: model.prefix,
+            key_hash: model.key_hash,
+            // Unknown scopes grant nothing
+            scopes: model.scopes.iter().filter_map(|s| s.parse().ok()).collect(),
+            created_at: model.created_at.into(),
+            last_used_at: model.last_used_at.map(Into::into),
+        }
+    }
+}
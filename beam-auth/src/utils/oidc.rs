@@ -0,0 +1,494 @@
+#[cfg(test)]
+#[path = "oidc_tests.rs"]
+mod oidc_tests;
+
+use std::time::Duration;
+
+use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use jsonwebtoken::{Algorithm, Validation, decode_header};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::sync::OnceCell;
+use tracing::debug;
+
+use crate::config::OidcProviderConfig;
+use crate::utils::keys::{JwkSet, KeyError, TokenKeys, TokenKind};
+use crate::utils::service::ExternalIdentity;
+
+/// How long a user has to finish logging in at the provider
+const LOGIN_STATE_TTL_MINUTES: i64 = 10;
+
+/// Timeout of each request to a provider
+const PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum OidcError {
+    #[error("Unknown identity provider")]
+    UnknownProvider,
+    #[error("Identity provider request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid identity provider response: {0}")]
+    InvalidResponse(String),
+    /// The login cookie is missing, expired, or was issued for another login
+    #[error("Login expired or was started elsewhere")]
+    InvalidState,
+    #[error("Identity provider refused the login: {0}")]
+    Denied(String),
+    #[error("Invalid ID token: {0}")]
+    InvalidIdToken(String),
+    #[error("Signing key error: {0}")]
+    Keys(#[from] KeyError),
+}
+
+type Result<T> = std::result::Result<T, OidcError>;
+
+/// The parts of a provider's `/.well-known/openid-configuration` used here
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub userinfo_endpoint: Option<String>,
+    /// Client authentication methods; `client_secret_basic` is assumed when empty
+    #[serde(default)]
+    pub token_endpoint_auth_methods_supported: Vec<String>,
+}
+
+impl ProviderMetadata {
+    /// Whether the client secret has to go in the request body rather than a basic
+    /// `Authorization` header.
+    fn wants_secret_in_body(&self) -> bool {
+        let methods = &self.token_endpoint_auth_methods_supported;
+        !methods.is_empty()
+            && !methods.iter().any(|m| m == "client_secret_basic")
+            && methods.iter().any(|m| m == "client_secret_post")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+/// State of a login in progress, kept in a signed cookie between the redirect to the
+/// provider and the callback
+#[derive(Debug, Serialize, Deserialize)]
+struct LoginStateClaims {
+    /// ID of the provider the login was started with
+    sub: String,
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    redirect_to: String,
+    exp: usize,
+}
+
+/// Where to send the browser to log in, and the state to keep until it comes back
+#[derive(Debug)]
+pub struct LoginStart {
+    pub authorization_url: String,
+    /// Opaque value for [`OidcService::complete_login`]
+    pub login_state: String,
+}
+
+/// A login the provider has vouched for
+#[derive(Debug)]
+pub struct CompletedLogin {
+    pub identity: ExternalIdentity,
+    /// Local path to return to, as passed to [`OidcService::begin_login`]
+    pub redirect_to: String,
+}
+
+/// One configured OpenID Connect provider.
+///
+/// Its metadata is discovered on first use, so beam-auth starts even while the provider
+/// is down. Signing keys are fetched again whenever an ID token names an unknown one.
+#[derive(Debug)]
+pub struct OidcProvider {
+    config: OidcProviderConfig,
+    redirect_uri: String,
+    client: reqwest::Client,
+    metadata: OnceCell<ProviderMetadata>,
+    keys: TokenKeys,
+}
+
+impl OidcProvider {
+    pub fn new(config: OidcProviderConfig, redirect_uri: String) -> Self {
+        Self {
+            config,
+            redirect_uri,
+            client: reqwest::Client::builder()
+                .timeout(PROVIDER_TIMEOUT)
+                .build()
+                .expect("HTTP client configuration is valid"),
+            metadata: OnceCell::new(),
+            keys: TokenKeys::from_jwks(JwkSet { keys: Vec::new() }).expect("empty JWKS"),
+        }
+    }
+
+    pub fn config(&self) -> &OidcProviderConfig {
+        &self.config
+    }
+
+    /// The callback URL to register at the provider.
+    pub fn redirect_uri(&self) -> &str {
+        &self.redirect_uri
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        Ok(self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// The provider's metadata, fetched from its discovery document on first use.
+    pub async fn metadata(&self) -> Result<&ProviderMetadata> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+                let metadata: ProviderMetadata = self.get_json(&url).await?;
+                if metadata.issuer.trim_end_matches('/') != self.config.issuer.trim_end_matches('/')
+                {
+                    return Err(OidcError::InvalidResponse(format!(
+                        "discovery document is for issuer {}",
+                        metadata.issuer
+                    )));
+                }
+                debug!("Discovered OpenID Connect provider {}", metadata.issuer);
+                Ok(metadata)
+            })
+            .await
+    }
+
+    fn authorization_url(
+        &self,
+        metadata: &ProviderMetadata,
+        state: &str,
+        nonce: &str,
+        code_challenge: &str,
+    ) -> Result<String> {
+        let mut url = Url::parse(&metadata.authorization_endpoint)
+            .map_err(|e| OidcError::InvalidResponse(format!("authorization endpoint: {e}")))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.config.client_id)
+            .append_pair("redirect_uri", &self.redirect_uri)
+            .append_pair("scope", &self.config.scopes.join(" "))
+            .append_pair("state", state)
+            .append_pair("nonce", nonce)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("code_challenge_method", "S256");
+        Ok(url.into())
+    }
+
+    /// Redeems an authorization code, proving with the PKCE verifier that this server
+    /// started the login.
+    async fn exchange_code(&self, code: &str, code_verifier: &str) -> Result<TokenResponse> {
+        let metadata = self.metadata().await?;
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &self.redirect_uri),
+            ("code_verifier", code_verifier),
+        ];
+        let mut request = self.client.post(&metadata.token_endpoint);
+        match &self.config.client_secret {
+            None => form.push(("client_id", &self.config.client_id)),
+            Some(secret) if metadata.wants_secret_in_body() => {
+                form.push(("client_id", &self.config.client_id));
+                form.push(("client_secret", secret));
+            }
+            Some(secret) => {
+                request = request.basic_auth(
+                    form_encode(&self.config.client_id),
+                    Some(form_encode(secret)),
+                );
+            }
+        }
+
+        let response = request.form(&form).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Denied(format!(
+                "token endpoint returned {status}: {body}"
+            )));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Checks an ID token's signature, issuer, audience, expiry and nonce, and returns its
+    /// claims.
+    async fn verify_id_token(&self, id_token: &str, nonce: &str) -> Result<Map<String, Value>> {
+        let metadata = self.metadata().await?;
+        let header =
+            decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+        if header
+            .kid
+            .as_deref()
+            .is_none_or(|kid| !self.keys.has_key(kid))
+        {
+            // New or rotated key, or the first login since startup
+            let jwks: JwkSet = self.get_json(&metadata.jwks_uri).await?;
+            self.keys.set_jwks(jwks)?;
+        }
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_required_spec_claims(&["exp", "sub", "iss", "aud"]);
+        validation.set_issuer(&[&metadata.issuer]);
+        validation.set_audience(&[&self.config.client_id]);
+        let claims: Map<String, Value> = self
+            .keys
+            .verify_external(id_token, validation)
+            .map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+
+        if claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            return Err(OidcError::InvalidIdToken("nonce does not match".into()));
+        }
+        if let Some(azp) = claims.get("azp").and_then(Value::as_str)
+            && azp != self.config.client_id
+        {
+            return Err(OidcError::InvalidIdToken(format!(
+                "issued to another client: {azp}"
+            )));
+        }
+        Ok(claims)
+    }
+
+    /// Adds claims from the userinfo endpoint that the ID token left out.
+    async fn add_userinfo(
+        &self,
+        claims: &mut Map<String, Value>,
+        access_token: &str,
+    ) -> Result<()> {
+        let Some(endpoint) = &self.metadata().await?.userinfo_endpoint else {
+            return Ok(());
+        };
+        let userinfo: Map<String, Value> = self
+            .client
+            .get(endpoint)
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if userinfo.get("sub") != claims.get("sub") {
+            return Err(OidcError::InvalidResponse(
+                "userinfo is for another subject".into(),
+            ));
+        }
+        for (name, value) in userinfo {
+            claims.entry(name).or_insert(value);
+        }
+        Ok(())
+    }
+
+    /// Whether the ID token lacks claims that are needed and may be found in userinfo.
+    fn missing_claims(&self, claims: &Map<String, Value>) -> bool {
+        !claims.contains_key("email")
+            || (self.config.admin_group.is_some()
+                && !claims.contains_key(&self.config.groups_claim))
+    }
+
+    fn identity(&self, claims: &Map<String, Value>) -> Result<ExternalIdentity> {
+        let string = |name: &str| {
+            claims
+                .get(name)
+                .and_then(Value::as_str)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let subject =
+            string("sub").ok_or_else(|| OidcError::InvalidIdToken("no subject".into()))?;
+
+        // Some providers send booleans as strings
+        let email_verified = match claims.get("email_verified") {
+            Some(Value::Bool(verified)) => *verified,
+            Some(Value::String(verified)) => verified == "true",
+            _ => false,
+        };
+
+        let is_admin = self.config.admin_group.as_ref().map(|admin_group| {
+            match claims.get(&self.config.groups_claim) {
+                Some(Value::Array(groups)) => {
+                    groups.iter().any(|g| g.as_str() == Some(admin_group))
+                }
+                Some(Value::String(group)) => group == admin_group,
+                _ => false,
+            }
+        });
+
+        Ok(ExternalIdentity {
+            provider: self.config.id.clone(),
+            subject,
+            email: string("email"),
+            email_verified,
+            username: string("preferred_username"),
+            display_name: string("name"),
+            is_admin,
+            link_by_email: self.config.link_by_email,
+            create_user: self.config.create_users,
+        })
+    }
+}
+
+/// Logs users in with the configured OpenID Connect providers using the authorization
+/// code flow with PKCE.
+///
+/// The state of a login in progress travels in a token signed with the JWT secret, so no
+/// server-side storage is needed between the redirect and the callback.
+#[derive(Debug)]
+pub struct OidcService {
+    providers: Vec<OidcProvider>,
+    login_keys: TokenKeys,
+}
+
+impl OidcService {
+    /// `base_url` is the public URL the OIDC routes are mounted at, e.g.
+    /// `https://beam.example.com/v1/auth/oidc`; providers redirect back to
+    /// `{base_url}/{id}/callback`.
+    pub fn new(configs: Vec<OidcProviderConfig>, jwt_secret: &str, base_url: &str) -> Self {
+        let base_url = base_url.trim_end_matches('/');
+        Self {
+            providers: configs
+                .into_iter()
+                .map(|config| {
+                    let redirect_uri = format!("{base_url}/{}/callback", config.id);
+                    OidcProvider::new(config, redirect_uri)
+                })
+                .collect(),
+            login_keys: TokenKeys::hmac(jwt_secret),
+        }
+    }
+
+    pub fn providers(&self) -> &[OidcProvider] {
+        &self.providers
+    }
+
+    pub fn provider(&self, id: &str) -> Result<&OidcProvider> {
+        self.providers
+            .iter()
+            .find(|p| p.config.id == id)
+            .ok_or(OidcError::UnknownProvider)
+    }
+
+    /// Starts a login with a provider, returning to the local path `redirect_to` after.
+    ///
+    /// Anything but a path on this site is replaced with `/`, so the login cannot be used
+    /// to redirect elsewhere.
+    pub async fn begin_login(&self, provider_id: &str, redirect_to: &str) -> Result<LoginStart> {
+        let provider = self.provider(provider_id)?;
+        let metadata = provider.metadata().await?;
+
+        let state = random_token(16);
+        let nonce = random_token(16);
+        let code_verifier = random_token(32);
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        let authorization_url =
+            provider.authorization_url(metadata, &state, &nonce, &code_challenge)?;
+        let expiration = Utc::now()
+            .checked_add_signed(chrono::Duration::minutes(LOGIN_STATE_TTL_MINUTES))
+            .expect("valid timestamp")
+            .timestamp() as usize;
+        let login_state = self.login_keys.sign(
+            TokenKind::OidcLogin,
+            &LoginStateClaims {
+                sub: provider_id.to_string(),
+                state,
+                nonce,
+                code_verifier,
+                redirect_to: local_path(redirect_to).to_string(),
+                exp: expiration,
+            },
+        )?;
+
+        Ok(LoginStart {
+            authorization_url,
+            login_state,
+        })
+    }
+
+    /// Finishes a login when the provider redirects back with `code` and `state`.
+    pub async fn complete_login(
+        &self,
+        provider_id: &str,
+        login_state: &str,
+        code: &str,
+        state: &str,
+    ) -> Result<CompletedLogin> {
+        let provider = self.provider(provider_id)?;
+        let login = self
+            .login_keys
+            .verify::<LoginStateClaims>(TokenKind::OidcLogin, login_state)
+            .map_err(|_| OidcError::InvalidState)?;
+        if login.sub != provider_id || login.state != state {
+            return Err(OidcError::InvalidState);
+        }
+
+        let tokens = provider.exchange_code(code, &login.code_verifier).await?;
+        let id_token = tokens.id_token.ok_or_else(|| {
+            OidcError::InvalidResponse("no ID token; is `openid` in the scopes?".into())
+        })?;
+        let mut claims = provider.verify_id_token(&id_token, &login.nonce).await?;
+        if provider.missing_claims(&claims) {
+            provider
+                .add_userinfo(&mut claims, &tokens.access_token)
+                .await?;
+        }
+
+        Ok(CompletedLogin {
+            identity: provider.identity(&claims)?,
+            redirect_to: login.redirect_to,
+        })
+    }
+}
+
+/// `path` if it is a path on this site, otherwise `/`.
+fn local_path(path: &str) -> &str {
+    if path.starts_with('/') && !path.starts_with("//") && !path.starts_with("/\\") {
+        path
+    } else {
+        "/"
+    }
+}
+
+/// URL-safe random string for state, nonce and PKCE verifier.
+fn random_token(bytes: usize) -> String {
+    use rand::Rng;
+
+    let mut buf = vec![0u8; bytes];
+    rand::rng().fill_bytes(&mut buf);
+    URL_SAFE_NO_PAD.encode(buf)
+}
+
+/// `application/x-www-form-urlencoded` encoding, which client credentials get before going
+/// into a basic `Authorization` header (RFC 6749, section 2.3.1).
+fn form_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'*' => {
+                (b as char).to_string()
+            }
+            b' ' => "+".to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
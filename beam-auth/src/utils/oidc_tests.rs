@@ -0,0 +1,126 @@
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value, json};
+
+    use crate::config::OidcProviderConfig;
+    use crate::utils::oidc::{OidcError, OidcProvider, OidcService, form_encode, local_path};
+
+    fn config(extra: Value) -> OidcProviderConfig {
+        let mut config = json!({
+            "id": "idp",
+            "name": "IdP",
+            "issuer": "https://idp.example.com",
+            "client_id": "beam",
+        });
+        config
+            .as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(config).unwrap()
+    }
+
+    fn claims(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn provider_config_defaults() {
+        let config = config(json!({}));
+        assert_eq!(config.scopes, ["openid", "email", "profile"]);
+        assert_eq!(config.groups_claim, "groups");
+        assert!(config.client_secret.is_none());
+        assert!(config.admin_group.is_none());
+        assert!(config.link_by_email);
+        assert!(config.create_users);
+    }
+
+    #[test]
+    fn redirect_uri_is_derived_from_base_url() {
+        let oidc = OidcService::new(
+            vec![config(json!({}))],
+            "secret",
+            "https://beam.example.com/v1/auth/oidc/",
+        );
+        assert_eq!(
+            oidc.provider("idp").unwrap().redirect_uri(),
+            "https://beam.example.com/v1/auth/oidc/idp/callback"
+        );
+        assert!(matches!(
+            oidc.provider("other"),
+            Err(OidcError::UnknownProvider)
+        ));
+    }
+
+    #[test]
+    fn identity_maps_standard_claims() {
+        let provider = OidcProvider::new(config(json!({})), String::new());
+        let identity = provider
+            .identity(&claims(json!({
+                "sub": "1234",
+                "email": "alice@example.com",
+                // Some providers send booleans as strings
+                "email_verified": "true",
+                "preferred_username": "alice",
+                "name": "Alice",
+            })))
+            .unwrap();
+        assert_eq!(identity.provider, "idp");
+        assert_eq!(identity.subject, "1234");
+        assert_eq!(identity.email.as_deref(), Some("alice@example.com"));
+        assert!(identity.email_verified);
+        assert_eq!(identity.username.as_deref(), Some("alice"));
+        assert_eq!(identity.display_name.as_deref(), Some("Alice"));
+        assert_eq!(identity.is_admin, None);
+
+        let unverified = provider
+            .identity(&claims(
+                json!({ "sub": "1234", "email": "alice@example.com" }),
+            ))
+            .unwrap();
+        assert!(!unverified.email_verified);
+    }
+
+    #[test]
+    fn identity_maps_admin_group_from_custom_claim() {
+        let provider = OidcProvider::new(
+            config(json!({ "admin_group": "beam-admins", "groups_claim": "roles" })),
+            String::new(),
+        );
+        let admin = |roles: Value| {
+            provider
+                .identity(&claims(json!({ "sub": "1", "roles": roles })))
+                .unwrap()
+                .is_admin
+        };
+        assert_eq!(admin(json!(["users", "beam-admins"])), Some(true));
+        assert_eq!(admin(json!("beam-admins")), Some(true));
+        assert_eq!(admin(json!(["users"])), Some(false));
+        assert_eq!(admin(Value::Null), Some(false));
+    }
+
+    #[test]
+    fn identity_requires_subject() {
+        let provider = OidcProvider::new(config(json!({})), String::new());
+        assert!(
+            provider
+                .identity(&claims(json!({ "email": "a@example.com" })))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn only_local_paths_are_kept_as_redirect_targets() {
+        assert_eq!(local_path("/library/1"), "/library/1");
+        assert_eq!(local_path("/"), "/");
+        assert_eq!(local_path("//evil.example.com"), "/");
+        assert_eq!(local_path("/\\evil.example.com"), "/");
+        assert_eq!(local_path("https://evil.example.com"), "/");
+        assert_eq!(local_path(""), "/");
+    }
+
+    #[test]
+    fn client_credentials_are_form_encoded() {
+        assert_eq!(form_encode("beam-client_1.x"), "beam-client_1.x");
+        assert_eq!(form_encode("a b/c+d:é"), "a+b%2Fc%2Bd%3A%C3%A9");
+    }
+}
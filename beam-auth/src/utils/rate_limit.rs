@@ -0,0 +1,350 @@
+#[cfg(test)]
+#[path = "rate_limit_tests.rs"]
+mod rate_limit_tests;
+
+use async_trait::async_trait;
+use bb8_redis::RedisConnectionManager;
+use bb8_redis::bb8::{Pool, PooledConnection};
+use chrono::{Duration, Utc};
+use redis::AsyncCommands;
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    sea_query::{Expr, OnConflict},
+};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+use crate::utils::session_store::SWEEP_INTERVAL;
+
+#[derive(Debug, Error)]
+pub enum RateLimitError {
+    #[error("Redis error: {0}")]
+    Redis(#[from] redis::RedisError),
+    #[error("Connection pool error: {0}")]
+    Pool(#[from] bb8_redis::bb8::RunError<redis::RedisError>),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+type Result<T> = std::result::Result<T, RateLimitError>;
+
+/// How a rate-limited key is slowed down after repeated login failures.
+///
+/// The first `free_attempts` failures cost nothing. Each further failure blocks the key
+/// for twice as long as the previous one, until `max_failures` locks it out for
+/// `lockout_secs`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    pub free_attempts: u32,
+    pub max_failures: u32,
+    pub lockout_secs: u64,
+    /// Failures are forgotten after this long without a new one
+    pub window_secs: u64,
+}
+
+impl LockoutPolicy {
+    /// Applied per account, whichever address the attempts come from.
+    pub const ACCOUNT: Self = Self {
+        free_attempts: 3,
+        max_failures: 10,
+        lockout_secs: 15 * 60,
+        window_secs: 60 * 60,
+    };
+
+    /// Applied per client IP. Looser, since many users may share one address.
+    pub const IP: Self = Self {
+        free_attempts: 10,
+        max_failures: 50,
+        lockout_secs: 60 * 60,
+        window_secs: 60 * 60,
+    };
+
+    /// Seconds the key is blocked after its `failures`-th failure, if at all.
+    pub fn delay_after(&self, failures: u32) -> Option<u64> {
+        if failures >= self.max_failures {
+            return Some(self.lockout_secs);
+        }
+        if failures <= self.free_attempts {
+            return None;
+        }
+        let doublings = (failures - self.free_attempts - 1).min(32);
+        Some((1u64 << doublings).min(self.lockout_secs))
+    }
+
+    /// Whether the `failures`-th failure triggers a full lockout.
+    pub fn is_lockout(&self, failures: u32) -> bool {
+        failures >= self.max_failures
+    }
+}
+
+/// Counts failed logins per key (an IP or an account) and blocks keys for a while.
+///
+/// The store only keeps counters and blocks; [`LockoutPolicy`] decides how long to block.
+#[async_trait]
+pub trait LoginRateLimiter: Send + Sync + std::fmt::Debug {
+    /// Seconds until `key` may try again, or `None` if it is not blocked.
+    async fn blocked_for(&self, key: &str) -> Result<Option<u64>>;
+
+    /// Counts a failed attempt for `key` and returns its failures within the window.
+    ///
+    /// The count resets once `window_secs` pass without a failure.
+    async fn record_failure(&self, key: &str, window_secs: u64) -> Result<u32>;
+
+    /// Blocks `key` for `secs` seconds.
+    async fn block(&self, key: &str, secs: u64) -> Result<()>;
+
+    /// Forgets the failures and any block for `key`.
+    async fn reset(&self, key: &str) -> Result<()>;
+}
+
+#[derive(Debug)]
+pub struct RedisLoginRateLimiter {
+    pool: Pool<RedisConnectionManager>,
+}
+
+impl RedisLoginRateLimiter {
+    /// Shares the connection pool of the session store.
+    pub fn new(pool: Pool<RedisConnectionManager>) -> Self {
+        Self { pool }
+    }
+
+    async fn get_conn(&self) -> Result<PooledConnection<'_, RedisConnectionManager>> {
+        self.pool.get().await.map_err(RateLimitError::Pool)
+    }
+
+    fn failures_key(key: &str) -> String {
+        format!("login_failures:{}", key)
+    }
+
+    fn blocked_key(key: &str) -> String {
+        format!("login_blocked:{}", key)
+    }
+}
+
+#[async_trait]
+impl LoginRateLimiter for RedisLoginRateLimiter {
+    async fn blocked_for(&self, key: &str) -> Result<Option<u64>> {
+        let mut conn = self.get_conn().await?;
+
+        // TTL is negative when the key is missing or has no expiry
+        let ttl: i64 = conn.ttl(Self::blocked_key(key)).await?;
+        Ok((ttl > 0).then_some(ttl as u64))
+    }
+
+    async fn record_failure(&self, key: &str, window_secs: u64) -> Result<u32> {
+        let failures_key = Self::failures_key(key);
+        let mut conn = self.get_conn().await?;
+
+        let (failures, _): (u32, bool) = redis::pipe()
+            .atomic()
+            .incr(&failures_key, 1)
+            .expire(&failures_key, window_secs as i64)
+            .query_async(&mut *conn)
+            .await?;
+        Ok(failures)
+    }
+
+    async fn block(&self, key: &str, secs: u64) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+
+        let _: () = conn.set_ex(Self::blocked_key(key), 1, secs).await?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> Result<()> {
+        let mut conn = self.get_conn().await?;
+
+        let _: () = conn
+            .del(&[Self::failures_key(key), Self::blocked_key(key)])
+            .await?;
+        Ok(())
+    }
+}
+
+/// Keeps login throttling in the main PostgreSQL database, next to
+/// [`SqlSessionStore`](crate::utils::session_store::SqlSessionStore).
+#[derive(Debug)]
+pub struct SqlLoginRateLimiter {
+    db: DatabaseConnection,
+}
+
+impl SqlLoginRateLimiter {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Deletes throttles whose failures are forgotten and that block nothing, and returns
+    /// how many there were.
+    pub async fn sweep_expired(&self) -> Result<u64> {
+        use beam_entity::login_throttle;
+
+        let now = Utc::now();
+        let result = login_throttle::Entity::delete_many()
+            .filter(login_throttle::Column::FailuresExpireAt.lte(now))
+            .filter(
+                Condition::any()
+                    .add(login_throttle::Column::BlockedUntil.is_null())
+                    .add(login_throttle::Column::BlockedUntil.lte(now)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Runs [`sweep_expired`](Self::sweep_expired) every [`SWEEP_INTERVAL`] in the
+    /// background.
+    pub fn spawn_sweeper(self: &Arc<Self>) {
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match limiter.sweep_expired().await {
+                    Ok(0) => {}
+                    Ok(swept) => debug!("Swept {} expired login throttles", swept),
+                    Err(e) => warn!("Failed to sweep expired login throttles: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl LoginRateLimiter for SqlLoginRateLimiter {
+    async fn blocked_for(&self, key: &str) -> Result<Option<u64>> {
+        use beam_entity::login_throttle;
+
+        let throttle = login_throttle::Entity::find_by_id(key)
+            .one(&self.db)
+            .await?;
+        let now = Utc::now();
+        Ok(throttle
+            .and_then(|t| t.blocked_until)
+            .map(|until| (until.with_timezone(&Utc) - now).num_seconds())
+            .filter(|secs| *secs > 0)
+            .map(|secs| secs as u64))
+    }
+
+    async fn record_failure(&self, key: &str, window_secs: u64) -> Result<u32> {
+        use beam_entity::login_throttle::{self, Column};
+
+        let now = Utc::now();
+        let model = login_throttle::ActiveModel {
+            key: Set(key.to_string()),
+            failures: Set(1),
+            failures_expire_at: Set((now + Duration::seconds(window_secs as i64)).into()),
+            blocked_until: Set(None),
+        };
+        // Count on from the stored failures unless they were already forgotten
+        let failures = Expr::case(
+            Expr::col((login_throttle::Entity, Column::FailuresExpireAt)).lte(now),
+            1,
+        )
+        .finally(Expr::col((login_throttle::Entity, Column::Failures)).add(1));
+        let throttle = login_throttle::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(Column::Key)
+                    .value(Column::Failures, failures)
+                    .update_column(Column::FailuresExpireAt)
+                    .to_owned(),
+            )
+            .exec_with_returning(&self.db)
+            .await?;
+        Ok(throttle.failures.max(0) as u32)
+    }
+
+    async fn block(&self, key: &str, secs: u64) -> Result<()> {
+        use beam_entity::login_throttle::{self, Column};
+
+        let now = Utc::now();
+        let model = login_throttle::ActiveModel {
+            key: Set(key.to_string()),
+            failures: Set(0),
+            failures_expire_at: Set(now.into()),
+            blocked_until: Set(Some((now + Duration::seconds(secs as i64)).into())),
+        };
+        login_throttle::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(Column::Key)
+                    .update_column(Column::BlockedUntil)
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> Result<()> {
+        use beam_entity::login_throttle;
+
+        login_throttle::Entity::delete_by_id(key)
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+}
+
+/// In-memory rate limiter for use in tests.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod in_memory {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default, Clone, Copy)]
+    struct Entry {
+        failures: u32,
+        failures_expire_at: i64,
+        blocked_until: i64,
+    }
+
+    #[derive(Debug, Default)]
+    pub struct InMemoryLoginRateLimiter {
+        entries: Mutex<HashMap<String, Entry>>,
+    }
+
+    #[async_trait]
+    impl LoginRateLimiter for InMemoryLoginRateLimiter {
+        async fn blocked_for(&self, key: &str) -> Result<Option<u64>> {
+            let now = Utc::now().timestamp();
+            Ok(self
+                .entries
+                .lock()
+                .unwrap()
+                .get(key)
+                .filter(|entry| entry.blocked_until > now)
+                .map(|entry| (entry.blocked_until - now) as u64))
+        }
+
+        async fn record_failure(&self, key: &str, window_secs: u64) -> Result<u32> {
+            let now = Utc::now().timestamp();
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries.entry(key.to_string()).or_default();
+            if entry.failures_expire_at <= now {
+                entry.failures = 0;
+            }
+            entry.failures += 1;
+            entry.failures_expire_at = now + window_secs as i64;
+            Ok(entry.failures)
+        }
+
+        async fn block(&self, key: &str, secs: u64) -> Result<()> {
+            let now = Utc::now().timestamp();
+            self.entries
+                .lock()
+                .unwrap()
+                .entry(key.to_string())
+                .or_default()
+                .blocked_until = now + secs as i64;
+            Ok(())
+        }
+
+        async fn reset(&self, key: &str) -> Result<()> {
+            self.entries.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,48 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::rate_limit::in_memory::InMemoryLoginRateLimiter;
+    use crate::utils::rate_limit::{LockoutPolicy, LoginRateLimiter};
+
+    #[test]
+    fn delay_doubles_after_free_attempts_then_locks_out() {
+        let policy = LockoutPolicy::ACCOUNT;
+
+        assert_eq!(policy.delay_after(1), None);
+        assert_eq!(policy.delay_after(3), None);
+        assert_eq!(policy.delay_after(4), Some(1));
+        assert_eq!(policy.delay_after(5), Some(2));
+        assert_eq!(policy.delay_after(9), Some(32));
+        assert_eq!(policy.delay_after(10), Some(policy.lockout_secs));
+        assert!(!policy.is_lockout(9));
+        assert!(policy.is_lockout(10));
+    }
+
+    #[test]
+    fn delay_never_exceeds_lockout() {
+        let policy = LockoutPolicy {
+            free_attempts: 0,
+            max_failures: 100,
+            lockout_secs: 60,
+            window_secs: 60,
+        };
+        assert_eq!(policy.delay_after(99), Some(60));
+    }
+
+    #[tokio::test]
+    async fn in_memory_limiter_counts_blocks_and_resets() {
+        let limiter = InMemoryLoginRateLimiter::default();
+
+        assert_eq!(limiter.record_failure("ip:1", 60).await.unwrap(), 1);
+        assert_eq!(limiter.record_failure("ip:1", 60).await.unwrap(), 2);
+        assert_eq!(limiter.record_failure("ip:2", 60).await.unwrap(), 1);
+        assert_eq!(limiter.blocked_for("ip:1").await.unwrap(), None);
+
+        limiter.block("ip:1", 30).await.unwrap();
+        let blocked = limiter.blocked_for("ip:1").await.unwrap().unwrap();
+        assert!(blocked > 0 && blocked <= 30);
+
+        limiter.reset("ip:1").await.unwrap();
+        assert_eq!(limiter.blocked_for("ip:1").await.unwrap(), None);
+        assert_eq!(limiter.record_failure("ip:1", 60).await.unwrap(), 1);
+    }
+}
@@ -2,7 +2,11 @@ use async_trait::async_trait;
 use sea_orm::*;
 use uuid::Uuid;
 
-use crate::utils::models::{CreateUser, User};
+use chrono::{DateTime, Utc};
+
+use crate::utils::models::{
+    ApiKey, CreateApiKey, CreateUser, Invite, Permission, Role, UpdateUser, User,
+};
 
 /// Repository for managing user data.
 #[async_trait]
@@ -18,6 +22,90 @@ pub trait UserRepository: Send + Sync + std::fmt::Debug {
 
     /// Creates a new user in the database.
     async fn create(&self, user: CreateUser) -> Result<User, DbErr>;
+
+    /// Creates the user only if there are no users yet, so that of several concurrent
+    /// calls exactly one succeeds.
+    ///
+    /// # Returns
+    /// `None`, creating nothing, if any user already exists.
+    async fn create_first(&self, user: CreateUser) -> Result<Option<User>, DbErr>;
+
+    /// Returns the permissions granted to a role.
+    async fn find_role_permissions(&self, role: Role) -> Result<Vec<Permission>, DbErr>;
+
+    /// Lists all users, oldest first.
+    async fn list(&self) -> Result<Vec<User>, DbErr>;
+
+    /// Counts all users, including disabled ones.
+    async fn count(&self) -> Result<u64, DbErr>;
+
+    /// Deletes a user. Returns `false` if no such user exists.
+    async fn delete(&self, id: Uuid) -> Result<bool, DbErr>;
+
+    /// Changes a user's role.
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<Option<User>, DbErr>;
+
+    /// Disables a user from `disabled_at`, or re-enables them with `None`.
+    async fn update_disabled(
+        &self,
+        id: Uuid,
+        disabled_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<User>, DbErr>;
+
+    /// Applies the set fields of `update` to a user.
+    async fn update(&self, id: Uuid, update: UpdateUser) -> Result<Option<User>, DbErr>;
+
+    /// Records `step` as the time step of the user's last accepted TOTP code. Returns
+    /// `false` if a code from that step or a later one was already accepted.
+    async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool, DbErr>;
+
+    /// Removes one of the user's recovery codes by its hash. Returns `false` if the user
+    /// has no such code, e.g. because a concurrent login used it first.
+    async fn consume_recovery_code(&self, id: Uuid, code_hash: &str) -> Result<bool, DbErr>;
+
+    /// Stores a new invite.
+    async fn create_invite(&self, invite: Invite) -> Result<Invite, DbErr>;
+
+    /// Finds an invite by its code.
+    async fn find_invite(&self, code: &str) -> Result<Option<Invite>, DbErr>;
+
+    /// Lists all invites, newest first.
+    async fn list_invites(&self) -> Result<Vec<Invite>, DbErr>;
+
+    /// Marks an invite as used by a user. Returns `false` if it was already used or missing.
+    async fn redeem_invite(&self, code: &str, user_id: Uuid) -> Result<bool, DbErr>;
+
+    /// Deletes an invite. Returns `false` if no such invite exists.
+    async fn delete_invite(&self, code: &str) -> Result<bool, DbErr>;
+
+    /// Finds the user an external identity provider account is linked to.
+    async fn find_by_identity(&self, provider: &str, subject: &str) -> Result<Option<User>, DbErr>;
+
+    /// Links an external identity provider account to a user.
+    async fn link_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), DbErr>;
+
+    /// Creates an API key.
+    async fn create_api_key(&self, key: CreateApiKey) -> Result<ApiKey, DbErr>;
+
+    /// Lists a user's API keys, newest first.
+    async fn list_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>, DbErr>;
+
+    /// Finds an API key by its ID.
+    async fn find_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DbErr>;
+
+    /// Finds the API key with this SHA-256 hash.
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, DbErr>;
+
+    /// Records when an API key was last used.
+    async fn touch_api_key(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), DbErr>;
+
+    /// Deletes one of a user's API keys. Returns `false` if the user has no such key.
+    async fn delete_api_key(&self, user_id: Uuid, id: Uuid) -> Result<bool, DbErr>;
 }
 
 #[derive(Debug)]
@@ -29,6 +117,37 @@ impl SqlUserRepository {
     pub fn new(db: DatabaseConnection) -> Self {
         Self { db }
     }
+
+    fn new_user(create: CreateUser) -> beam_entity::user::ActiveModel {
+        use beam_entity::user;
+        use sea_orm::Set;
+
+        let CreateUser {
+            username,
+            email,
+            password_hash,
+            role,
+        } = create;
+
+        let now = Utc::now();
+        user::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            username: Set(username),
+            email: Set(email),
+            password_hash: Set(password_hash),
+            role: Set(role.as_str().to_string()),
+            disabled_at: Set(None),
+            password_reset_required: Set(false),
+            display_name: Set(None),
+            avatar_url: Set(None),
+            totp_secret: Set(None),
+            totp_enabled_at: Set(None),
+            totp_recovery_codes: Set(Vec::new()),
+            totp_last_step: Set(None),
+            created_at: Set(now.into()),
+            updated_at: Set(now.into()),
+        }
+    }
 }
 
 #[async_trait]
@@ -64,30 +183,359 @@ impl UserRepository for SqlUserRepository {
     }
 
     async fn create(&self, create: CreateUser) -> Result<User, DbErr> {
+        use sea_orm::ActiveModelTrait;
+
+        let result = Self::new_user(create).insert(&self.db).await?;
+        Ok(User::from(result))
+    }
+
+    async fn create_first(&self, create: CreateUser) -> Result<Option<User>, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{ActiveModelTrait, EntityTrait, PaginatorTrait, TransactionTrait};
+
+        let txn = self.db.begin().await?;
+        // Blocks every other insert into users until the transaction ends, so no one can
+        // register between the count and the insert
+        txn.execute_unprepared("LOCK TABLE users IN SHARE ROW EXCLUSIVE MODE")
+            .await?;
+        if user::Entity::find().count(&txn).await? > 0 {
+            return Ok(None);
+        }
+        let result = Self::new_user(create).insert(&txn).await?;
+        txn.commit().await?;
+        Ok(Some(User::from(result)))
+    }
+
+    async fn find_role_permissions(&self, role: Role) -> Result<Vec<Permission>, DbErr> {
+        use beam_entity::role;
+        use sea_orm::EntityTrait;
+
+        let model = role::Entity::find_by_id(role.as_str())
+            .one(&self.db)
+            .await?;
+
+        // Permissions this build doesn't know about are ignored
+        Ok(model
+            .map(|m| {
+                m.permissions
+                    .iter()
+                    .filter_map(|p| p.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn list(&self) -> Result<Vec<User>, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{EntityTrait, QueryOrder};
+
+        let models = user::Entity::find()
+            .order_by_asc(user::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(models.into_iter().map(User::from).collect())
+    }
+
+    async fn count(&self) -> Result<u64, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{EntityTrait, PaginatorTrait};
+
+        user::Entity::find().count(&self.db).await
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<bool, DbErr> {
         use beam_entity::user;
-        use chrono::Utc;
+        use sea_orm::EntityTrait;
+
+        let result = user::Entity::delete_by_id(id).exec(&self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn update_role(&self, id: Uuid, role: Role) -> Result<Option<User>, DbErr> {
+        use sea_orm::Set;
+
+        self.update_user(id, |model| {
+            model.role = Set(role.as_str().to_string());
+        })
+        .await
+    }
+
+    async fn update_disabled(
+        &self,
+        id: Uuid,
+        disabled_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<User>, DbErr> {
+        use sea_orm::Set;
+
+        self.update_user(id, |model| {
+            model.disabled_at = Set(disabled_at.map(Into::into));
+        })
+        .await
+    }
+
+    async fn update(&self, id: Uuid, update: UpdateUser) -> Result<Option<User>, DbErr> {
+        use sea_orm::Set;
+
+        self.update_user(id, |model| {
+            if let Some(email) = update.email {
+                model.email = Set(email);
+            }
+            if let Some(password_hash) = update.password_hash {
+                model.password_hash = Set(password_hash);
+            }
+            if let Some(reset_required) = update.password_reset_required {
+                model.password_reset_required = Set(reset_required);
+            }
+            if let Some(display_name) = update.display_name {
+                model.display_name = Set(display_name);
+            }
+            if let Some(avatar_url) = update.avatar_url {
+                model.avatar_url = Set(avatar_url);
+            }
+            if let Some(totp_secret) = update.totp_secret {
+                model.totp_secret = Set(totp_secret);
+            }
+            if let Some(enabled_at) = update.totp_enabled_at {
+                model.totp_enabled_at = Set(enabled_at.map(Into::into));
+            }
+            if let Some(recovery_codes) = update.totp_recovery_codes {
+                model.totp_recovery_codes = Set(recovery_codes);
+            }
+        })
+        .await
+    }
+
+    async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, sea_query::Expr};
+
+        // Compare and set in one statement, so a code can't be replayed concurrently
+        let result = user::Entity::update_many()
+            .col_expr(user::Column::TotpLastStep, Expr::value(step))
+            .filter(user::Column::Id.eq(id))
+            .filter(
+                Condition::any()
+                    .add(user::Column::TotpLastStep.is_null())
+                    .add(user::Column::TotpLastStep.lt(step)),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn consume_recovery_code(&self, id: Uuid, code_hash: &str) -> Result<bool, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+
+        // Only one login can use up a recovery code
+        let result = user::Entity::update_many()
+            .col_expr(
+                user::Column::TotpRecoveryCodes,
+                Expr::cust_with_values("array_remove(totp_recovery_codes, $1)", [code_hash]),
+            )
+            .filter(user::Column::Id.eq(id))
+            .filter(Expr::cust_with_values(
+                "$1 = ANY(totp_recovery_codes)",
+                [code_hash],
+            ))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn create_invite(&self, invite: Invite) -> Result<Invite, DbErr> {
+        use beam_entity::invite;
         use sea_orm::{ActiveModelTrait, Set};
 
-        let CreateUser {
-            username,
-            email,
-            password_hash,
-            is_admin,
-        } = create;
+        let model = invite::ActiveModel {
+            code: Set(invite.code),
+            role: Set(invite.role.as_str().to_string()),
+            created_by: Set(invite.created_by),
+            created_at: Set(invite.created_at.into()),
+            expires_at: Set(invite.expires_at.map(Into::into)),
+            used_by: Set(invite.used_by),
+            used_at: Set(invite.used_at.map(Into::into)),
+        };
 
-        let now = Utc::now();
-        let new_user = user::ActiveModel {
+        let result = model.insert(&self.db).await?;
+        Ok(Invite::from(result))
+    }
+
+    async fn find_invite(&self, code: &str) -> Result<Option<Invite>, DbErr> {
+        use beam_entity::invite;
+        use sea_orm::EntityTrait;
+
+        let model = invite::Entity::find_by_id(code).one(&self.db).await?;
+        Ok(model.map(Invite::from))
+    }
+
+    async fn list_invites(&self) -> Result<Vec<Invite>, DbErr> {
+        use beam_entity::invite;
+        use sea_orm::{EntityTrait, QueryOrder};
+
+        let models = invite::Entity::find()
+            .order_by_desc(invite::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(models.into_iter().map(Invite::from).collect())
+    }
+
+    async fn redeem_invite(&self, code: &str, user_id: Uuid) -> Result<bool, DbErr> {
+        use beam_entity::invite;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+
+        // Only one registration can claim an unused invite
+        let result = invite::Entity::update_many()
+            .col_expr(invite::Column::UsedBy, Expr::value(user_id))
+            .col_expr(invite::Column::UsedAt, Expr::current_timestamp().into())
+            .filter(invite::Column::Code.eq(code))
+            .filter(invite::Column::UsedAt.is_null())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn delete_invite(&self, code: &str) -> Result<bool, DbErr> {
+        use beam_entity::invite;
+        use sea_orm::EntityTrait;
+
+        let result = invite::Entity::delete_by_id(code).exec(&self.db).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn find_by_identity(&self, provider: &str, subject: &str) -> Result<Option<User>, DbErr> {
+        use beam_entity::{user, user_identity};
+        use sea_orm::EntityTrait;
+
+        let Some(identity) =
+            user_identity::Entity::find_by_id((provider.to_string(), subject.to_string()))
+                .one(&self.db)
+                .await?
+        else {
+            return Ok(None);
+        };
+        let model = user::Entity::find_by_id(identity.user_id)
+            .one(&self.db)
+            .await?;
+        Ok(model.map(User::from))
+    }
+
+    async fn link_identity(
+        &self,
+        user_id: Uuid,
+        provider: &str,
+        subject: &str,
+    ) -> Result<(), DbErr> {
+        use beam_entity::user_identity;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        user_identity::ActiveModel {
+            provider: Set(provider.to_string()),
+            subject: Set(subject.to_string()),
+            user_id: Set(user_id),
+            created_at: Set(Utc::now().into()),
+        }
+        .insert(&self.db)
+        .await?;
+        Ok(())
+    }
+
+    async fn create_api_key(&self, key: CreateApiKey) -> Result<ApiKey, DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let model = api_key::ActiveModel {
             id: Set(Uuid::new_v4()),
-            username: Set(username),
-            email: Set(email),
-            password_hash: Set(password_hash),
-            is_admin: Set(is_admin),
-            created_at: Set(now.into()),
-            updated_at: Set(now.into()),
+            user_id: Set(key.user_id),
+            name: Set(key.name),
+            prefix: Set(key.prefix),
+            key_hash: Set(key.key_hash),
+            scopes: Set(key.scopes.iter().map(|s| s.as_str().to_string()).collect()),
+            created_at: Set(Utc::now().into()),
+            last_used_at: Set(None),
         };
 
-        let result = new_user.insert(&self.db).await?;
-        Ok(User::from(result))
+        let result = model.insert(&self.db).await?;
+        Ok(ApiKey::from(result))
+    }
+
+    async fn list_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>, DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+
+        let models = api_key::Entity::find()
+            .filter(api_key::Column::UserId.eq(user_id))
+            .order_by_desc(api_key::Column::CreatedAt)
+            .all(&self.db)
+            .await?;
+        Ok(models.into_iter().map(ApiKey::from).collect())
+    }
+
+    async fn find_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::EntityTrait;
+
+        let model = api_key::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(model.map(ApiKey::from))
+    }
+
+    async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let model = api_key::Entity::find()
+            .filter(api_key::Column::KeyHash.eq(key_hash))
+            .one(&self.db)
+            .await?;
+        Ok(model.map(ApiKey::from))
+    }
+
+    async fn touch_api_key(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, sea_query::Expr};
+
+        api_key::Entity::update_many()
+            .col_expr(
+                api_key::Column::LastUsedAt,
+                Expr::value(Some(sea_orm::prelude::DateTimeWithTimeZone::from(used_at))),
+            )
+            .filter(api_key::Column::Id.eq(id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_api_key(&self, user_id: Uuid, id: Uuid) -> Result<bool, DbErr> {
+        use beam_entity::api_key;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let result = api_key::Entity::delete_many()
+            .filter(api_key::Column::Id.eq(id))
+            .filter(api_key::Column::UserId.eq(user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+impl SqlUserRepository {
+    /// Applies `change` to a user and bumps `updated_at`.
+    async fn update_user(
+        &self,
+        id: Uuid,
+        change: impl FnOnce(&mut beam_entity::user::ActiveModel),
+    ) -> Result<Option<User>, DbErr> {
+        use beam_entity::user;
+        use sea_orm::{ActiveModelTrait, EntityTrait, IntoActiveModel, Set};
+
+        let Some(model) = user::Entity::find_by_id(id).one(&self.db).await? else {
+            return Ok(None);
+        };
+        let mut active = model.into_active_model();
+        change(&mut active);
+        active.updated_at = Set(Utc::now().into());
+        let result = active.update(&self.db).await?;
+        Ok(Some(User::from(result)))
     }
 }
 
@@ -95,13 +543,46 @@ impl UserRepository for SqlUserRepository {
 #[cfg(any(test, feature = "test-utils"))]
 pub mod in_memory {
     use super::*;
-    use chrono::Utc;
     use std::collections::HashMap;
     use std::sync::Mutex;
 
     #[derive(Debug, Default)]
     pub struct InMemoryUserRepository {
         users: Mutex<HashMap<Uuid, User>>,
+        invites: Mutex<HashMap<String, Invite>>,
+        identities: Mutex<HashMap<(String, String), Uuid>>,
+        api_keys: Mutex<HashMap<Uuid, ApiKey>>,
+    }
+
+    impl InMemoryUserRepository {
+        fn new_user(user: CreateUser) -> User {
+            let now = Utc::now();
+            User {
+                id: Uuid::new_v4(),
+                username: user.username,
+                email: user.email,
+                password_hash: user.password_hash,
+                role: user.role,
+                disabled_at: None,
+                password_reset_required: false,
+                display_name: None,
+                avatar_url: None,
+                totp_secret: None,
+                totp_enabled_at: None,
+                totp_recovery_codes: Vec::new(),
+                totp_last_step: None,
+                created_at: now,
+                updated_at: now,
+            }
+        }
+
+        fn update_user(&self, id: Uuid, change: impl FnOnce(&mut User)) -> Option<User> {
+            let mut users = self.users.lock().unwrap();
+            let user = users.get_mut(&id)?;
+            change(user);
+            user.updated_at = Utc::now();
+            Some(user.clone())
+        }
     }
 
     #[async_trait]
@@ -121,21 +602,224 @@ pub mod in_memory {
         }
 
         async fn create(&self, user: CreateUser) -> Result<User, DbErr> {
-            let now = Utc::now();
-            let new_user = User {
-                id: Uuid::new_v4(),
-                username: user.username,
-                email: user.email,
-                password_hash: user.password_hash,
-                is_admin: user.is_admin,
-                created_at: now,
-                updated_at: now,
-            };
+            let new_user = Self::new_user(user);
             self.users
                 .lock()
                 .unwrap()
                 .insert(new_user.id, new_user.clone());
             Ok(new_user)
         }
+
+        async fn create_first(&self, user: CreateUser) -> Result<Option<User>, DbErr> {
+            let mut users = self.users.lock().unwrap();
+            if !users.is_empty() {
+                return Ok(None);
+            }
+            let new_user = Self::new_user(user);
+            users.insert(new_user.id, new_user.clone());
+            Ok(Some(new_user))
+        }
+
+        async fn find_role_permissions(&self, role: Role) -> Result<Vec<Permission>, DbErr> {
+            Ok(role.default_permissions())
+        }
+
+        async fn list(&self) -> Result<Vec<User>, DbErr> {
+            let mut users: Vec<User> = self.users.lock().unwrap().values().cloned().collect();
+            users.sort_by_key(|u| u.created_at);
+            Ok(users)
+        }
+
+        async fn count(&self) -> Result<u64, DbErr> {
+            Ok(self.users.lock().unwrap().len() as u64)
+        }
+
+        async fn delete(&self, id: Uuid) -> Result<bool, DbErr> {
+            Ok(self.users.lock().unwrap().remove(&id).is_some())
+        }
+
+        async fn update_role(&self, id: Uuid, role: Role) -> Result<Option<User>, DbErr> {
+            Ok(self.update_user(id, |u| u.role = role))
+        }
+
+        async fn update_disabled(
+            &self,
+            id: Uuid,
+            disabled_at: Option<DateTime<Utc>>,
+        ) -> Result<Option<User>, DbErr> {
+            Ok(self.update_user(id, |u| u.disabled_at = disabled_at))
+        }
+
+        async fn update(&self, id: Uuid, update: UpdateUser) -> Result<Option<User>, DbErr> {
+            Ok(self.update_user(id, |u| {
+                if let Some(email) = update.email {
+                    u.email = email;
+                }
+                if let Some(password_hash) = update.password_hash {
+                    u.password_hash = password_hash;
+                }
+                if let Some(reset_required) = update.password_reset_required {
+                    u.password_reset_required = reset_required;
+                }
+                if let Some(display_name) = update.display_name {
+                    u.display_name = display_name;
+                }
+                if let Some(avatar_url) = update.avatar_url {
+                    u.avatar_url = avatar_url;
+                }
+                if let Some(totp_secret) = update.totp_secret {
+                    u.totp_secret = totp_secret;
+                }
+                if let Some(enabled_at) = update.totp_enabled_at {
+                    u.totp_enabled_at = enabled_at;
+                }
+                if let Some(recovery_codes) = update.totp_recovery_codes {
+                    u.totp_recovery_codes = recovery_codes;
+                }
+            }))
+        }
+
+        async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool, DbErr> {
+            let mut users = self.users.lock().unwrap();
+            match users.get_mut(&id) {
+                Some(user) if user.totp_last_step.is_none_or(|last| last < step) => {
+                    user.totp_last_step = Some(step);
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        async fn consume_recovery_code(&self, id: Uuid, code_hash: &str) -> Result<bool, DbErr> {
+            let mut users = self.users.lock().unwrap();
+            let Some(user) = users.get_mut(&id) else {
+                return Ok(false);
+            };
+            let before = user.totp_recovery_codes.len();
+            user.totp_recovery_codes
+                .retain(|stored| stored != code_hash);
+            Ok(user.totp_recovery_codes.len() < before)
+        }
+
+        async fn create_invite(&self, invite: Invite) -> Result<Invite, DbErr> {
+            self.invites
+                .lock()
+                .unwrap()
+                .insert(invite.code.clone(), invite.clone());
+            Ok(invite)
+        }
+
+        async fn find_invite(&self, code: &str) -> Result<Option<Invite>, DbErr> {
+            Ok(self.invites.lock().unwrap().get(code).cloned())
+        }
+
+        async fn list_invites(&self) -> Result<Vec<Invite>, DbErr> {
+            let mut invites: Vec<Invite> = self.invites.lock().unwrap().values().cloned().collect();
+            invites.sort_by_key(|i| std::cmp::Reverse(i.created_at));
+            Ok(invites)
+        }
+
+        async fn redeem_invite(&self, code: &str, user_id: Uuid) -> Result<bool, DbErr> {
+            let mut invites = self.invites.lock().unwrap();
+            match invites.get_mut(code) {
+                Some(invite) if invite.used_at.is_none() => {
+                    invite.used_by = Some(user_id);
+                    invite.used_at = Some(Utc::now());
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        async fn delete_invite(&self, code: &str) -> Result<bool, DbErr> {
+            Ok(self.invites.lock().unwrap().remove(code).is_some())
+        }
+
+        async fn find_by_identity(
+            &self,
+            provider: &str,
+            subject: &str,
+        ) -> Result<Option<User>, DbErr> {
+            let user_id = self
+                .identities
+                .lock()
+                .unwrap()
+                .get(&(provider.to_string(), subject.to_string()))
+                .copied();
+            Ok(user_id.and_then(|id| self.users.lock().unwrap().get(&id).cloned()))
+        }
+
+        async fn link_identity(
+            &self,
+            user_id: Uuid,
+            provider: &str,
+            subject: &str,
+        ) -> Result<(), DbErr> {
+            let mut identities = self.identities.lock().unwrap();
+            let key = (provider.to_string(), subject.to_string());
+            if identities.contains_key(&key) {
+                return Err(DbErr::Custom("identity is already linked".into()));
+            }
+            identities.insert(key, user_id);
+            Ok(())
+        }
+
+        async fn create_api_key(&self, key: CreateApiKey) -> Result<ApiKey, DbErr> {
+            let key = ApiKey {
+                id: Uuid::new_v4(),
+                user_id: key.user_id,
+                name: key.name,
+                prefix: key.prefix,
+                key_hash: key.key_hash,
+                scopes: key.scopes,
+                created_at: Utc::now(),
+                last_used_at: None,
+            };
+            self.api_keys.lock().unwrap().insert(key.id, key.clone());
+            Ok(key)
+        }
+
+        async fn list_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>, DbErr> {
+            let mut keys: Vec<ApiKey> = self
+                .api_keys
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|key| key.user_id == user_id)
+                .cloned()
+                .collect();
+            keys.sort_by_key(|key| std::cmp::Reverse(key.created_at));
+            Ok(keys)
+        }
+
+        async fn find_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DbErr> {
+            Ok(self.api_keys.lock().unwrap().get(&id).cloned())
+        }
+
+        async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, DbErr> {
+            Ok(self
+                .api_keys
+                .lock()
+                .unwrap()
+                .values()
+                .find(|key| key.key_hash == key_hash)
+                .cloned())
+        }
+
+        async fn touch_api_key(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), DbErr> {
+            if let Some(key) = self.api_keys.lock().unwrap().get_mut(&id) {
+                key.last_used_at = Some(used_at);
+            }
+            Ok(())
+        }
+
+        async fn delete_api_key(&self, user_id: Uuid, id: Uuid) -> Result<bool, DbErr> {
+            let mut keys = self.api_keys.lock().unwrap();
+            if keys.get(&id).is_some_and(|key| key.user_id == user_id) {
+                keys.remove(&id);
+                return Ok(true);
+            }
+            Ok(false)
+        }
     }
 }
@@ -2,16 +2,30 @@
 #[path = "service_tests.rs"]
 mod service_tests;
 
-use crate::utils::models::{CreateUser, User};
+use crate::config::RegistrationMode;
+use crate::utils::admin_log::{AdminLogSink, NoOpAdminLogSink};
+use crate::utils::backend::{
+    Authenticated, AuthenticationBackend, LocalPasswordBackend, verify_password,
+};
+use crate::utils::device::{
+    device_hash, device_name, format_user_code, generate_user_code, normalize_user_code,
+};
+use crate::utils::keys::{KeyError, TokenKeys, TokenKind};
+use crate::utils::models::{
+    ApiKey, ApiKeyScope, CreateApiKey, CreateUser, Invite, Permission, Role, UpdateUser, User,
+};
+use crate::utils::rate_limit::{LockoutPolicy, LoginRateLimiter};
 use crate::utils::repository::UserRepository;
-use crate::utils::session_store::{SessionData, SessionStore};
+use crate::utils::session_store::{DeviceAuthorization, DeviceDecision, SessionData, SessionStore};
+use crate::utils::totp::{self, SecretCipher};
 use argon2::{
     Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    password_hash::{PasswordHasher, SaltString, rand_core::OsRng},
 };
 use async_trait::async_trait;
+use beam_entity::admin_log::AdminLogLevel;
 use chrono::Utc;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
+use jsonwebtoken::jwk::JwkSet;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use thiserror::Error;
@@ -29,8 +43,56 @@ pub enum AuthError {
     Session(String),
     #[error("Token error: {0}")]
     Token(#[from] jsonwebtoken::errors::Error),
+    #[error("Signing key error: {0}")]
+    Keys(#[from] KeyError),
     #[error("Password hashing error: {0}")]
     PasswordHash(#[from] argon2::password_hash::Error),
+    #[error("Registration is closed")]
+    RegistrationClosed,
+    #[error("Invalid or expired invite code")]
+    InvalidInvite,
+    #[error("Account is disabled")]
+    AccountDisabled,
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Cannot remove the last active admin")]
+    LastAdmin,
+    #[error("Session not found")]
+    SessionNotFound,
+    /// A rotated-out refresh token was presented again; its session has been revoked
+    #[error("Refresh token reuse detected")]
+    RefreshTokenReused,
+    /// Password was correct; finish with [`AuthService::complete_mfa_login`]
+    #[error("Two-factor authentication required")]
+    MfaRequired { challenge_token: String },
+    #[error("Invalid two-factor code")]
+    InvalidMfaCode,
+    #[error("Two-factor authentication is already enabled")]
+    MfaAlreadyEnabled,
+    #[error("Two-factor authentication is not set up")]
+    MfaNotEnrolled,
+    /// Too many failed logins from this address or for this account
+    #[error("Too many failed login attempts")]
+    TooManyAttempts { retry_after_secs: u64 },
+    /// An external identity cannot be turned into an account without an email address
+    #[error("The identity provider did not share an email address")]
+    MissingEmail,
+    /// A password backend such as the LDAP directory could not be reached
+    #[error("Authentication backend unavailable: {0}")]
+    Backend(String),
+    #[error("Invalid API key: {0}")]
+    InvalidApiKey(&'static str),
+    #[error("API key not found")]
+    ApiKeyNotFound,
+    /// A device has no session yet; carries the RFC 8628 error code telling it why
+    #[error("Device pairing: {0}")]
+    DevicePairing(&'static str),
+    #[error("Unknown or expired pairing code")]
+    PairingCodeNotFound,
+    /// The user holds a temporary password and may only use their account until they
+    /// change it
+    #[error("Password change required")]
+    PasswordResetRequired,
 }
 
 type Result<T> = std::result::Result<T, AuthError>;
@@ -39,16 +101,117 @@ type Result<T> = std::result::Result<T, AuthError>;
 pub struct Claims {
     pub sub: String, // user_id
     pub sid: String, // session_id
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    /// Set while the user must change their password; only account endpoints accept it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub password_reset: bool,
     pub exp: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StreamClaims {
     pub sub: String, // user_id
+    pub sid: String, // session_id
+    pub stream_id: String,
+    #[serde(default)]
+    pub scope: StreamScope,
+    pub exp: usize,
+}
+
+/// Limits on what a stream token may play. The default allows everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamScope {
+    /// Names of the variants that may be played, e.g. `1080p`; any when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<String>>,
+    /// Highest bitrate in bits per second that may be played; unlimited when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bitrate: Option<u64>,
+}
+
+impl StreamScope {
+    pub fn allows_variant(&self, variant: &str) -> bool {
+        self.variants
+            .as_ref()
+            .is_none_or(|variants| variants.iter().any(|v| v == variant))
+    }
+
+    /// Media of unknown bitrate is only allowed when the bitrate is unlimited.
+    pub fn allows_bitrate(&self, bitrate: Option<u64>) -> bool {
+        match (self.max_bitrate, bitrate) {
+            (None, _) => true,
+            (Some(max), Some(bitrate)) => bitrate <= max,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// What a valid stream token grants
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamGrant {
+    pub user_id: String,
+    pub session_id: String,
     pub stream_id: String,
+    pub scope: StreamScope,
+}
+
+/// Short-lived token proving the password step of a two-factor login
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaChallengeClaims {
+    pub sub: String, // user_id
     pub exp: usize,
 }
 
+/// An account at an external identity provider that has just authenticated
+#[derive(Debug, Clone, Default)]
+pub struct ExternalIdentity {
+    /// Which configured provider vouched for the account
+    pub provider: String,
+    /// The provider's stable identifier for the account, e.g. the OpenID Connect `sub`
+    pub subject: String,
+    pub email: Option<String>,
+    /// Whether the provider has verified that the email belongs to the account
+    pub email_verified: bool,
+    /// Preferred username; the email's local part is used when missing or taken
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    /// Whether the account is in the provider's admin group, or `None` when the provider
+    /// does not manage admins
+    pub is_admin: Option<bool>,
+    /// Sign in to an existing user with the same verified email
+    pub link_by_email: bool,
+    /// Create a user when no existing one matches
+    pub create_user: bool,
+}
+
+/// 7 days, extended on every refresh
+const SESSION_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// How many rotated-out refresh tokens per session are remembered for reuse detection
+const RETIRED_REFRESH_TOKENS: usize = 32;
+
+/// Start of every API key, which tells them apart from access tokens
+const API_KEY_PREFIX: &str = "beam_";
+
+/// Characters of an API key kept in the clear to tell keys apart
+const API_KEY_SHOWN_CHARS: usize = API_KEY_PREFIX.len() + 6;
+
+/// Stands in for the session ID of requests made with an API key, followed by the key ID
+const API_KEY_SESSION_PREFIX: &str = "api-key:";
+
+/// How often the last use of an API key is written, at most
+const API_KEY_TOUCH_INTERVAL_SECS: i64 = 60;
+
+/// How long a device has to be approved after it asks to be paired
+const DEVICE_CODE_TTL_SECS: u64 = 10 * 60;
+
+/// How long a pairing device should wait between polls
+const DEVICE_POLL_INTERVAL_SECS: u64 = 5;
+
+/// Longest device name kept for a paired device
+const DEVICE_NAME_MAX_CHARS: usize = 64;
+
 #[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
 #[derive(Debug, Serialize)]
 pub struct AuthUserResponse {
@@ -56,7 +219,16 @@ pub struct AuthUserResponse {
     pub id: String,
     pub username: String,
     pub email: String,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
     pub is_admin: bool,
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    /// The user must choose a new password before doing anything else
+    pub password_reset_required: bool,
+    pub mfa_enabled: bool,
+    /// Admin permissions are withheld until the user enables two-factor authentication
+    pub mfa_setup_required: bool,
 }
 
 #[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
@@ -64,13 +236,171 @@ pub struct AuthUserResponse {
 pub struct AuthResponse {
     pub token: String,
     pub session_id: String,
+    /// Single-use credential for `refresh`; replaced by a new one on every refresh
+    pub refresh_token: String,
     pub user: AuthUserResponse,
 }
 
+/// A user as seen by admins managing accounts
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct UserAccount {
+    pub id: String,
+    pub username: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub role: Role,
+    pub disabled: bool,
+    pub password_reset_required: bool,
+    pub mfa_enabled: bool,
+    /// Unix timestamp in seconds
+    pub created_at: i64,
+}
+
+impl From<User> for UserAccount {
+    fn from(user: User) -> Self {
+        Self {
+            disabled: user.is_disabled(),
+            mfa_enabled: user.mfa_enabled(),
+            id: user.id.to_string(),
+            username: user.username,
+            email: user.email,
+            display_name: user.display_name,
+            role: user.role,
+            password_reset_required: user.password_reset_required,
+            created_at: user.created_at.timestamp(),
+        }
+    }
+}
+
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct InviteSummary {
+    pub code: String,
+    /// Role given to the account registered with this invite
+    pub role: Role,
+    pub created_by: Option<String>,
+    /// Unix timestamps in seconds
+    pub created_at: i64,
+    pub expires_at: Option<i64>,
+    pub used_by: Option<String>,
+    pub used_at: Option<i64>,
+}
+
+impl From<Invite> for InviteSummary {
+    fn from(invite: Invite) -> Self {
+        Self {
+            code: invite.code,
+            role: invite.role,
+            created_by: invite.created_by.map(|id| id.to_string()),
+            created_at: invite.created_at.timestamp(),
+            expires_at: invite.expires_at.map(|t| t.timestamp()),
+            used_by: invite.used_by.map(|id| id.to_string()),
+            used_at: invite.used_at.map(|t| t.timestamp()),
+        }
+    }
+}
+
+/// An API key as shown to its owner; the key itself is only shown once
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiKeySummary {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key
+    pub prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Unix timestamps in seconds
+    pub created_at: i64,
+    pub last_used_at: Option<i64>,
+}
+
+impl From<ApiKey> for ApiKeySummary {
+    fn from(key: ApiKey) -> Self {
+        Self {
+            id: key.id.to_string(),
+            name: key.name,
+            prefix: key.prefix,
+            scopes: key.scopes,
+            created_at: key.created_at.timestamp(),
+            last_used_at: key.last_used_at.map(|t| t.timestamp()),
+        }
+    }
+}
+
+/// A newly created API key
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedApiKey {
+    /// Send as `Authorization: Bearer <key>`; shown only this once
+    pub key: String,
+    pub api_key: ApiKeySummary,
+}
+
+/// Codes a device shows and polls with while it waits to be paired
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCode {
+    /// Secret the device polls with; never shown on screen
+    pub device_code: String,
+    /// Code to show for the user to enter in the web UI, e.g. `BCDF-GHJK`
+    pub user_code: String,
+    /// Seconds until both codes expire
+    pub expires_in: u64,
+    /// Seconds to wait between polls
+    pub interval: u64,
+}
+
+/// A device waiting to be paired, as shown to the user asked to approve it
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingDevice {
+    pub user_code: String,
+    pub device_name: String,
+    /// IP the pairing was started from
+    pub ip: String,
+    /// Unix timestamps in seconds
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+impl From<DeviceAuthorization> for PendingDevice {
+    fn from(pairing: DeviceAuthorization) -> Self {
+        Self {
+            user_code: format_user_code(&pairing.user_code),
+            device_name: pairing.device_name,
+            ip: pairing.ip,
+            created_at: pairing.created_at,
+            expires_at: pairing.expires_at,
+        }
+    }
+}
+
+/// A TOTP secret awaiting confirmation with a code from the authenticator app
+#[cfg_attr(feature = "server", derive(salvo::oapi::ToSchema))]
+#[derive(Debug, Clone, Serialize)]
+pub struct TotpEnrollment {
+    /// Base32 secret for manual entry
+    pub secret: String,
+    /// `otpauth://` URI, usually rendered as a QR code
+    pub otpauth_uri: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub user_id: String,
+    /// For API keys, a stand-in that stream tokens can be tied to
     pub session_id: String,
+    pub role: Role,
+    pub permissions: Vec<Permission>,
+    /// Set when the request was made with an API key instead of a session
+    pub api_key_id: Option<String>,
+}
+
+impl AuthenticatedUser {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
 }
 
 #[async_trait]
@@ -81,46 +411,249 @@ pub trait AuthService: Send + Sync + std::fmt::Debug + 'static {
         username: &str,
         email: &str,
         password: &str,
-        device_hash: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse>;
+
+    /// Register a new user, redeeming an invite code if one is given.
+    ///
+    /// The first user ever registered becomes admin regardless of the registration mode.
+    async fn register_with_invite(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        invite_code: Option<&str>,
+        user_agent: &str,
         ip: &str,
     ) -> Result<AuthResponse>;
 
     /// Login a user with username/email and password.
+    ///
+    /// Accounts with two-factor authentication fail with [`AuthError::MfaRequired`],
+    /// carrying a challenge token for [`complete_mfa_login`](Self::complete_mfa_login).
     async fn login(
         &self,
         username_or_email: &str,
         password: &str,
-        device_hash: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse>;
+
+    /// Finish a two-factor login with a TOTP code or an unused recovery code.
+    async fn complete_mfa_login(
+        &self,
+        challenge_token: &str,
+        code: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse>;
+
+    /// Sign in with an account an external identity provider has authenticated.
+    ///
+    /// The account is matched by its provider and subject. Unknown accounts are linked to
+    /// the user with the same verified email, or get a new user, as the identity allows;
+    /// both are recorded so later logins match directly. When the provider manages admins
+    /// the user's admin role follows it on every login, except that the last admin is
+    /// never demoted.
+    ///
+    /// Local two-factor authentication is not asked for: the provider is trusted to
+    /// enforce its own.
+    async fn login_with_identity(
+        &self,
+        identity: &ExternalIdentity,
+        user_agent: &str,
         ip: &str,
     ) -> Result<AuthResponse>;
 
-    /// Refresh an existing session.
-    async fn refresh(&self, session_id: &str) -> Result<AuthResponse>;
+    /// Start pairing a device that cannot show a login form, such as a TV. The device
+    /// shows the user code and polls [`poll_device_authorization`](Self::poll_device_authorization)
+    /// with the device code until a signed-in user approves or denies it.
+    ///
+    /// `device_name` is what the device calls itself; the user-agent is used when empty.
+    async fn start_device_authorization(
+        &self,
+        device_name: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<DeviceCode>;
+
+    /// Exchange the device code of an approved device for a session of its own. Works
+    /// once.
+    ///
+    /// Until then fails with [`AuthError::DevicePairing`] carrying the RFC 8628 error:
+    /// `authorization_pending`, `slow_down` when polled faster than the interval,
+    /// `access_denied` or `expired_token`.
+    async fn poll_device_authorization(&self, device_code: &str, ip: &str) -> Result<AuthResponse>;
+
+    /// Look up a device waiting to be paired by the code it shows.
+    async fn get_pending_device(&self, user_code: &str) -> Result<PendingDevice>;
 
-    /// Verify a JWT token and return the authenticated user.
+    /// Approve a waiting device; its next poll signs it in as the user.
+    async fn approve_device(&self, user_id: &str, user_code: &str) -> Result<PendingDevice>;
+
+    /// Refuse a waiting device on behalf of the user.
+    async fn deny_device(&self, user_id: &str, user_code: &str) -> Result<()>;
+
+    /// Exchange a refresh token for a new access token and a new refresh token, recording
+    /// `ip` as the session's last-seen address.
+    ///
+    /// Each refresh token works once. Presenting one that was already rotated out revokes
+    /// the whole session and fails with [`AuthError::RefreshTokenReused`].
+    async fn refresh(&self, refresh_token: &str, ip: &str) -> Result<AuthResponse>;
+
+    /// Verify an access token or API key and return the authenticated user.
+    ///
+    /// Fails with [`AuthError::PasswordResetRequired`] until a user holding a temporary
+    /// password has changed it.
     async fn verify_token(&self, token: &str) -> Result<AuthenticatedUser>;
 
+    /// Like [`verify_token`](Self::verify_token), but also accepts the token of a user who
+    /// must change their password, for the account endpoints that let them do so.
+    async fn verify_account_token(&self, token: &str) -> Result<AuthenticatedUser>;
+
     /// Logout a user by invalidating their session.
     async fn logout(&self, session_id: &str) -> Result<()>;
 
+    /// Logout the session a refresh token belongs to. Unknown tokens are ignored.
+    async fn logout_refresh_token(&self, refresh_token: &str) -> Result<()>;
+
     /// Logout all sessions for a specific user.
     async fn logout_all(&self, user_id: &str) -> Result<u64>;
 
     /// Get all active sessions for a user.
     async fn get_sessions(&self, user_id: &str) -> Result<Vec<(String, SessionData)>>;
 
-    /// Create a temporary token for accessing a specific stream.
-    fn create_stream_token(&self, user_id: &str, stream_id: &str) -> Result<String>;
+    /// Revoke one of the user's sessions. Sessions belonging to someone else are
+    /// reported as not found.
+    async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<()>;
+
+    /// Public keys that verify access tokens, served at `/.well-known/jwks.json`.
+    ///
+    /// Empty when tokens are signed with a shared secret.
+    fn jwks(&self) -> JwkSet;
+
+    /// Create a temporary token for playing a specific stream within `scope`.
+    ///
+    /// The token is tied to the session it was requested from and stops working when
+    /// that session ends. Stream tokens are signed with the JWT secret, since the service
+    /// that issues them is the only one that checks them.
+    fn create_stream_token(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        stream_id: &str,
+        scope: &StreamScope,
+    ) -> Result<String>;
+
+    /// Verify a stream token and check that its session is still active.
+    async fn verify_stream_token(&self, token: &str) -> Result<StreamGrant>;
+
+    /// Get the profile of the signed-in user.
+    async fn get_account(&self, user_id: &str) -> Result<AuthUserResponse>;
+
+    /// Change the signed-in user's password after checking the current one. Every other
+    /// session of the user is revoked; returns how many were.
+    async fn change_password(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<u64>;
+
+    /// Change the signed-in user's email after checking their password.
+    async fn change_email(
+        &self,
+        user_id: &str,
+        password: &str,
+        new_email: &str,
+    ) -> Result<AuthUserResponse>;
+
+    /// Replace the signed-in user's display name and avatar.
+    async fn update_profile(
+        &self,
+        user_id: &str,
+        display_name: Option<String>,
+        avatar_url: Option<String>,
+    ) -> Result<AuthUserResponse>;
+
+    /// Delete the signed-in user's account after checking their password.
+    async fn delete_account(&self, user_id: &str, password: &str) -> Result<()>;
+
+    /// Generate a new TOTP secret for the user, replacing any unconfirmed one. Two-factor
+    /// authentication stays off until the secret is confirmed.
+    async fn begin_totp_enrollment(&self, user_id: &str) -> Result<TotpEnrollment>;
+
+    /// Turn on two-factor authentication with a code from the pending secret. Returns the
+    /// recovery codes, which are only ever shown this once.
+    async fn confirm_totp_enrollment(&self, user_id: &str, code: &str) -> Result<Vec<String>>;
+
+    /// Turn off two-factor authentication after checking the password.
+    async fn disable_totp(&self, user_id: &str, password: &str) -> Result<()>;
+
+    /// Replace the recovery codes after checking a current TOTP code.
+    async fn regenerate_recovery_codes(&self, user_id: &str, code: &str) -> Result<Vec<String>>;
+
+    /// Create a named API key for the user, limited to `scopes` and to what the user's
+    /// role allows at the time it is used.
+    async fn create_api_key(
+        &self,
+        user_id: &str,
+        name: &str,
+        scopes: &[ApiKeyScope],
+    ) -> Result<CreatedApiKey>;
+
+    /// List the user's API keys, newest first.
+    async fn list_api_keys(&self, user_id: &str) -> Result<Vec<ApiKeySummary>>;
+
+    /// Revoke one of the user's API keys. Keys belonging to someone else are reported as
+    /// not found.
+    async fn revoke_api_key(&self, user_id: &str, key_id: &str) -> Result<()>;
+
+    /// List all user accounts, oldest first.
+    async fn list_users(&self) -> Result<Vec<UserAccount>>;
+
+    /// Create an invite code granting `role`, optionally expiring after `expires_in`.
+    async fn create_invite(
+        &self,
+        created_by: &str,
+        role: Role,
+        expires_in: Option<chrono::Duration>,
+    ) -> Result<InviteSummary>;
+
+    /// List all invites, newest first.
+    async fn list_invites(&self) -> Result<Vec<InviteSummary>>;
+
+    /// Delete an invite. Returns `false` if it did not exist.
+    async fn delete_invite(&self, code: &str) -> Result<bool>;
+
+    /// Disable or re-enable an account. Disabling revokes all of its sessions.
+    async fn set_user_disabled(&self, user_id: &str, disabled: bool) -> Result<UserAccount>;
 
-    /// Verify a stream token and return the associated stream ID.
-    fn verify_stream_token(&self, token: &str) -> Result<String>;
+    /// Delete an account and revoke all of its sessions.
+    async fn delete_user(&self, user_id: &str) -> Result<()>;
+
+    /// Change an account's role. The new role applies from the user's next token.
+    async fn set_user_role(&self, user_id: &str, role: Role) -> Result<UserAccount>;
+
+    /// Replace an account's password with a random temporary one that must be changed
+    /// after the next login, revoking all of its sessions. Returns the temporary password.
+    async fn reset_user_password(&self, user_id: &str) -> Result<String>;
 }
 
 #[derive(Debug)]
 pub struct LocalAuthService {
     user_repo: Arc<dyn UserRepository>,
     session_store: Arc<dyn SessionStore>,
-    jwt_secret: String,
+    token_keys: Arc<TokenKeys>,
+    stream_keys: TokenKeys,
+    registration_mode: RegistrationMode,
+    totp_cipher: SecretCipher,
+    require_admin_mfa: bool,
+    login_limiter: Option<Arc<dyn LoginRateLimiter>>,
+    admin_log: Arc<dyn AdminLogSink>,
+    backends: Vec<Arc<dyn AuthenticationBackend>>,
 }
 
 impl LocalAuthService {
@@ -130,259 +663,1505 @@ impl LocalAuthService {
         jwt_secret: String,
     ) -> Self {
         Self {
+            backends: vec![Arc::new(LocalPasswordBackend::new(user_repo.clone()))],
             user_repo,
             session_store,
-            jwt_secret,
+            totp_cipher: SecretCipher::new(&jwt_secret),
+            token_keys: Arc::new(TokenKeys::hmac(&jwt_secret)),
+            stream_keys: TokenKeys::hmac(&jwt_secret),
+            registration_mode: RegistrationMode::default(),
+            require_admin_mfa: false,
+            login_limiter: None,
+            admin_log: Arc::new(NoOpAdminLogSink),
         }
     }
 
-    fn hash_password(&self, password: &str) -> Result<String> {
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(password.as_bytes(), &salt)?
-            .to_string();
-        Ok(password_hash)
+    /// Sign and verify access tokens with these keys instead of the JWT secret.
+    pub fn with_token_keys(mut self, token_keys: Arc<TokenKeys>) -> Self {
+        self.token_keys = token_keys;
+        self
     }
 
-    fn verify_password(&self, password: &str, password_hash: &str) -> bool {
-        let parsed_hash = match PasswordHash::new(password_hash) {
-            Ok(hash) => hash,
-            Err(_) => return false,
-        };
-        Argon2::default()
-            .verify_password(password.as_bytes(), &parsed_hash)
-            .is_ok()
+    /// Restrict who may register. Registration is open by default.
+    pub fn with_registration_mode(mut self, registration_mode: RegistrationMode) -> Self {
+        self.registration_mode = registration_mode;
+        self
     }
 
-    fn create_token(&self, user_id: &str, session_id: &str) -> Result<String> {
-        let expiration = Utc::now()
-            .checked_add_signed(chrono::Duration::minutes(15))
-            .expect("valid timestamp")
-            .timestamp() as usize;
-
-        let claims = Claims {
-            sub: user_id.to_string(),
-            sid: session_id.to_string(),
-            exp: expiration,
-        };
-
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )?;
+    /// Encrypt TOTP secrets with a key of their own instead of the JWT secret.
+    pub fn with_totp_encryption_key(mut self, key: &str) -> Self {
+        self.totp_cipher = SecretCipher::new(key);
+        self
+    }
 
-        Ok(token)
+    /// Withhold admin permissions from admins who have not enabled two-factor authentication.
+    pub fn with_require_admin_mfa(mut self, require_admin_mfa: bool) -> Self {
+        self.require_admin_mfa = require_admin_mfa;
+        self
     }
 
-    async fn create_session(
-        &self,
-        user: &User,
-        device_hash: &str,
-        ip: &str,
-    ) -> Result<(String, String)> {
-        // 7 days session TTL
-        let ttl_secs = 7 * 24 * 60 * 60;
+    /// Throttle failed logins per IP and per account. Logins are not throttled by default.
+    pub fn with_login_rate_limiter(mut self, limiter: Arc<dyn LoginRateLimiter>) -> Self {
+        self.login_limiter = Some(limiter);
+        self
+    }
 
-        let session_data = SessionData {
-            user_id: user.id.to_string(),
-            device_hash: device_hash.to_string(),
-            ip: ip.to_string(),
-            created_at: Utc::now().timestamp(),
-            last_active: Utc::now().timestamp(),
-        };
+    /// Record security events such as lockouts in the admin log.
+    pub fn with_admin_log(mut self, admin_log: Arc<dyn AdminLogSink>) -> Self {
+        self.admin_log = admin_log;
+        self
+    }
 
-        let session_id = self
-            .session_store
-            .create(&session_data, ttl_secs)
-            .await
-            .map_err(|e| AuthError::Session(e.to_string()))?;
+    /// Check login passwords against these backends, in order, instead of only the local
+    /// password hashes.
+    pub fn with_authentication_backends(
+        mut self,
+        backends: Vec<Arc<dyn AuthenticationBackend>>,
+    ) -> Self {
+        self.backends = backends;
+        self
+    }
 
-        let token = self.create_token(&user.id.to_string(), &session_id)?;
+    /// URL-safe random string used for invite codes and temporary passwords.
+    fn random_token(bytes: usize) -> String {
+        use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+        use rand::Rng;
 
-        Ok((token, session_id))
+        let mut buf = vec![0u8; bytes];
+        rand::rng().fill_bytes(&mut buf);
+        URL_SAFE_NO_PAD.encode(buf)
     }
-}
 
-#[async_trait]
-impl AuthService for LocalAuthService {
-    async fn register(
-        &self,
-        username: &str,
-        email: &str,
-        password: &str,
-        device_hash: &str,
-        ip: &str,
-    ) -> Result<AuthResponse> {
-        if self
-            .user_repo
-            .find_by_username(username)
+    async fn find_user(&self, user_id: &str) -> Result<User> {
+        let id = Uuid::parse_str(user_id).map_err(|_| AuthError::UserNotFound)?;
+        self.user_repo
+            .find_by_id(id)
             .await
             .map_err(|e| AuthError::Database(e.to_string()))?
-            .is_some()
-        {
-            return Err(AuthError::UserAlreadyExists);
-        }
+            .ok_or(AuthError::UserNotFound)
+    }
 
-        if self
-            .user_repo
-            .find_by_email(email)
-            .await
-            .map_err(|e| AuthError::Database(e.to_string()))?
-            .is_some()
-        {
-            return Err(AuthError::UserAlreadyExists);
+    /// Refuses to take away the last enabled admin account.
+    async fn ensure_other_admin(&self, user: &User) -> Result<()> {
+        if user.role != Role::Admin || user.is_disabled() {
+            return Ok(());
         }
-
-        let password_hash = self.hash_password(password)?;
-
-        let user = CreateUser {
-            username: username.to_string(),
-            email: email.to_string(),
-            password_hash,
-            is_admin: false,
-        };
-
-        let user = self
+        let users = self
             .user_repo
-            .create(user)
+            .list()
             .await
             .map_err(|e| AuthError::Database(e.to_string()))?;
+        let others = users
+            .iter()
+            .any(|u| u.id != user.id && u.role == Role::Admin && !u.is_disabled());
+        if others {
+            Ok(())
+        } else {
+            Err(AuthError::LastAdmin)
+        }
+    }
 
-        let (access_token, session_id) = self.create_session(&user, device_hash, ip).await?;
+    /// Writes an admin log entry. Failures are only traced so they never block a login.
+    async fn audit(&self, level: AdminLogLevel, message: String, details: serde_json::Value) {
+        if let Err(e) = self.admin_log.log(level, message, Some(details)).await {
+            tracing::warn!("Failed to write admin log entry: {e}");
+        }
+    }
 
-        Ok(AuthResponse {
-            token: access_token,
-            session_id,
-            user: AuthUserResponse {
-                id: user.id.to_string(),
-                username: user.username.clone(),
-                email: user.email.clone(),
-                is_admin: user.is_admin,
-            },
-        })
+    /// Adds where a request came from to admin log `details`.
+    fn with_client(details: serde_json::Value, ip: &str, user_agent: &str) -> serde_json::Value {
+        Self::merge_details(
+            details,
+            serde_json::json!({
+                "ip": ip,
+                "device_hash": device_hash(user_agent),
+                "device_name": device_name(user_agent),
+            }),
+        )
     }
 
-    async fn login(
-        &self,
-        username_or_email: &str,
-        password: &str,
-        device_hash: &str,
-        ip: &str,
-    ) -> Result<AuthResponse> {
-        // Try to find by username first, then email
-        let user = if let Some(u) = self
-            .user_repo
-            .find_by_username(username_or_email)
-            .await
-            .map_err(|e| AuthError::Database(e.to_string()))?
-        {
-            Some(u)
+    /// Admin log details for an existing session.
+    fn session_details(session: &SessionData) -> serde_json::Value {
+        let ip = if session.last_ip.is_empty() {
+            &session.ip
         } else {
-            self.user_repo
-                .find_by_email(username_or_email)
-                .await
-                .map_err(|e| AuthError::Database(e.to_string()))?
+            &session.last_ip
         };
+        serde_json::json!({
+            "user_id": session.user_id,
+            "ip": ip,
+            "device_hash": session.device_hash,
+            "device_name": session.device_name,
+        })
+    }
 
-        let user = user.ok_or(AuthError::InvalidCredentials)?;
+    /// Admin log details describing a user, merged with `extra`.
+    fn user_details(user: &User, extra: serde_json::Value) -> serde_json::Value {
+        Self::merge_details(
+            serde_json::json!({
+                "user_id": user.id.to_string(),
+                "username": user.username,
+            }),
+            extra,
+        )
+    }
 
-        if !self.verify_password(password, &user.password_hash) {
-            return Err(AuthError::InvalidCredentials);
+    fn merge_details(
+        mut details: serde_json::Value,
+        extra: serde_json::Value,
+    ) -> serde_json::Value {
+        if let (Some(details), serde_json::Value::Object(extra)) = (details.as_object_mut(), extra)
+        {
+            details.extend(extra);
         }
-
-        let (access_token, session_id) = self.create_session(&user, device_hash, ip).await?;
-
-        Ok(AuthResponse {
-            token: access_token,
-            session_id,
-            user: AuthUserResponse {
-                id: user.id.to_string(),
-                username: user.username,
-                email: user.email,
-                is_admin: user.is_admin,
-            },
-        })
+        details
     }
 
-    async fn refresh(&self, session_id: &str) -> Result<AuthResponse> {
+    /// Deletes a session and returns what it held, if it existed.
+    async fn delete_session(&self, session_id: &str) -> Result<Option<SessionData>> {
         let session = self
             .session_store
             .get(session_id)
             .await
-            .map_err(|e| AuthError::Session(e.to_string()))?
-            .ok_or(AuthError::InvalidCredentials)?;
-
-        // Touch session
-        let ttl_secs = 7 * 24 * 60 * 60;
+            .map_err(|e| AuthError::Session(e.to_string()))?;
         self.session_store
-            .touch(session_id, ttl_secs)
+            .delete(session_id)
             .await
             .map_err(|e| AuthError::Session(e.to_string()))?;
+        Ok(session)
+    }
 
-        // Create new token
-        let access_token = self.create_token(&session.user_id, session_id)?;
+    /// Rate-limit keys for a login attempt: the client IP and the targeted account.
+    ///
+    /// Unknown identifiers are keyed by the identifier itself so probing for accounts is
+    /// throttled the same way as guessing passwords.
+    fn login_keys(ip: &str, account: &str) -> [(String, LockoutPolicy); 2] {
+        [
+            (format!("ip:{ip}"), LockoutPolicy::IP),
+            (
+                format!("account:{}", account.to_lowercase()),
+                LockoutPolicy::ACCOUNT,
+            ),
+        ]
+    }
 
-        // Fetch username for response
-        let user_uuid = Uuid::parse_str(&session.user_id).unwrap_or_default();
-        let user = self
+    /// Fails with [`AuthError::TooManyAttempts`] while any of `keys` is blocked.
+    async fn check_login_throttle(&self, keys: &[(String, LockoutPolicy)]) -> Result<()> {
+        let Some(limiter) = &self.login_limiter else {
+            return Ok(());
+        };
+
+        let mut retry_after_secs = 0;
+        for (key, _) in keys {
+            if let Some(secs) = limiter
+                .blocked_for(key)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?
+            {
+                retry_after_secs = retry_after_secs.max(secs);
+            }
+        }
+
+        if retry_after_secs > 0 {
+            Err(AuthError::TooManyAttempts { retry_after_secs })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Counts a failed login against `keys`, blocking them per their policy and
+    /// logging lockouts.
+    async fn record_login_failure(&self, keys: &[(String, LockoutPolicy)], ip: &str) -> Result<()> {
+        let Some(limiter) = &self.login_limiter else {
+            return Ok(());
+        };
+
+        for (key, policy) in keys {
+            let failures = limiter
+                .record_failure(key, policy.window_secs)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+            let Some(delay) = policy.delay_after(failures) else {
+                continue;
+            };
+            limiter
+                .block(key, delay)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+
+            if policy.is_lockout(failures) {
+                tracing::warn!("Locked out {key} for {delay}s after {failures} failed logins");
+                self.audit(
+                    AdminLogLevel::Warning,
+                    format!("Login locked out for {key} after {failures} failed attempts"),
+                    serde_json::json!({
+                        "key": key,
+                        "ip": ip,
+                        "failures": failures,
+                        "lockout_secs": delay,
+                    }),
+                )
+                .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Clears the failures of an account after a successful login. The IP keeps its
+    /// count so one valid account cannot be used to keep spraying others.
+    async fn reset_login_failures(&self, keys: &[(String, LockoutPolicy)]) -> Result<()> {
+        let Some(limiter) = &self.login_limiter else {
+            return Ok(());
+        };
+
+        for (key, _) in keys.iter().filter(|(key, _)| key.starts_with("account:")) {
+            limiter
+                .reset(key)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Looks up a user and checks `password` against theirs.
+    async fn find_user_with_password(&self, user_id: &str, password: &str) -> Result<User> {
+        let user = self.find_user(user_id).await?;
+        if verify_password(password, &user.password_hash) {
+            Ok(user)
+        } else {
+            Err(AuthError::InvalidCredentials)
+        }
+    }
+
+    async fn update_user(&self, user: &User, update: UpdateUser) -> Result<User> {
+        self.user_repo
+            .update(user.id, update)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .ok_or(AuthError::UserNotFound)
+    }
+
+    async fn account_response(&self, user: User) -> Result<AuthUserResponse> {
+        let permissions = self.user_permissions(&user).await?;
+        Ok(self.user_response(user, permissions))
+    }
+
+    async fn revoke_sessions(&self, user_id: &str) -> Result<u64> {
+        self.session_store
+            .delete_all_for_user(user_id)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))
+    }
+
+    fn hash_password(&self, password: &str) -> Result<String> {
+        let salt = SaltString::generate(&mut OsRng);
+        let argon2 = Argon2::default();
+        let password_hash = argon2
+            .hash_password(password.as_bytes(), &salt)?
+            .to_string();
+        Ok(password_hash)
+    }
+
+    async fn role_permissions(&self, role: Role) -> Result<Vec<Permission>> {
+        self.user_repo
+            .find_role_permissions(role)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))
+    }
+
+    /// Whether the user is an admin who still has to enable two-factor authentication.
+    fn mfa_setup_required(&self, user: &User) -> bool {
+        self.require_admin_mfa && user.role == Role::Admin && !user.mfa_enabled()
+    }
+
+    /// Permissions to put in the user's tokens.
+    async fn user_permissions(&self, user: &User) -> Result<Vec<Permission>> {
+        if user.password_reset_required || self.mfa_setup_required(user) {
+            return Ok(Vec::new());
+        }
+        self.role_permissions(user.role).await
+    }
+
+    fn user_response(&self, user: User, permissions: Vec<Permission>) -> AuthUserResponse {
+        AuthUserResponse {
+            mfa_enabled: user.mfa_enabled(),
+            mfa_setup_required: self.mfa_setup_required(&user),
+            id: user.id.to_string(),
+            username: user.username,
+            email: user.email,
+            display_name: user.display_name,
+            avatar_url: user.avatar_url,
+            is_admin: user.role == Role::Admin,
+            role: user.role,
+            permissions,
+            password_reset_required: user.password_reset_required,
+        }
+    }
+
+    fn create_token(
+        &self,
+        user: &User,
+        session_id: &str,
+        permissions: &[Permission],
+    ) -> Result<String> {
+        let expiration = Utc::now()
+            .checked_add_signed(chrono::Duration::minutes(15))
+            .expect("valid timestamp")
+            .timestamp() as usize;
+
+        let claims = Claims {
+            sub: user.id.to_string(),
+            sid: session_id.to_string(),
+            role: user.role,
+            permissions: permissions.to_vec(),
+            password_reset: user.password_reset_required,
+            exp: expiration,
+        };
+
+        Ok(self.token_keys.sign(TokenKind::Access, &claims)?)
+    }
+
+    fn create_mfa_challenge(&self, user: &User) -> Result<String> {
+        let expiration = Utc::now()
+            .checked_add_signed(chrono::Duration::minutes(5))
+            .expect("valid timestamp")
+            .timestamp() as usize;
+
+        let claims = MfaChallengeClaims {
+            sub: user.id.to_string(),
+            exp: expiration,
+        };
+
+        Ok(self.token_keys.sign(TokenKind::MfaChallenge, &claims)?)
+    }
+
+    /// Returns the user ID a valid challenge token was issued for.
+    fn verify_mfa_challenge(&self, challenge_token: &str) -> Result<String> {
+        let claims = self
+            .token_keys
+            .verify::<MfaChallengeClaims>(TokenKind::MfaChallenge, challenge_token)?;
+        Ok(claims.sub)
+    }
+
+    fn totp_secret(&self, user: &User) -> Result<Vec<u8>> {
+        let encrypted = user
+            .totp_secret
+            .as_deref()
+            .ok_or(AuthError::MfaNotEnrolled)?;
+        self.totp_cipher.decrypt(encrypted).ok_or_else(|| {
+            tracing::warn!(
+                "Cannot decrypt TOTP secret of user {}; was the encryption key changed?",
+                user.id
+            );
+            AuthError::InvalidMfaCode
+        })
+    }
+
+    /// Accepts a TOTP code once: codes from the step of the last accepted one or earlier
+    /// are refused, so an observed code cannot be replayed.
+    async fn verify_totp_code(&self, user: &User, code: &str) -> Result<()> {
+        let secret = self.totp_secret(user)?;
+        let step =
+            totp::verify(&secret, code, Utc::now().timestamp()).ok_or(AuthError::InvalidMfaCode)?;
+        let fresh = self
+            .user_repo
+            .record_totp_step(user.id, step)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        if fresh {
+            Ok(())
+        } else {
+            Err(AuthError::InvalidMfaCode)
+        }
+    }
+
+    /// Accepts a TOTP code, or a recovery code which is then used up.
+    async fn verify_second_factor(&self, user: &User, code: &str) -> Result<()> {
+        match self.verify_totp_code(user, code).await {
+            Err(AuthError::InvalidMfaCode) => {}
+            result => return result,
+        }
+
+        // Removing the code is the check, so two logins cannot both use it
+        let used = self
+            .user_repo
+            .consume_recovery_code(user.id, &totp::hash_recovery_code(code))
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        if used {
+            Ok(())
+        } else {
+            Err(AuthError::InvalidMfaCode)
+        }
+    }
+
+    /// Generates fresh recovery codes, stores their hashes and returns them.
+    async fn replace_recovery_codes(&self, user: &User, update: UpdateUser) -> Result<Vec<String>> {
+        let codes = totp::generate_recovery_codes();
+        self.update_user(
+            user,
+            UpdateUser {
+                totp_recovery_codes: Some(
+                    codes.iter().map(|c| totp::hash_recovery_code(c)).collect(),
+                ),
+                ..update
+            },
+        )
+        .await?;
+        Ok(codes)
+    }
+
+    /// A new refresh token secret and the hash stored for it.
+    fn new_refresh_secret() -> (String, String) {
+        let secret = Self::random_token(32);
+        let hash = Self::hash_refresh_secret(&secret);
+        (secret, hash)
+    }
+
+    fn hash_refresh_secret(secret: &str) -> String {
+        use sha2::{Digest, Sha256};
+
+        format!("{:x}", Sha256::digest(secret.as_bytes()))
+    }
+
+    /// Checks an API key and lets it act for its user within its scopes.
+    async fn verify_api_key(&self, key: &str) -> Result<AuthenticatedUser> {
+        let api_key = self
+            .user_repo
+            .find_api_key_by_hash(&Self::hash_refresh_secret(key))
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .ok_or(AuthError::InvalidCredentials)?;
+        let user = self
+            .user_repo
+            .find_by_id(api_key.user_id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .filter(|user| !user.is_disabled())
+            .ok_or(AuthError::InvalidCredentials)?;
+        if user.password_reset_required {
+            return Err(AuthError::PasswordResetRequired);
+        }
+
+        let now = Utc::now();
+        if api_key
+            .last_used_at
+            .is_none_or(|at| (now - at).num_seconds() >= API_KEY_TOUCH_INTERVAL_SECS)
+        {
+            self.user_repo
+                .touch_api_key(api_key.id, now)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?;
+        }
+
+        let permissions = self
+            .user_permissions(&user)
+            .await?
+            .into_iter()
+            .filter(|p| api_key.scopes.iter().any(|s| s.permissions().contains(p)))
+            .collect();
+        Ok(AuthenticatedUser {
+            user_id: user.id.to_string(),
+            session_id: format!("{API_KEY_SESSION_PREFIX}{}", api_key.id),
+            role: user.role,
+            permissions,
+            api_key_id: Some(api_key.id.to_string()),
+        })
+    }
+
+    /// Checks an access token or API key. Users who must change their password are only
+    /// let through with `allow_password_reset`.
+    async fn verify_access(
+        &self,
+        token: &str,
+        allow_password_reset: bool,
+    ) -> Result<AuthenticatedUser> {
+        if token.starts_with(API_KEY_PREFIX) {
+            return self.verify_api_key(token).await;
+        }
+        self.token_keys.refresh_for(token).await;
+        let claims = self.token_keys.verify::<Claims>(TokenKind::Access, token)?;
+        if claims.password_reset && !allow_password_reset {
+            return Err(AuthError::PasswordResetRequired);
+        }
+
+        // Verify session still exists
+        let session_id = claims.sid;
+        let session = self
+            .session_store
+            .get(&session_id)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+
+        if session.is_none() {
+            return Err(AuthError::InvalidCredentials); // Session revoked/expired
+        }
+
+        Ok(AuthenticatedUser {
+            user_id: claims.sub,
+            session_id,
+            role: claims.role,
+            permissions: claims.permissions,
+            api_key_id: None,
+        })
+    }
+
+    /// The user an API key may still stream for: the key must keep its `stream` scope
+    /// and its owner must be able to use it.
+    async fn stream_api_key_owner(&self, key_id: &str) -> Result<Option<String>> {
+        let Ok(id) = Uuid::parse_str(key_id) else {
+            return Ok(None);
+        };
+        let Some(api_key) = self
+            .user_repo
+            .find_api_key(id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .filter(|key| key.scopes.contains(&ApiKeyScope::Stream))
+        else {
+            return Ok(None);
+        };
+        let owner = self
+            .user_repo
+            .find_by_id(api_key.user_id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .filter(|user| !user.is_disabled() && !user.password_reset_required);
+        Ok(owner.map(|user| user.id.to_string()))
+    }
+
+    /// A device still waiting for someone to approve or deny it.
+    async fn find_pending_pairing(&self, user_code: &str) -> Result<DeviceAuthorization> {
+        self.session_store
+            .find_device_authorization(&normalize_user_code(user_code))
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?
+            .filter(|pairing| {
+                pairing.approved_by.is_none()
+                    && pairing.denied_by.is_none()
+                    && pairing.expires_at > Utc::now().timestamp()
+            })
+            .ok_or(AuthError::PairingCodeNotFound)
+    }
+
+    /// Records an enabled user's decision on a waiting device.
+    ///
+    /// Only the first decision counts; a device approved or denied in the meantime is
+    /// reported as not found.
+    async fn decide_pending_pairing(
+        &self,
+        user_id: &str,
+        user_code: &str,
+        decision: DeviceDecision,
+    ) -> Result<(User, DeviceAuthorization)> {
+        let user = self.find_user(user_id).await?;
+        if user.is_disabled() {
+            return Err(AuthError::AccountDisabled);
+        }
+        let pairing = self.find_pending_pairing(user_code).await?;
+        let decided = self
+            .session_store
+            .decide_device_authorization(&pairing.device_code_hash, &user.id.to_string(), decision)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+        if !decided {
+            return Err(AuthError::PairingCodeNotFound);
+        }
+        Ok((user, pairing))
+    }
+
+    /// Refresh tokens are `<session id>.<secret>` so the session can be looked up directly.
+    fn split_refresh_token(refresh_token: &str) -> Option<(&str, &str)> {
+        refresh_token
+            .split_once('.')
+            .filter(|(session_id, secret)| !session_id.is_empty() && !secret.is_empty())
+    }
+
+    /// Someone is replaying a refresh token that was already exchanged, so either the client
+    /// or an attacker holds a stolen copy. Revokes the session for both.
+    async fn revoke_reused_refresh_token(
+        &self,
+        session_id: &str,
+        session: &SessionData,
+        ip: &str,
+    ) -> AuthError {
+        tracing::warn!("Refresh token reuse detected for session {session_id}");
+        if let Err(err) = self.delete_session(session_id).await {
+            return err;
+        }
+        let mut details = Self::session_details(session);
+        details["presented_from_ip"] = ip.into();
+        self.audit(
+            AdminLogLevel::Warning,
+            "Refresh token reuse detected; session revoked".to_string(),
+            details,
+        )
+        .await;
+        AuthError::RefreshTokenReused
+    }
+
+    /// Asks each backend in turn. One that cannot answer does not stop the rest from
+    /// being asked; its error is returned only when none of them accepts the password.
+    async fn authenticate(
+        &self,
+        username_or_email: &str,
+        password: &str,
+    ) -> Result<Option<Authenticated>> {
+        let mut error = None;
+        for backend in &self.backends {
+            match backend.authenticate(username_or_email, password).await {
+                Ok(Some(authenticated)) => return Ok(Some(authenticated)),
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!("Authentication backend failed: {e}");
+                    error = Some(e);
+                }
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
+
+    /// Finds or creates the user an external identity signs in as.
+    async fn identity_user(&self, identity: &ExternalIdentity) -> Result<User> {
+        if let Some(user) = self
+            .user_repo
+            .find_by_identity(&identity.provider, &identity.subject)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+        {
+            return Ok(user);
+        }
+
+        let existing = match &identity.email {
+            Some(email) => self
+                .user_repo
+                .find_by_email(email)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?,
+            None => None,
+        };
+        let (user, created) = match existing {
+            Some(user) if identity.link_by_email && identity.email_verified => (user, false),
+            // Never hand over an account on the strength of an unverified email
+            Some(_) => return Err(AuthError::UserAlreadyExists),
+            None if !identity.create_user => return Err(AuthError::RegistrationClosed),
+            None => (self.create_identity_user(identity).await?, true),
+        };
+
+        self.user_repo
+            .link_identity(user.id, &identity.provider, &identity.subject)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            if created {
+                format!("User {} created from {}", user.username, identity.provider)
+            } else {
+                format!("{} account linked to {}", identity.provider, user.username)
+            },
+            Self::user_details(
+                &user,
+                serde_json::json!({
+                    "provider": identity.provider,
+                    "subject": identity.subject,
+                    "role": user.role.as_str(),
+                }),
+            ),
+        )
+        .await;
+        Ok(user)
+    }
+
+    /// Creates a user for an external identity, with a random password nobody knows.
+    async fn create_identity_user(&self, identity: &ExternalIdentity) -> Result<User> {
+        let email = identity.email.as_deref().ok_or(AuthError::MissingEmail)?;
+        let first_user = self
+            .user_repo
+            .count()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            == 0;
+
+        let base = identity
+            .username
+            .as_deref()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or(email))
+            .trim()
+            .to_string();
+        let mut username = base.clone();
+        let mut suffix = 1;
+        while self
+            .user_repo
+            .find_by_username(&username)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .is_some()
+        {
+            suffix += 1;
+            username = format!("{base}{suffix}");
+        }
+
+        let new_user = CreateUser {
+            username,
+            email: email.to_string(),
+            password_hash: self.hash_password(&Self::random_token(32))?,
+            role: if identity.is_admin == Some(true) {
+                Role::Admin
+            } else {
+                Role::default()
+            },
+        };
+
+        // Of several concurrent first sign-ins only one becomes the admin
+        let first_admin = if first_user {
+            self.user_repo
+                .create_first(CreateUser {
+                    role: Role::Admin,
+                    ..new_user.clone()
+                })
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+        } else {
+            None
+        };
+        let user = match first_admin {
+            Some(user) => user,
+            None => self
+                .user_repo
+                .create(new_user)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?,
+        };
+
+        if let Some(display_name) = &identity.display_name {
+            return self
+                .update_user(
+                    &user,
+                    UpdateUser {
+                        display_name: Some(Some(display_name.clone())),
+                        ..Default::default()
+                    },
+                )
+                .await;
+        }
+        Ok(user)
+    }
+
+    /// Grants or takes away admin as the identity provider says, keeping the last admin.
+    async fn sync_identity_admin(&self, user: User, identity: &ExternalIdentity) -> Result<User> {
+        let role = match identity.is_admin {
+            Some(true) if user.role != Role::Admin => Role::Admin,
+            Some(false) if user.role == Role::Admin => match self.ensure_other_admin(&user).await {
+                Ok(()) => Role::default(),
+                Err(AuthError::LastAdmin) => {
+                    tracing::warn!(
+                        "Keeping {} as admin although {} does not list them as one: \
+                         they are the last admin",
+                        user.username,
+                        identity.provider
+                    );
+                    return Ok(user);
+                }
+                Err(e) => return Err(e),
+            },
+            _ => return Ok(user),
+        };
+
+        let previous_role = user.role;
+        let user = self
+            .user_repo
+            .update_role(user.id, role)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .ok_or(AuthError::UserNotFound)?;
+        self.audit(
+            AdminLogLevel::Warning,
+            format!(
+                "Role of {} changed from {} to {} by {}",
+                user.username,
+                previous_role.as_str(),
+                role.as_str(),
+                identity.provider
+            ),
+            Self::user_details(
+                &user,
+                serde_json::json!({
+                    "previous_role": previous_role.as_str(),
+                    "role": role.as_str(),
+                    "provider": identity.provider,
+                }),
+            ),
+        )
+        .await;
+        Ok(user)
+    }
+
+    /// Starts a session for a user who has fully authenticated.
+    async fn create_session(&self, user: User, user_agent: &str, ip: &str) -> Result<AuthResponse> {
+        self.create_device_session(user, device_hash(user_agent), device_name(user_agent), ip)
+            .await
+    }
+
+    /// Starts a session on a device that was identified some other way than by the
+    /// request's user-agent.
+    async fn create_device_session(
+        &self,
+        user: User,
+        device_hash: String,
+        device_name: String,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        let (refresh_secret, refresh_token_hash) = Self::new_refresh_secret();
+
+        let session_data = SessionData {
+            user_id: user.id.to_string(),
+            device_hash,
+            device_name,
+            ip: ip.to_string(),
+            last_ip: ip.to_string(),
+            created_at: Utc::now().timestamp(),
+            last_active: Utc::now().timestamp(),
+            refresh_token_hash,
+            retired_refresh_token_hashes: Vec::new(),
+        };
+
+        let session_id = self
+            .session_store
+            .create(&session_data, SESSION_TTL_SECS)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+
+        let permissions = self.user_permissions(&user).await?;
+        let token = self.create_token(&user, &session_id, &permissions)?;
+
+        Ok(AuthResponse {
+            token,
+            refresh_token: format!("{session_id}.{refresh_secret}"),
+            session_id,
+            user: self.user_response(user, permissions),
+        })
+    }
+}
+
+#[async_trait]
+impl AuthService for LocalAuthService {
+    async fn register(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        self.register_with_invite(username, email, password, None, user_agent, ip)
+            .await
+    }
+
+    async fn register_with_invite(
+        &self,
+        username: &str,
+        email: &str,
+        password: &str,
+        invite_code: Option<&str>,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        // The first account bootstraps the server as its admin
+        let first_user = self
+            .user_repo
+            .count()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            == 0;
+
+        let invite = match invite_code {
+            _ if first_user => None,
+            _ if self.registration_mode == RegistrationMode::Closed => {
+                return Err(AuthError::RegistrationClosed);
+            }
+            Some(code) => {
+                let invite = self
+                    .user_repo
+                    .find_invite(code)
+                    .await
+                    .map_err(|e| AuthError::Database(e.to_string()))?
+                    .filter(|invite| invite.is_redeemable(Utc::now()))
+                    .ok_or(AuthError::InvalidInvite)?;
+                Some(invite)
+            }
+            None if self.registration_mode == RegistrationMode::Invite => {
+                return Err(AuthError::RegistrationClosed);
+            }
+            None => None,
+        };
+
+        if self
+            .user_repo
+            .find_by_username(username)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .is_some()
+        {
+            return Err(AuthError::UserAlreadyExists);
+        }
+
+        if self
+            .user_repo
+            .find_by_email(email)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .is_some()
+        {
+            return Err(AuthError::UserAlreadyExists);
+        }
+
+        let password_hash = self.hash_password(password)?;
+
+        let user = CreateUser {
+            username: username.to_string(),
+            email: email.to_string(),
+            password_hash,
+            role: match &invite {
+                _ if first_user => Role::Admin,
+                Some(invite) => invite.role,
+                None => Role::default(),
+            },
+        };
+
+        let user = if first_user {
+            // Of several concurrent first registrations only one becomes the admin; the
+            // others start over under the usual registration rules
+            match self
+                .user_repo
+                .create_first(user)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+            {
+                Some(user) => user,
+                None => {
+                    return self
+                        .register_with_invite(
+                            username,
+                            email,
+                            password,
+                            invite_code,
+                            user_agent,
+                            ip,
+                        )
+                        .await;
+                }
+            }
+        } else {
+            self.user_repo
+                .create(user)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+        };
+
+        if let Some(invite) = &invite {
+            let redeemed = self
+                .user_repo
+                .redeem_invite(&invite.code, user.id)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?;
+            if !redeemed {
+                // Someone else used the invite first
+                self.user_repo
+                    .delete(user.id)
+                    .await
+                    .map_err(|e| AuthError::Database(e.to_string()))?;
+                return Err(AuthError::InvalidInvite);
+            }
+        }
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("User {} registered", user.username),
+            Self::with_client(
+                Self::user_details(
+                    &user,
+                    serde_json::json!({
+                        "role": user.role.as_str(),
+                        "invite_code": invite.as_ref().map(|i| &i.code),
+                    }),
+                ),
+                ip,
+                user_agent,
+            ),
+        )
+        .await;
+
+        self.create_session(user, user_agent, ip).await
+    }
+
+    async fn login(
+        &self,
+        username_or_email: &str,
+        password: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        // Failures count against the local account when there is one, whichever backend
+        // checks the password. Try to find by username first, then email.
+        let user = if let Some(u) = self
+            .user_repo
+            .find_by_username(username_or_email)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+        {
+            Some(u)
+        } else {
+            self.user_repo
+                .find_by_email(username_or_email)
+                .await
+                .map_err(|e| AuthError::Database(e.to_string()))?
+        };
+
+        let account = user
+            .as_ref()
+            .map_or_else(|| username_or_email.to_string(), |u| u.id.to_string());
+        let keys = Self::login_keys(ip, &account);
+        self.check_login_throttle(&keys).await?;
+
+        let Some(authenticated) = self.authenticate(username_or_email, password).await? else {
+            self.audit(
+                AdminLogLevel::Warning,
+                format!("Failed login for {username_or_email}"),
+                Self::with_client(
+                    serde_json::json!({
+                        "identifier": username_or_email,
+                        "reason": "invalid_credentials",
+                    }),
+                    ip,
+                    user_agent,
+                ),
+            )
+            .await;
+            self.record_login_failure(&keys, ip).await?;
+            return Err(AuthError::InvalidCredentials);
+        };
+        let (user, identity) = match authenticated {
+            Authenticated::Local(user) => (user, None),
+            Authenticated::External(identity) => {
+                (self.identity_user(&identity).await?, Some(identity))
+            }
+        };
+        let provider = identity.as_ref().map(|identity| identity.provider.as_str());
+
+        if user.is_disabled() {
+            self.audit(
+                AdminLogLevel::Warning,
+                format!("Login to disabled account {}", user.username),
+                Self::with_client(
+                    Self::user_details(&user, serde_json::json!({ "provider": provider })),
+                    ip,
+                    user_agent,
+                ),
+            )
+            .await;
+            return Err(AuthError::AccountDisabled);
+        }
+        let user = match &identity {
+            Some(identity) => self.sync_identity_admin(user, identity).await?,
+            None => user,
+        };
+
+        if user.mfa_enabled() {
+            return Err(AuthError::MfaRequired {
+                challenge_token: self.create_mfa_challenge(&user)?,
+            });
+        }
+        self.reset_login_failures(&keys).await?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            match provider {
+                Some(provider) => format!("User {} logged in with {provider}", user.username),
+                None => format!("User {} logged in", user.username),
+            },
+            Self::with_client(
+                Self::user_details(
+                    &user,
+                    serde_json::json!({ "mfa": false, "provider": provider }),
+                ),
+                ip,
+                user_agent,
+            ),
+        )
+        .await;
+        self.create_session(user, user_agent, ip).await
+    }
+
+    async fn login_with_identity(
+        &self,
+        identity: &ExternalIdentity,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        let user = self.identity_user(identity).await?;
+
+        if user.is_disabled() {
+            self.audit(
+                AdminLogLevel::Warning,
+                format!("Login to disabled account {}", user.username),
+                Self::with_client(
+                    Self::user_details(&user, serde_json::json!({ "provider": identity.provider })),
+                    ip,
+                    user_agent,
+                ),
+            )
+            .await;
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let user = self.sync_identity_admin(user, identity).await?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!(
+                "User {} logged in with {}",
+                user.username, identity.provider
+            ),
+            Self::with_client(
+                Self::user_details(&user, serde_json::json!({ "provider": identity.provider })),
+                ip,
+                user_agent,
+            ),
+        )
+        .await;
+        self.create_session(user, user_agent, ip).await
+    }
+
+    async fn complete_mfa_login(
+        &self,
+        challenge_token: &str,
+        code: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<AuthResponse> {
+        let user_id = self.verify_mfa_challenge(challenge_token)?;
+        let user = self
+            .find_user(&user_id)
+            .await
+            .map_err(|_| AuthError::InvalidCredentials)?;
+
+        if user.is_disabled() {
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let keys = Self::login_keys(ip, &user_id);
+        self.check_login_throttle(&keys).await?;
+        if let Err(err) = self.verify_second_factor(&user, code).await {
+            if matches!(err, AuthError::InvalidMfaCode) {
+                self.audit(
+                    AdminLogLevel::Warning,
+                    format!("Failed two-factor code for {}", user.username),
+                    Self::with_client(
+                        Self::user_details(
+                            &user,
+                            serde_json::json!({ "reason": "invalid_mfa_code" }),
+                        ),
+                        ip,
+                        user_agent,
+                    ),
+                )
+                .await;
+                self.record_login_failure(&keys, ip).await?;
+            }
+            return Err(err);
+        }
+        self.reset_login_failures(&keys).await?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("User {} logged in", user.username),
+            Self::with_client(
+                Self::user_details(&user, serde_json::json!({ "mfa": true })),
+                ip,
+                user_agent,
+            ),
+        )
+        .await;
+        self.create_session(user, user_agent, ip).await
+    }
+
+    async fn start_device_authorization(
+        &self,
+        device_name: &str,
+        user_agent: &str,
+        ip: &str,
+    ) -> Result<DeviceCode> {
+        let device_name = match device_name.trim() {
+            "" => self::device_name(user_agent),
+            name => name.chars().take(DEVICE_NAME_MAX_CHARS).collect(),
+        };
+        let device_code = Self::random_token(32);
+        let now = Utc::now().timestamp();
+
+        // User codes are short enough that two waiting devices could draw the same one
+        for _ in 0..5 {
+            let pairing = DeviceAuthorization {
+                device_code_hash: Self::hash_refresh_secret(&device_code),
+                user_code: generate_user_code(),
+                device_name: device_name.clone(),
+                device_hash: device_hash(user_agent),
+                ip: ip.to_string(),
+                created_at: now,
+                expires_at: now + DEVICE_CODE_TTL_SECS as i64,
+                last_polled_at: None,
+                approved_by: None,
+                denied_by: None,
+            };
+            let created = self
+                .session_store
+                .create_device_authorization(&pairing, DEVICE_CODE_TTL_SECS)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+            if created {
+                return Ok(DeviceCode {
+                    device_code,
+                    user_code: format_user_code(&pairing.user_code),
+                    expires_in: DEVICE_CODE_TTL_SECS,
+                    interval: DEVICE_POLL_INTERVAL_SECS,
+                });
+            }
+        }
+        Err(AuthError::Session(
+            "Could not find a free pairing code".to_string(),
+        ))
+    }
+
+    async fn poll_device_authorization(&self, device_code: &str, ip: &str) -> Result<AuthResponse> {
+        let now = Utc::now().timestamp();
+        let pairing = self
+            .session_store
+            .get_device_authorization(&Self::hash_refresh_secret(device_code))
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?
+            .filter(|pairing| pairing.expires_at > now)
+            .ok_or(AuthError::DevicePairing("expired_token"))?;
+
+        if pairing.denied_by.is_some() {
+            self.session_store
+                .delete_device_authorization(&pairing)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+            return Err(AuthError::DevicePairing("access_denied"));
+        }
+
+        let Some(user_id) = pairing.approved_by.clone() else {
+            let too_soon = pairing
+                .last_polled_at
+                .is_some_and(|at| now - at < DEVICE_POLL_INTERVAL_SECS as i64);
+            self.session_store
+                .record_device_poll(&pairing.device_code_hash, now)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?;
+            return Err(AuthError::DevicePairing(if too_soon {
+                "slow_down"
+            } else {
+                "authorization_pending"
+            }));
+        };
+
+        // Whoever removes the pairing gets the session, so two polls cannot both sign in
+        let taken = self
+            .session_store
+            .delete_device_authorization(&pairing)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+        if !taken {
+            return Err(AuthError::DevicePairing("expired_token"));
+        }
+
+        let user = self
+            .find_user(&user_id)
+            .await
+            .map_err(|_| AuthError::DevicePairing("access_denied"))?;
+        if user.is_disabled() {
+            return Err(AuthError::AccountDisabled);
+        }
+        self.create_device_session(user, pairing.device_hash, pairing.device_name, ip)
+            .await
+    }
+
+    async fn get_pending_device(&self, user_code: &str) -> Result<PendingDevice> {
+        Ok(self.find_pending_pairing(user_code).await?.into())
+    }
+
+    async fn approve_device(&self, user_id: &str, user_code: &str) -> Result<PendingDevice> {
+        let (user, pairing) = self
+            .decide_pending_pairing(user_id, user_code, DeviceDecision::Approve)
+            .await?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("Device {} paired by {}", pairing.device_name, user.username),
+            Self::user_details(
+                &user,
+                serde_json::json!({
+                    "ip": pairing.ip,
+                    "device_hash": pairing.device_hash,
+                    "device_name": pairing.device_name,
+                }),
+            ),
+        )
+        .await;
+        Ok(pairing.into())
+    }
+
+    async fn deny_device(&self, user_id: &str, user_code: &str) -> Result<()> {
+        let (user, pairing) = self
+            .decide_pending_pairing(user_id, user_code, DeviceDecision::Deny)
+            .await?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("Device {} denied by {}", pairing.device_name, user.username),
+            Self::user_details(
+                &user,
+                serde_json::json!({
+                    "ip": pairing.ip,
+                    "device_hash": pairing.device_hash,
+                    "device_name": pairing.device_name,
+                }),
+            ),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn refresh(&self, refresh_token: &str, ip: &str) -> Result<AuthResponse> {
+        let (session_id, secret) =
+            Self::split_refresh_token(refresh_token).ok_or(AuthError::InvalidCredentials)?;
+
+        let mut session = self
+            .session_store
+            .get(session_id)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?
+            .ok_or(AuthError::InvalidCredentials)?;
+
+        let presented_hash = Self::hash_refresh_secret(secret);
+        if session
+            .retired_refresh_token_hashes
+            .contains(&presented_hash)
+        {
+            return Err(self
+                .revoke_reused_refresh_token(session_id, &session, ip)
+                .await);
+        }
+        if session.refresh_token_hash != presented_hash {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        let (new_secret, new_hash) = Self::new_refresh_secret();
+        let retired = std::mem::replace(&mut session.refresh_token_hash, new_hash);
+        session.retired_refresh_token_hashes.push(retired);
+        let excess = session
+            .retired_refresh_token_hashes
+            .len()
+            .saturating_sub(RETIRED_REFRESH_TOKENS);
+        session.retired_refresh_token_hashes.drain(..excess);
+
+        session.last_ip = ip.to_string();
+        session.last_active = Utc::now().timestamp();
+        let rotated = self
+            .session_store
+            .rotate_refresh_hash(session_id, &presented_hash, &session)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+        if !rotated {
+            // Another refresh exchanged the same token in the meantime
+            return Err(self
+                .revoke_reused_refresh_token(session_id, &session, ip)
+                .await);
+        }
+
+        // Touch session
+        self.session_store
+            .touch(session_id, SESSION_TTL_SECS)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+
+        // Fetch the user so the new token carries their current role
+        let user_uuid = Uuid::parse_str(&session.user_id).unwrap_or_default();
+        let user = self
             .user_repo
             .find_by_id(user_uuid)
             .await
             .map_err(|e| AuthError::Database(e.to_string()))?
             .ok_or(AuthError::InvalidCredentials)?;
 
+        if user.is_disabled() {
+            return Err(AuthError::AccountDisabled);
+        }
+
+        let permissions = self.user_permissions(&user).await?;
+        let access_token = self.create_token(&user, session_id, &permissions)?;
+
         Ok(AuthResponse {
             token: access_token,
             session_id: session_id.to_string(),
-            user: AuthUserResponse {
-                id: session.user_id,
-                username: user.username,
-                email: user.email,
-                is_admin: user.is_admin,
-            },
+            refresh_token: format!("{session_id}.{new_secret}"),
+            user: self.user_response(user, permissions),
         })
     }
 
     async fn verify_token(&self, token: &str) -> Result<AuthenticatedUser> {
-        let validation = Validation::default();
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &validation,
-        )?;
+        self.verify_access(token, false).await
+    }
 
-        // Verify session still exists
-        let session_id = token_data.claims.sid;
-        let session = self
-            .session_store
-            .get(&session_id)
-            .await
-            .map_err(|e| AuthError::Session(e.to_string()))?;
+    async fn verify_account_token(&self, token: &str) -> Result<AuthenticatedUser> {
+        self.verify_access(token, true).await
+    }
 
-        if session.is_none() {
-            return Err(AuthError::InvalidCredentials); // Session revoked/expired
+    async fn logout(&self, session_id: &str) -> Result<()> {
+        if let Some(session) = self.delete_session(session_id).await? {
+            self.audit(
+                AdminLogLevel::Info,
+                "User logged out".to_string(),
+                Self::session_details(&session),
+            )
+            .await;
         }
-
-        Ok(AuthenticatedUser {
-            user_id: token_data.claims.sub,
-            session_id,
-        })
+        Ok(())
     }
 
-    async fn logout(&self, session_id: &str) -> Result<()> {
-        self.session_store
-            .delete(session_id)
+    async fn logout_refresh_token(&self, refresh_token: &str) -> Result<()> {
+        let Some((session_id, secret)) = Self::split_refresh_token(refresh_token) else {
+            return Ok(());
+        };
+        let Some(session) = self
+            .session_store
+            .get(session_id)
             .await
-            .map_err(|e| AuthError::Session(e.to_string()))
+            .map_err(|e| AuthError::Session(e.to_string()))?
+        else {
+            return Ok(());
+        };
+
+        // Old tokens count too: their holder was entitled to the session at some point
+        let hash = Self::hash_refresh_secret(secret);
+        if session.refresh_token_hash == hash
+            || session.retired_refresh_token_hashes.contains(&hash)
+        {
+            self.logout(session_id).await?;
+        }
+        Ok(())
     }
 
     async fn logout_all(&self, user_id: &str) -> Result<u64> {
-        self.session_store
-            .delete_all_for_user(user_id)
-            .await
-            .map_err(|e| AuthError::Session(e.to_string()))
+        let revoked = self.revoke_sessions(user_id).await?;
+        self.audit(
+            AdminLogLevel::Info,
+            "User logged out of all sessions".to_string(),
+            serde_json::json!({ "user_id": user_id, "sessions": revoked }),
+        )
+        .await;
+        Ok(revoked)
     }
 
     async fn get_sessions(&self, user_id: &str) -> Result<Vec<(String, SessionData)>> {
@@ -392,7 +2171,39 @@ impl AuthService for LocalAuthService {
             .map_err(|e| AuthError::Session(e.to_string()))
     }
 
-    fn create_stream_token(&self, user_id: &str, stream_id: &str) -> Result<String> {
+    async fn revoke_session(&self, user_id: &str, session_id: &str) -> Result<()> {
+        let session = self
+            .session_store
+            .get(session_id)
+            .await
+            .map_err(|e| AuthError::Session(e.to_string()))?;
+
+        match session {
+            Some(session) if session.user_id == user_id => {
+                self.delete_session(session_id).await?;
+                self.audit(
+                    AdminLogLevel::Info,
+                    "Session revoked".to_string(),
+                    Self::session_details(&session),
+                )
+                .await;
+                Ok(())
+            }
+            _ => Err(AuthError::SessionNotFound),
+        }
+    }
+
+    fn jwks(&self) -> JwkSet {
+        self.token_keys.jwks()
+    }
+
+    fn create_stream_token(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        stream_id: &str,
+        scope: &StreamScope,
+    ) -> Result<String> {
         let expiration = Utc::now()
             .checked_add_signed(chrono::Duration::hours(6)) // 6 hours validity
             .expect("valid timestamp")
@@ -400,27 +2211,441 @@ impl AuthService for LocalAuthService {
 
         let claims = StreamClaims {
             sub: user_id.to_string(),
+            sid: session_id.to_string(),
             stream_id: stream_id.to_string(),
+            scope: scope.clone(),
             exp: expiration,
         };
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )?;
+        Ok(self.stream_keys.sign(TokenKind::Stream, &claims)?)
+    }
+
+    async fn verify_stream_token(&self, token: &str) -> Result<StreamGrant> {
+        let claims = self
+            .stream_keys
+            .verify::<StreamClaims>(TokenKind::Stream, token)?;
+
+        // Stream tokens end with the session or API key they were issued to
+        let owner = match claims.sid.strip_prefix(API_KEY_SESSION_PREFIX) {
+            Some(key_id) => self.stream_api_key_owner(key_id).await?,
+            None => self
+                .session_store
+                .get(&claims.sid)
+                .await
+                .map_err(|e| AuthError::Session(e.to_string()))?
+                .map(|session| session.user_id),
+        };
+        if owner.is_none_or(|owner| owner != claims.sub) {
+            return Err(AuthError::InvalidCredentials);
+        }
+
+        Ok(StreamGrant {
+            user_id: claims.sub,
+            session_id: claims.sid,
+            stream_id: claims.stream_id,
+            scope: claims.scope,
+        })
+    }
+
+    async fn get_account(&self, user_id: &str) -> Result<AuthUserResponse> {
+        let user = self.find_user(user_id).await?;
+        self.account_response(user).await
+    }
+
+    async fn change_password(
+        &self,
+        user_id: &str,
+        session_id: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<u64> {
+        let user = self
+            .find_user_with_password(user_id, current_password)
+            .await?;
+
+        let password_hash = self.hash_password(new_password)?;
+        self.update_user(
+            &user,
+            UpdateUser {
+                password_hash: Some(password_hash),
+                password_reset_required: Some(false),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        // Sign out everywhere else in case the old password leaked
+        let sessions = self.get_sessions(&user.id.to_string()).await?;
+        let mut revoked = 0;
+        for (other_id, _) in sessions.iter().filter(|(id, _)| id != session_id) {
+            self.delete_session(other_id).await?;
+            revoked += 1;
+        }
+        self.audit(
+            AdminLogLevel::Info,
+            format!("User {} changed their password", user.username),
+            Self::user_details(&user, serde_json::json!({ "revoked_sessions": revoked })),
+        )
+        .await;
+        Ok(revoked)
+    }
+
+    async fn change_email(
+        &self,
+        user_id: &str,
+        password: &str,
+        new_email: &str,
+    ) -> Result<AuthUserResponse> {
+        let user = self.find_user_with_password(user_id, password).await?;
+
+        if let Some(existing) = self
+            .user_repo
+            .find_by_email(new_email)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            && existing.id != user.id
+        {
+            return Err(AuthError::UserAlreadyExists);
+        }
+
+        let user = self
+            .update_user(
+                &user,
+                UpdateUser {
+                    email: Some(new_email.to_string()),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.account_response(user).await
+    }
+
+    async fn update_profile(
+        &self,
+        user_id: &str,
+        display_name: Option<String>,
+        avatar_url: Option<String>,
+    ) -> Result<AuthUserResponse> {
+        let user = self.find_user(user_id).await?;
+        let user = self
+            .update_user(
+                &user,
+                UpdateUser {
+                    display_name: Some(display_name),
+                    avatar_url: Some(avatar_url),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        self.account_response(user).await
+    }
+
+    async fn delete_account(&self, user_id: &str, password: &str) -> Result<()> {
+        let user = self.find_user_with_password(user_id, password).await?;
+        self.delete_user(&user.id.to_string()).await
+    }
+
+    async fn begin_totp_enrollment(&self, user_id: &str) -> Result<TotpEnrollment> {
+        let user = self.find_user(user_id).await?;
+        if user.mfa_enabled() {
+            return Err(AuthError::MfaAlreadyEnabled);
+        }
+
+        let secret = totp::generate_secret();
+        self.update_user(
+            &user,
+            UpdateUser {
+                totp_secret: Some(Some(self.totp_cipher.encrypt(&secret))),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        Ok(TotpEnrollment {
+            secret: totp::encode_secret(&secret),
+            otpauth_uri: totp::otpauth_uri(&user.username, &secret),
+        })
+    }
+
+    async fn confirm_totp_enrollment(&self, user_id: &str, code: &str) -> Result<Vec<String>> {
+        let user = self.find_user(user_id).await?;
+        if user.mfa_enabled() {
+            return Err(AuthError::MfaAlreadyEnabled);
+        }
+
+        self.verify_totp_code(&user, code).await?;
+        self.replace_recovery_codes(
+            &user,
+            UpdateUser {
+                totp_enabled_at: Some(Some(Utc::now())),
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    async fn disable_totp(&self, user_id: &str, password: &str) -> Result<()> {
+        let user = self.find_user_with_password(user_id, password).await?;
+        if user.totp_secret.is_none() {
+            return Err(AuthError::MfaNotEnrolled);
+        }
+
+        self.update_user(
+            &user,
+            UpdateUser {
+                totp_secret: Some(None),
+                totp_enabled_at: Some(None),
+                totp_recovery_codes: Some(Vec::new()),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn regenerate_recovery_codes(&self, user_id: &str, code: &str) -> Result<Vec<String>> {
+        let user = self.find_user(user_id).await?;
+        if !user.mfa_enabled() {
+            return Err(AuthError::MfaNotEnrolled);
+        }
+
+        self.verify_totp_code(&user, code).await?;
+        self.replace_recovery_codes(&user, UpdateUser::default())
+            .await
+    }
+
+    async fn create_api_key(
+        &self,
+        user_id: &str,
+        name: &str,
+        scopes: &[ApiKeyScope],
+    ) -> Result<CreatedApiKey> {
+        let user = self.find_user(user_id).await?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AuthError::InvalidApiKey("a name is required"));
+        }
+        if scopes.is_empty() {
+            return Err(AuthError::InvalidApiKey("at least one scope is required"));
+        }
+        let mut scopes = scopes.to_vec();
+        scopes.sort_by_key(|scope| scope.as_str());
+        scopes.dedup();
+
+        let key = format!("{API_KEY_PREFIX}{}", Self::random_token(32));
+        let api_key = self
+            .user_repo
+            .create_api_key(CreateApiKey {
+                user_id: user.id,
+                name: name.to_string(),
+                prefix: key[..API_KEY_SHOWN_CHARS].to_string(),
+                key_hash: Self::hash_refresh_secret(&key),
+                scopes,
+            })
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("API key {} created by {}", api_key.name, user.username),
+            Self::user_details(
+                &user,
+                serde_json::json!({
+                    "api_key_id": api_key.id.to_string(),
+                    "scopes": api_key.scopes.iter().map(ApiKeyScope::as_str).collect::<Vec<_>>(),
+                }),
+            ),
+        )
+        .await;
+        Ok(CreatedApiKey {
+            key,
+            api_key: api_key.into(),
+        })
+    }
+
+    async fn list_api_keys(&self, user_id: &str) -> Result<Vec<ApiKeySummary>> {
+        let user = self.find_user(user_id).await?;
+        let keys = self
+            .user_repo
+            .list_api_keys(user.id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        Ok(keys.into_iter().map(ApiKeySummary::from).collect())
+    }
+
+    async fn revoke_api_key(&self, user_id: &str, key_id: &str) -> Result<()> {
+        let user = self.find_user(user_id).await?;
+        let id = Uuid::parse_str(key_id).map_err(|_| AuthError::ApiKeyNotFound)?;
+        if !self
+            .user_repo
+            .delete_api_key(user.id, id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+        {
+            return Err(AuthError::ApiKeyNotFound);
+        }
+
+        self.audit(
+            AdminLogLevel::Info,
+            format!("API key revoked by {}", user.username),
+            Self::user_details(&user, serde_json::json!({ "api_key_id": key_id })),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn list_users(&self) -> Result<Vec<UserAccount>> {
+        let users = self
+            .user_repo
+            .list()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        Ok(users.into_iter().map(UserAccount::from).collect())
+    }
+
+    async fn create_invite(
+        &self,
+        created_by: &str,
+        role: Role,
+        expires_in: Option<chrono::Duration>,
+    ) -> Result<InviteSummary> {
+        let now = Utc::now();
+        let invite = Invite {
+            code: Self::random_token(12),
+            role,
+            created_by: Uuid::parse_str(created_by).ok(),
+            created_at: now,
+            expires_at: expires_in.map(|d| now + d),
+            used_by: None,
+            used_at: None,
+        };
+        let invite = self
+            .user_repo
+            .create_invite(invite)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        Ok(invite.into())
+    }
+
+    async fn list_invites(&self) -> Result<Vec<InviteSummary>> {
+        let invites = self
+            .user_repo
+            .list_invites()
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        Ok(invites.into_iter().map(InviteSummary::from).collect())
+    }
+
+    async fn delete_invite(&self, code: &str) -> Result<bool> {
+        self.user_repo
+            .delete_invite(code)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))
+    }
+
+    async fn set_user_disabled(&self, user_id: &str, disabled: bool) -> Result<UserAccount> {
+        let user = self.find_user(user_id).await?;
+        if disabled {
+            self.ensure_other_admin(&user).await?;
+        }
+
+        let disabled_at = match (disabled, user.disabled_at) {
+            (true, Some(since)) => Some(since),
+            (true, None) => Some(Utc::now()),
+            (false, _) => None,
+        };
+        let user = self
+            .user_repo
+            .update_disabled(user.id, disabled_at)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .ok_or(AuthError::UserNotFound)?;
+
+        if disabled {
+            self.revoke_sessions(&user.id.to_string()).await?;
+        }
+        self.audit(
+            AdminLogLevel::Warning,
+            format!(
+                "Account {} {}",
+                user.username,
+                if disabled { "disabled" } else { "enabled" }
+            ),
+            Self::user_details(&user, serde_json::json!({ "disabled": disabled })),
+        )
+        .await;
+        Ok(user.into())
+    }
+
+    async fn delete_user(&self, user_id: &str) -> Result<()> {
+        let user = self.find_user(user_id).await?;
+        self.ensure_other_admin(&user).await?;
+
+        self.revoke_sessions(&user.id.to_string()).await?;
+        let deleted = self
+            .user_repo
+            .delete(user.id)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?;
+        if deleted {
+            Ok(())
+        } else {
+            Err(AuthError::UserNotFound)
+        }
+    }
+
+    async fn set_user_role(&self, user_id: &str, role: Role) -> Result<UserAccount> {
+        let user = self.find_user(user_id).await?;
+        if role != Role::Admin {
+            self.ensure_other_admin(&user).await?;
+        }
+
+        let previous_role = user.role;
+        let user = self
+            .user_repo
+            .update_role(user.id, role)
+            .await
+            .map_err(|e| AuthError::Database(e.to_string()))?
+            .ok_or(AuthError::UserNotFound)?;
 
-        Ok(token)
+        if previous_role != role {
+            self.audit(
+                AdminLogLevel::Warning,
+                format!(
+                    "Role of {} changed from {} to {}",
+                    user.username,
+                    previous_role.as_str(),
+                    role.as_str()
+                ),
+                Self::user_details(
+                    &user,
+                    serde_json::json!({
+                        "previous_role": previous_role.as_str(),
+                        "role": role.as_str(),
+                    }),
+                ),
+            )
+            .await;
+        }
+        Ok(user.into())
     }
 
-    fn verify_stream_token(&self, token: &str) -> Result<String> {
-        let validation = Validation::default();
-        let token_data = decode::<StreamClaims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &validation,
-        )?;
+    async fn reset_user_password(&self, user_id: &str) -> Result<String> {
+        let user = self.find_user(user_id).await?;
+
+        let temporary_password = Self::random_token(12);
+        let password_hash = self.hash_password(&temporary_password)?;
+        self.update_user(
+            &user,
+            UpdateUser {
+                password_hash: Some(password_hash),
+                password_reset_required: Some(true),
+                ..Default::default()
+            },
+        )
+        .await?;
 
-        Ok(token_data.claims.stream_id)
+        self.revoke_sessions(&user.id.to_string()).await?;
+        Ok(temporary_password)
     }
 }
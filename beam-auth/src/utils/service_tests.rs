@@ -1,11 +1,32 @@
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
 
+    use chrono::{DateTime, Utc};
+    use confique::Config;
+    use sea_orm::DbErr;
+    use uuid::Uuid;
+
+    use crate::config::{LdapConfig, RegistrationMode};
     use crate::utils::{
+        admin_log::in_memory::InMemoryAdminLogSink,
+        backend::{AuthenticationBackend, LocalPasswordBackend},
+        ldap::{LdapBackend, in_memory::InMemoryDirectory},
+        models::{
+            ApiKey, ApiKeyScope, CreateApiKey, CreateUser, Invite, Permission, Role, UpdateUser,
+            User,
+        },
+        rate_limit::{LockoutPolicy, LoginRateLimiter, in_memory::InMemoryLoginRateLimiter},
         repository::{UserRepository, in_memory::InMemoryUserRepository},
-        service::{AuthError, AuthService, LocalAuthService},
-        session_store::{SessionStore, in_memory::InMemorySessionStore},
+        service::{
+            AuthError, AuthResponse, AuthService, ExternalIdentity, LocalAuthService, StreamScope,
+        },
+        session_store::{
+            DeviceAuthorization, DeviceDecision, SessionData, SessionError, SessionStore,
+            in_memory::InMemorySessionStore,
+        },
+        totp,
     };
 
     const TEST_JWT_SECRET: &str = "test-secret";
@@ -258,6 +279,206 @@ mod tests {
         assert!(session.is_some());
     }
 
+    // ─── login throttling ─────────────────────────────────────────────────────
+
+    fn build_throttled_service() -> (
+        Arc<LocalAuthService>,
+        Arc<InMemoryLoginRateLimiter>,
+        Arc<InMemoryAdminLogSink>,
+    ) {
+        let limiter = Arc::new(InMemoryLoginRateLimiter::default());
+        let admin_log = Arc::new(InMemoryAdminLogSink::default());
+        let svc = LocalAuthService::new(
+            Arc::new(InMemoryUserRepository::default()),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_login_rate_limiter(limiter.clone())
+        .with_admin_log(admin_log.clone());
+        (Arc::new(svc), limiter, admin_log)
+    }
+
+    #[tokio::test]
+    async fn login_backs_off_after_free_attempts() {
+        let (svc, _, _) = build_throttled_service();
+        register_user(&svc, "ursula").await;
+
+        for _ in 0..LockoutPolicy::ACCOUNT.free_attempts {
+            let result = svc.login("ursula", "wrong", "ua", "10.0.0.1").await;
+            assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+        }
+        let result = svc.login("ursula", "wrong", "ua", "10.0.0.1").await;
+        assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+
+        // Even the right password is refused while the account is backing off
+        let result = svc.login("ursula", "password123", "ua", "10.0.0.2").await;
+        assert!(
+            matches!(result, Err(AuthError::TooManyAttempts { retry_after_secs }) if retry_after_secs >= 1),
+            "expected TooManyAttempts, got: {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn login_locks_out_account_and_writes_admin_log() {
+        let (svc, limiter, admin_log) = build_throttled_service();
+        let victor = register_user(&svc, "victor").await;
+
+        // Earlier failures whose backoff has already passed
+        let key = format!("account:{}", victor.user.id);
+        for _ in 1..LockoutPolicy::ACCOUNT.max_failures {
+            limiter.record_failure(&key, 3600).await.unwrap();
+        }
+
+        let result = svc.login("victor", "wrong", "ua", "10.0.1.9").await;
+        assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+        let result = svc.login("victor", "password123", "ua", "10.0.1.10").await;
+        assert!(
+            matches!(result, Err(AuthError::TooManyAttempts { retry_after_secs }) if retry_after_secs > 60)
+        );
+
+        let lockouts: Vec<_> = admin_log
+            .entries()
+            .into_iter()
+            .filter(|entry| entry.message.contains("locked out"))
+            .collect();
+        assert_eq!(lockouts.len(), 1);
+        let details = lockouts[0].details.as_ref().unwrap();
+        assert_eq!(details["failures"], LockoutPolicy::ACCOUNT.max_failures);
+        assert_eq!(details["ip"], "10.0.1.9");
+    }
+
+    #[tokio::test]
+    async fn login_throttles_password_spraying_from_one_ip() {
+        let (svc, _, _) = build_throttled_service();
+
+        // Unknown accounts still count against the IP
+        for i in 0..=LockoutPolicy::IP.free_attempts {
+            let _ = svc
+                .login(&format!("user{i}"), "password", "ua", "203.0.113.5")
+                .await;
+        }
+
+        let result = svc.login("someone", "password", "ua", "203.0.113.5").await;
+        assert!(matches!(result, Err(AuthError::TooManyAttempts { .. })));
+        let other_ip = svc.login("someone", "password", "ua", "203.0.113.6").await;
+        assert!(matches!(other_ip, Err(AuthError::InvalidCredentials)));
+    }
+
+    #[tokio::test]
+    async fn successful_login_clears_account_failures() {
+        let (svc, _, _) = build_throttled_service();
+        register_user(&svc, "wendy").await;
+
+        for _ in 0..LockoutPolicy::ACCOUNT.free_attempts {
+            let _ = svc.login("wendy", "wrong", "ua", "10.0.2.1").await;
+        }
+        svc.login("wendy", "password123", "ua", "10.0.2.1")
+            .await
+            .unwrap();
+
+        // Without the reset this would be the first failure past the free attempts
+        let _ = svc.login("wendy", "wrong", "ua", "10.0.2.1").await;
+        svc.login("wendy", "password123", "ua", "10.0.2.1")
+            .await
+            .unwrap();
+    }
+
+    // ─── admin log ────────────────────────────────────────────────────────────
+
+    fn audit_messages(admin_log: &InMemoryAdminLogSink) -> Vec<String> {
+        admin_log
+            .entries()
+            .into_iter()
+            .map(|entry| entry.message)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn login_events_are_written_with_ip_and_device_hash() {
+        let (svc, _, admin_log) = build_throttled_service();
+        register_user(&svc, "xena").await;
+
+        let _ = svc.login("xena", "wrong", "curl/8.5.0", "192.0.2.1").await;
+        svc.login("xena", "password123", "curl/8.5.0", "192.0.2.1")
+            .await
+            .unwrap();
+
+        let entries = admin_log.entries();
+        assert_eq!(
+            audit_messages(&admin_log),
+            [
+                "User xena registered",
+                "Failed login for xena",
+                "User xena logged in"
+            ]
+        );
+        let failed = entries[1].details.as_ref().unwrap();
+        assert_eq!(failed["ip"], "192.0.2.1");
+        assert_eq!(
+            failed["device_hash"],
+            crate::utils::device::device_hash("curl/8.5.0")
+        );
+        assert_eq!(entries[2].details.as_ref().unwrap()["username"], "xena");
+    }
+
+    #[tokio::test]
+    async fn session_events_are_written() {
+        let (svc, _, admin_log) = build_throttled_service();
+        let first = register_user(&svc, "yuri").await;
+        let second = svc
+            .login("yuri", "password123", "ua", "192.0.2.2")
+            .await
+            .unwrap();
+        let user_id = first.user.id.clone();
+
+        svc.revoke_session(&user_id, &second.session_id)
+            .await
+            .unwrap();
+        let rotated = svc.refresh(&first.refresh_token, "ip").await.unwrap();
+        let _ = svc.refresh(&first.refresh_token, "198.51.100.7").await;
+        let third = svc.login("yuri", "password123", "ua", "ip").await.unwrap();
+        svc.logout(&third.session_id).await.unwrap();
+        svc.logout_all(&user_id).await.unwrap();
+        assert!(svc.refresh(&rotated.refresh_token, "ip").await.is_err());
+
+        let messages = audit_messages(&admin_log);
+        assert!(messages.contains(&"Session revoked".to_string()));
+        assert!(messages.contains(&"User logged out".to_string()));
+        assert!(messages.contains(&"User logged out of all sessions".to_string()));
+        let reuse = admin_log
+            .entries()
+            .into_iter()
+            .find(|entry| entry.message.contains("reuse"))
+            .expect("reuse should be logged");
+        let details = reuse.details.unwrap();
+        assert_eq!(details["presented_from_ip"], "198.51.100.7");
+        assert_eq!(details["user_id"], user_id);
+    }
+
+    #[tokio::test]
+    async fn role_changes_are_written() {
+        let (svc, _, admin_log) = build_throttled_service();
+        register_user(&svc, "owner").await;
+        let zoe = register_user(&svc, "zoe").await;
+
+        svc.set_user_role(&zoe.user.id, Role::Admin).await.unwrap();
+        svc.set_user_role(&zoe.user.id, Role::Admin).await.unwrap();
+
+        let changes: Vec<_> = admin_log
+            .entries()
+            .into_iter()
+            .filter(|entry| entry.message.starts_with("Role of"))
+            .collect();
+        assert_eq!(changes.len(), 1, "unchanged roles are not logged");
+        assert_eq!(
+            changes[0].message,
+            "Role of zoe changed from viewer to admin"
+        );
+        let details = changes[0].details.as_ref().unwrap();
+        assert_eq!(details["previous_role"], "viewer");
+        assert_eq!(details["role"], "admin");
+    }
+
     // ─── verify_token ─────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -277,6 +498,64 @@ mod tests {
         assert_eq!(auth_user.user_id, resp.user.id);
     }
 
+    #[tokio::test]
+    async fn verify_token_carries_role_and_permissions() {
+        let (svc, _, _) = build_service();
+        // The first account becomes admin; the next one gets the default role
+        svc.register(
+            "jon",
+            "jon@example.com",
+            "password123",
+            "device-hash",
+            "127.0.0.1",
+        )
+        .await
+        .unwrap();
+        let resp = svc
+            .register(
+                "kim",
+                "kim@example.com",
+                "password123",
+                "device-hash",
+                "127.0.0.1",
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.user.role, Role::Viewer);
+        assert!(!resp.user.is_admin);
+
+        let auth_user = svc.verify_token(&resp.token).await.unwrap();
+        assert_eq!(auth_user.role, Role::Viewer);
+        assert_eq!(auth_user.permissions, Role::Viewer.default_permissions());
+        assert!(auth_user.has_permission(Permission::Transcode));
+        assert!(!auth_user.has_permission(Permission::TriggerScans));
+    }
+
+    #[tokio::test]
+    async fn login_as_library_manager_grants_scan_permission() {
+        let (svc, user_repo, _) = build_service();
+        user_repo
+            .create(CreateUser {
+                username: "lena".to_string(),
+                email: "lena@example.com".to_string(),
+                password_hash: svc.hash_password("password123").unwrap(),
+                role: Role::LibraryManager,
+            })
+            .await
+            .unwrap();
+
+        let resp = svc
+            .login("lena", "password123", "device-hash", "127.0.0.1")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.role, Role::LibraryManager);
+        assert!(resp.user.permissions.contains(&Permission::TriggerScans));
+
+        let auth_user = svc.verify_token(&resp.token).await.unwrap();
+        assert!(auth_user.has_permission(Permission::TriggerScans));
+        assert!(!auth_user.has_permission(Permission::ManageUsers));
+    }
+
     #[tokio::test]
     async fn verify_token_tampered_returns_error() {
         let (svc, _, _) = build_service();
@@ -347,6 +626,69 @@ mod tests {
         );
     }
 
+    // ─── stream tokens ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn stream_token_carries_scope_and_session() {
+        let (svc, _, _) = build_service();
+        let resp = register_user(&svc, "sam").await;
+        let scope = StreamScope {
+            variants: Some(vec!["1080p".to_string()]),
+            max_bitrate: Some(8_000_000),
+        };
+
+        let token = svc
+            .create_stream_token(&resp.user.id, &resp.session_id, "stream-1", &scope)
+            .unwrap();
+        let grant = svc.verify_stream_token(&token).await.unwrap();
+        assert_eq!(grant.user_id, resp.user.id);
+        assert_eq!(grant.session_id, resp.session_id);
+        assert_eq!(grant.stream_id, "stream-1");
+        assert_eq!(grant.scope, scope);
+        assert!(grant.scope.allows_variant("1080p"));
+        assert!(!grant.scope.allows_variant("2160p"));
+        assert!(!grant.scope.allows_bitrate(Some(9_000_000)));
+        assert!(!grant.scope.allows_bitrate(None));
+    }
+
+    #[tokio::test]
+    async fn stream_and_access_tokens_are_not_interchangeable() {
+        let (svc, _, _) = build_service();
+        let resp = register_user(&svc, "sue").await;
+        let stream_token = svc
+            .create_stream_token(
+                &resp.user.id,
+                &resp.session_id,
+                "stream-1",
+                &StreamScope::default(),
+            )
+            .unwrap();
+
+        assert!(svc.verify_token(&stream_token).await.is_err());
+        assert!(svc.verify_stream_token(&resp.token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn stream_token_is_revoked_with_its_session() {
+        let (svc, _, _) = build_service();
+        let resp = register_user(&svc, "sid").await;
+        let token = svc
+            .create_stream_token(
+                &resp.user.id,
+                &resp.session_id,
+                "stream-1",
+                &StreamScope::default(),
+            )
+            .unwrap();
+        assert!(svc.verify_stream_token(&token).await.is_ok());
+
+        svc.logout(&resp.session_id).await.unwrap();
+        assert!(matches!(
+            svc.verify_stream_token(&token).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
     // ─── refresh ──────────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -362,19 +704,225 @@ mod tests {
             )
             .await
             .unwrap();
-        let refreshed = svc.refresh(&resp.session_id).await.unwrap();
+        let refreshed = svc.refresh(&resp.refresh_token, "127.0.0.2").await.unwrap();
         assert!(!refreshed.token.is_empty());
         assert_eq!(refreshed.session_id, resp.session_id);
         assert_eq!(refreshed.user.username, "olivia");
     }
 
+    #[tokio::test]
+    async fn refresh_rotates_token_and_stores_only_its_hash() {
+        let (svc, _, session_store) = build_service();
+        let resp = register_user(&svc, "oscar").await;
+
+        let first = svc.refresh(&resp.refresh_token, "ip").await.unwrap();
+        let second = svc.refresh(&first.refresh_token, "ip").await.unwrap();
+        assert_ne!(first.refresh_token, resp.refresh_token);
+        assert_ne!(second.refresh_token, first.refresh_token);
+
+        let session = session_store.get(&resp.session_id).await.unwrap().unwrap();
+        let (_, secret) = second.refresh_token.split_once('.').unwrap();
+        assert!(!session.refresh_token_hash.is_empty());
+        assert_ne!(session.refresh_token_hash, secret);
+        assert_eq!(session.retired_refresh_token_hashes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn refresh_token_reuse_revokes_session() {
+        let (svc, _, session_store) = build_service();
+        let resp = register_user(&svc, "odin").await;
+        let rotated = svc.refresh(&resp.refresh_token, "ip").await.unwrap();
+
+        let reused = svc.refresh(&resp.refresh_token, "ip").await;
+        assert!(matches!(reused, Err(AuthError::RefreshTokenReused)));
+        assert!(session_store.get(&resp.session_id).await.unwrap().is_none());
+
+        // The legitimate holder of the newest token is logged out too
+        assert!(svc.refresh(&rotated.refresh_token, "ip").await.is_err());
+        assert!(svc.verify_token(&rotated.token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn refresh_rejects_session_id_alone() {
+        let (svc, _, session_store) = build_service();
+        let resp = register_user(&svc, "olga").await;
+
+        let result = svc.refresh(&resp.session_id, "ip").await;
+        assert!(matches!(result, Err(AuthError::InvalidCredentials)));
+        let forged = svc
+            .refresh(&format!("{}.not-the-secret", resp.session_id), "ip")
+            .await;
+        assert!(matches!(forged, Err(AuthError::InvalidCredentials)));
+        assert!(session_store.get(&resp.session_id).await.unwrap().is_some());
+    }
+
     #[tokio::test]
     async fn refresh_unknown_session_returns_error() {
         let (svc, _, _) = build_service();
-        let result = svc.refresh("non-existent-session-id").await;
+        let result = svc.refresh("non-existent-session-id", "127.0.0.1").await;
         assert!(result.is_err(), "refresh with unknown session should fail");
     }
 
+    #[tokio::test]
+    async fn refresh_records_last_seen_ip() {
+        let (svc, _, session_store) = build_service();
+        let resp = svc
+            .register(
+                "otto",
+                "otto@example.com",
+                "password123",
+                "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0",
+                "127.0.0.1",
+            )
+            .await
+            .unwrap();
+        svc.refresh(&resp.refresh_token, "10.0.0.7").await.unwrap();
+
+        let session = session_store.get(&resp.session_id).await.unwrap().unwrap();
+        assert_eq!(session.device_name, "Firefox on Linux");
+        assert_eq!(session.ip, "127.0.0.1");
+        assert_eq!(session.last_ip, "10.0.0.7");
+    }
+
+    /// Once armed, holds `get` until two callers have read the session so that concurrent
+    /// refreshes both see the same refresh token before either rotates it.
+    #[derive(Debug)]
+    struct RacingSessionStore {
+        inner: InMemorySessionStore,
+        armed: AtomicBool,
+        barrier: tokio::sync::Barrier,
+    }
+
+    #[async_trait::async_trait]
+    impl SessionStore for RacingSessionStore {
+        async fn create(&self, data: &SessionData, ttl_secs: u64) -> Result<String, SessionError> {
+            self.inner.create(data, ttl_secs).await
+        }
+
+        async fn get(&self, session_id: &str) -> Result<Option<SessionData>, SessionError> {
+            let session = self.inner.get(session_id).await;
+            if self.armed.load(Ordering::SeqCst) {
+                self.barrier.wait().await;
+                self.armed.store(false, Ordering::SeqCst);
+            }
+            session
+        }
+
+        async fn touch(&self, session_id: &str, ttl_secs: u64) -> Result<(), SessionError> {
+            self.inner.touch(session_id, ttl_secs).await
+        }
+
+        async fn update(&self, session_id: &str, data: &SessionData) -> Result<(), SessionError> {
+            self.inner.update(session_id, data).await
+        }
+
+        async fn rotate_refresh_hash(
+            &self,
+            session_id: &str,
+            old_hash: &str,
+            data: &SessionData,
+        ) -> Result<bool, SessionError> {
+            self.inner
+                .rotate_refresh_hash(session_id, old_hash, data)
+                .await
+        }
+
+        async fn delete(&self, session_id: &str) -> Result<(), SessionError> {
+            self.inner.delete(session_id).await
+        }
+
+        async fn delete_all_for_user(&self, user_id: &str) -> Result<u64, SessionError> {
+            self.inner.delete_all_for_user(user_id).await
+        }
+
+        async fn list_for_user(
+            &self,
+            user_id: &str,
+        ) -> Result<Vec<(String, SessionData)>, SessionError> {
+            self.inner.list_for_user(user_id).await
+        }
+
+        async fn create_device_authorization(
+            &self,
+            data: &DeviceAuthorization,
+            ttl_secs: u64,
+        ) -> Result<bool, SessionError> {
+            self.inner.create_device_authorization(data, ttl_secs).await
+        }
+
+        async fn get_device_authorization(
+            &self,
+            device_code_hash: &str,
+        ) -> Result<Option<DeviceAuthorization>, SessionError> {
+            self.inner.get_device_authorization(device_code_hash).await
+        }
+
+        async fn find_device_authorization(
+            &self,
+            user_code: &str,
+        ) -> Result<Option<DeviceAuthorization>, SessionError> {
+            self.inner.find_device_authorization(user_code).await
+        }
+
+        async fn record_device_poll(
+            &self,
+            device_code_hash: &str,
+            polled_at: i64,
+        ) -> Result<(), SessionError> {
+            self.inner
+                .record_device_poll(device_code_hash, polled_at)
+                .await
+        }
+
+        async fn decide_device_authorization(
+            &self,
+            device_code_hash: &str,
+            user_id: &str,
+            decision: DeviceDecision,
+        ) -> Result<bool, SessionError> {
+            self.inner
+                .decide_device_authorization(device_code_hash, user_id, decision)
+                .await
+        }
+
+        async fn delete_device_authorization(
+            &self,
+            data: &DeviceAuthorization,
+        ) -> Result<bool, SessionError> {
+            self.inner.delete_device_authorization(data).await
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_with_one_token_revoke_session() {
+        let session_store = Arc::new(RacingSessionStore {
+            inner: InMemorySessionStore::default(),
+            armed: AtomicBool::new(false),
+            barrier: tokio::sync::Barrier::new(2),
+        });
+        let svc = LocalAuthService::new(
+            Arc::new(InMemoryUserRepository::default()),
+            session_store.clone(),
+            TEST_JWT_SECRET.to_string(),
+        );
+        let resp = register_user(&svc, "orla").await;
+
+        session_store.armed.store(true, Ordering::SeqCst);
+        let (first, second) = tokio::join!(
+            svc.refresh(&resp.refresh_token, "ip"),
+            svc.refresh(&resp.refresh_token, "ip"),
+        );
+
+        let results = [&first, &second];
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert!(
+            results
+                .iter()
+                .any(|r| matches!(r, Err(AuthError::RefreshTokenReused)))
+        );
+        assert!(session_store.get(&resp.session_id).await.unwrap().is_none());
+    }
+
     // ─── logout ───────────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -461,4 +1009,1507 @@ mod tests {
             "second session should be gone"
         );
     }
+
+    #[tokio::test]
+    async fn revoke_session_only_revokes_own_sessions() {
+        let (svc, _, session_store) = build_service();
+        let pia = register_user(&svc, "pia").await;
+        let tablet = svc.login("pia", "password123", "d", "ip").await.unwrap();
+        let quinn = register_user(&svc, "quinn").await;
+
+        let err = svc
+            .revoke_session(&quinn.user.id, &tablet.session_id)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::SessionNotFound));
+
+        svc.revoke_session(&pia.user.id, &tablet.session_id)
+            .await
+            .unwrap();
+        assert!(
+            session_store
+                .get(&tablet.session_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(session_store.get(&pia.session_id).await.unwrap().is_some());
+    }
+
+    // ─── user management ──────────────────────────────────────────────────────
+
+    async fn register_user(svc: &LocalAuthService, username: &str) -> AuthResponse {
+        svc.register(
+            username,
+            &format!("{username}@example.com"),
+            "password123",
+            "device-hash",
+            "127.0.0.1",
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn first_registered_user_becomes_admin() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let second = register_user(&svc, "second").await;
+        assert_eq!(owner.user.role, Role::Admin);
+        assert!(owner.user.is_admin);
+        assert_eq!(second.user.role, Role::Viewer);
+    }
+
+    /// Once armed, holds `count` until two callers have counted the users so that
+    /// concurrent first registrations both see an empty server.
+    #[derive(Debug)]
+    struct RacingUserRepository {
+        inner: InMemoryUserRepository,
+        armed: AtomicBool,
+        barrier: tokio::sync::Barrier,
+    }
+
+    #[async_trait::async_trait]
+    impl UserRepository for RacingUserRepository {
+        async fn find_by_id(&self, id: Uuid) -> Result<Option<User>, DbErr> {
+            self.inner.find_by_id(id).await
+        }
+
+        async fn find_by_username(&self, username: &str) -> Result<Option<User>, DbErr> {
+            self.inner.find_by_username(username).await
+        }
+
+        async fn find_by_email(&self, email: &str) -> Result<Option<User>, DbErr> {
+            self.inner.find_by_email(email).await
+        }
+
+        async fn create(&self, user: CreateUser) -> Result<User, DbErr> {
+            self.inner.create(user).await
+        }
+
+        async fn create_first(&self, user: CreateUser) -> Result<Option<User>, DbErr> {
+            self.inner.create_first(user).await
+        }
+
+        async fn find_role_permissions(&self, role: Role) -> Result<Vec<Permission>, DbErr> {
+            self.inner.find_role_permissions(role).await
+        }
+
+        async fn list(&self) -> Result<Vec<User>, DbErr> {
+            self.inner.list().await
+        }
+
+        async fn count(&self) -> Result<u64, DbErr> {
+            let count = self.inner.count().await;
+            if self.armed.load(Ordering::SeqCst) {
+                self.barrier.wait().await;
+                self.armed.store(false, Ordering::SeqCst);
+            }
+            count
+        }
+
+        async fn delete(&self, id: Uuid) -> Result<bool, DbErr> {
+            self.inner.delete(id).await
+        }
+
+        async fn update_role(&self, id: Uuid, role: Role) -> Result<Option<User>, DbErr> {
+            self.inner.update_role(id, role).await
+        }
+
+        async fn update_disabled(
+            &self,
+            id: Uuid,
+            disabled_at: Option<DateTime<Utc>>,
+        ) -> Result<Option<User>, DbErr> {
+            self.inner.update_disabled(id, disabled_at).await
+        }
+
+        async fn update(&self, id: Uuid, update: UpdateUser) -> Result<Option<User>, DbErr> {
+            self.inner.update(id, update).await
+        }
+
+        async fn record_totp_step(&self, id: Uuid, step: i64) -> Result<bool, DbErr> {
+            self.inner.record_totp_step(id, step).await
+        }
+
+        async fn consume_recovery_code(&self, id: Uuid, code_hash: &str) -> Result<bool, DbErr> {
+            self.inner.consume_recovery_code(id, code_hash).await
+        }
+
+        async fn create_invite(&self, invite: Invite) -> Result<Invite, DbErr> {
+            self.inner.create_invite(invite).await
+        }
+
+        async fn find_invite(&self, code: &str) -> Result<Option<Invite>, DbErr> {
+            self.inner.find_invite(code).await
+        }
+
+        async fn list_invites(&self) -> Result<Vec<Invite>, DbErr> {
+            self.inner.list_invites().await
+        }
+
+        async fn redeem_invite(&self, code: &str, user_id: Uuid) -> Result<bool, DbErr> {
+            self.inner.redeem_invite(code, user_id).await
+        }
+
+        async fn delete_invite(&self, code: &str) -> Result<bool, DbErr> {
+            self.inner.delete_invite(code).await
+        }
+
+        async fn find_by_identity(
+            &self,
+            provider: &str,
+            subject: &str,
+        ) -> Result<Option<User>, DbErr> {
+            self.inner.find_by_identity(provider, subject).await
+        }
+
+        async fn link_identity(
+            &self,
+            user_id: Uuid,
+            provider: &str,
+            subject: &str,
+        ) -> Result<(), DbErr> {
+            self.inner.link_identity(user_id, provider, subject).await
+        }
+
+        async fn create_api_key(&self, key: CreateApiKey) -> Result<ApiKey, DbErr> {
+            self.inner.create_api_key(key).await
+        }
+
+        async fn list_api_keys(&self, user_id: Uuid) -> Result<Vec<ApiKey>, DbErr> {
+            self.inner.list_api_keys(user_id).await
+        }
+
+        async fn find_api_key(&self, id: Uuid) -> Result<Option<ApiKey>, DbErr> {
+            self.inner.find_api_key(id).await
+        }
+
+        async fn find_api_key_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>, DbErr> {
+            self.inner.find_api_key_by_hash(key_hash).await
+        }
+
+        async fn touch_api_key(&self, id: Uuid, used_at: DateTime<Utc>) -> Result<(), DbErr> {
+            self.inner.touch_api_key(id, used_at).await
+        }
+
+        async fn delete_api_key(&self, user_id: Uuid, id: Uuid) -> Result<bool, DbErr> {
+            self.inner.delete_api_key(user_id, id).await
+        }
+    }
+
+    fn build_racing_service(
+        registration_mode: RegistrationMode,
+    ) -> (LocalAuthService, Arc<RacingUserRepository>) {
+        let user_repo = Arc::new(RacingUserRepository {
+            inner: InMemoryUserRepository::default(),
+            armed: AtomicBool::new(true),
+            barrier: tokio::sync::Barrier::new(2),
+        });
+        let svc = LocalAuthService::new(
+            user_repo.clone(),
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_registration_mode(registration_mode);
+        (svc, user_repo)
+    }
+
+    #[tokio::test]
+    async fn concurrent_first_registrations_make_one_admin() {
+        let (svc, user_repo) = build_racing_service(RegistrationMode::Invite);
+
+        let (alice, bob) = tokio::join!(
+            svc.register("alice", "alice@example.com", "password123", "d", "ip"),
+            svc.register("bob", "bob@example.com", "password123", "d", "ip"),
+        );
+
+        // The loser is no longer the first user, so it needs an invite like anyone else
+        let (admin, refused) = if alice.is_ok() {
+            (alice, bob)
+        } else {
+            (bob, alice)
+        };
+        assert!(admin.unwrap().user.is_admin);
+        assert!(matches!(refused, Err(AuthError::RegistrationClosed)));
+        assert_eq!(user_repo.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_first_registration_and_identity_make_one_admin() {
+        let (svc, user_repo) = build_racing_service(RegistrationMode::Open);
+        let carol = identity("sub-1", "carol@example.com");
+
+        let (local, external) = tokio::join!(
+            svc.register("alice", "alice@example.com", "password123", "d", "ip"),
+            svc.login_with_identity(&carol, "d", "ip"),
+        );
+
+        let roles = [local.unwrap().user.role, external.unwrap().user.role];
+        assert_eq!(
+            roles.iter().filter(|role| **role == Role::Admin).count(),
+            1,
+            "{roles:?}"
+        );
+        assert_eq!(user_repo.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn invite_mode_requires_unused_invite() {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let svc = LocalAuthService::new(
+            user_repo,
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_registration_mode(RegistrationMode::Invite);
+
+        // Bootstrapping works without an invite
+        let owner = register_user(&svc, "owner").await;
+
+        let result = svc
+            .register("rita", "rita@example.com", "password123", "d", "ip")
+            .await;
+        assert!(matches!(result, Err(AuthError::RegistrationClosed)));
+
+        let invite = svc
+            .create_invite(&owner.user.id, Role::LibraryManager, None)
+            .await
+            .unwrap();
+        let resp = svc
+            .register_with_invite(
+                "rita",
+                "rita@example.com",
+                "password123",
+                Some(&invite.code),
+                "d",
+                "ip",
+            )
+            .await
+            .unwrap();
+        assert_eq!(resp.user.role, Role::LibraryManager);
+
+        let invites = svc.list_invites().await.unwrap();
+        assert_eq!(invites[0].used_by.as_deref(), Some(resp.user.id.as_str()));
+
+        let reused = svc
+            .register_with_invite(
+                "sam",
+                "sam@example.com",
+                "password123",
+                Some(&invite.code),
+                "d",
+                "ip",
+            )
+            .await;
+        assert!(matches!(reused, Err(AuthError::InvalidInvite)));
+    }
+
+    #[tokio::test]
+    async fn closed_registration_rejects_invites() {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let svc = LocalAuthService::new(
+            user_repo,
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_registration_mode(RegistrationMode::Closed);
+        let owner = register_user(&svc, "owner").await;
+        let invite = svc
+            .create_invite(&owner.user.id, Role::Viewer, None)
+            .await
+            .unwrap();
+
+        let result = svc
+            .register_with_invite(
+                "tom",
+                "tom@example.com",
+                "password123",
+                Some(&invite.code),
+                "d",
+                "ip",
+            )
+            .await;
+        assert!(matches!(result, Err(AuthError::RegistrationClosed)));
+    }
+
+    #[tokio::test]
+    async fn disabled_user_cannot_login_and_loses_sessions() {
+        let (svc, _, session_store) = build_service();
+        register_user(&svc, "owner").await;
+        let uma = register_user(&svc, "uma").await;
+
+        let account = svc.set_user_disabled(&uma.user.id, true).await.unwrap();
+        assert!(account.disabled);
+        assert!(session_store.get(&uma.session_id).await.unwrap().is_none());
+
+        let result = svc.login("uma", "password123", "d", "ip").await;
+        assert!(matches!(result, Err(AuthError::AccountDisabled)));
+
+        svc.set_user_disabled(&uma.user.id, false).await.unwrap();
+        assert!(svc.login("uma", "password123", "d", "ip").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn last_admin_cannot_be_removed() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let id = owner.user.id.as_str();
+
+        assert!(matches!(
+            svc.delete_user(id).await,
+            Err(AuthError::LastAdmin)
+        ));
+        assert!(matches!(
+            svc.set_user_disabled(id, true).await,
+            Err(AuthError::LastAdmin)
+        ));
+        assert!(matches!(
+            svc.set_user_role(id, Role::Viewer).await,
+            Err(AuthError::LastAdmin)
+        ));
+
+        // Once someone else is promoted the original admin can step down
+        let vic = register_user(&svc, "vic").await;
+        svc.set_user_role(&vic.user.id, Role::Admin).await.unwrap();
+        let account = svc.set_user_role(id, Role::Viewer).await.unwrap();
+        assert_eq!(account.role, Role::Viewer);
+    }
+
+    #[tokio::test]
+    async fn delete_user_removes_account_and_sessions() {
+        let (svc, user_repo, session_store) = build_service();
+        register_user(&svc, "owner").await;
+        let walt = register_user(&svc, "walt").await;
+
+        svc.delete_user(&walt.user.id).await.unwrap();
+        assert!(user_repo.find_by_username("walt").await.unwrap().is_none());
+        assert!(session_store.get(&walt.session_id).await.unwrap().is_none());
+        assert!(matches!(
+            svc.delete_user(&walt.user.id).await,
+            Err(AuthError::UserNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn reset_user_password_issues_temporary_password() {
+        let (svc, _, session_store) = build_service();
+        register_user(&svc, "owner").await;
+        let xena = register_user(&svc, "xena").await;
+
+        let temporary = svc.reset_user_password(&xena.user.id).await.unwrap();
+        assert!(session_store.get(&xena.session_id).await.unwrap().is_none());
+        assert!(svc.login("xena", "password123", "d", "ip").await.is_err());
+
+        let resp = svc.login("xena", &temporary, "d", "ip").await.unwrap();
+        assert!(resp.user.password_reset_required);
+    }
+
+    #[tokio::test]
+    async fn reset_user_is_limited_to_their_account_until_password_changes() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let key = svc
+            .create_api_key(&owner.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+
+        let temporary = svc.reset_user_password(&owner.user.id).await.unwrap();
+        let resp = svc.login("owner", &temporary, "d", "ip").await.unwrap();
+        assert!(resp.user.permissions.is_empty());
+        assert!(matches!(
+            svc.verify_token(&resp.token).await,
+            Err(AuthError::PasswordResetRequired)
+        ));
+        assert!(matches!(
+            svc.verify_token(&key.key).await,
+            Err(AuthError::PasswordResetRequired)
+        ));
+        assert!(svc.verify_account_token(&resp.token).await.is_ok());
+
+        svc.change_password(&owner.user.id, &resp.session_id, &temporary, "newpass456")
+            .await
+            .unwrap();
+        let refreshed = svc.refresh(&resp.refresh_token, "ip").await.unwrap();
+        let user = svc.verify_token(&refreshed.token).await.unwrap();
+        assert!(user.has_permission(Permission::ManageUsers));
+        assert!(svc.verify_token(&key.key).await.is_ok());
+    }
+
+    // ─── self-service account ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn change_password_requires_current_and_revokes_other_sessions() {
+        let (svc, _, session_store) = build_service();
+        let yuri = register_user(&svc, "yuri").await;
+        let other = svc.login("yuri", "password123", "d", "ip").await.unwrap();
+
+        let err = svc
+            .change_password(&yuri.user.id, &yuri.session_id, "wrong", "newpass456")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::InvalidCredentials));
+
+        let revoked = svc
+            .change_password(&yuri.user.id, &yuri.session_id, "password123", "newpass456")
+            .await
+            .unwrap();
+        assert_eq!(revoked, 1);
+        assert!(session_store.get(&yuri.session_id).await.unwrap().is_some());
+        assert!(
+            session_store
+                .get(&other.session_id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(svc.login("yuri", "password123", "d", "ip").await.is_err());
+        let resp = svc.login("yuri", "newpass456", "d", "ip").await.unwrap();
+        assert!(!resp.user.password_reset_required);
+    }
+
+    #[tokio::test]
+    async fn change_email_rejects_address_in_use() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let zara = register_user(&svc, "zara").await;
+
+        let err = svc
+            .change_email(&zara.user.id, "password123", "owner@example.com")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AuthError::UserAlreadyExists));
+
+        let user = svc
+            .change_email(&zara.user.id, "password123", "zara@new.example.com")
+            .await
+            .unwrap();
+        assert_eq!(user.email, "zara@new.example.com");
+        assert!(
+            svc.login("zara@new.example.com", "password123", "d", "ip")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn update_profile_replaces_display_name_and_avatar() {
+        let (svc, _, _) = build_service();
+        let ann = register_user(&svc, "ann").await;
+
+        let user = svc
+            .update_profile(
+                &ann.user.id,
+                Some("Ann".to_string()),
+                Some("https://example.com/ann.png".to_string()),
+            )
+            .await
+            .unwrap();
+        assert_eq!(user.display_name.as_deref(), Some("Ann"));
+        assert_eq!(
+            user.avatar_url.as_deref(),
+            Some("https://example.com/ann.png")
+        );
+
+        let user = svc
+            .update_profile(&ann.user.id, Some("Annie".to_string()), None)
+            .await
+            .unwrap();
+        assert_eq!(user.display_name.as_deref(), Some("Annie"));
+        assert!(user.avatar_url.is_none());
+    }
+
+    #[tokio::test]
+    async fn delete_account_requires_password() {
+        let (svc, user_repo, session_store) = build_service();
+        register_user(&svc, "owner").await;
+        let ben = register_user(&svc, "ben").await;
+
+        let err = svc.delete_account(&ben.user.id, "wrong").await.unwrap_err();
+        assert!(matches!(err, AuthError::InvalidCredentials));
+
+        svc.delete_account(&ben.user.id, "password123")
+            .await
+            .unwrap();
+        assert!(user_repo.find_by_username("ben").await.unwrap().is_none());
+        assert!(session_store.get(&ben.session_id).await.unwrap().is_none());
+    }
+
+    // ─── two-factor authentication ──────────────────────────────────────────
+
+    /// Code for the base32 secret returned by enrollment, `offset_secs` from now.
+    fn code_in(secret: &str, offset_secs: i64) -> String {
+        let secret = data_encoding::BASE32_NOPAD
+            .decode(secret.as_bytes())
+            .unwrap();
+        totp::code_at(&secret, chrono::Utc::now().timestamp() + offset_secs)
+    }
+
+    /// Current code for the base32 secret returned by enrollment.
+    fn current_code(secret: &str) -> String {
+        code_in(secret, 0)
+    }
+
+    /// Enrolls the user in TOTP and returns the base32 secret and recovery codes.
+    ///
+    /// Enrollment uses the previous step's code, which clock drift allows, so the current
+    /// code is still unused afterwards.
+    async fn enable_totp(svc: &LocalAuthService, user_id: &str) -> (String, Vec<String>) {
+        let enrollment = svc.begin_totp_enrollment(user_id).await.unwrap();
+        let codes = svc
+            .confirm_totp_enrollment(user_id, &code_in(&enrollment.secret, -30))
+            .await
+            .unwrap();
+        (enrollment.secret, codes)
+    }
+
+    fn challenge_token(result: Result<AuthResponse, AuthError>) -> String {
+        match result {
+            Err(AuthError::MfaRequired { challenge_token }) => challenge_token,
+            other => panic!("expected MfaRequired, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn totp_enrollment_requires_a_valid_code() {
+        let (svc, user_repo, _) = build_service();
+        let cleo = register_user(&svc, "cleo").await;
+
+        let enrollment = svc.begin_totp_enrollment(&cleo.user.id).await.unwrap();
+        assert!(
+            enrollment
+                .otpauth_uri
+                .starts_with("otpauth://totp/Beam:cleo?")
+        );
+
+        let stored = user_repo.find_by_username("cleo").await.unwrap().unwrap();
+        assert_ne!(
+            stored.totp_secret.as_deref(),
+            Some(enrollment.secret.as_str())
+        );
+        assert!(!stored.mfa_enabled());
+
+        let err = svc.confirm_totp_enrollment(&cleo.user.id, "000000").await;
+        assert!(matches!(err, Err(AuthError::InvalidMfaCode)));
+
+        let codes = svc
+            .confirm_totp_enrollment(&cleo.user.id, &current_code(&enrollment.secret))
+            .await
+            .unwrap();
+        assert_eq!(codes.len(), totp::RECOVERY_CODE_COUNT);
+        assert!(svc.get_account(&cleo.user.id).await.unwrap().mfa_enabled);
+
+        let again = svc.begin_totp_enrollment(&cleo.user.id).await;
+        assert!(matches!(again, Err(AuthError::MfaAlreadyEnabled)));
+    }
+
+    #[tokio::test]
+    async fn login_with_totp_takes_two_steps() {
+        let (svc, _, _) = build_service();
+        let dina = register_user(&svc, "dina").await;
+        let (secret, _) = enable_totp(&svc, &dina.user.id).await;
+
+        let challenge = challenge_token(svc.login("dina", "password123", "d", "ip").await);
+
+        let wrong = svc
+            .complete_mfa_login(&challenge, "000000", "d", "ip")
+            .await;
+        assert!(matches!(wrong, Err(AuthError::InvalidMfaCode)));
+
+        let resp = svc
+            .complete_mfa_login(&challenge, &current_code(&secret), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.username, "dina");
+        assert!(svc.verify_token(&resp.token).await.is_ok());
+
+        // A challenge is not an access token and vice versa
+        assert!(svc.verify_token(&challenge).await.is_err());
+        let misuse = svc
+            .complete_mfa_login(&resp.token, &current_code(&secret), "d", "ip")
+            .await;
+        assert!(misuse.is_err());
+    }
+
+    #[tokio::test]
+    async fn totp_codes_work_once() {
+        let (svc, _, _) = build_service();
+        let hana = register_user(&svc, "hana").await;
+        let (secret, _) = enable_totp(&svc, &hana.user.id).await;
+
+        let code = current_code(&secret);
+        let challenge = challenge_token(svc.login("hana", "password123", "d", "ip").await);
+        svc.complete_mfa_login(&challenge, &code, "d", "ip")
+            .await
+            .unwrap();
+
+        // Neither the same code nor one from an earlier step is accepted again
+        let challenge = challenge_token(svc.login("hana", "password123", "d", "ip").await);
+        let replayed = svc.complete_mfa_login(&challenge, &code, "d", "ip").await;
+        assert!(matches!(replayed, Err(AuthError::InvalidMfaCode)));
+        let older = svc
+            .complete_mfa_login(&challenge, &code_in(&secret, -30), "d", "ip")
+            .await;
+        assert!(matches!(older, Err(AuthError::InvalidMfaCode)));
+        svc.complete_mfa_login(&challenge, &code_in(&secret, 30), "d", "ip")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn recovery_code_used_concurrently_only_works_once() {
+        let (svc, _, _) = build_service();
+        let ivo = register_user(&svc, "ivo").await;
+        let (_, codes) = enable_totp(&svc, &ivo.user.id).await;
+
+        let first = challenge_token(svc.login("ivo", "password123", "d", "ip").await);
+        let second = challenge_token(svc.login("ivo", "password123", "d", "ip").await);
+        let (a, b) = tokio::join!(
+            svc.complete_mfa_login(&first, &codes[0], "d", "ip"),
+            svc.complete_mfa_login(&second, &codes[0], "d", "ip"),
+        );
+        assert_eq!(a.is_ok() as u8 + b.is_ok() as u8, 1);
+    }
+
+    #[tokio::test]
+    async fn recovery_codes_work_once() {
+        let (svc, _, _) = build_service();
+        let emil = register_user(&svc, "emil").await;
+        let (_, codes) = enable_totp(&svc, &emil.user.id).await;
+
+        let challenge = challenge_token(svc.login("emil", "password123", "d", "ip").await);
+        svc.complete_mfa_login(&challenge, &codes[0], "d", "ip")
+            .await
+            .unwrap();
+
+        let challenge = challenge_token(svc.login("emil", "password123", "d", "ip").await);
+        let reused = svc
+            .complete_mfa_login(&challenge, &codes[0], "d", "ip")
+            .await;
+        assert!(matches!(reused, Err(AuthError::InvalidMfaCode)));
+        svc.complete_mfa_login(&challenge, &codes[1].to_uppercase(), "d", "ip")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn disable_totp_requires_password() {
+        let (svc, _, _) = build_service();
+        let fay = register_user(&svc, "fay").await;
+        enable_totp(&svc, &fay.user.id).await;
+
+        let err = svc.disable_totp(&fay.user.id, "wrong").await;
+        assert!(matches!(err, Err(AuthError::InvalidCredentials)));
+
+        svc.disable_totp(&fay.user.id, "password123").await.unwrap();
+        assert!(svc.login("fay", "password123", "d", "ip").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn totp_secrets_do_not_survive_a_key_change() {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let session_store = Arc::new(InMemorySessionStore::default());
+        let svc = LocalAuthService::new(
+            user_repo.clone(),
+            session_store.clone(),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_totp_encryption_key("first-key");
+        let gil = register_user(&svc, "gil").await;
+        let (secret, _) = enable_totp(&svc, &gil.user.id).await;
+
+        let rotated = LocalAuthService::new(user_repo, session_store, TEST_JWT_SECRET.to_string())
+            .with_totp_encryption_key("second-key");
+        let challenge = challenge_token(rotated.login("gil", "password123", "d", "ip").await);
+        let result = rotated
+            .complete_mfa_login(&challenge, &current_code(&secret), "d", "ip")
+            .await;
+        assert!(matches!(result, Err(AuthError::InvalidMfaCode)));
+    }
+
+    #[tokio::test]
+    async fn require_admin_mfa_withholds_permissions_until_enrolled() {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let session_store = Arc::new(InMemorySessionStore::default());
+        let svc = LocalAuthService::new(user_repo, session_store, TEST_JWT_SECRET.to_string())
+            .with_require_admin_mfa(true);
+
+        let owner = register_user(&svc, "owner").await;
+        assert!(owner.user.mfa_setup_required);
+        assert!(owner.user.permissions.is_empty());
+        let claims = svc.verify_token(&owner.token).await.unwrap();
+        assert!(!claims.has_permission(Permission::ManageUsers));
+
+        // Regular users are not affected
+        let viewer = register_user(&svc, "viewer").await;
+        assert!(!viewer.user.mfa_setup_required);
+        assert!(!viewer.user.permissions.is_empty());
+
+        let (secret, _) = enable_totp(&svc, &owner.user.id).await;
+        let challenge = challenge_token(svc.login("owner", "password123", "d", "ip").await);
+        let resp = svc
+            .complete_mfa_login(&challenge, &current_code(&secret), "d", "ip")
+            .await
+            .unwrap();
+        assert!(!resp.user.mfa_setup_required);
+        let claims = svc.verify_token(&resp.token).await.unwrap();
+        assert!(claims.has_permission(Permission::ManageUsers));
+    }
+
+    // ─── external identities ─────────────────────────────────────────────────
+
+    fn identity(subject: &str, email: &str) -> ExternalIdentity {
+        ExternalIdentity {
+            provider: "idp".into(),
+            subject: subject.into(),
+            email: Some(email.into()),
+            email_verified: true,
+            username: email.split('@').next().map(str::to_string),
+            display_name: None,
+            is_admin: None,
+            link_by_email: true,
+            create_user: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_creates_and_then_reuses_user() {
+        let (svc, user_repo, _) = build_service();
+        register_user(&svc, "owner").await;
+
+        let first = svc
+            .login_with_identity(&identity("sub-1", "carol@example.com"), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(first.user.username, "carol");
+        assert_eq!(first.user.role, Role::Viewer);
+
+        // Matched by subject even after the email changed at the provider
+        let again = svc
+            .login_with_identity(&identity("sub-1", "carol@new.example.com"), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(again.user.id, first.user.id);
+        assert_eq!(user_repo.count().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_picks_a_free_username() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "dave").await;
+
+        let resp = svc
+            .login_with_identity(&identity("sub-1", "dave@idp.example.com"), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.username, "dave2");
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_links_only_verified_email() {
+        let (svc, user_repo, _) = build_service();
+        let existing = register_user(&svc, "erin").await;
+
+        let unverified = ExternalIdentity {
+            email_verified: false,
+            ..identity("sub-1", "erin@example.com")
+        };
+        assert!(matches!(
+            svc.login_with_identity(&unverified, "d", "ip").await,
+            Err(AuthError::UserAlreadyExists)
+        ));
+        let unlinked = ExternalIdentity {
+            link_by_email: false,
+            ..identity("sub-1", "erin@example.com")
+        };
+        assert!(matches!(
+            svc.login_with_identity(&unlinked, "d", "ip").await,
+            Err(AuthError::UserAlreadyExists)
+        ));
+
+        let resp = svc
+            .login_with_identity(&identity("sub-1", "erin@example.com"), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.id, existing.user.id);
+        assert!(
+            user_repo
+                .find_by_identity("idp", "sub-1")
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_without_account_fails_when_creation_is_off() {
+        let (svc, _, _) = build_service();
+        let no_create = ExternalIdentity {
+            create_user: false,
+            ..identity("sub-1", "frank@example.com")
+        };
+        assert!(matches!(
+            svc.login_with_identity(&no_create, "d", "ip").await,
+            Err(AuthError::RegistrationClosed)
+        ));
+
+        let no_email = ExternalIdentity {
+            email: None,
+            ..identity("sub-1", "frank@example.com")
+        };
+        assert!(matches!(
+            svc.login_with_identity(&no_email, "d", "ip").await,
+            Err(AuthError::MissingEmail)
+        ));
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_follows_admin_group_but_keeps_last_admin() {
+        let (svc, _, _) = build_service();
+
+        // The first user is admin, and as the only one stays admin outside the group
+        let owner = ExternalIdentity {
+            is_admin: Some(false),
+            ..identity("owner", "owner@example.com")
+        };
+        let resp = svc.login_with_identity(&owner, "d", "ip").await.unwrap();
+        assert_eq!(resp.user.role, Role::Admin);
+        let resp = svc.login_with_identity(&owner, "d", "ip").await.unwrap();
+        assert_eq!(resp.user.role, Role::Admin);
+
+        let grace = |is_admin| ExternalIdentity {
+            is_admin: Some(is_admin),
+            ..identity("grace", "grace@example.com")
+        };
+        let resp = svc
+            .login_with_identity(&grace(true), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.role, Role::Admin);
+
+        // With another admin around the owner can now be demoted
+        let resp = svc.login_with_identity(&owner, "d", "ip").await.unwrap();
+        assert_eq!(resp.user.role, Role::Viewer);
+        let resp = svc
+            .login_with_identity(&grace(true), "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.role, Role::Admin);
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_rejects_disabled_account() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let resp = svc
+            .login_with_identity(&identity("sub-1", "heidi@example.com"), "d", "ip")
+            .await
+            .unwrap();
+        svc.set_user_disabled(&resp.user.id, true).await.unwrap();
+
+        assert!(matches!(
+            svc.login_with_identity(&identity("sub-1", "heidi@example.com"), "d", "ip")
+                .await,
+            Err(AuthError::AccountDisabled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn login_with_identity_skips_local_two_factor() {
+        let (svc, _, _) = build_service();
+        let resp = svc
+            .login_with_identity(&identity("sub-1", "ivan@example.com"), "d", "ip")
+            .await
+            .unwrap();
+        enable_totp(&svc, &resp.user.id).await;
+
+        let again = svc
+            .login_with_identity(&identity("sub-1", "ivan@example.com"), "d", "ip")
+            .await
+            .unwrap();
+        assert!(again.user.mfa_enabled);
+        assert!(!again.token.is_empty());
+    }
+
+    // ─── password backends ───────────────────────────────────────────────────
+
+    const DIRECTORY_ADMINS: &str = "cn=beam-admins,ou=groups,dc=example,dc=com";
+
+    /// A service checking passwords against an in-memory directory, then locally.
+    fn build_directory_service(
+        backends: &[&str],
+    ) -> (
+        Arc<LocalAuthService>,
+        Arc<InMemoryDirectory>,
+        Arc<InMemoryLoginRateLimiter>,
+    ) {
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let directory = Arc::new(InMemoryDirectory::default());
+        directory.add_entry(
+            "uid=judy,ou=people,dc=example,dc=com",
+            "directory-pw",
+            &[
+                ("uid", &["judy"]),
+                ("mail", &["judy@example.com"]),
+                ("cn", &["Judy Hopps"]),
+                ("memberOf", &[DIRECTORY_ADMINS]),
+            ],
+        );
+        directory.add_entry(
+            "uid=kim,ou=people,dc=example,dc=com",
+            "kims-pw",
+            &[("uid", &["kim"]), ("mail", &["kim@example.com"])],
+        );
+
+        let mut config = LdapConfig::builder().load().unwrap();
+        config.user_base_dn = "ou=people,dc=example,dc=com".into();
+        config.admin_filter = Some(format!("(memberOf={DIRECTORY_ADMINS})"));
+        let ldap: Arc<dyn AuthenticationBackend> =
+            Arc::new(LdapBackend::with_directory(config, directory.clone()));
+        let local: Arc<dyn AuthenticationBackend> =
+            Arc::new(LocalPasswordBackend::new(user_repo.clone()));
+
+        let limiter = Arc::new(InMemoryLoginRateLimiter::default());
+        let svc = LocalAuthService::new(
+            user_repo,
+            Arc::new(InMemorySessionStore::default()),
+            TEST_JWT_SECRET.to_string(),
+        )
+        .with_login_rate_limiter(limiter.clone())
+        .with_authentication_backends(
+            backends
+                .iter()
+                .map(|name| match *name {
+                    "ldap" => ldap.clone(),
+                    _ => local.clone(),
+                })
+                .collect(),
+        );
+        (Arc::new(svc), directory, limiter)
+    }
+
+    #[tokio::test]
+    async fn directory_login_creates_the_user_once() {
+        let (svc, _, _) = build_directory_service(&["ldap", "local"]);
+        register_user(&svc, "owner").await;
+
+        let first = svc.login("kim", "kims-pw", "d", "ip").await.unwrap();
+        assert_eq!(first.user.username, "kim");
+        assert_eq!(first.user.email, "kim@example.com");
+        assert_eq!(first.user.role, Role::Viewer);
+
+        let again = svc
+            .login("kim@example.com", "kims-pw", "d", "ip")
+            .await
+            .unwrap();
+        assert_eq!(again.user.id, first.user.id);
+        // The directory password is not copied into Beam
+        assert!(matches!(
+            svc.change_password(&first.user.id, &again.session_id, "kims-pw", "new-password")
+                .await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn directory_admin_group_grants_admin() {
+        let (svc, _, _) = build_directory_service(&["ldap", "local"]);
+        register_user(&svc, "owner").await;
+
+        let judy = svc.login("judy", "directory-pw", "d", "ip").await.unwrap();
+        assert_eq!(judy.user.role, Role::Admin);
+        assert_eq!(judy.user.display_name.as_deref(), Some("Judy Hopps"));
+        let kim = svc.login("kim", "kims-pw", "d", "ip").await.unwrap();
+        assert_eq!(kim.user.role, Role::Viewer);
+    }
+
+    #[tokio::test]
+    async fn backends_are_tried_in_order() {
+        let (both, _, _) = build_directory_service(&["ldap", "local"]);
+        register_user(&both, "owner").await;
+        both.login("owner", "password123", "d", "ip").await.unwrap();
+
+        let (directory_only, _, _) = build_directory_service(&["ldap"]);
+        register_user(&directory_only, "owner").await;
+        assert!(matches!(
+            directory_only
+                .login("owner", "password123", "d", "ip")
+                .await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        directory_only
+            .login("kim", "kims-pw", "d", "ip")
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unreachable_directory_does_not_lock_anyone_out() {
+        let (svc, directory, limiter) = build_directory_service(&["ldap", "local"]);
+        register_user(&svc, "owner").await;
+        directory.set_unavailable(true);
+
+        // Local accounts still get in
+        svc.login("owner", "password123", "d", "ip").await.unwrap();
+
+        for _ in 0..=LockoutPolicy::ACCOUNT.free_attempts {
+            assert!(matches!(
+                svc.login("kim", "kims-pw", "d", "ip").await,
+                Err(AuthError::Backend(_))
+            ));
+        }
+        assert_eq!(limiter.blocked_for("account:kim").await.unwrap(), None);
+
+        directory.set_unavailable(false);
+        svc.login("kim", "kims-pw", "d", "ip").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn directory_login_keeps_local_two_factor() {
+        let (svc, _, _) = build_directory_service(&["ldap", "local"]);
+        register_user(&svc, "owner").await;
+        let kim = svc.login("kim", "kims-pw", "d", "ip").await.unwrap();
+        enable_totp(&svc, &kim.user.id).await;
+
+        assert!(matches!(
+            svc.login("kim", "kims-pw", "d", "ip").await,
+            Err(AuthError::MfaRequired { .. })
+        ));
+    }
+
+    // ─── api keys ────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn api_key_is_stored_hashed_and_shown_once() {
+        let (svc, repo, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+
+        let created = svc
+            .create_api_key(&owner.user.id, "  Kodi  ", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+        assert!(created.key.starts_with("beam_"));
+        assert!(created.key.starts_with(&created.api_key.prefix));
+        assert_eq!(created.api_key.name, "Kodi");
+
+        let user_id = owner.user.id.parse().unwrap();
+        let stored = repo.list_api_keys(user_id).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_ne!(stored[0].key_hash, created.key);
+        assert!(!stored[0].key_hash.contains(&created.key));
+
+        let listed = svc.list_api_keys(&owner.user.id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, created.api_key.id);
+        assert_eq!(listed[0].last_used_at, None);
+    }
+
+    #[tokio::test]
+    async fn api_key_acts_for_its_user_within_its_scopes() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let created = svc
+            .create_api_key(&owner.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+
+        let auth = svc.verify_token(&created.key).await.unwrap();
+        assert_eq!(auth.user_id, owner.user.id);
+        assert_eq!(
+            auth.api_key_id.as_deref(),
+            Some(created.api_key.id.as_str())
+        );
+        assert!(auth.has_permission(Permission::Transcode));
+        assert!(!auth.has_permission(Permission::ManageUsers));
+
+        let listed = svc.list_api_keys(&owner.user.id).await.unwrap();
+        assert!(listed[0].last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn api_key_scopes_never_exceed_the_users_role() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let member = register_user(&svc, "member").await;
+
+        let admin_key = svc
+            .create_api_key(&member.user.id, "script", &[ApiKeyScope::Admin])
+            .await
+            .unwrap();
+        let auth = svc.verify_token(&admin_key.key).await.unwrap();
+        assert!(auth.permissions.is_empty());
+
+        let read_only = svc
+            .create_api_key(&member.user.id, "reader", &[ApiKeyScope::ReadOnly])
+            .await
+            .unwrap();
+        let auth = svc.verify_token(&read_only.key).await.unwrap();
+        assert!(!auth.has_permission(Permission::Transcode));
+    }
+
+    #[tokio::test]
+    async fn revoked_and_unknown_api_keys_are_rejected() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let other = register_user(&svc, "other").await;
+        let created = svc
+            .create_api_key(&owner.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+
+        // Only the owner can revoke it
+        assert!(matches!(
+            svc.revoke_api_key(&other.user.id, &created.api_key.id)
+                .await,
+            Err(AuthError::ApiKeyNotFound)
+        ));
+        svc.revoke_api_key(&owner.user.id, &created.api_key.id)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            svc.verify_token(&created.key).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        assert!(svc.verify_token("beam_not-a-key").await.is_err());
+        assert!(svc.list_api_keys(&owner.user.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn disabled_users_api_keys_stop_working() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let member = register_user(&svc, "member").await;
+        let created = svc
+            .create_api_key(&member.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+
+        svc.set_user_disabled(&member.user.id, true).await.unwrap();
+        assert!(svc.verify_token(&created.key).await.is_err());
+        svc.set_user_disabled(&member.user.id, false).await.unwrap();
+        assert!(svc.verify_token(&created.key).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn stream_token_from_api_key_ends_with_the_key() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let created = svc
+            .create_api_key(&owner.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+        let auth = svc.verify_token(&created.key).await.unwrap();
+        let token = svc
+            .create_stream_token(
+                &auth.user_id,
+                &auth.session_id,
+                "stream-1",
+                &StreamScope::default(),
+            )
+            .unwrap();
+        assert!(svc.verify_stream_token(&token).await.is_ok());
+
+        svc.revoke_api_key(&owner.user.id, &created.api_key.id)
+            .await
+            .unwrap();
+        assert!(matches!(
+            svc.verify_stream_token(&token).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn stream_token_from_api_key_needs_an_enabled_owner_and_the_stream_scope() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let member = register_user(&svc, "member").await;
+        let stream_token = |session_id: &str| {
+            svc.create_stream_token(
+                &member.user.id,
+                session_id,
+                "stream-1",
+                &StreamScope::default(),
+            )
+            .unwrap()
+        };
+
+        let streaming = svc
+            .create_api_key(&member.user.id, "box", &[ApiKeyScope::Stream])
+            .await
+            .unwrap();
+        let auth = svc.verify_token(&streaming.key).await.unwrap();
+        let token = stream_token(&auth.session_id);
+        assert!(svc.verify_stream_token(&token).await.is_ok());
+
+        svc.set_user_disabled(&member.user.id, true).await.unwrap();
+        assert!(matches!(
+            svc.verify_stream_token(&token).await,
+            Err(AuthError::InvalidCredentials)
+        ));
+        svc.set_user_disabled(&member.user.id, false).await.unwrap();
+        assert!(svc.verify_stream_token(&token).await.is_ok());
+
+        let read_only = svc
+            .create_api_key(&member.user.id, "reader", &[ApiKeyScope::ReadOnly])
+            .await
+            .unwrap();
+        let auth = svc.verify_token(&read_only.key).await.unwrap();
+        assert!(matches!(
+            svc.verify_stream_token(&stream_token(&auth.session_id))
+                .await,
+            Err(AuthError::InvalidCredentials)
+        ));
+    }
+
+    #[tokio::test]
+    async fn api_key_needs_a_name_and_a_scope() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+
+        assert!(matches!(
+            svc.create_api_key(&owner.user.id, "  ", &[ApiKeyScope::Stream])
+                .await,
+            Err(AuthError::InvalidApiKey(_))
+        ));
+        assert!(matches!(
+            svc.create_api_key(&owner.user.id, "box", &[]).await,
+            Err(AuthError::InvalidApiKey(_))
+        ));
+    }
+
+    // ─── device pairing ──────────────────────────────────────────────────────
+
+    fn pairing_error(result: Result<AuthResponse, AuthError>) -> &'static str {
+        match result {
+            Err(AuthError::DevicePairing(error)) => error,
+            other => panic!("expected a pairing error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn approved_device_gets_its_own_named_session_once() {
+        let (svc, _, session_store) = build_service();
+        let owner = register_user(&svc, "owner").await;
+
+        let code = svc
+            .start_device_authorization("Living room TV", "Kodi/21.0", "10.0.0.5")
+            .await
+            .unwrap();
+        assert_eq!(code.user_code.len(), 9);
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "authorization_pending"
+        );
+
+        let pending = svc
+            .get_pending_device(&code.user_code.to_lowercase().replace('-', " "))
+            .await
+            .unwrap();
+        assert_eq!(pending.device_name, "Living room TV");
+        assert_eq!(pending.ip, "10.0.0.5");
+
+        svc.approve_device(&owner.user.id, &code.user_code)
+            .await
+            .unwrap();
+        let resp = svc
+            .poll_device_authorization(&code.device_code, "10.0.0.5")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.id, owner.user.id);
+        let session = session_store.get(&resp.session_id).await.unwrap().unwrap();
+        assert_eq!(session.device_name, "Living room TV");
+        assert!(svc.verify_token(&resp.token).await.is_ok());
+
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "expired_token"
+        );
+    }
+
+    #[tokio::test]
+    async fn device_polling_too_fast_is_told_to_slow_down() {
+        let (svc, _, _) = build_service();
+        let code = svc
+            .start_device_authorization("", "Kodi/21.0", "ip")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "authorization_pending"
+        );
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "slow_down"
+        );
+        // The user-agent names devices that do not name themselves
+        let pending = svc.get_pending_device(&code.user_code).await.unwrap();
+        assert_eq!(pending.device_name, "Kodi");
+    }
+
+    #[tokio::test]
+    async fn poll_does_not_undo_a_concurrent_approval() {
+        let (svc, _, session_store) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let code = svc
+            .start_device_authorization("TV", "ua", "ip")
+            .await
+            .unwrap();
+
+        // A poll that read the pairing before the approval landed writes its timestamp after it
+        let pairing = session_store
+            .find_device_authorization(&code.user_code.replace('-', ""))
+            .await
+            .unwrap()
+            .unwrap();
+        svc.approve_device(&owner.user.id, &code.user_code)
+            .await
+            .unwrap();
+        session_store
+            .record_device_poll(&pairing.device_code_hash, 0)
+            .await
+            .unwrap();
+
+        let resp = svc
+            .poll_device_authorization(&code.device_code, "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.id, owner.user.id);
+    }
+
+    #[tokio::test]
+    async fn denied_device_is_refused() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let code = svc
+            .start_device_authorization("TV", "ua", "ip")
+            .await
+            .unwrap();
+
+        svc.deny_device(&owner.user.id, &code.user_code)
+            .await
+            .unwrap();
+        assert!(matches!(
+            svc.approve_device(&owner.user.id, &code.user_code).await,
+            Err(AuthError::PairingCodeNotFound)
+        ));
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "access_denied"
+        );
+        assert_eq!(
+            pairing_error(svc.poll_device_authorization(&code.device_code, "ip").await),
+            "expired_token"
+        );
+    }
+
+    #[tokio::test]
+    async fn pairing_codes_are_approved_only_once() {
+        let (svc, _, _) = build_service();
+        let owner = register_user(&svc, "owner").await;
+        let other = register_user(&svc, "other").await;
+        let code = svc
+            .start_device_authorization("TV", "ua", "ip")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            svc.get_pending_device("AEIO-UAEI").await,
+            Err(AuthError::PairingCodeNotFound)
+        ));
+        svc.approve_device(&owner.user.id, &code.user_code)
+            .await
+            .unwrap();
+        assert!(matches!(
+            svc.approve_device(&other.user.id, &code.user_code).await,
+            Err(AuthError::PairingCodeNotFound)
+        ));
+
+        let resp = svc
+            .poll_device_authorization(&code.device_code, "ip")
+            .await
+            .unwrap();
+        assert_eq!(resp.user.id, owner.user.id);
+    }
+
+    #[tokio::test]
+    async fn device_approved_by_a_since_disabled_user_is_refused() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let member = register_user(&svc, "member").await;
+        let code = svc
+            .start_device_authorization("TV", "ua", "ip")
+            .await
+            .unwrap();
+
+        svc.approve_device(&member.user.id, &code.user_code)
+            .await
+            .unwrap();
+        svc.set_user_disabled(&member.user.id, true).await.unwrap();
+        assert!(matches!(
+            svc.poll_device_authorization(&code.device_code, "ip").await,
+            Err(AuthError::AccountDisabled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn device_decisions_are_audited() {
+        let (svc, _, admin_log) = build_throttled_service();
+        let owner = register_user(&svc, "owner").await;
+        let approved = svc
+            .start_device_authorization("TV", "ua", "10.0.0.5")
+            .await
+            .unwrap();
+        let denied = svc
+            .start_device_authorization("Tablet", "ua", "10.0.0.6")
+            .await
+            .unwrap();
+
+        svc.approve_device(&owner.user.id, &approved.user_code)
+            .await
+            .unwrap();
+        svc.deny_device(&owner.user.id, &denied.user_code)
+            .await
+            .unwrap();
+        // The first decision sticks
+        assert!(matches!(
+            svc.deny_device(&owner.user.id, &approved.user_code).await,
+            Err(AuthError::PairingCodeNotFound)
+        ));
+
+        let messages = audit_messages(&admin_log);
+        assert_eq!(
+            messages[messages.len() - 2..],
+            ["Device TV paired by owner", "Device Tablet denied by owner"]
+        );
+        let details = admin_log.entries().pop().unwrap().details.unwrap();
+        assert_eq!(details["username"], "owner");
+        assert_eq!(details["ip"], "10.0.0.6");
+    }
+
+    #[tokio::test]
+    async fn disabled_or_unknown_users_cannot_deny_devices() {
+        let (svc, _, _) = build_service();
+        register_user(&svc, "owner").await;
+        let member = register_user(&svc, "member").await;
+        let code = svc
+            .start_device_authorization("TV", "ua", "ip")
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            svc.deny_device(&uuid::Uuid::new_v4().to_string(), &code.user_code)
+                .await,
+            Err(AuthError::UserNotFound)
+        ));
+        svc.set_user_disabled(&member.user.id, true).await.unwrap();
+        assert!(matches!(
+            svc.deny_device(&member.user.id, &code.user_code).await,
+            Err(AuthError::AccountDisabled)
+        ));
+        // Still waiting for someone allowed to decide
+        assert!(svc.get_pending_device(&code.user_code).await.is_ok());
+    }
 }
@@ -1,18 +1,73 @@
 use async_trait::async_trait;
 use bb8_redis::RedisConnectionManager;
 use bb8_redis::bb8::{Pool, PooledConnection};
+use chrono::{Duration, Utc};
 use redis::AsyncCommands;
+use sea_orm::{
+    ActiveValue::Set,
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    sea_query::{Expr, OnConflict},
+};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
 use thiserror::Error;
-use tracing::debug;
+use tracing::{debug, warn};
+
+/// How often expired rows are deleted from the SQL session store and login throttles
+pub const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SessionData {
     pub user_id: String,
     pub device_hash: String,
+    /// Readable name parsed from the user-agent, e.g. "Firefox on Windows"
+    #[serde(default)]
+    pub device_name: String,
+    /// IP the session was created from
     pub ip: String,
+    /// IP of the most recent refresh
+    #[serde(default)]
+    pub last_ip: String,
     pub created_at: i64,
     pub last_active: i64,
+    /// SHA-256 of the refresh token currently valid for this session
+    #[serde(default)]
+    pub refresh_token_hash: String,
+    /// Hashes of recently rotated-out refresh tokens, kept to detect reuse
+    #[serde(default)]
+    pub retired_refresh_token_hashes: Vec<String>,
+}
+
+/// A TV or other client without a keyboard, waiting for a signed-in user to pair it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeviceAuthorization {
+    /// SHA-256 of the device code the client polls with
+    pub device_code_hash: String,
+    /// Code the user enters to approve the device, without the dash
+    pub user_code: String,
+    /// Name the device gave itself, e.g. "Living room TV"
+    pub device_name: String,
+    pub device_hash: String,
+    /// IP the pairing was started from
+    pub ip: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+    /// When the device last polled, to hold it to the polling interval
+    #[serde(default)]
+    pub last_polled_at: Option<i64>,
+    /// User who approved the device
+    #[serde(default)]
+    pub approved_by: Option<String>,
+    /// User who refused the device
+    #[serde(default)]
+    pub denied_by: Option<String>,
+}
+
+/// What a signed-in user decided about a waiting device
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceDecision {
+    Approve,
+    Deny,
 }
 
 #[derive(Debug, Error)]
@@ -23,10 +78,23 @@ pub enum SessionError {
     Pool(#[from] bb8_redis::bb8::RunError<redis::RedisError>),
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
 }
 
 type Result<T> = std::result::Result<T, SessionError>;
 
+fn generate_session_id() -> String {
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use rand::Rng;
+
+    let mut bytes = [0u8; 32];
+    let mut rng = rand::rng();
+    rng.fill_bytes(&mut bytes);
+
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
 /// A thread-safe, asynchronous store for managing user sessions.
 ///
 /// This trait abstracts the underlying storage mechanism (e.g., Redis, PostgreSQL,
@@ -59,6 +127,23 @@ pub trait SessionStore: Send + Sync + std::fmt::Debug {
     /// Returns an error if the session does not exist or the store is unreachable.
     async fn touch(&self, session_id: &str, ttl_secs: u64) -> Result<()>;
 
+    /// Overwrites the data of an existing session without changing its expiry.
+    ///
+    /// Does nothing if the session does not exist.
+    async fn update(&self, session_id: &str, data: &SessionData) -> Result<()>;
+
+    /// Overwrites a session with `data`, which carries a rotated refresh token, but only
+    /// while the session's refresh token hash is still `old_hash`. The expiry is unchanged.
+    ///
+    /// # Returns
+    /// `false`, changing nothing, if the session is gone or another refresh rotated it first.
+    async fn rotate_refresh_hash(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        data: &SessionData,
+    ) -> Result<bool>;
+
     /// Immediately invalidates and removes a specific session.
     async fn delete(&self, session_id: &str) -> Result<()>;
 
@@ -74,6 +159,51 @@ pub trait SessionStore: Send + Sync + std::fmt::Debug {
     ///
     /// Each entry in the vector is a tuple containing the `(session_id, SessionData)`.
     async fn list_for_user(&self, user_id: &str) -> Result<Vec<(String, SessionData)>>;
+
+    /// Stores a device waiting to be paired until `ttl_secs` have passed.
+    ///
+    /// # Returns
+    /// `false`, storing nothing, if another waiting device already shows the same user code.
+    async fn create_device_authorization(
+        &self,
+        data: &DeviceAuthorization,
+        ttl_secs: u64,
+    ) -> Result<bool>;
+
+    /// Retrieves a waiting device by the hash of its device code.
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>>;
+
+    /// Retrieves a waiting device by the user code it shows.
+    async fn find_device_authorization(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>>;
+
+    /// Records when a waiting device last polled, leaving the rest of it untouched so a
+    /// poll cannot undo a concurrent approval.
+    ///
+    /// Does nothing if it has expired or been removed.
+    async fn record_device_poll(&self, device_code_hash: &str, polled_at: i64) -> Result<()>;
+
+    /// Records that `user_id` approved or denied a waiting device.
+    ///
+    /// # Returns
+    /// `false`, changing nothing, if it has expired, been removed or already been decided.
+    async fn decide_device_authorization(
+        &self,
+        device_code_hash: &str,
+        user_id: &str,
+        decision: DeviceDecision,
+    ) -> Result<bool>;
+
+    /// Removes a waiting device.
+    ///
+    /// # Returns
+    /// Whether it was still there, so that only one caller gets to act on it.
+    async fn delete_device_authorization(&self, data: &DeviceAuthorization) -> Result<bool>;
 }
 
 #[derive(Debug)]
@@ -88,9 +218,16 @@ impl RedisSessionStore {
             .build(manager)
             .await
             .map_err(SessionError::Redis)?;
+        // The pool connects lazily; connect once now so a wrong URL fails at startup
+        drop(pool.get().await.map_err(SessionError::Pool)?);
         Ok(Self { pool })
     }
 
+    /// The underlying connection pool, for sharing with other Redis-backed stores.
+    pub fn pool(&self) -> Pool<RedisConnectionManager> {
+        self.pool.clone()
+    }
+
     async fn get_conn(&self) -> Result<PooledConnection<'_, RedisConnectionManager>> {
         self.pool.get().await.map_err(SessionError::Pool)
     }
@@ -103,22 +240,19 @@ impl RedisSessionStore {
         format!("user_sessions:{}", user_id)
     }
 
-    fn generate_session_id() -> String {
-        use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
-        use rand::Rng;
-
-        let mut bytes = [0u8; 32];
-        let mut rng = rand::rng();
-        rng.fill_bytes(&mut bytes);
+    fn device_code_key(device_code_hash: &str) -> String {
+        format!("device_code:{}", device_code_hash)
+    }
 
-        URL_SAFE_NO_PAD.encode(bytes)
+    fn user_code_key(user_code: &str) -> String {
+        format!("user_code:{}", user_code)
     }
 }
 
 #[async_trait]
 impl SessionStore for RedisSessionStore {
     async fn create(&self, data: &SessionData, ttl_secs: u64) -> Result<String> {
-        let session_id = Self::generate_session_id();
+        let session_id = generate_session_id();
         let key = Self::session_key(&session_id);
         let user_key = Self::user_sessions_key(&data.user_id);
         let value = serde_json::to_string(data)?;
@@ -157,6 +291,54 @@ impl SessionStore for RedisSessionStore {
         Ok(())
     }
 
+    async fn update(&self, session_id: &str, data: &SessionData) -> Result<()> {
+        let key = Self::session_key(session_id);
+        let value = serde_json::to_string(data)?;
+        let mut conn = self.get_conn().await?;
+
+        // XX: only overwrite live sessions; KEEPTTL: leave the expiry alone
+        let _: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(&value)
+            .arg("XX")
+            .arg("KEEPTTL")
+            .query_async(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn rotate_refresh_hash(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        data: &SessionData,
+    ) -> Result<bool> {
+        // Compare and set in one script so concurrent refreshes cannot both rotate
+        static ROTATE: LazyLock<redis::Script> = LazyLock::new(|| {
+            redis::Script::new(
+                r"
+                local current = redis.call('GET', KEYS[1])
+                if not current or cjson.decode(current).refresh_token_hash ~= ARGV[1] then
+                    return 0
+                end
+                redis.call('SET', KEYS[1], ARGV[2], 'KEEPTTL')
+                return 1
+                ",
+            )
+        });
+
+        let value = serde_json::to_string(data)?;
+        let mut conn = self.get_conn().await?;
+
+        let rotated: i64 = ROTATE
+            .key(Self::session_key(session_id))
+            .arg(old_hash)
+            .arg(&value)
+            .invoke_async(&mut *conn)
+            .await?;
+        Ok(rotated == 1)
+    }
+
     async fn delete(&self, session_id: &str) -> Result<()> {
         let key = Self::session_key(session_id);
         let mut conn = self.get_conn().await?;
@@ -230,6 +412,443 @@ impl SessionStore for RedisSessionStore {
 
         Ok(sessions)
     }
+
+    async fn create_device_authorization(
+        &self,
+        data: &DeviceAuthorization,
+        ttl_secs: u64,
+    ) -> Result<bool> {
+        let key = Self::device_code_key(&data.device_code_hash);
+        let value = serde_json::to_string(data)?;
+        let mut conn = self.get_conn().await?;
+
+        // Claim the user code first; NX fails if another device is showing it
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(Self::user_code_key(&data.user_code))
+            .arg(&data.device_code_hash)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl_secs)
+            .query_async(&mut *conn)
+            .await?;
+        if claimed.is_none() {
+            return Ok(false);
+        }
+
+        let _: () = conn.set_ex(&key, &value, ttl_secs).await?;
+        debug!("Created device authorization {}", data.user_code);
+        Ok(true)
+    }
+
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        let key = Self::device_code_key(device_code_hash);
+        let mut conn = self.get_conn().await?;
+
+        let value: Option<String> = conn.get(&key).await?;
+        match value {
+            Some(v) => Ok(Some(serde_json::from_str(&v)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_device_authorization(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        let device_code_hash: Option<String> = {
+            let mut conn = self.get_conn().await?;
+            conn.get(Self::user_code_key(user_code)).await?
+        };
+        match device_code_hash {
+            Some(hash) => self.get_device_authorization(&hash).await,
+            None => Ok(None),
+        }
+    }
+
+    async fn record_device_poll(&self, device_code_hash: &str, polled_at: i64) -> Result<()> {
+        // Read-modify-write in one script so a concurrent approval is never overwritten
+        static RECORD_POLL: LazyLock<redis::Script> = LazyLock::new(|| {
+            redis::Script::new(
+                r"
+                local current = redis.call('GET', KEYS[1])
+                if not current then
+                    return 0
+                end
+                local pairing = cjson.decode(current)
+                pairing.last_polled_at = tonumber(ARGV[1])
+                redis.call('SET', KEYS[1], cjson.encode(pairing), 'KEEPTTL')
+                return 1
+                ",
+            )
+        });
+
+        let mut conn = self.get_conn().await?;
+        let _: i64 = RECORD_POLL
+            .key(Self::device_code_key(device_code_hash))
+            .arg(polled_at)
+            .invoke_async(&mut *conn)
+            .await?;
+        Ok(())
+    }
+
+    async fn decide_device_authorization(
+        &self,
+        device_code_hash: &str,
+        user_id: &str,
+        decision: DeviceDecision,
+    ) -> Result<bool> {
+        static DECIDE: LazyLock<redis::Script> = LazyLock::new(|| {
+            redis::Script::new(
+                r"
+                local current = redis.call('GET', KEYS[1])
+                if not current then
+                    return 0
+                end
+                local pairing = cjson.decode(current)
+                local undecided = function(field)
+                    return pairing[field] == nil or pairing[field] == cjson.null
+                end
+                if not undecided('approved_by') or not undecided('denied_by') then
+                    return 0
+                end
+                pairing[ARGV[2]] = ARGV[1]
+                redis.call('SET', KEYS[1], cjson.encode(pairing), 'KEEPTTL')
+                return 1
+                ",
+            )
+        });
+
+        let field = match decision {
+            DeviceDecision::Approve => "approved_by",
+            DeviceDecision::Deny => "denied_by",
+        };
+        let mut conn = self.get_conn().await?;
+        let decided: i64 = DECIDE
+            .key(Self::device_code_key(device_code_hash))
+            .arg(user_id)
+            .arg(field)
+            .invoke_async(&mut *conn)
+            .await?;
+        Ok(decided == 1)
+    }
+
+    async fn delete_device_authorization(&self, data: &DeviceAuthorization) -> Result<bool> {
+        let mut conn = self.get_conn().await?;
+
+        let (removed, _): (u64, u64) = redis::pipe()
+            .atomic()
+            .del(Self::device_code_key(&data.device_code_hash))
+            .del(Self::user_code_key(&data.user_code))
+            .query_async(&mut *conn)
+            .await?;
+        Ok(removed > 0)
+    }
+}
+
+/// Keeps sessions in the main PostgreSQL database, for installs without Redis.
+///
+/// Expired rows are never returned; [`spawn_sweeper`](Self::spawn_sweeper) deletes them.
+#[derive(Debug)]
+pub struct SqlSessionStore {
+    db: DatabaseConnection,
+}
+
+impl SqlSessionStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Deletes expired sessions and device pairings and returns how many there were.
+    pub async fn sweep_expired(&self) -> Result<u64> {
+        use beam_entity::{device_authorization, session};
+
+        let now = Utc::now();
+        let sessions = session::Entity::delete_many()
+            .filter(session::Column::ExpiresAt.lte(now))
+            .exec(&self.db)
+            .await?;
+        let pairings = device_authorization::Entity::delete_many()
+            .filter(device_authorization::Column::ExpiresAt.lte(now))
+            .exec(&self.db)
+            .await?;
+        Ok(sessions.rows_affected + pairings.rows_affected)
+    }
+
+    /// Runs [`sweep_expired`](Self::sweep_expired) every [`SWEEP_INTERVAL`] in the
+    /// background.
+    pub fn spawn_sweeper(self: &Arc<Self>) {
+        let store = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                match store.sweep_expired().await {
+                    Ok(0) => {}
+                    Ok(swept) => debug!("Swept {} expired sessions and pairings", swept),
+                    Err(e) => warn!("Failed to sweep expired sessions: {}", e),
+                }
+            }
+        });
+    }
+
+    fn expires_at(ttl_secs: u64) -> chrono::DateTime<Utc> {
+        Utc::now() + Duration::seconds(ttl_secs as i64)
+    }
+}
+
+#[async_trait]
+impl SessionStore for SqlSessionStore {
+    async fn create(&self, data: &SessionData, ttl_secs: u64) -> Result<String> {
+        use beam_entity::session;
+
+        let session_id = generate_session_id();
+        let model = session::ActiveModel {
+            id: Set(session_id.clone()),
+            user_id: Set(data.user_id.clone()),
+            data: Set(serde_json::to_value(data)?),
+            expires_at: Set(Self::expires_at(ttl_secs).into()),
+        };
+        session::Entity::insert(model)
+            .exec_without_returning(&self.db)
+            .await?;
+
+        debug!("Created session {} for user {}", session_id, data.user_id);
+        Ok(session_id)
+    }
+
+    async fn get(&self, session_id: &str) -> Result<Option<SessionData>> {
+        use beam_entity::session;
+
+        let model = session::Entity::find_by_id(session_id)
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .one(&self.db)
+            .await?;
+        match model {
+            Some(m) => Ok(Some(serde_json::from_value(m.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn touch(&self, session_id: &str, ttl_secs: u64) -> Result<()> {
+        use beam_entity::session;
+
+        session::Entity::update_many()
+            .col_expr(
+                session::Column::ExpiresAt,
+                Expr::value(Self::expires_at(ttl_secs)),
+            )
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, session_id: &str, data: &SessionData) -> Result<()> {
+        use beam_entity::session;
+
+        session::Entity::update_many()
+            .col_expr(
+                session::Column::Data,
+                Expr::value(serde_json::to_value(data)?),
+            )
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn rotate_refresh_hash(
+        &self,
+        session_id: &str,
+        old_hash: &str,
+        data: &SessionData,
+    ) -> Result<bool> {
+        use beam_entity::session;
+
+        let result = session::Entity::update_many()
+            .col_expr(
+                session::Column::Data,
+                Expr::value(serde_json::to_value(data)?),
+            )
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .filter(Expr::cust_with_values(
+                "data->>'refresh_token_hash' = $1",
+                [old_hash],
+            ))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn delete(&self, session_id: &str) -> Result<()> {
+        use beam_entity::session;
+
+        session::Entity::delete_by_id(session_id)
+            .exec(&self.db)
+            .await?;
+
+        debug!("Deleted session {}", session_id);
+        Ok(())
+    }
+
+    async fn delete_all_for_user(&self, user_id: &str) -> Result<u64> {
+        use beam_entity::session;
+
+        // Expired sessions are left to the sweeper so they are not counted
+        let result = session::Entity::delete_many()
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+
+        debug!(
+            "Deleted all {} sessions for user {}",
+            result.rows_affected, user_id
+        );
+        Ok(result.rows_affected)
+    }
+
+    async fn list_for_user(&self, user_id: &str) -> Result<Vec<(String, SessionData)>> {
+        use beam_entity::session;
+
+        let models = session::Entity::find()
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .all(&self.db)
+            .await?;
+        models
+            .into_iter()
+            .map(|m| Ok((m.id, serde_json::from_value(m.data)?)))
+            .collect()
+    }
+
+    async fn create_device_authorization(
+        &self,
+        data: &DeviceAuthorization,
+        ttl_secs: u64,
+    ) -> Result<bool> {
+        use beam_entity::device_authorization;
+
+        // An expired pairing that has not been swept yet must not hold on to its code
+        device_authorization::Entity::delete_many()
+            .filter(device_authorization::Column::UserCode.eq(&data.user_code))
+            .filter(device_authorization::Column::ExpiresAt.lte(Utc::now()))
+            .exec(&self.db)
+            .await?;
+
+        let model = device_authorization::ActiveModel {
+            device_code_hash: Set(data.device_code_hash.clone()),
+            user_code: Set(data.user_code.clone()),
+            data: Set(serde_json::to_value(data)?),
+            expires_at: Set(Self::expires_at(ttl_secs).into()),
+        };
+        let inserted = device_authorization::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(device_authorization::Column::UserCode)
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&self.db)
+            .await?;
+        if inserted > 0 {
+            debug!("Created device authorization {}", data.user_code);
+        }
+        Ok(inserted > 0)
+    }
+
+    async fn get_device_authorization(
+        &self,
+        device_code_hash: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        use beam_entity::device_authorization;
+
+        let model = device_authorization::Entity::find_by_id(device_code_hash)
+            .filter(device_authorization::Column::ExpiresAt.gt(Utc::now()))
+            .one(&self.db)
+            .await?;
+        match model {
+            Some(m) => Ok(Some(serde_json::from_value(m.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn find_device_authorization(
+        &self,
+        user_code: &str,
+    ) -> Result<Option<DeviceAuthorization>> {
+        use beam_entity::device_authorization;
+
+        let model = device_authorization::Entity::find()
+            .filter(device_authorization::Column::UserCode.eq(user_code))
+            .filter(device_authorization::Column::ExpiresAt.gt(Utc::now()))
+            .one(&self.db)
+            .await?;
+        match model {
+            Some(m) => Ok(Some(serde_json::from_value(m.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn record_device_poll(&self, device_code_hash: &str, polled_at: i64) -> Result<()> {
+        use beam_entity::device_authorization;
+
+        // Only the one field is written so a concurrent approval is never overwritten
+        device_authorization::Entity::update_many()
+            .col_expr(
+                device_authorization::Column::Data,
+                Expr::cust_with_values(
+                    "jsonb_set(data, '{last_polled_at}', to_jsonb($1::bigint))",
+                    [polled_at],
+                ),
+            )
+            .filter(device_authorization::Column::DeviceCodeHash.eq(device_code_hash))
+            .filter(device_authorization::Column::ExpiresAt.gt(Utc::now()))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn decide_device_authorization(
+        &self,
+        device_code_hash: &str,
+        user_id: &str,
+        decision: DeviceDecision,
+    ) -> Result<bool> {
+        use beam_entity::device_authorization;
+
+        let set = match decision {
+            DeviceDecision::Approve => "jsonb_set(data, '{approved_by}', to_jsonb($1::text))",
+            DeviceDecision::Deny => "jsonb_set(data, '{denied_by}', to_jsonb($1::text))",
+        };
+        let result = device_authorization::Entity::update_many()
+            .col_expr(
+                device_authorization::Column::Data,
+                Expr::cust_with_values(set, [user_id]),
+            )
+            .filter(device_authorization::Column::DeviceCodeHash.eq(device_code_hash))
+            .filter(device_authorization::Column::ExpiresAt.gt(Utc::now()))
+            .filter(Expr::cust(
+                "data->>'approved_by' IS NULL AND data->>'denied_by' IS NULL",
+            ))
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    async fn delete_device_authorization(&self, data: &DeviceAuthorization) -> Result<bool> {
+        use beam_entity::device_authorization;
+
+        let result = device_authorization::Entity::delete_by_id(data.device_code_hash.as_str())
+            .exec(&self.db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
 }
 
 /// In-memory session store for use in tests and offline scenarios.
@@ -243,6 +862,7 @@ pub mod in_memory {
     #[derive(Debug, Default)]
     pub struct InMemorySessionStore {
         sessions: Mutex<HashMap<String, SessionData>>,
+        device_authorizations: Mutex<HashMap<String, DeviceAuthorization>>,
     }
 
     #[async_trait]
@@ -264,6 +884,28 @@ pub mod in_memory {
             Ok(())
         }
 
+        async fn update(&self, session_id: &str, data: &SessionData) -> Result<()> {
+            if let Some(session) = self.sessions.lock().unwrap().get_mut(session_id) {
+                *session = data.clone();
+            }
+            Ok(())
+        }
+
+        async fn rotate_refresh_hash(
+            &self,
+            session_id: &str,
+            old_hash: &str,
+            data: &SessionData,
+        ) -> Result<bool> {
+            match self.sessions.lock().unwrap().get_mut(session_id) {
+                Some(session) if session.refresh_token_hash == old_hash => {
+                    *session = data.clone();
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
         async fn delete(&self, session_id: &str) -> Result<()> {
             self.sessions.lock().unwrap().remove(session_id);
             Ok(())
@@ -291,5 +933,84 @@ pub mod in_memory {
                 .map(|(k, v)| (k.clone(), v.clone()))
                 .collect())
         }
+
+        async fn create_device_authorization(
+            &self,
+            data: &DeviceAuthorization,
+            _ttl_secs: u64,
+        ) -> Result<bool> {
+            let mut pending = self.device_authorizations.lock().unwrap();
+            if pending.values().any(|d| d.user_code == data.user_code) {
+                return Ok(false);
+            }
+            pending.insert(data.device_code_hash.clone(), data.clone());
+            Ok(true)
+        }
+
+        async fn get_device_authorization(
+            &self,
+            device_code_hash: &str,
+        ) -> Result<Option<DeviceAuthorization>> {
+            Ok(self
+                .device_authorizations
+                .lock()
+                .unwrap()
+                .get(device_code_hash)
+                .cloned())
+        }
+
+        async fn find_device_authorization(
+            &self,
+            user_code: &str,
+        ) -> Result<Option<DeviceAuthorization>> {
+            Ok(self
+                .device_authorizations
+                .lock()
+                .unwrap()
+                .values()
+                .find(|d| d.user_code == user_code)
+                .cloned())
+        }
+
+        async fn record_device_poll(&self, device_code_hash: &str, polled_at: i64) -> Result<()> {
+            if let Some(pending) = self
+                .device_authorizations
+                .lock()
+                .unwrap()
+                .get_mut(device_code_hash)
+            {
+                pending.last_polled_at = Some(polled_at);
+            }
+            Ok(())
+        }
+
+        async fn decide_device_authorization(
+            &self,
+            device_code_hash: &str,
+            user_id: &str,
+            decision: DeviceDecision,
+        ) -> Result<bool> {
+            let mut pending = self.device_authorizations.lock().unwrap();
+            let Some(pairing) = pending
+                .get_mut(device_code_hash)
+                .filter(|p| p.approved_by.is_none() && p.denied_by.is_none())
+            else {
+                return Ok(false);
+            };
+            match decision {
+                DeviceDecision::Approve => pairing.approved_by = Some(user_id.to_string()),
+                DeviceDecision::Deny => pairing.denied_by = Some(user_id.to_string()),
+            }
+            Ok(true)
+        }
+
+        async fn delete_device_authorization(&self, data: &DeviceAuthorization) -> Result<bool> {
+            Ok(self
+                .device_authorizations
+                .lock()
+                .unwrap()
+                .remove(&data.device_code_hash)
+                .is_some())
+        }
     }
 }
@@ -0,0 +1,181 @@
+#[cfg(test)]
+#[path = "totp_tests.rs"]
+mod totp_tests;
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::{Rng, RngExt};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+/// Issuer shown by authenticator apps
+pub const ISSUER: &str = "Beam";
+
+/// Number of recovery codes handed out when 2FA is enabled
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const SECRET_LEN: usize = 20;
+const STEP_SECS: i64 = 30;
+const DIGITS: u32 = 6;
+/// Accept codes one step either side of now to tolerate clock drift
+const SKEW_STEPS: i64 = 1;
+const NONCE_LEN: usize = 12;
+
+/// Generates a new random TOTP secret.
+pub fn generate_secret() -> Vec<u8> {
+    let mut secret = vec![0u8; SECRET_LEN];
+    rand::rng().fill_bytes(&mut secret);
+    secret
+}
+
+/// Base32 form of a secret, for manual entry in an authenticator app.
+pub fn encode_secret(secret: &[u8]) -> String {
+    BASE32_NOPAD.encode(secret)
+}
+
+/// `otpauth://` URI for enrolling `account` in an authenticator app, usually shown as a QR code.
+pub fn otpauth_uri(account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={DIGITS}&period={STEP_SECS}",
+        issuer = percent_encode(ISSUER),
+        account = percent_encode(account),
+        secret = encode_secret(secret),
+    )
+}
+
+/// The code an authenticator app shows at `unix_time` (RFC 6238, HMAC-SHA1).
+pub fn code_at(secret: &[u8], unix_time: i64) -> String {
+    hotp(secret, unix_time.div_euclid(STEP_SECS))
+}
+
+/// Checks `code` against the codes valid around `unix_time` and returns the time step it
+/// belongs to, so callers can refuse a code that was already used.
+pub fn verify(secret: &[u8], code: &str, unix_time: i64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize {
+        return None;
+    }
+
+    let step = unix_time.div_euclid(STEP_SECS);
+    (-SKEW_STEPS..=SKEW_STEPS)
+        .map(|offset| step + offset)
+        .find(|&step| constant_time_eq(&hotp(secret, step), code))
+}
+
+/// Generates one-time recovery codes such as `k3x9-p2qa`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    const ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+    let mut rng = rand::rng();
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let chars: String = (0..8)
+                .map(|_| ALPHABET[rng.random_range(0..ALPHABET.len())] as char)
+                .collect();
+            format!("{}-{}", &chars[..4], &chars[4..])
+        })
+        .collect()
+}
+
+/// Hash stored for a recovery code. Case, spaces and dashes are ignored.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+/// Encrypts TOTP secrets at rest with AES-256-GCM.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for SecretCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretCipher").finish_non_exhaustive()
+    }
+}
+
+impl SecretCipher {
+    /// Derives the encryption key from arbitrary key material.
+    pub fn new(key_material: &str) -> Self {
+        let key = Sha256::digest(key_material.as_bytes());
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        }
+    }
+
+    /// Returns base64 of a random nonce followed by the ciphertext.
+    pub fn encrypt(&self, plaintext: &[u8]) -> String {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .expect("AES-GCM encryption of a short secret cannot fail");
+
+        let mut out = nonce.to_vec();
+        out.extend(ciphertext);
+        STANDARD.encode(out)
+    }
+
+    /// Reverses [`encrypt`](Self::encrypt). `None` if the data is corrupt or the key changed.
+    pub fn decrypt(&self, encoded: &str) -> Option<Vec<u8>> {
+        let data = STANDARD.decode(encoded).ok()?;
+        if data.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .ok()
+    }
+}
+
+fn hotp(secret: &[u8], counter: i64) -> String {
+    let mut mac =
+        <Hmac<Sha1> as Mac>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    // Dynamic truncation, RFC 4226 section 5.3
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(DIGITS),
+        width = DIGITS as usize
+    )
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
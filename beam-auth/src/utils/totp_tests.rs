@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod tests {
+    use crate::utils::totp::{
+        RECOVERY_CODE_COUNT, SecretCipher, code_at, generate_recovery_codes, generate_secret,
+        hash_recovery_code, otpauth_uri, verify,
+    };
+
+    // RFC 6238 appendix B, SHA1 variant, truncated to six digits
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn code_at_matches_rfc_6238_vectors() {
+        assert_eq!(code_at(RFC_SECRET, 59), "287082");
+        assert_eq!(code_at(RFC_SECRET, 1_111_111_109), "081804");
+        assert_eq!(code_at(RFC_SECRET, 1_234_567_890), "005924");
+        assert_eq!(code_at(RFC_SECRET, 2_000_000_000), "279037");
+    }
+
+    #[test]
+    fn verify_tolerates_one_step_of_drift() {
+        let secret = generate_secret();
+        let now = 1_700_000_000;
+        let code = code_at(&secret, now);
+
+        assert_eq!(verify(&secret, &code, now), Some(now / 30));
+        assert_eq!(verify(&secret, &code, now + 30), Some(now / 30));
+        assert_eq!(verify(&secret, &code, now + 90), None);
+        assert_eq!(verify(&secret, "12345", now), None);
+    }
+
+    #[test]
+    fn otpauth_uri_encodes_account_and_secret() {
+        let uri = otpauth_uri("jane doe", RFC_SECRET);
+        assert!(uri.starts_with("otpauth://totp/Beam:jane%20doe?"));
+        assert!(uri.contains("secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"));
+        assert!(uri.contains("issuer=Beam"));
+    }
+
+    #[test]
+    fn recovery_codes_are_unique_and_hash_loosely() {
+        let codes = generate_recovery_codes();
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+
+        let code = &codes[0];
+        assert_eq!(
+            hash_recovery_code(code),
+            hash_recovery_code(&code.to_uppercase().replace('-', " "))
+        );
+        assert_ne!(hash_recovery_code(code), hash_recovery_code(&codes[1]));
+    }
+
+    #[test]
+    fn secret_cipher_round_trips_and_rejects_other_keys() {
+        let cipher = SecretCipher::new("key-one");
+        let encrypted = cipher.encrypt(RFC_SECRET);
+
+        assert_ne!(encrypted.as_bytes(), RFC_SECRET);
+        assert_eq!(cipher.decrypt(&encrypted).as_deref(), Some(RFC_SECRET));
+        assert!(SecretCipher::new("key-two").decrypt(&encrypted).is_none());
+    }
+}
@@ -14,6 +14,7 @@ pub enum AdminLogCategory {
     LibraryScan,
     System,
     Auth,
+    Integrity,
 }
 
 #[derive(Debug, Clone)]
@@ -34,6 +35,23 @@ pub struct CreateAdminLog {
     pub details: Option<Value>,
 }
 
+/// Restricts which admin log entries are listed. Unset fields match everything.
+#[derive(Debug, Clone, Default)]
+pub struct AdminLogFilter {
+    pub level: Option<AdminLogLevel>,
+    pub category: Option<AdminLogCategory>,
+}
+
+impl AdminLogFilter {
+    pub fn matches(&self, log: &AdminLog) -> bool {
+        self.level.as_ref().is_none_or(|level| *level == log.level)
+            && self
+                .category
+                .as_ref()
+                .is_none_or(|category| *category == log.category)
+    }
+}
+
 #[cfg(feature = "entity")]
 impl From<beam_entity::admin_log::AdminLogLevel> for AdminLogLevel {
     fn from(level: beam_entity::admin_log::AdminLogLevel) -> Self {
@@ -63,6 +81,7 @@ impl From<beam_entity::admin_log::AdminLogCategory> for AdminLogCategory {
             beam_entity::admin_log::AdminLogCategory::LibraryScan => AdminLogCategory::LibraryScan,
             beam_entity::admin_log::AdminLogCategory::System => AdminLogCategory::System,
             beam_entity::admin_log::AdminLogCategory::Auth => AdminLogCategory::Auth,
+            beam_entity::admin_log::AdminLogCategory::Integrity => AdminLogCategory::Integrity,
         }
     }
 }
@@ -74,6 +93,7 @@ impl From<AdminLogCategory> for beam_entity::admin_log::AdminLogCategory {
             AdminLogCategory::LibraryScan => beam_entity::admin_log::AdminLogCategory::LibraryScan,
             AdminLogCategory::System => beam_entity::admin_log::AdminLogCategory::System,
             AdminLogCategory::Auth => beam_entity::admin_log::AdminLogCategory::Auth,
+            AdminLogCategory::Integrity => beam_entity::admin_log::AdminLogCategory::Integrity,
         }
     }
 }
@@ -4,6 +4,8 @@ use std::path::PathBuf;
 use std::time::Duration;
 use uuid::Uuid;
 
+use crate::utils::hash::HashStrategy;
+
 /// Represents a media file in the library
 #[derive(Debug, Clone)]
 pub struct MediaFile {
@@ -11,13 +13,22 @@ pub struct MediaFile {
     pub library_id: Uuid,
     pub path: PathBuf,
     pub hash: u64,
+    /// Strategy that produced `hash`
+    pub hash_strategy: HashStrategy,
+    /// Cheap hash of the file's head and tail, used to pre-screen move detection
+    pub partial_hash: Option<u64>,
     pub size_bytes: u64,
     pub mime_type: Option<String>,
     pub duration: Option<Duration>,
     pub container_format: Option<String>,
     pub content: Option<MediaFileContent>,
     pub status: FileStatus,
+    /// Whether this file is preferred over duplicates of the same content
+    pub is_primary: bool,
+    /// When the file was last probed
     pub scanned_at: DateTime<Utc>,
+    /// When the file's content last matched its hash during an integrity check
+    pub verified_at: Option<DateTime<Utc>>,
     pub updated_at: DateTime<Utc>,
 }
 
@@ -70,6 +81,8 @@ pub struct CreateMediaFile {
     pub library_id: Uuid,
     pub path: PathBuf,
     pub hash: u64,
+    pub hash_strategy: HashStrategy,
+    pub partial_hash: Option<u64>,
     pub size_bytes: u64,
     pub mime_type: Option<String>,
     pub duration: Option<Duration>,
@@ -79,16 +92,25 @@ pub struct CreateMediaFile {
 }
 
 /// Parameters for updating an existing media file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct UpdateMediaFile {
     pub id: Uuid,
+    /// Moves the record to a new path, e.g. after a detected rename
+    pub path: Option<PathBuf>,
     pub hash: Option<u64>,
+    pub hash_strategy: Option<HashStrategy>,
+    pub partial_hash: Option<u64>,
     pub size_bytes: Option<u64>,
     pub mime_type: Option<String>,
     pub duration: Option<Duration>,
     pub container_format: Option<String>,
     pub content: Option<MediaFileContent>,
     pub status: Option<FileStatus>,
+    pub is_primary: Option<bool>,
+    /// When the file was last probed
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// When the file last passed an integrity check
+    pub verified_at: Option<DateTime<Utc>>,
 }
 
 #[cfg(feature = "entity")]
@@ -110,13 +132,17 @@ impl From<beam_entity::files::Model> for MediaFile {
             library_id: model.library_id,
             path: PathBuf::from(model.file_path),
             hash: model.hash_xxh3 as u64,
+            hash_strategy: model.hash_strategy.parse().unwrap_or_default(),
+            partial_hash: model.hash_partial.map(|h| h as u64),
             size_bytes: model.file_size as u64,
             mime_type: model.mime_type,
             duration: model.duration_secs.map(Duration::from_secs_f64),
             container_format: model.container_format,
             content,
             status,
+            is_primary: model.is_primary,
             scanned_at: model.scanned_at.with_timezone(&Utc),
+            verified_at: model.verified_at.map(|t| t.with_timezone(&Utc)),
             updated_at: model.updated_at.with_timezone(&Utc),
         }
     }
@@ -1,3 +1,35 @@
+/// Field-locking helpers for `Update*` structs. Each listed field must be an `Option`;
+/// its name doubles as the lock name stored in the item's `locked_fields`.
+macro_rules! lockable_fields {
+    ($update:ty { $($field:ident),* $(,)? }) => {
+        impl $update {
+            /// Names of the fields that can be locked
+            pub const LOCKABLE_FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            /// Names of the fields this update sets
+            pub fn changed_fields(&self) -> Vec<String> {
+                let mut fields = Vec::new();
+                $(
+                    if self.$field.is_some() {
+                        fields.push(stringify!($field).to_string());
+                    }
+                )*
+                fields
+            }
+
+            /// Drop changes to locked fields, for updates from automated sources
+            pub fn without_locked(mut self, locked: &[String]) -> Self {
+                $(
+                    if locked.iter().any(|f| f == stringify!($field)) {
+                        self.$field = None;
+                    }
+                )*
+                self
+            }
+        }
+    };
+}
+
 pub mod admin_log;
 pub mod file;
 pub mod library;
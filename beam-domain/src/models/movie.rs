@@ -19,6 +19,8 @@ pub struct Movie {
     pub tvdb_id: Option<u32>,
     pub rating_tmdb: Option<f32>,
     pub rating_imdb: Option<f32>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -41,6 +43,43 @@ pub struct CreateMovie {
     pub runtime: Option<Duration>,
 }
 
+/// Parameters for updating an existing movie. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateMovie {
+    pub id: Uuid,
+    pub title: Option<String>,
+    pub title_localized: Option<String>,
+    pub description: Option<String>,
+    pub year: Option<u32>,
+    pub release_date: Option<NaiveDate>,
+    pub runtime: Option<Duration>,
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+    pub tmdb_id: Option<u32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    pub rating_tmdb: Option<f32>,
+    pub rating_imdb: Option<f32>,
+    /// Replaces the item's locked fields when set
+    pub locked_fields: Option<Vec<String>>,
+}
+
+lockable_fields!(UpdateMovie {
+    title,
+    title_localized,
+    description,
+    year,
+    release_date,
+    runtime,
+    poster_url,
+    backdrop_url,
+    tmdb_id,
+    imdb_id,
+    tvdb_id,
+    rating_tmdb,
+    rating_imdb,
+});
+
 /// Parameters for creating a movie entry
 #[derive(Debug, Clone)]
 pub struct CreateMovieEntry {
@@ -50,6 +89,17 @@ pub struct CreateMovieEntry {
     pub is_primary: bool,
 }
 
+/// Parameters for updating a movie entry. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateMovieEntry {
+    pub id: Uuid,
+    /// Moves the entry to another movie
+    pub movie_id: Option<Uuid>,
+    /// `Some(None)` clears the edition
+    pub edition: Option<Option<String>>,
+    pub is_primary: Option<bool>,
+}
+
 #[cfg(feature = "entity")]
 impl From<beam_entity::movie::Model> for Movie {
     fn from(model: beam_entity::movie::Model) -> Self {
@@ -70,6 +120,7 @@ impl From<beam_entity::movie::Model> for Movie {
             tvdb_id: model.tvdb_id.map(|id| id as u32),
             rating_tmdb: model.rating_tmdb,
             rating_imdb: model.rating_imdb,
+            locked_fields: model.locked_fields,
             created_at: model.created_at.with_timezone(&Utc),
             updated_at: model.updated_at.with_timezone(&Utc),
         }
@@ -15,6 +15,8 @@ pub struct Show {
     pub tmdb_id: Option<u32>,
     pub imdb_id: Option<String>,
     pub tvdb_id: Option<u32>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -28,6 +30,8 @@ pub struct Season {
     pub poster_url: Option<String>,
     pub first_aired: Option<NaiveDate>,
     pub last_aired: Option<NaiveDate>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
 }
 
 /// An episode within a season
@@ -41,6 +45,8 @@ pub struct Episode {
     pub air_date: Option<String>,
     pub runtime: Option<Duration>,
     pub thumbnail_url: Option<String>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -53,6 +59,73 @@ pub struct CreateEpisode {
     pub runtime: Option<Duration>,
 }
 
+/// Parameters for updating an existing show. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateShow {
+    pub id: Uuid,
+    pub title: Option<String>,
+    pub title_localized: Option<String>,
+    pub description: Option<String>,
+    pub year: Option<u32>,
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+    pub tmdb_id: Option<u32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    /// Replaces the item's locked fields when set
+    pub locked_fields: Option<Vec<String>>,
+}
+
+lockable_fields!(UpdateShow {
+    title,
+    title_localized,
+    description,
+    year,
+    poster_url,
+    backdrop_url,
+    tmdb_id,
+    imdb_id,
+    tvdb_id,
+});
+
+/// Parameters for updating an existing season. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSeason {
+    pub id: Uuid,
+    pub poster_url: Option<String>,
+    pub first_aired: Option<NaiveDate>,
+    pub last_aired: Option<NaiveDate>,
+    /// Replaces the item's locked fields when set
+    pub locked_fields: Option<Vec<String>>,
+}
+
+lockable_fields!(UpdateSeason {
+    poster_url,
+    first_aired,
+    last_aired,
+});
+
+/// Parameters for updating an existing episode. Fields left as `None` are unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateEpisode {
+    pub id: Uuid,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub air_date: Option<NaiveDate>,
+    pub runtime: Option<Duration>,
+    pub thumbnail_url: Option<String>,
+    /// Replaces the item's locked fields when set
+    pub locked_fields: Option<Vec<String>>,
+}
+
+lockable_fields!(UpdateEpisode {
+    title,
+    description,
+    air_date,
+    runtime,
+    thumbnail_url,
+});
+
 #[cfg(feature = "entity")]
 impl From<beam_entity::show::Model> for Show {
     fn from(model: beam_entity::show::Model) -> Self {
@@ -67,6 +140,7 @@ impl From<beam_entity::show::Model> for Show {
             tmdb_id: model.tmdb_id.map(|id| id as u32),
             imdb_id: model.imdb_id,
             tvdb_id: model.tvdb_id.map(|id| id as u32),
+            locked_fields: model.locked_fields,
             created_at: model.created_at.with_timezone(&Utc),
             updated_at: model.updated_at.with_timezone(&Utc),
         }
@@ -83,6 +157,7 @@ impl From<beam_entity::season::Model> for Season {
             poster_url: model.poster_url,
             first_aired: model.first_aired,
             last_aired: model.last_aired,
+            locked_fields: model.locked_fields,
         }
     }
 }
@@ -101,6 +176,7 @@ impl From<beam_entity::episode::Model> for Episode {
                 .runtime_mins
                 .map(|mins| Duration::from_secs((mins * 60) as u64)),
             thumbnail_url: model.thumbnail_url,
+            locked_fields: model.locked_fields,
             created_at: model.created_at.with_timezone(&Utc),
         }
     }
@@ -1,13 +1,18 @@
 use async_trait::async_trait;
 use sea_orm::DbErr;
 
-use crate::models::admin_log::{AdminLog, CreateAdminLog};
+use crate::models::admin_log::{AdminLog, AdminLogFilter, CreateAdminLog};
 
 #[async_trait]
 pub trait AdminLogRepository: Send + Sync + std::fmt::Debug {
     async fn create(&self, entry: CreateAdminLog) -> Result<AdminLog, DbErr>;
-    async fn list(&self, limit: u64, offset: u64) -> Result<Vec<AdminLog>, DbErr>;
-    async fn count(&self) -> Result<u64, DbErr>;
+    async fn list(
+        &self,
+        filter: &AdminLogFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<AdminLog>, DbErr>;
+    async fn count(&self, filter: &AdminLogFilter) -> Result<u64, DbErr>;
 }
 
 #[cfg(any(test, feature = "test-utils"))]
@@ -37,17 +42,31 @@ pub mod in_memory {
             Ok(log)
         }
 
-        async fn list(&self, limit: u64, offset: u64) -> Result<Vec<AdminLog>, DbErr> {
+        async fn list(
+            &self,
+            filter: &AdminLogFilter,
+            limit: u64,
+            offset: u64,
+        ) -> Result<Vec<AdminLog>, DbErr> {
             let logs = self.logs.read();
-            let mut sorted: Vec<AdminLog> = logs.clone();
+            let mut sorted: Vec<AdminLog> = logs
+                .iter()
+                .filter(|log| filter.matches(log))
+                .cloned()
+                .collect();
             sorted.sort_by(|a, b| b.created_at.cmp(&a.created_at));
             let start = offset as usize;
             let end = (offset + limit) as usize;
             Ok(sorted.into_iter().skip(start).take(end - start).collect())
         }
 
-        async fn count(&self) -> Result<u64, DbErr> {
-            Ok(self.logs.read().len() as u64)
+        async fn count(&self, filter: &AdminLogFilter) -> Result<u64, DbErr> {
+            Ok(self
+                .logs
+                .read()
+                .iter()
+                .filter(|log| filter.matches(log))
+                .count() as u64)
         }
     }
 }
@@ -3,15 +3,31 @@ use sea_orm::DbErr;
 use uuid::Uuid;
 
 use crate::models::file::{CreateMediaFile, MediaFile, UpdateMediaFile};
+use crate::utils::hash::HashStrategy;
 
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
 #[async_trait]
 pub trait FileRepository: Send + Sync + std::fmt::Debug {
     async fn find_by_id(&self, id: Uuid) -> Result<Option<MediaFile>, DbErr>;
     async fn find_by_path(&self, path: &str) -> Result<Option<MediaFile>, DbErr>;
+    async fn find_all(&self) -> Result<Vec<MediaFile>, DbErr>;
     async fn find_all_by_library(&self, library_id: Uuid) -> Result<Vec<MediaFile>, DbErr>;
     async fn find_by_movie_entry_id(&self, movie_entry_id: Uuid) -> Result<Vec<MediaFile>, DbErr>;
     async fn find_by_episode_id(&self, episode_id: Uuid) -> Result<Vec<MediaFile>, DbErr>;
+    /// Files with this content hash, computed with `hash_strategy`, and size
+    async fn find_by_hash(
+        &self,
+        hash: u64,
+        hash_strategy: HashStrategy,
+        size_bytes: u64,
+    ) -> Result<Vec<MediaFile>, DbErr>;
+    /// Hashed files whose hash and size match at least one other file
+    async fn find_with_shared_hash(&self) -> Result<Vec<MediaFile>, DbErr>;
+    /// Files whose movie, or whose episode by show, season and episode number, has at least
+    /// one other file
+    async fn find_with_shared_content(&self) -> Result<Vec<MediaFile>, DbErr>;
+    /// Any file linked to an episode whose path lies under `dir`
+    async fn find_episode_file_in_dir(&self, dir: &str) -> Result<Option<MediaFile>, DbErr>;
     async fn create(&self, create: CreateMediaFile) -> Result<MediaFile, DbErr>;
     async fn update(&self, update: UpdateMediaFile) -> Result<MediaFile, DbErr>;
     async fn delete(&self, id: Uuid) -> Result<(), DbErr>;
@@ -47,6 +63,10 @@ pub mod in_memory {
                 .cloned())
         }
 
+        async fn find_all(&self) -> Result<Vec<MediaFile>, DbErr> {
+            Ok(self.files.lock().unwrap().values().cloned().collect())
+        }
+
         async fn find_all_by_library(&self, library_id: Uuid) -> Result<Vec<MediaFile>, DbErr> {
             Ok(self
                 .files
@@ -87,19 +107,81 @@ pub mod in_memory {
                 .collect())
         }
 
+        async fn find_by_hash(
+            &self,
+            hash: u64,
+            hash_strategy: HashStrategy,
+            size_bytes: u64,
+        ) -> Result<Vec<MediaFile>, DbErr> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|f| {
+                    f.hash == hash && f.hash_strategy == hash_strategy && f.size_bytes == size_bytes
+                })
+                .cloned()
+                .collect())
+        }
+
+        async fn find_with_shared_hash(&self) -> Result<Vec<MediaFile>, DbErr> {
+            let files = self.files.lock().unwrap();
+            let key = |f: &MediaFile| (f.hash, f.hash_strategy, f.size_bytes);
+            let mut counts: HashMap<_, usize> = HashMap::new();
+            for file in files.values().filter(|f| f.hash != 0) {
+                *counts.entry(key(file)).or_default() += 1;
+            }
+            Ok(files
+                .values()
+                .filter(|f| f.hash != 0 && counts[&key(f)] > 1)
+                .cloned()
+                .collect())
+        }
+
+        async fn find_with_shared_content(&self) -> Result<Vec<MediaFile>, DbErr> {
+            // Movies and episodes live in other repositories, so every linked file is a
+            // candidate; callers group them by content anyway
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|f| f.content.is_some())
+                .cloned()
+                .collect())
+        }
+
+        async fn find_episode_file_in_dir(&self, dir: &str) -> Result<Option<MediaFile>, DbErr> {
+            Ok(self
+                .files
+                .lock()
+                .unwrap()
+                .values()
+                .find(|f| {
+                    f.path.starts_with(dir)
+                        && matches!(f.content, Some(MediaFileContent::Episode { .. }))
+                })
+                .cloned())
+        }
+
         async fn create(&self, create: CreateMediaFile) -> Result<MediaFile, DbErr> {
             let file = MediaFile {
                 id: Uuid::new_v4(),
                 library_id: create.library_id,
                 path: create.path,
                 hash: create.hash,
+                hash_strategy: create.hash_strategy,
+                partial_hash: create.partial_hash,
                 size_bytes: create.size_bytes,
                 mime_type: create.mime_type,
                 duration: create.duration,
                 container_format: create.container_format,
                 content: create.content,
                 status: create.status,
+                is_primary: true,
                 scanned_at: chrono::Utc::now(),
+                verified_at: None,
                 updated_at: chrono::Utc::now(),
             };
             self.files.lock().unwrap().insert(file.id, file.clone());
@@ -114,9 +196,18 @@ pub mod in_memory {
                     "File {} not found",
                     update.id
                 )))?;
+            if let Some(path) = update.path {
+                file.path = path;
+            }
             if let Some(hash) = update.hash {
                 file.hash = hash;
             }
+            if let Some(hash_strategy) = update.hash_strategy {
+                file.hash_strategy = hash_strategy;
+            }
+            if let Some(partial_hash) = update.partial_hash {
+                file.partial_hash = Some(partial_hash);
+            }
             if let Some(size) = update.size_bytes {
                 file.size_bytes = size;
             }
@@ -135,6 +226,15 @@ pub mod in_memory {
             if let Some(content) = update.content {
                 file.content = Some(content);
             }
+            if let Some(is_primary) = update.is_primary {
+                file.is_primary = is_primary;
+            }
+            if let Some(scanned_at) = update.scanned_at {
+                file.scanned_at = scanned_at;
+            }
+            if let Some(verified_at) = update.verified_at {
+                file.verified_at = Some(verified_at);
+            }
             file.updated_at = chrono::Utc::now();
             Ok(file.clone())
         }
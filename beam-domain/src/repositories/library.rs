@@ -20,18 +20,31 @@ pub trait LibraryRepository: Send + Sync + std::fmt::Debug {
         file_count: Option<i32>,
     ) -> Result<(), DbErr>;
     async fn delete(&self, id: Uuid) -> Result<(), DbErr>;
+    /// Libraries a user has been granted access to
+    async fn find_ids_by_user(&self, user_id: Uuid) -> Result<Vec<Uuid>, DbErr>;
+    /// Users granted access to a library
+    async fn find_user_ids(&self, library_id: Uuid) -> Result<Vec<Uuid>, DbErr>;
+    /// Grant a user access to a library. Granting twice is a no-op.
+    async fn grant_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr>;
+    async fn revoke_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr>;
+    /// Libraries holding a movie, show, season or episode that uses an artwork URL
+    async fn find_ids_by_artwork(&self, artwork_url: &str) -> Result<Vec<Uuid>, DbErr>;
 }
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod in_memory {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Mutex;
 
     #[derive(Debug, Default)]
     pub struct InMemoryLibraryRepository {
         pub libraries: Mutex<HashMap<Uuid, Library>>,
         pub file_counts: Mutex<HashMap<Uuid, u64>>,
+        /// `(library_id, user_id)` access grants
+        pub access: Mutex<HashSet<(Uuid, Uuid)>>,
+        /// Libraries using each artwork URL
+        pub artwork: Mutex<HashMap<String, Vec<Uuid>>>,
     }
 
     #[async_trait]
@@ -96,7 +109,53 @@ pub mod in_memory {
 
         async fn delete(&self, id: Uuid) -> Result<(), DbErr> {
             self.libraries.lock().unwrap().remove(&id);
+            self.access
+                .lock()
+                .unwrap()
+                .retain(|(library_id, _)| *library_id != id);
+            Ok(())
+        }
+
+        async fn find_ids_by_user(&self, user_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+            Ok(self
+                .access
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, u)| *u == user_id)
+                .map(|(library_id, _)| *library_id)
+                .collect())
+        }
+
+        async fn find_user_ids(&self, library_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+            Ok(self
+                .access
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(l, _)| *l == library_id)
+                .map(|(_, user_id)| *user_id)
+                .collect())
+        }
+
+        async fn grant_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr> {
+            self.access.lock().unwrap().insert((library_id, user_id));
+            Ok(())
+        }
+
+        async fn revoke_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr> {
+            self.access.lock().unwrap().remove(&(library_id, user_id));
             Ok(())
         }
+
+        async fn find_ids_by_artwork(&self, artwork_url: &str) -> Result<Vec<Uuid>, DbErr> {
+            Ok(self
+                .artwork
+                .lock()
+                .unwrap()
+                .get(artwork_url)
+                .cloned()
+                .unwrap_or_default())
+        }
     }
 }
@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use sea_orm::DbErr;
 use uuid::Uuid;
 
-use crate::models::movie::{CreateMovie, CreateMovieEntry, Movie, MovieEntry};
+use crate::models::movie::{
+    CreateMovie, CreateMovieEntry, Movie, MovieEntry, UpdateMovie, UpdateMovieEntry,
+};
 
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
 #[async_trait]
@@ -11,25 +13,42 @@ pub trait MovieRepository: Send + Sync + std::fmt::Debug {
     async fn find_by_title(&self, title: &str) -> Result<Option<Movie>, DbErr>;
     async fn find_all(&self) -> Result<Vec<Movie>, DbErr>;
     async fn create(&self, create: CreateMovie) -> Result<Movie, DbErr>;
+    async fn update(&self, update: UpdateMovie) -> Result<Movie, DbErr>;
+    /// Delete a movie along with its entries, genres and library associations
+    async fn delete(&self, id: Uuid) -> Result<(), DbErr>;
     async fn create_entry(&self, create: CreateMovieEntry) -> Result<MovieEntry, DbErr>;
+    async fn find_entry_by_id(&self, id: Uuid) -> Result<Option<MovieEntry>, DbErr>;
     async fn find_entries_by_movie_id(&self, movie_id: Uuid) -> Result<Vec<MovieEntry>, DbErr>;
+    async fn find_all_entries(&self) -> Result<Vec<MovieEntry>, DbErr>;
+    async fn update_entry(&self, update: UpdateMovieEntry) -> Result<MovieEntry, DbErr>;
+    /// Make an entry its movie's primary version, demoting the movie's other entries
+    async fn set_primary_entry(&self, entry_id: Uuid) -> Result<(), DbErr>;
     async fn ensure_library_association(
         &self,
         library_id: Uuid,
         movie_id: Uuid,
     ) -> Result<(), DbErr>;
+    /// Libraries the movie is associated with
+    async fn find_library_ids(&self, movie_id: Uuid) -> Result<Vec<Uuid>, DbErr>;
+    /// Genre names attached to a movie, sorted alphabetically
+    async fn find_genres(&self, movie_id: Uuid) -> Result<Vec<String>, DbErr>;
+    /// Replace the genres attached to a movie, creating missing genres by name
+    async fn set_genres(&self, movie_id: Uuid, genres: Vec<String>) -> Result<(), DbErr>;
 }
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod in_memory {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Mutex;
 
     #[derive(Debug, Default)]
     pub struct InMemoryMovieRepository {
         pub movies: Mutex<HashMap<Uuid, Movie>>,
         pub entries: Mutex<HashMap<Uuid, MovieEntry>>,
+        pub genres: Mutex<HashMap<Uuid, Vec<String>>>,
+        /// Library ids by movie id
+        pub libraries: Mutex<HashMap<Uuid, HashSet<Uuid>>>,
     }
 
     #[async_trait]
@@ -68,6 +87,7 @@ pub mod in_memory {
                 tvdb_id: None,
                 rating_tmdb: None,
                 rating_imdb: None,
+                locked_fields: vec![],
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             };
@@ -75,6 +95,68 @@ pub mod in_memory {
             Ok(movie)
         }
 
+        async fn update(&self, update: UpdateMovie) -> Result<Movie, DbErr> {
+            let mut movies = self.movies.lock().unwrap();
+            let movie = movies
+                .get_mut(&update.id)
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "Movie {} not found",
+                    update.id
+                )))?;
+            if let Some(title) = update.title {
+                movie.title = title;
+            }
+            if let Some(title_localized) = update.title_localized {
+                movie.title_localized = Some(title_localized);
+            }
+            if let Some(description) = update.description {
+                movie.description = Some(description);
+            }
+            if let Some(year) = update.year {
+                movie.year = Some(year);
+            }
+            if let Some(release_date) = update.release_date {
+                movie.release_date = Some(release_date);
+            }
+            if let Some(runtime) = update.runtime {
+                movie.runtime = Some(runtime);
+            }
+            if let Some(poster_url) = update.poster_url {
+                movie.poster_url = Some(poster_url);
+            }
+            if let Some(backdrop_url) = update.backdrop_url {
+                movie.backdrop_url = Some(backdrop_url);
+            }
+            if let Some(tmdb_id) = update.tmdb_id {
+                movie.tmdb_id = Some(tmdb_id);
+            }
+            if let Some(imdb_id) = update.imdb_id {
+                movie.imdb_id = Some(imdb_id);
+            }
+            if let Some(tvdb_id) = update.tvdb_id {
+                movie.tvdb_id = Some(tvdb_id);
+            }
+            if let Some(rating) = update.rating_tmdb {
+                movie.rating_tmdb = Some(rating);
+            }
+            if let Some(rating) = update.rating_imdb {
+                movie.rating_imdb = Some(rating);
+            }
+            if let Some(locked_fields) = update.locked_fields {
+                movie.locked_fields = locked_fields;
+            }
+            movie.updated_at = chrono::Utc::now();
+            Ok(movie.clone())
+        }
+
+        async fn delete(&self, id: Uuid) -> Result<(), DbErr> {
+            self.movies.lock().unwrap().remove(&id);
+            self.entries.lock().unwrap().retain(|_, e| e.movie_id != id);
+            self.genres.lock().unwrap().remove(&id);
+            self.libraries.lock().unwrap().remove(&id);
+            Ok(())
+        }
+
         async fn create_entry(&self, create: CreateMovieEntry) -> Result<MovieEntry, DbErr> {
             let entry = MovieEntry {
                 id: Uuid::new_v4(),
@@ -88,6 +170,10 @@ pub mod in_memory {
             Ok(entry)
         }
 
+        async fn find_entry_by_id(&self, id: Uuid) -> Result<Option<MovieEntry>, DbErr> {
+            Ok(self.entries.lock().unwrap().get(&id).cloned())
+        }
+
         async fn find_entries_by_movie_id(&self, movie_id: Uuid) -> Result<Vec<MovieEntry>, DbErr> {
             Ok(self
                 .entries
@@ -99,11 +185,83 @@ pub mod in_memory {
                 .collect())
         }
 
+        async fn find_all_entries(&self) -> Result<Vec<MovieEntry>, DbErr> {
+            Ok(self.entries.lock().unwrap().values().cloned().collect())
+        }
+
+        async fn update_entry(&self, update: UpdateMovieEntry) -> Result<MovieEntry, DbErr> {
+            let mut entries = self.entries.lock().unwrap();
+            let entry = entries
+                .get_mut(&update.id)
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "Movie entry {} not found",
+                    update.id
+                )))?;
+            if let Some(movie_id) = update.movie_id {
+                entry.movie_id = movie_id;
+            }
+            if let Some(edition) = update.edition {
+                entry.edition = edition;
+            }
+            if let Some(is_primary) = update.is_primary {
+                entry.is_primary = is_primary;
+            }
+            Ok(entry.clone())
+        }
+
+        async fn set_primary_entry(&self, entry_id: Uuid) -> Result<(), DbErr> {
+            let mut entries = self.entries.lock().unwrap();
+            let movie_id =
+                entries
+                    .get(&entry_id)
+                    .map(|e| e.movie_id)
+                    .ok_or(DbErr::RecordNotFound(format!(
+                        "Movie entry {entry_id} not found"
+                    )))?;
+            for entry in entries.values_mut().filter(|e| e.movie_id == movie_id) {
+                entry.is_primary = entry.id == entry_id;
+            }
+            Ok(())
+        }
+
         async fn ensure_library_association(
             &self,
-            _library_id: Uuid,
-            _movie_id: Uuid,
+            library_id: Uuid,
+            movie_id: Uuid,
         ) -> Result<(), DbErr> {
+            self.libraries
+                .lock()
+                .unwrap()
+                .entry(movie_id)
+                .or_default()
+                .insert(library_id);
+            Ok(())
+        }
+
+        async fn find_library_ids(&self, movie_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+            Ok(self
+                .libraries
+                .lock()
+                .unwrap()
+                .get(&movie_id)
+                .map(|ids| ids.iter().copied().collect())
+                .unwrap_or_default())
+        }
+
+        async fn find_genres(&self, movie_id: Uuid) -> Result<Vec<String>, DbErr> {
+            let mut genres = self
+                .genres
+                .lock()
+                .unwrap()
+                .get(&movie_id)
+                .cloned()
+                .unwrap_or_default();
+            genres.sort();
+            Ok(genres)
+        }
+
+        async fn set_genres(&self, movie_id: Uuid, genres: Vec<String>) -> Result<(), DbErr> {
+            self.genres.lock().unwrap().insert(movie_id, genres);
             Ok(())
         }
     }
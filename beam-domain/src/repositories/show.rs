@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use sea_orm::DbErr;
 use uuid::Uuid;
 
-use crate::models::show::{CreateEpisode, Episode, Season, Show};
+use crate::models::show::{
+    CreateEpisode, Episode, Season, Show, UpdateEpisode, UpdateSeason, UpdateShow,
+};
 
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
 #[async_trait]
@@ -11,25 +13,36 @@ pub trait ShowRepository: Send + Sync + std::fmt::Debug {
     async fn find_by_title(&self, title: &str) -> Result<Option<Show>, DbErr>;
     async fn find_all(&self) -> Result<Vec<Show>, DbErr>;
     async fn create(&self, title: String) -> Result<Show, DbErr>;
+    async fn update(&self, update: UpdateShow) -> Result<Show, DbErr>;
     async fn ensure_library_association(
         &self,
         library_id: Uuid,
         show_id: Uuid,
     ) -> Result<(), DbErr>;
+    /// Libraries the show is associated with
+    async fn find_library_ids(&self, show_id: Uuid) -> Result<Vec<Uuid>, DbErr>;
     async fn find_or_create_season(
         &self,
         show_id: Uuid,
         season_number: u32,
     ) -> Result<Season, DbErr>;
+    async fn find_season_by_id(&self, id: Uuid) -> Result<Option<Season>, DbErr>;
     async fn find_seasons_by_show_id(&self, show_id: Uuid) -> Result<Vec<Season>, DbErr>;
+    async fn update_season(&self, update: UpdateSeason) -> Result<Season, DbErr>;
+    async fn find_episode_by_id(&self, id: Uuid) -> Result<Option<Episode>, DbErr>;
     async fn find_episodes_by_season_id(&self, season_id: Uuid) -> Result<Vec<Episode>, DbErr>;
     async fn create_episode(&self, create: CreateEpisode) -> Result<Episode, DbErr>;
+    async fn update_episode(&self, update: UpdateEpisode) -> Result<Episode, DbErr>;
+    /// Genre names attached to a show, sorted alphabetically
+    async fn find_genres(&self, show_id: Uuid) -> Result<Vec<String>, DbErr>;
+    /// Replace the genres attached to a show, creating missing genres by name
+    async fn set_genres(&self, show_id: Uuid, genres: Vec<String>) -> Result<(), DbErr>;
 }
 
 #[cfg(any(test, feature = "test-utils"))]
 pub mod in_memory {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use std::sync::Mutex;
 
     #[derive(Debug, Default)]
@@ -37,6 +50,9 @@ pub mod in_memory {
         pub shows: Mutex<HashMap<Uuid, Show>>,
         pub seasons: Mutex<HashMap<Uuid, Season>>,
         pub episodes: Mutex<HashMap<Uuid, Episode>>,
+        pub genres: Mutex<HashMap<Uuid, Vec<String>>>,
+        /// Library ids by show id
+        pub libraries: Mutex<HashMap<Uuid, HashSet<Uuid>>>,
     }
 
     #[async_trait]
@@ -71,6 +87,7 @@ pub mod in_memory {
                 tmdb_id: None,
                 imdb_id: None,
                 tvdb_id: None,
+                locked_fields: vec![],
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             };
@@ -78,14 +95,72 @@ pub mod in_memory {
             Ok(show)
         }
 
+        async fn update(&self, update: UpdateShow) -> Result<Show, DbErr> {
+            let mut shows = self.shows.lock().unwrap();
+            let show = shows
+                .get_mut(&update.id)
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "Show {} not found",
+                    update.id
+                )))?;
+            if let Some(title) = update.title {
+                show.title = title;
+            }
+            if let Some(title_localized) = update.title_localized {
+                show.title_localized = Some(title_localized);
+            }
+            if let Some(description) = update.description {
+                show.description = Some(description);
+            }
+            if let Some(year) = update.year {
+                show.year = Some(year);
+            }
+            if let Some(poster_url) = update.poster_url {
+                show.poster_url = Some(poster_url);
+            }
+            if let Some(backdrop_url) = update.backdrop_url {
+                show.backdrop_url = Some(backdrop_url);
+            }
+            if let Some(tmdb_id) = update.tmdb_id {
+                show.tmdb_id = Some(tmdb_id);
+            }
+            if let Some(imdb_id) = update.imdb_id {
+                show.imdb_id = Some(imdb_id);
+            }
+            if let Some(tvdb_id) = update.tvdb_id {
+                show.tvdb_id = Some(tvdb_id);
+            }
+            if let Some(locked_fields) = update.locked_fields {
+                show.locked_fields = locked_fields;
+            }
+            show.updated_at = chrono::Utc::now();
+            Ok(show.clone())
+        }
+
         async fn ensure_library_association(
             &self,
-            _library_id: Uuid,
-            _show_id: Uuid,
+            library_id: Uuid,
+            show_id: Uuid,
         ) -> Result<(), DbErr> {
+            self.libraries
+                .lock()
+                .unwrap()
+                .entry(show_id)
+                .or_default()
+                .insert(library_id);
             Ok(())
         }
 
+        async fn find_library_ids(&self, show_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+            Ok(self
+                .libraries
+                .lock()
+                .unwrap()
+                .get(&show_id)
+                .map(|ids| ids.iter().copied().collect())
+                .unwrap_or_default())
+        }
+
         async fn find_or_create_season(
             &self,
             show_id: Uuid,
@@ -107,6 +182,7 @@ pub mod in_memory {
                 poster_url: None,
                 first_aired: None,
                 last_aired: None,
+                locked_fields: vec![],
             };
             self.seasons
                 .lock()
@@ -115,6 +191,10 @@ pub mod in_memory {
             Ok(season)
         }
 
+        async fn find_season_by_id(&self, id: Uuid) -> Result<Option<Season>, DbErr> {
+            Ok(self.seasons.lock().unwrap().get(&id).cloned())
+        }
+
         async fn find_seasons_by_show_id(&self, show_id: Uuid) -> Result<Vec<Season>, DbErr> {
             let mut seasons: Vec<Season> = self
                 .seasons
@@ -128,6 +208,33 @@ pub mod in_memory {
             Ok(seasons)
         }
 
+        async fn update_season(&self, update: UpdateSeason) -> Result<Season, DbErr> {
+            let mut seasons = self.seasons.lock().unwrap();
+            let season = seasons
+                .get_mut(&update.id)
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "Season {} not found",
+                    update.id
+                )))?;
+            if let Some(poster_url) = update.poster_url {
+                season.poster_url = Some(poster_url);
+            }
+            if let Some(first_aired) = update.first_aired {
+                season.first_aired = Some(first_aired);
+            }
+            if let Some(last_aired) = update.last_aired {
+                season.last_aired = Some(last_aired);
+            }
+            if let Some(locked_fields) = update.locked_fields {
+                season.locked_fields = locked_fields;
+            }
+            Ok(season.clone())
+        }
+
+        async fn find_episode_by_id(&self, id: Uuid) -> Result<Option<Episode>, DbErr> {
+            Ok(self.episodes.lock().unwrap().get(&id).cloned())
+        }
+
         async fn find_episodes_by_season_id(&self, season_id: Uuid) -> Result<Vec<Episode>, DbErr> {
             let mut episodes: Vec<Episode> = self
                 .episodes
@@ -151,10 +258,57 @@ pub mod in_memory {
                 air_date: None,
                 runtime: create.runtime,
                 thumbnail_url: None,
+                locked_fields: vec![],
                 created_at: chrono::Utc::now(),
             };
             self.episodes.lock().unwrap().insert(ep.id, ep.clone());
             Ok(ep)
         }
+
+        async fn update_episode(&self, update: UpdateEpisode) -> Result<Episode, DbErr> {
+            let mut episodes = self.episodes.lock().unwrap();
+            let ep = episodes
+                .get_mut(&update.id)
+                .ok_or(DbErr::RecordNotFound(format!(
+                    "Episode {} not found",
+                    update.id
+                )))?;
+            if let Some(title) = update.title {
+                ep.title = title;
+            }
+            if let Some(description) = update.description {
+                ep.description = Some(description);
+            }
+            if let Some(air_date) = update.air_date {
+                ep.air_date = Some(air_date.to_string());
+            }
+            if let Some(runtime) = update.runtime {
+                ep.runtime = Some(runtime);
+            }
+            if let Some(thumbnail_url) = update.thumbnail_url {
+                ep.thumbnail_url = Some(thumbnail_url);
+            }
+            if let Some(locked_fields) = update.locked_fields {
+                ep.locked_fields = locked_fields;
+            }
+            Ok(ep.clone())
+        }
+
+        async fn find_genres(&self, show_id: Uuid) -> Result<Vec<String>, DbErr> {
+            let mut genres = self
+                .genres
+                .lock()
+                .unwrap()
+                .get(&show_id)
+                .cloned()
+                .unwrap_or_default();
+            genres.sort();
+            Ok(genres)
+        }
+
+        async fn set_genres(&self, show_id: Uuid, genres: Vec<String>) -> Result<(), DbErr> {
+            self.genres.lock().unwrap().insert(show_id, genres);
+            Ok(())
+        }
     }
 }
@@ -9,6 +9,8 @@ use crate::models::stream::{CreateMediaStream, MediaStream};
 pub trait MediaStreamRepository: Send + Sync + std::fmt::Debug {
     async fn insert_streams(&self, streams: Vec<CreateMediaStream>) -> Result<u32, DbErr>;
     async fn find_by_file_id(&self, file_id: Uuid) -> Result<Vec<MediaStream>, DbErr>;
+    /// Delete all streams of a file, returning how many were removed
+    async fn delete_by_file_id(&self, file_id: Uuid) -> Result<u64, DbErr>;
 }
 
 #[cfg(any(test, feature = "test-utils"))]
@@ -56,5 +58,10 @@ pub mod in_memory {
             streams.sort_by_key(|s| s.index);
             Ok(streams)
         }
+
+        async fn delete_by_file_id(&self, file_id: Uuid) -> Result<u64, DbErr> {
+            let removed = self.streams.lock().unwrap().remove(&file_id);
+            Ok(removed.map_or(0, |s| s.len() as u64))
+        }
     }
 }
@@ -1,6 +1,7 @@
 use std::{
+    fmt,
     fs::File,
-    io::{self, BufReader, Read},
+    io::{self, BufReader, Read, Seek, SeekFrom},
     ops::Deref,
     path::Path,
 };
@@ -53,3 +54,155 @@ pub fn compute_hash(path: &Path) -> io::Result<u64> {
 
     Ok(hasher.digest())
 }
+
+/// How much of a file goes into its content hash.
+///
+/// Sampled and head hashes also cover the file size. Hashes are only comparable when
+/// produced by the same strategy, so the strategy is stored alongside each hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum HashStrategy {
+    /// Every byte of the file
+    #[default]
+    Full,
+    /// The head, the tail and `chunks` evenly spaced [`SAMPLE_CHUNK_SIZE`] chunks between them
+    Sampled { chunks: u32 },
+    /// The first `megabytes` MiB
+    Head { megabytes: u32 },
+}
+
+impl fmt::Display for HashStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HashStrategy::Full => write!(f, "full"),
+            HashStrategy::Sampled { chunks } => write!(f, "sampled:{}", chunks),
+            HashStrategy::Head { megabytes } => write!(f, "head:{}", megabytes),
+        }
+    }
+}
+
+impl std::str::FromStr for HashStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid hash strategy: {}", s);
+        let (kind, param) = match s.split_once(':') {
+            Some((kind, param)) => (kind, Some(param.parse::<u32>().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        match (kind, param) {
+            ("full", None) => Ok(HashStrategy::Full),
+            ("sampled", Some(chunks)) => Ok(HashStrategy::Sampled { chunks }),
+            ("head", Some(megabytes)) if megabytes > 0 => Ok(HashStrategy::Head { megabytes }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for HashStrategy {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<HashStrategy> for String {
+    fn from(strategy: HashStrategy) -> Self {
+        strategy.to_string()
+    }
+}
+
+/// Size of each chunk read by [`HashStrategy::Sampled`]
+pub const SAMPLE_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// Computes the hash of a file using the given strategy.
+pub fn compute_hash_with(path: &Path, strategy: HashStrategy) -> io::Result<u64> {
+    match strategy {
+        HashStrategy::Full => compute_hash(path),
+        HashStrategy::Sampled { chunks } => compute_sampled_hash(path, chunks),
+        HashStrategy::Head { megabytes } => compute_head_hash(path, megabytes),
+    }
+}
+
+/// XXH3 over the file size plus its head, tail and `chunks` evenly spaced chunks.
+/// Files too small to sample are hashed whole.
+fn compute_sampled_hash(path: &Path, chunks: u32) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    let samples = chunks as u64 + 2;
+    let mut buffer = Vec::with_capacity(SAMPLE_CHUNK_SIZE as usize);
+    if size <= samples * SAMPLE_CHUNK_SIZE {
+        file.read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+        return Ok(hasher.digest());
+    }
+
+    // Offsets run from the head (i = 0) to the tail (i = samples - 1)
+    let last = size - SAMPLE_CHUNK_SIZE;
+    for i in 0..samples {
+        file.seek(SeekFrom::Start(last * i / (samples - 1)))?;
+        buffer.clear();
+        (&mut file)
+            .take(SAMPLE_CHUNK_SIZE)
+            .read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(hasher.digest())
+}
+
+/// XXH3 over the file size plus its first `megabytes` MiB.
+fn compute_head_hash(path: &Path, megabytes: u32) -> io::Result<u64> {
+    let file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    let mut reader = BufReader::with_capacity(SAMPLE_CHUNK_SIZE as usize, file)
+        .take(megabytes as u64 * 1024 * 1024);
+    let mut buffer = vec![0; SAMPLE_CHUNK_SIZE as usize];
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => break,
+            bytes_read => hasher.update(&buffer[..bytes_read]),
+        }
+    }
+
+    Ok(hasher.digest())
+}
+
+/// Size of the head and tail chunks read by [`compute_partial_hash`]
+pub const PARTIAL_HASH_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Computes a cheap XXH3 over the file size plus its first and last 64 KiB.
+///
+/// Equal partial hashes don't prove two files are identical, but differing ones
+/// rule it out without reading the whole file.
+pub fn compute_partial_hash(path: &Path) -> io::Result<u64> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    let mut buffer = Vec::with_capacity(PARTIAL_HASH_CHUNK_SIZE as usize);
+    (&mut file)
+        .take(PARTIAL_HASH_CHUNK_SIZE)
+        .read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+
+    if size > PARTIAL_HASH_CHUNK_SIZE {
+        let tail_start = size
+            .saturating_sub(PARTIAL_HASH_CHUNK_SIZE)
+            .max(PARTIAL_HASH_CHUNK_SIZE);
+        file.seek(SeekFrom::Start(tail_start))?;
+        buffer.clear();
+        file.read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(hasher.digest())
+}
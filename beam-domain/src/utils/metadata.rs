@@ -685,6 +685,60 @@ impl VideoFileMetadata {
     }
 }
 
+/// Demux every packet of a file and decode its best video and audio streams, failing on
+/// the first read or decode error. Much slower than probing, so reserved for integrity
+/// checks.
+pub fn check_decode(file_path: &Path) -> Result<(), MetadataError> {
+    let mut context = ffmpeg::format::input(file_path)?;
+
+    let mut decoders = HashMap::new();
+    for media_type in [ffmpeg::media::Type::Video, ffmpeg::media::Type::Audio] {
+        if let Some(stream) = context.streams().best(media_type) {
+            let codec = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+            let decoder = match media_type {
+                ffmpeg::media::Type::Video => codec.decoder().video()?.0,
+                _ => codec.decoder().audio()?.0,
+            };
+            decoders.insert(stream.index(), decoder);
+        }
+    }
+
+    // Scratch frame; decoded data is discarded
+    let mut frame = ffmpeg::frame::Video::empty();
+    let mut drain = |decoder: &mut ffmpeg::decoder::Opened| -> Result<(), ffmpeg::Error> {
+        loop {
+            match decoder.receive_frame(&mut frame) {
+                Ok(()) => continue,
+                Err(ffmpeg::Error::Eof) => return Ok(()),
+                Err(ffmpeg::Error::Other { errno }) if errno == ffmpeg::error::EAGAIN => {
+                    return Ok(());
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    };
+
+    let mut packet = ffmpeg::Packet::empty();
+    loop {
+        match packet.read(&mut context) {
+            Ok(()) => {}
+            Err(ffmpeg::Error::Eof) => break,
+            Err(e) => return Err(e.into()),
+        }
+        if let Some(decoder) = decoders.get_mut(&packet.stream()) {
+            decoder.send_packet(&packet)?;
+            drain(decoder)?;
+        }
+    }
+
+    for decoder in decoders.values_mut() {
+        decoder.send_eof()?;
+        drain(decoder)?;
+    }
+
+    Ok(())
+}
+
 #[derive(Debug, Error)]
 pub enum MetadataError {
     #[error("FFmpeg error: {0}")]
@@ -21,6 +21,8 @@ pub enum AdminLogCategory {
     System,
     #[sea_orm(string_value = "auth")]
     Auth,
+    #[sea_orm(string_value = "integrity")]
+    Integrity,
 }
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
@@ -0,0 +1,46 @@
+//! Long-lived API key a user's headless clients authenticate with
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_keys")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub user_id: Uuid,
+
+    pub name: String,
+
+    /// First characters of the key, shown to tell keys apart
+    pub prefix: String,
+
+    /// SHA-256 hash of the key
+    #[sea_orm(unique)]
+    pub key_hash: String,
+
+    pub scopes: Vec<String>,
+
+    pub created_at: DateTimeWithTimeZone,
+
+    pub last_used_at: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,26 @@
+//! Device waiting to be paired, when sessions are kept in PostgreSQL instead of Redis
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "device_authorizations")]
+pub struct Model {
+    /// SHA-256 hash of the device code the device polls with
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub device_code_hash: String,
+
+    /// Code the user enters to approve the device
+    #[sea_orm(unique)]
+    pub user_code: String,
+
+    /// The pairing's `DeviceAuthorization` as JSON
+    pub data: Json,
+
+    pub expires_at: DateTimeWithTimeZone,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -17,6 +17,9 @@ pub struct Model {
     pub runtime_mins: Option<i32>,
     pub thumbnail_url: Option<String>,
 
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
+
     pub created_at: DateTimeWithTimeZone,
 }
 
@@ -21,6 +21,11 @@ pub struct Model {
 
     #[sea_orm(column_type = "BigInteger")]
     pub hash_xxh3: i64,
+    /// XXH3 of the file's head and tail, see `compute_partial_hash`
+    #[sea_orm(column_type = "BigInteger", nullable)]
+    pub hash_partial: Option<i64>,
+    /// Strategy that produced `hash_xxh3`, e.g. `full` or `sampled:8`
+    pub hash_strategy: String,
 
     pub duration_secs: Option<f64>,
     pub container_format: Option<String>,
@@ -32,6 +37,8 @@ pub struct Model {
     pub is_primary: bool,
 
     pub scanned_at: DateTimeWithTimeZone,
+    /// Last time the file's content matched its hash
+    pub verified_at: Option<DateTimeWithTimeZone>,
     pub updated_at: DateTimeWithTimeZone,
     pub file_status: String,
 }
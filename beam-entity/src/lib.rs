@@ -3,35 +3,51 @@
 //! These entities map to the database tables created by migrations.
 
 pub mod admin_log;
+pub mod api_key;
+pub mod device_authorization;
 pub mod episode;
 pub mod files;
 pub mod genre;
+pub mod invite;
 pub mod library;
+pub mod library_access;
 pub mod library_movie;
 pub mod library_show;
+pub mod login_throttle;
 pub mod media_stream;
 pub mod movie;
 pub mod movie_entry;
 pub mod movie_genre;
+pub mod role;
 pub mod season;
+pub mod session;
 pub mod show;
 pub mod show_genre;
 pub mod stream_cache;
 pub mod user;
+pub mod user_identity;
 
 pub use admin_log::Entity as AdminLog;
+pub use api_key::Entity as ApiKey;
+pub use device_authorization::Entity as DeviceAuthorization;
 pub use episode::Entity as Episode;
 pub use files::Entity as Files;
 pub use genre::Entity as Genre;
+pub use invite::Entity as Invite;
 pub use library::Entity as Library;
+pub use library_access::Entity as LibraryAccess;
 pub use library_movie::Entity as LibraryMovie;
 pub use library_show::Entity as LibraryShow;
+pub use login_throttle::Entity as LoginThrottle;
 pub use media_stream::Entity as MediaStream;
 pub use movie::Entity as Movie;
 pub use movie_entry::Entity as MovieEntry;
 pub use movie_genre::Entity as MovieGenre;
+pub use role::Entity as Role;
 pub use season::Entity as Season;
+pub use session::Entity as Session;
 pub use show::Entity as Show;
 pub use show_genre::Entity as ShowGenre;
 pub use stream_cache::Entity as StreamCache;
 pub use user::Entity as User;
+pub use user_identity::Entity as UserIdentity;
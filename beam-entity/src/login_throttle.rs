@@ -0,0 +1,25 @@
+//! Failed logins counted for an IP or account, when sessions are kept in PostgreSQL
+//! instead of Redis
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_throttles")]
+pub struct Model {
+    /// What is throttled, e.g. `ip:203.0.113.7` or `account:alice`
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub key: String,
+
+    pub failures: i32,
+
+    /// When `failures` is forgotten unless another failure comes first
+    pub failures_expire_at: DateTimeWithTimeZone,
+
+    pub blocked_until: Option<DateTimeWithTimeZone>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
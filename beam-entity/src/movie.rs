@@ -26,6 +26,9 @@ pub struct Model {
     pub rating_tmdb: Option<f32>,
     pub rating_imdb: Option<f32>,
 
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
+
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
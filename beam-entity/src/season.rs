@@ -14,6 +14,9 @@ pub struct Model {
     pub poster_url: Option<String>,
     pub first_aired: Option<Date>,
     pub last_aired: Option<Date>,
+
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
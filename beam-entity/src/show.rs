@@ -21,6 +21,9 @@ pub struct Model {
     pub imdb_id: Option<String>,
     pub tvdb_id: Option<i32>,
 
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
+
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -11,7 +11,15 @@ pub struct Model {
     #[sea_orm(unique)]
     pub email: String,
     pub password_hash: String,
-    pub is_admin: bool,
+    pub role: String,
+    pub disabled_at: Option<DateTimeWithTimeZone>,
+    pub password_reset_required: bool,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled_at: Option<DateTimeWithTimeZone>,
+    pub totp_recovery_codes: Vec<String>,
+    pub totp_last_step: Option<i64>,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
 }
@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use confique::Config;
 
+use crate::utils::hash::HashStrategy;
+
 #[derive(Debug, Clone, Config)]
 pub struct IndexConfig {
     #[config(
@@ -13,4 +17,17 @@ pub struct IndexConfig {
 
     #[config(env = "GRPC_PORT", default = 50051)]
     pub port: u16,
+
+    /// Write Kodi-style `.nfo` files for scanned items that don't have one
+    #[config(env = "NFO_EXPORT", default = false)]
+    pub nfo_export: bool,
+
+    /// Directory for cached artwork (shared with beam-stream)
+    #[config(env = "CACHE_DIR", default = "./cache")]
+    pub cache_dir: PathBuf,
+
+    /// How much of each file to hash: `full`, `sampled:<chunks>` (head, tail and evenly
+    /// spaced 1 MiB chunks) or `head:<megabytes>`
+    #[config(env = "HASH_STRATEGY", default = "full")]
+    pub hash_strategy: HashStrategy,
 }
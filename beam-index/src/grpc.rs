@@ -3,8 +3,10 @@ use std::sync::Arc;
 use tonic::{Request, Response, Status};
 
 use crate::proto::index_service_server::IndexService as IndexServiceTrait;
-use crate::proto::{ScanLibraryRequest, ScanLibraryResponse};
-use crate::services::index::{IndexError, IndexService, LocalIndexService};
+use crate::proto::{
+    ScanLibraryRequest, ScanLibraryResponse, VerifyLibraryRequest, VerifyLibraryResponse,
+};
+use crate::services::index::{IndexError, IndexService, LocalIndexService, VerifyOptions};
 
 #[derive(Debug)]
 pub struct IndexServiceGrpc {
@@ -26,10 +28,32 @@ impl IndexServiceTrait for IndexServiceGrpc {
         let library_id = request.into_inner().library_id;
         match self.inner.scan_library(library_id).await {
             Ok(files_added) => Ok(Response::new(ScanLibraryResponse { files_added })),
-            Err(IndexError::LibraryNotFound) => Err(Status::not_found("Library not found")),
-            Err(IndexError::InvalidId) => Err(Status::invalid_argument("Invalid library ID")),
-            Err(IndexError::PathNotFound(s)) => Err(Status::not_found(s)),
-            Err(IndexError::Db(e)) => Err(Status::internal(e.to_string())),
+            Err(e) => Err(to_status(e)),
         }
     }
+
+    async fn verify_library(
+        &self,
+        request: Request<VerifyLibraryRequest>,
+    ) -> Result<Response<VerifyLibraryResponse>, Status> {
+        let request = request.into_inner();
+        let options = VerifyOptions {
+            limit: request.limit,
+            upgrade_hashes: request.upgrade_hashes,
+            check_decode: request.check_decode,
+        };
+        match self.inner.verify_library(request.library_id, options).await {
+            Ok(()) => Ok(Response::new(VerifyLibraryResponse {})),
+            Err(e) => Err(to_status(e)),
+        }
+    }
+}
+
+fn to_status(e: IndexError) -> Status {
+    match e {
+        IndexError::LibraryNotFound => Status::not_found("Library not found"),
+        IndexError::InvalidId => Status::invalid_argument("Invalid library ID"),
+        IndexError::PathNotFound(s) => Status::not_found(s),
+        IndexError::Db(e) => Status::internal(e.to_string()),
+    }
 }
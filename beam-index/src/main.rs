@@ -14,9 +14,11 @@ use beam_index::repositories::{
     SqlMovieRepository, SqlShowRepository,
 };
 use beam_index::services::admin_log::LocalAdminLogService;
+use beam_index::services::artwork::LocalArtworkService;
 use beam_index::services::hash::{HashConfig, LocalHashService};
 use beam_index::services::index::LocalIndexService;
 use beam_index::services::media_info::LocalMediaInfoService;
+use beam_index::services::nfo::LocalNfoService;
 use beam_index::services::notification::LocalNotificationService;
 
 #[tokio::main]
@@ -57,8 +59,13 @@ async fn main() -> Result<()> {
 
     // Build services
     let notification_service = Arc::new(LocalNotificationService::new());
-    let hash_service = Arc::new(LocalHashService::new(HashConfig::default()));
+    let hash_service = Arc::new(LocalHashService::new(HashConfig {
+        strategy: config.hash_strategy,
+        ..Default::default()
+    }));
     let media_info_service = Arc::new(LocalMediaInfoService::default());
+    let nfo_service = Arc::new(LocalNfoService::new(config.nfo_export));
+    let artwork_service = Arc::new(LocalArtworkService::new(config.cache_dir.clone()));
     let admin_log_service = Arc::new(LocalAdminLogService::new(admin_log_repo));
 
     let index_service = Arc::new(LocalIndexService::new(
@@ -69,6 +76,8 @@ async fn main() -> Result<()> {
         stream_repo,
         hash_service,
         media_info_service,
+        nfo_service,
+        artwork_service,
         notification_service,
         admin_log_service,
     ));
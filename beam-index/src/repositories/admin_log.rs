@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use sea_orm::*;
 use uuid::Uuid;
 
-use beam_domain::models::{AdminLog, CreateAdminLog};
+use beam_domain::models::{AdminLog, AdminLogFilter, CreateAdminLog};
 use beam_domain::repositories::AdminLogRepository;
 
 #[derive(Debug)]
@@ -38,11 +38,16 @@ impl AdminLogRepository for SqlAdminLogRepository {
         Ok(AdminLog::from(result))
     }
 
-    async fn list(&self, limit: u64, offset: u64) -> Result<Vec<AdminLog>, DbErr> {
+    async fn list(
+        &self,
+        filter: &AdminLogFilter,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<AdminLog>, DbErr> {
         use beam_entity::admin_log;
-        use sea_orm::{EntityTrait, QueryOrder, QuerySelect};
+        use sea_orm::{QueryOrder, QuerySelect};
 
-        let models = admin_log::Entity::find()
+        let models = Self::filtered(filter)
             .order_by_desc(admin_log::Column::CreatedAt)
             .limit(limit)
             .offset(offset)
@@ -52,10 +57,23 @@ impl AdminLogRepository for SqlAdminLogRepository {
         Ok(models.into_iter().map(AdminLog::from).collect())
     }
 
-    async fn count(&self) -> Result<u64, DbErr> {
+    async fn count(&self, filter: &AdminLogFilter) -> Result<u64, DbErr> {
+        Self::filtered(filter).count(&self.db).await
+    }
+}
+
+impl SqlAdminLogRepository {
+    fn filtered(filter: &AdminLogFilter) -> Select<beam_entity::admin_log::Entity> {
         use beam_entity::admin_log;
-        use sea_orm::EntityTrait;
 
-        admin_log::Entity::find().count(&self.db).await
+        admin_log::Entity::find()
+            .apply_if(filter.level.clone(), |query, level| {
+                query.filter(admin_log::Column::Level.eq(admin_log::AdminLogLevel::from(level)))
+            })
+            .apply_if(filter.category.clone(), |query, category| {
+                query.filter(
+                    admin_log::Column::Category.eq(admin_log::AdminLogCategory::from(category)),
+                )
+            })
     }
 }
@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 use beam_domain::models::{CreateMediaFile, MediaFile, MediaFileContent, UpdateMediaFile};
 use beam_domain::repositories::FileRepository;
+use beam_domain::utils::hash::HashStrategy;
 
 /// SQL-based implementation of the FileRepository trait.
 #[derive(Debug, Clone)]
@@ -39,6 +40,14 @@ impl FileRepository for SqlFileRepository {
         Ok(model.map(MediaFile::from))
     }
 
+    async fn find_all(&self) -> Result<Vec<MediaFile>, DbErr> {
+        use beam_entity::files;
+        use sea_orm::EntityTrait;
+
+        let models = files::Entity::find().all(&self.db).await?;
+        Ok(models.into_iter().map(MediaFile::from).collect())
+    }
+
     async fn find_all_by_library(&self, library_id: Uuid) -> Result<Vec<MediaFile>, DbErr> {
         use beam_entity::files;
         use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
@@ -75,6 +84,99 @@ impl FileRepository for SqlFileRepository {
         Ok(models.into_iter().map(MediaFile::from).collect())
     }
 
+    async fn find_by_hash(
+        &self,
+        hash: u64,
+        hash_strategy: HashStrategy,
+        size_bytes: u64,
+    ) -> Result<Vec<MediaFile>, DbErr> {
+        use beam_entity::files;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let models = files::Entity::find()
+            .filter(files::Column::HashXxh3.eq(hash as i64))
+            .filter(files::Column::HashStrategy.eq(hash_strategy.to_string()))
+            .filter(files::Column::FileSize.eq(size_bytes as i64))
+            .all(&self.db)
+            .await?;
+
+        Ok(models.into_iter().map(MediaFile::from).collect())
+    }
+
+    async fn find_with_shared_hash(&self) -> Result<Vec<MediaFile>, DbErr> {
+        use beam_entity::files;
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let models = files::Entity::find()
+            .filter(files::Column::HashXxh3.ne(0))
+            .filter(Expr::cust(
+                r#"("files"."hash_xxh3", "files"."hash_strategy", "files"."file_size") IN (
+                    SELECT f.hash_xxh3, f.hash_strategy, f.file_size FROM files f
+                    WHERE f.hash_xxh3 <> 0
+                    GROUP BY f.hash_xxh3, f.hash_strategy, f.file_size
+                    HAVING COUNT(*) > 1
+                )"#,
+            ))
+            .all(&self.db)
+            .await?;
+
+        Ok(models.into_iter().map(MediaFile::from).collect())
+    }
+
+    async fn find_with_shared_content(&self) -> Result<Vec<MediaFile>, DbErr> {
+        use beam_entity::files;
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{EntityTrait, QueryFilter};
+
+        // Every episode file gets its own episode row, so episodes match on their numbers
+        let models = files::Entity::find()
+            .filter(Expr::cust(
+                r#""files"."movie_entry_id" IN (
+                    SELECT me.id FROM movie_entries me WHERE me.movie_id IN (
+                        SELECT e.movie_id FROM files f
+                        JOIN movie_entries e ON e.id = f.movie_entry_id
+                        GROUP BY e.movie_id
+                        HAVING COUNT(*) > 1
+                    )
+                ) OR "files"."episode_id" IN (
+                    SELECT e.id FROM episodes e JOIN seasons s ON s.id = e.season_id
+                    WHERE (s.show_id, s.season_number, e.episode_number) IN (
+                        SELECT s2.show_id, s2.season_number, e2.episode_number FROM files f
+                        JOIN episodes e2 ON e2.id = f.episode_id
+                        JOIN seasons s2 ON s2.id = e2.season_id
+                        GROUP BY s2.show_id, s2.season_number, e2.episode_number
+                        HAVING COUNT(*) > 1
+                    )
+                )"#,
+            ))
+            .all(&self.db)
+            .await?;
+
+        Ok(models.into_iter().map(MediaFile::from).collect())
+    }
+
+    async fn find_episode_file_in_dir(&self, dir: &str) -> Result<Option<MediaFile>, DbErr> {
+        use beam_entity::files;
+        use sea_orm::sea_query::LikeExpr;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let dir = dir.trim_end_matches(std::path::MAIN_SEPARATOR);
+        let escaped = dir
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("{escaped}{}%", std::path::MAIN_SEPARATOR);
+
+        let model = files::Entity::find()
+            .filter(files::Column::FilePath.like(LikeExpr::new(pattern).escape('\\')))
+            .filter(files::Column::EpisodeId.is_not_null())
+            .one(&self.db)
+            .await?;
+
+        Ok(model.map(MediaFile::from))
+    }
+
     async fn create(&self, create: CreateMediaFile) -> Result<MediaFile, DbErr> {
         use beam_entity::files;
         use chrono::Utc;
@@ -92,6 +194,8 @@ impl FileRepository for SqlFileRepository {
             library_id: Set(create.library_id),
             file_path: Set(create.path.to_string_lossy().to_string()),
             hash_xxh3: Set(create.hash as i64),
+            hash_partial: Set(create.partial_hash.map(|h| h as i64)),
+            hash_strategy: Set(create.hash_strategy.to_string()),
             file_size: Set(create.size_bytes as i64),
             mime_type: Set(create.mime_type),
             duration_secs: Set(create.duration.map(|d| d.as_secs_f64())),
@@ -103,6 +207,7 @@ impl FileRepository for SqlFileRepository {
             movie_entry_id: Set(movie_entry_id),
             episode_id: Set(episode_id),
             scanned_at: Set(now.into()),
+            verified_at: Set(None),
             updated_at: Set(now.into()),
             file_status: Set(create.status.to_string()),
         };
@@ -120,9 +225,18 @@ impl FileRepository for SqlFileRepository {
             ..Default::default()
         };
 
+        if let Some(path) = update.path {
+            active_model.file_path = Set(path.to_string_lossy().to_string());
+        }
         if let Some(hash) = update.hash {
             active_model.hash_xxh3 = Set(hash as i64);
         }
+        if let Some(hash_strategy) = update.hash_strategy {
+            active_model.hash_strategy = Set(hash_strategy.to_string());
+        }
+        if let Some(partial_hash) = update.partial_hash {
+            active_model.hash_partial = Set(Some(partial_hash as i64));
+        }
         if let Some(size) = update.size_bytes {
             active_model.file_size = Set(size as i64);
         }
@@ -138,6 +252,15 @@ impl FileRepository for SqlFileRepository {
         if let Some(status) = update.status {
             active_model.file_status = Set(status.to_string());
         }
+        if let Some(scanned_at) = update.scanned_at {
+            active_model.scanned_at = Set(scanned_at.into());
+        }
+        if let Some(is_primary) = update.is_primary {
+            active_model.is_primary = Set(is_primary);
+        }
+        if let Some(verified_at) = update.verified_at {
+            active_model.verified_at = Set(Some(verified_at.into()));
+        }
 
         if let Some(content) = update.content {
             match content {
@@ -0,0 +1,53 @@
+use sea_orm::{DatabaseConnection, DbErr};
+use uuid::Uuid;
+
+/// Derive the URL-safe slug stored alongside a genre name ("Science Fiction" -> "science-fiction")
+pub(crate) fn genre_slug(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Resolve genre names to ids, inserting any genre that does not exist yet.
+/// Names that produce an empty slug are skipped and duplicates are collapsed.
+pub(crate) async fn find_or_create_genre_ids(
+    db: &DatabaseConnection,
+    names: &[String],
+) -> Result<Vec<Uuid>, DbErr> {
+    use beam_entity::genre;
+    use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
+
+    let mut ids = Vec::with_capacity(names.len());
+    for name in names {
+        let name = name.trim();
+        let slug = genre_slug(name);
+        if slug.is_empty() {
+            continue;
+        }
+
+        let existing = genre::Entity::find()
+            .filter(genre::Column::Slug.eq(slug.as_str()))
+            .one(db)
+            .await?;
+
+        let id = match existing {
+            Some(model) => model.id,
+            None => {
+                let new_genre = genre::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    name: Set(name.to_string()),
+                    slug: Set(slug),
+                };
+                new_genre.insert(db).await?.id
+            }
+        };
+
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    Ok(ids)
+}
@@ -103,4 +103,117 @@ impl LibraryRepository for SqlLibraryRepository {
         library::Entity::delete_by_id(id).exec(&self.db).await?;
         Ok(())
     }
+
+    async fn find_ids_by_user(&self, user_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+        use beam_entity::library_access;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let grants = library_access::Entity::find()
+            .filter(library_access::Column::UserId.eq(user_id))
+            .all(&self.db)
+            .await?;
+        Ok(grants.into_iter().map(|g| g.library_id).collect())
+    }
+
+    async fn find_user_ids(&self, library_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+        use beam_entity::library_access;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let grants = library_access::Entity::find()
+            .filter(library_access::Column::LibraryId.eq(library_id))
+            .all(&self.db)
+            .await?;
+        Ok(grants.into_iter().map(|g| g.user_id).collect())
+    }
+
+    async fn grant_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr> {
+        use beam_entity::library_access;
+        use sea_orm::{ActiveModelTrait, EntityTrait, Set};
+
+        let exists = library_access::Entity::find_by_id((library_id, user_id))
+            .one(&self.db)
+            .await?
+            .is_some();
+
+        if !exists {
+            let grant = library_access::ActiveModel {
+                library_id: Set(library_id),
+                user_id: Set(user_id),
+                created_at: Set(Utc::now().into()),
+            };
+            grant.insert(&self.db).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn revoke_access(&self, library_id: Uuid, user_id: Uuid) -> Result<(), DbErr> {
+        use beam_entity::library_access;
+        use sea_orm::EntityTrait;
+
+        library_access::Entity::delete_by_id((library_id, user_id))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    async fn find_ids_by_artwork(&self, artwork_url: &str) -> Result<Vec<Uuid>, DbErr> {
+        use beam_entity::{episode, library_movie, library_show, movie, season, show};
+        use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QuerySelect, QueryTrait};
+
+        let movie_ids = movie::Entity::find()
+            .select_only()
+            .column(movie::Column::Id)
+            .filter(
+                Condition::any()
+                    .add(movie::Column::PosterUrl.eq(artwork_url))
+                    .add(movie::Column::BackdropUrl.eq(artwork_url)),
+            )
+            .into_query();
+        let show_ids = show::Entity::find()
+            .select_only()
+            .column(show::Column::Id)
+            .filter(
+                Condition::any()
+                    .add(show::Column::PosterUrl.eq(artwork_url))
+                    .add(show::Column::BackdropUrl.eq(artwork_url)),
+            )
+            .into_query();
+        let episode_season_ids = episode::Entity::find()
+            .select_only()
+            .column(episode::Column::SeasonId)
+            .filter(episode::Column::ThumbnailUrl.eq(artwork_url))
+            .into_query();
+        let season_show_ids = season::Entity::find()
+            .select_only()
+            .column(season::Column::ShowId)
+            .filter(
+                Condition::any()
+                    .add(season::Column::PosterUrl.eq(artwork_url))
+                    .add(season::Column::Id.in_subquery(episode_season_ids)),
+            )
+            .into_query();
+
+        let movie_links = library_movie::Entity::find()
+            .filter(library_movie::Column::MovieId.in_subquery(movie_ids))
+            .all(&self.db)
+            .await?;
+        let show_links = library_show::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(library_show::Column::ShowId.in_subquery(show_ids))
+                    .add(library_show::Column::ShowId.in_subquery(season_show_ids)),
+            )
+            .all(&self.db)
+            .await?;
+
+        let mut library_ids: Vec<Uuid> = movie_links
+            .into_iter()
+            .map(|link| link.library_id)
+            .chain(show_links.into_iter().map(|link| link.library_id))
+            .collect();
+        library_ids.sort_unstable();
+        library_ids.dedup();
+        Ok(library_ids)
+    }
 }
@@ -1,5 +1,6 @@
 pub mod admin_log;
 pub mod file;
+pub(crate) mod genre;
 pub mod library;
 pub mod movie;
 pub mod show;
@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use sea_orm::{DatabaseConnection, DbErr};
 use uuid::Uuid;
 
-use beam_domain::models::{CreateMovie, CreateMovieEntry, Movie, MovieEntry};
+use beam_domain::models::{
+    CreateMovie, CreateMovieEntry, Movie, MovieEntry, UpdateMovie, UpdateMovieEntry,
+};
 use beam_domain::repositories::MovieRepository;
 
 /// SQL-based implementation of the MovieRepository trait.
@@ -66,6 +68,73 @@ impl MovieRepository for SqlMovieRepository {
         Ok(Movie::from(result))
     }
 
+    async fn update(&self, update: UpdateMovie) -> Result<Movie, DbErr> {
+        use beam_entity::movie;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let mut active_model = movie::ActiveModel {
+            id: Set(update.id),
+            ..Default::default()
+        };
+
+        if let Some(title) = update.title {
+            active_model.title = Set(title);
+        }
+        if let Some(title_localized) = update.title_localized {
+            active_model.title_localized = Set(Some(title_localized));
+        }
+        if let Some(description) = update.description {
+            active_model.description = Set(Some(description));
+        }
+        if let Some(year) = update.year {
+            active_model.year = Set(Some(year as i32));
+        }
+        if let Some(release_date) = update.release_date {
+            active_model.release_date = Set(Some(release_date));
+        }
+        if let Some(runtime) = update.runtime {
+            active_model.runtime_mins = Set(Some((runtime.as_secs() / 60) as i32));
+        }
+        if let Some(poster_url) = update.poster_url {
+            active_model.poster_url = Set(Some(poster_url));
+        }
+        if let Some(backdrop_url) = update.backdrop_url {
+            active_model.backdrop_url = Set(Some(backdrop_url));
+        }
+        if let Some(tmdb_id) = update.tmdb_id {
+            active_model.tmdb_id = Set(Some(tmdb_id as i32));
+        }
+        if let Some(imdb_id) = update.imdb_id {
+            active_model.imdb_id = Set(Some(imdb_id));
+        }
+        if let Some(tvdb_id) = update.tvdb_id {
+            active_model.tvdb_id = Set(Some(tvdb_id as i32));
+        }
+        if let Some(rating) = update.rating_tmdb {
+            active_model.rating_tmdb = Set(Some(rating));
+        }
+        if let Some(rating) = update.rating_imdb {
+            active_model.rating_imdb = Set(Some(rating));
+        }
+        if let Some(locked_fields) = update.locked_fields {
+            active_model.locked_fields = Set(locked_fields);
+        }
+
+        active_model.updated_at = Set(chrono::Utc::now().into());
+
+        let result = active_model.update(&self.db).await?;
+        Ok(Movie::from(result))
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), DbErr> {
+        use beam_entity::movie;
+        use sea_orm::EntityTrait;
+
+        // Entries, genres and library associations cascade
+        movie::Entity::delete_by_id(id).exec(&self.db).await?;
+        Ok(())
+    }
+
     async fn create_entry(&self, create: CreateMovieEntry) -> Result<MovieEntry, DbErr> {
         use beam_entity::movie_entry;
         use chrono::Utc;
@@ -85,6 +154,14 @@ impl MovieRepository for SqlMovieRepository {
         Ok(MovieEntry::from(result))
     }
 
+    async fn find_entry_by_id(&self, id: Uuid) -> Result<Option<MovieEntry>, DbErr> {
+        use beam_entity::movie_entry;
+        use sea_orm::EntityTrait;
+
+        let model = movie_entry::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(model.map(MovieEntry::from))
+    }
+
     async fn find_entries_by_movie_id(&self, movie_id: Uuid) -> Result<Vec<MovieEntry>, DbErr> {
         use beam_entity::movie_entry;
         use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
@@ -97,6 +174,62 @@ impl MovieRepository for SqlMovieRepository {
         Ok(models.into_iter().map(MovieEntry::from).collect())
     }
 
+    async fn find_all_entries(&self) -> Result<Vec<MovieEntry>, DbErr> {
+        use beam_entity::movie_entry;
+        use sea_orm::EntityTrait;
+
+        let models = movie_entry::Entity::find().all(&self.db).await?;
+        Ok(models.into_iter().map(MovieEntry::from).collect())
+    }
+
+    async fn update_entry(&self, update: UpdateMovieEntry) -> Result<MovieEntry, DbErr> {
+        use beam_entity::movie_entry;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let mut active_model = movie_entry::ActiveModel {
+            id: Set(update.id),
+            ..Default::default()
+        };
+
+        if let Some(movie_id) = update.movie_id {
+            active_model.movie_id = Set(movie_id);
+        }
+        if let Some(edition) = update.edition {
+            active_model.edition = Set(edition);
+        }
+        if let Some(is_primary) = update.is_primary {
+            active_model.is_primary = Set(is_primary);
+        }
+
+        let result = active_model.update(&self.db).await?;
+        Ok(MovieEntry::from(result))
+    }
+
+    async fn set_primary_entry(&self, entry_id: Uuid) -> Result<(), DbErr> {
+        use beam_entity::movie_entry;
+        use sea_orm::sea_query::Expr;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let entry = movie_entry::Entity::find_by_id(entry_id)
+            .one(&self.db)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!(
+                "Movie entry {entry_id} not found"
+            )))?;
+
+        // A single statement flips every entry of the movie, so exactly one stays primary
+        movie_entry::Entity::update_many()
+            .col_expr(
+                movie_entry::Column::IsPrimary,
+                Expr::col(movie_entry::Column::Id).eq(entry_id),
+            )
+            .filter(movie_entry::Column::MovieId.eq(entry.movie_id))
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
     async fn ensure_library_association(
         &self,
         library_id: Uuid,
@@ -123,4 +256,60 @@ impl MovieRepository for SqlMovieRepository {
 
         Ok(())
     }
+
+    async fn find_library_ids(&self, movie_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+        use beam_entity::library_movie;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let associations = library_movie::Entity::find()
+            .filter(library_movie::Column::MovieId.eq(movie_id))
+            .all(&self.db)
+            .await?;
+        Ok(associations.into_iter().map(|a| a.library_id).collect())
+    }
+
+    async fn find_genres(&self, movie_id: Uuid) -> Result<Vec<String>, DbErr> {
+        use beam_entity::{genre, movie_genre};
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let rows = movie_genre::Entity::find()
+            .filter(movie_genre::Column::MovieId.eq(movie_id))
+            .find_also_related(genre::Entity)
+            .all(&self.db)
+            .await?;
+
+        let mut names: Vec<String> = rows
+            .into_iter()
+            .filter_map(|(_, genre)| genre.map(|g| g.name))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn set_genres(&self, movie_id: Uuid, genres: Vec<String>) -> Result<(), DbErr> {
+        use super::genre::find_or_create_genre_ids;
+        use beam_entity::movie_genre;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+
+        let genre_ids = find_or_create_genre_ids(&self.db, &genres).await?;
+
+        let txn = self.db.begin().await?;
+        movie_genre::Entity::delete_many()
+            .filter(movie_genre::Column::MovieId.eq(movie_id))
+            .exec(&txn)
+            .await?;
+
+        if !genre_ids.is_empty() {
+            let links = genre_ids
+                .into_iter()
+                .map(|genre_id| movie_genre::ActiveModel {
+                    movie_id: Set(movie_id),
+                    genre_id: Set(genre_id),
+                });
+            movie_genre::Entity::insert_many(links).exec(&txn).await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
 }
@@ -2,7 +2,9 @@ use async_trait::async_trait;
 use sea_orm::{DatabaseConnection, DbErr};
 use uuid::Uuid;
 
-use beam_domain::models::{CreateEpisode, Episode, Season, Show};
+use beam_domain::models::{
+    CreateEpisode, Episode, Season, Show, UpdateEpisode, UpdateSeason, UpdateShow,
+};
 use beam_domain::repositories::ShowRepository;
 
 /// SQL-based implementation of the ShowRepository trait.
@@ -65,6 +67,52 @@ impl ShowRepository for SqlShowRepository {
         Ok(Show::from(result))
     }
 
+    async fn update(&self, update: UpdateShow) -> Result<Show, DbErr> {
+        use beam_entity::show;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let mut active_model = show::ActiveModel {
+            id: Set(update.id),
+            ..Default::default()
+        };
+
+        if let Some(title) = update.title {
+            active_model.title = Set(title);
+        }
+        if let Some(title_localized) = update.title_localized {
+            active_model.title_localized = Set(Some(title_localized));
+        }
+        if let Some(description) = update.description {
+            active_model.description = Set(Some(description));
+        }
+        if let Some(year) = update.year {
+            active_model.year = Set(Some(year as i32));
+        }
+        if let Some(poster_url) = update.poster_url {
+            active_model.poster_url = Set(Some(poster_url));
+        }
+        if let Some(backdrop_url) = update.backdrop_url {
+            active_model.backdrop_url = Set(Some(backdrop_url));
+        }
+        if let Some(tmdb_id) = update.tmdb_id {
+            active_model.tmdb_id = Set(Some(tmdb_id as i32));
+        }
+        if let Some(imdb_id) = update.imdb_id {
+            active_model.imdb_id = Set(Some(imdb_id));
+        }
+        if let Some(tvdb_id) = update.tvdb_id {
+            active_model.tvdb_id = Set(Some(tvdb_id as i32));
+        }
+        if let Some(locked_fields) = update.locked_fields {
+            active_model.locked_fields = Set(locked_fields);
+        }
+
+        active_model.updated_at = Set(chrono::Utc::now().into());
+
+        let result = active_model.update(&self.db).await?;
+        Ok(Show::from(result))
+    }
+
     async fn ensure_library_association(
         &self,
         library_id: Uuid,
@@ -92,6 +140,17 @@ impl ShowRepository for SqlShowRepository {
         Ok(())
     }
 
+    async fn find_library_ids(&self, show_id: Uuid) -> Result<Vec<Uuid>, DbErr> {
+        use beam_entity::library_show;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let associations = library_show::Entity::find()
+            .filter(library_show::Column::ShowId.eq(show_id))
+            .all(&self.db)
+            .await?;
+        Ok(associations.into_iter().map(|a| a.library_id).collect())
+    }
+
     async fn find_or_create_season(
         &self,
         show_id: Uuid,
@@ -123,6 +182,14 @@ impl ShowRepository for SqlShowRepository {
         Ok(Season::from(result))
     }
 
+    async fn find_season_by_id(&self, id: Uuid) -> Result<Option<Season>, DbErr> {
+        use beam_entity::season;
+        use sea_orm::EntityTrait;
+
+        let model = season::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(model.map(Season::from))
+    }
+
     async fn find_seasons_by_show_id(&self, show_id: Uuid) -> Result<Vec<Season>, DbErr> {
         use beam_entity::season;
         use sea_orm::{ColumnTrait, EntityTrait, Order, QueryFilter, QueryOrder};
@@ -136,6 +203,40 @@ impl ShowRepository for SqlShowRepository {
         Ok(models.into_iter().map(Season::from).collect())
     }
 
+    async fn update_season(&self, update: UpdateSeason) -> Result<Season, DbErr> {
+        use beam_entity::season;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let mut active_model = season::ActiveModel {
+            id: Set(update.id),
+            ..Default::default()
+        };
+
+        if let Some(poster_url) = update.poster_url {
+            active_model.poster_url = Set(Some(poster_url));
+        }
+        if let Some(first_aired) = update.first_aired {
+            active_model.first_aired = Set(Some(first_aired));
+        }
+        if let Some(last_aired) = update.last_aired {
+            active_model.last_aired = Set(Some(last_aired));
+        }
+        if let Some(locked_fields) = update.locked_fields {
+            active_model.locked_fields = Set(locked_fields);
+        }
+
+        let result = active_model.update(&self.db).await?;
+        Ok(Season::from(result))
+    }
+
+    async fn find_episode_by_id(&self, id: Uuid) -> Result<Option<Episode>, DbErr> {
+        use beam_entity::episode;
+        use sea_orm::EntityTrait;
+
+        let model = episode::Entity::find_by_id(id).one(&self.db).await?;
+        Ok(model.map(Episode::from))
+    }
+
     async fn find_episodes_by_season_id(&self, season_id: Uuid) -> Result<Vec<Episode>, DbErr> {
         use beam_entity::episode;
         use sea_orm::{ColumnTrait, EntityTrait, Order, QueryFilter, QueryOrder};
@@ -168,4 +269,81 @@ impl ShowRepository for SqlShowRepository {
         let result = new_episode.insert(&self.db).await?;
         Ok(Episode::from(result))
     }
+
+    async fn update_episode(&self, update: UpdateEpisode) -> Result<Episode, DbErr> {
+        use beam_entity::episode;
+        use sea_orm::{ActiveModelTrait, Set};
+
+        let mut active_model = episode::ActiveModel {
+            id: Set(update.id),
+            ..Default::default()
+        };
+
+        if let Some(title) = update.title {
+            active_model.title = Set(title);
+        }
+        if let Some(description) = update.description {
+            active_model.description = Set(Some(description));
+        }
+        if let Some(air_date) = update.air_date {
+            active_model.air_date = Set(Some(air_date));
+        }
+        if let Some(runtime) = update.runtime {
+            active_model.runtime_mins = Set(Some((runtime.as_secs() / 60) as i32));
+        }
+        if let Some(thumbnail_url) = update.thumbnail_url {
+            active_model.thumbnail_url = Set(Some(thumbnail_url));
+        }
+        if let Some(locked_fields) = update.locked_fields {
+            active_model.locked_fields = Set(locked_fields);
+        }
+
+        let result = active_model.update(&self.db).await?;
+        Ok(Episode::from(result))
+    }
+
+    async fn find_genres(&self, show_id: Uuid) -> Result<Vec<String>, DbErr> {
+        use beam_entity::{genre, show_genre};
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+        let rows = show_genre::Entity::find()
+            .filter(show_genre::Column::ShowId.eq(show_id))
+            .find_also_related(genre::Entity)
+            .all(&self.db)
+            .await?;
+
+        let mut names: Vec<String> = rows
+            .into_iter()
+            .filter_map(|(_, genre)| genre.map(|g| g.name))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    async fn set_genres(&self, show_id: Uuid, genres: Vec<String>) -> Result<(), DbErr> {
+        use super::genre::find_or_create_genre_ids;
+        use beam_entity::show_genre;
+        use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, Set, TransactionTrait};
+
+        let genre_ids = find_or_create_genre_ids(&self.db, &genres).await?;
+
+        let txn = self.db.begin().await?;
+        show_genre::Entity::delete_many()
+            .filter(show_genre::Column::ShowId.eq(show_id))
+            .exec(&txn)
+            .await?;
+
+        if !genre_ids.is_empty() {
+            let links = genre_ids
+                .into_iter()
+                .map(|genre_id| show_genre::ActiveModel {
+                    show_id: Set(show_id),
+                    genre_id: Set(genre_id),
+                });
+            show_genre::Entity::insert_many(links).exec(&txn).await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
 }
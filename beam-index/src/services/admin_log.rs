@@ -4,7 +4,9 @@ use async_trait::async_trait;
 use serde_json::Value;
 use thiserror::Error;
 
-use beam_domain::models::{AdminLog, AdminLogCategory, AdminLogLevel, CreateAdminLog};
+use beam_domain::models::{
+    AdminLog, AdminLogCategory, AdminLogFilter, AdminLogLevel, CreateAdminLog,
+};
 use beam_domain::repositories::AdminLogRepository;
 
 #[derive(Debug, Error)]
@@ -31,9 +33,14 @@ pub trait AdminLogService: Send + Sync + std::fmt::Debug {
         details: Option<Value>,
     ) -> Result<()>;
 
-    async fn get_logs(&self, limit: u32, offset: u32) -> Result<Vec<AdminLog>>;
+    async fn get_logs(
+        &self,
+        filter: &AdminLogFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AdminLog>>;
 
-    async fn count(&self) -> Result<u64>;
+    async fn count(&self, filter: &AdminLogFilter) -> Result<u64>;
 }
 
 #[derive(Debug)]
@@ -67,12 +74,17 @@ impl AdminLogService for LocalAdminLogService {
         Ok(())
     }
 
-    async fn get_logs(&self, limit: u32, offset: u32) -> Result<Vec<AdminLog>> {
-        Ok(self.repo.list(limit as u64, offset as u64).await?)
+    async fn get_logs(
+        &self,
+        filter: &AdminLogFilter,
+        limit: u32,
+        offset: u32,
+    ) -> Result<Vec<AdminLog>> {
+        Ok(self.repo.list(filter, limit as u64, offset as u64).await?)
     }
 
-    async fn count(&self) -> Result<u64> {
-        Ok(self.repo.count().await?)
+    async fn count(&self, filter: &AdminLogFilter) -> Result<u64> {
+        Ok(self.repo.count(filter).await?)
     }
 }
 
@@ -92,11 +104,16 @@ impl AdminLogService for NoOpAdminLogService {
         Ok(())
     }
 
-    async fn get_logs(&self, _limit: u32, _offset: u32) -> Result<Vec<AdminLog>> {
+    async fn get_logs(
+        &self,
+        _filter: &AdminLogFilter,
+        _limit: u32,
+        _offset: u32,
+    ) -> Result<Vec<AdminLog>> {
         Ok(vec![])
     }
 
-    async fn count(&self) -> Result<u64> {
+    async fn count(&self, _filter: &AdminLogFilter) -> Result<u64> {
         Ok(0)
     }
 }
@@ -131,13 +148,16 @@ mod tests {
             .await
             .unwrap();
 
-        let logs = service.get_logs(10, 0).await.unwrap();
+        let logs = service
+            .get_logs(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
         assert_eq!(logs.len(), 2);
         // Most recent first
         assert_eq!(logs[0].message, "Disk space low");
         assert_eq!(logs[1].message, "Scan started");
 
-        let count = service.count().await.unwrap();
+        let count = service.count(&AdminLogFilter::default()).await.unwrap();
         assert_eq!(count, 2);
     }
 
@@ -153,7 +173,10 @@ mod tests {
             )
             .await
             .unwrap();
-        let logs = service.get_logs(10, 0).await.unwrap();
+        let logs = service
+            .get_logs(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
         assert!(logs.is_empty());
     }
 }
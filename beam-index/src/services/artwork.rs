@@ -0,0 +1,469 @@
+//! Local artwork discovery and the content-addressed artwork cache.
+//!
+//! Images are looked up next to the media (`poster.jpg`, `folder.jpg`, `fanart.jpg`,
+//! `<episode>-thumb.jpg`), then taken from embedded cover art, and finally grabbed
+//! from a representative video frame. Every image is stored once under
+//! `<cache_dir>/artwork/<xxh3>` and served by beam-stream's image endpoint.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+use tracing::debug;
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Sub-directory of the cache directory holding original artwork
+pub const ARTWORK_DIR: &str = "artwork";
+
+/// Route (relative to the stream server) that serves cached artwork
+const ARTWORK_ROUTE: &str = "/v1/images";
+
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp"];
+const POSTER_NAMES: &[&str] = &["poster", "folder", "cover"];
+const BACKDROP_NAMES: &[&str] = &["fanart", "backdrop", "background"];
+
+/// Position of the representative frame, as a fraction of the runtime
+const FRAME_GRAB_POSITION: f64 = 0.1;
+/// Width of grabbed frames; height follows the source aspect ratio
+const FRAME_GRAB_WIDTH: u32 = 1280;
+
+#[derive(Debug, Error)]
+pub enum ArtworkError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Unsupported image {0}")]
+    UnsupportedImage(String),
+    #[error("Failed to extract artwork: {0}")]
+    Extraction(String),
+    #[error("Failed to encode image: {0}")]
+    Encode(#[from] image::ImageError),
+}
+
+/// Cached artwork URLs for a movie or show
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Artwork {
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+}
+
+/// Id of a cached artwork image: the XXH3 hash of its bytes as 16 hex digits
+pub fn artwork_id(bytes: &[u8]) -> String {
+    format!("{:016x}", xxh3_64(bytes))
+}
+
+/// Whether `id` has the shape of an artwork id (guards against path traversal)
+pub fn is_valid_artwork_id(id: &str) -> bool {
+    id.len() == 16
+        && id
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_uppercase())
+}
+
+/// Location of a cached artwork image
+pub fn artwork_path(cache_dir: &Path, id: &str) -> PathBuf {
+    cache_dir.join(ARTWORK_DIR).join(id)
+}
+
+/// URL under which beam-stream serves a cached artwork image
+pub fn artwork_url(id: &str) -> String {
+    format!("{ARTWORK_ROUTE}/{id}")
+}
+
+/// Service for discovering artwork and storing it in the artwork cache.
+#[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
+#[async_trait::async_trait]
+pub trait ArtworkService: Send + Sync + std::fmt::Debug {
+    /// Poster and backdrop for a movie file
+    async fn movie_artwork(&self, video_path: &Path) -> Result<Artwork, ArtworkError>;
+
+    /// Poster and backdrop stored in a show directory
+    async fn show_artwork(&self, show_dir: &Path) -> Result<Artwork, ArtworkError>;
+
+    /// Thumbnail URL for an episode file
+    async fn episode_thumbnail(&self, video_path: &Path) -> Result<Option<String>, ArtworkError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalArtworkService {
+    cache_dir: PathBuf,
+}
+
+impl LocalArtworkService {
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+
+    /// Store image bytes in the cache, returning the artwork URL
+    async fn store(&self, bytes: Vec<u8>) -> Result<String, ArtworkError> {
+        image::guess_format(&bytes)
+            .map_err(|_| ArtworkError::UnsupportedImage("unrecognised image data".into()))?;
+
+        let id = artwork_id(&bytes);
+        let path = artwork_path(&self.cache_dir, &id);
+        if !tokio::fs::try_exists(&path).await? {
+            // Write to a temp file of our own, then rename, so readers never observe a
+            // partial image and concurrent stores of the same artwork don't collide
+            tokio::task::spawn_blocking(move || -> std::io::Result<()> {
+                let dir = path.parent().unwrap_or(Path::new("."));
+                std::fs::create_dir_all(dir)?;
+                let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+                tmp.write_all(&bytes)?;
+                tmp.persist(&path).map_err(|e| e.error)?;
+                Ok(())
+            })
+            .await
+            .map_err(std::io::Error::other)??;
+        }
+        Ok(artwork_url(&id))
+    }
+
+    async fn store_file(&self, path: &Path) -> Result<String, ArtworkError> {
+        debug!("Importing artwork {}", path.display());
+        let bytes = tokio::fs::read(path).await?;
+        self.store(bytes).await.map_err(|e| match e {
+            ArtworkError::UnsupportedImage(_) => {
+                ArtworkError::UnsupportedImage(path.display().to_string())
+            }
+            other => other,
+        })
+    }
+
+    async fn store_first(&self, candidates: Vec<PathBuf>) -> Result<Option<String>, ArtworkError> {
+        match first_existing(candidates) {
+            Some(path) => self.store_file(&path).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Embedded cover art, falling back to a frame grab
+    async fn extract_from_video(&self, video_path: &Path) -> Result<Option<String>, ArtworkError> {
+        let path = video_path.to_path_buf();
+        let bytes =
+            tokio::task::spawn_blocking(move || -> Result<Option<Vec<u8>>, ArtworkError> {
+                if let Some(cover) = extract_embedded_cover(&path)? {
+                    return Ok(Some(cover));
+                }
+                grab_frame(&path)
+            })
+            .await
+            .map_err(|e| ArtworkError::Extraction(format!("Blocking task join error: {e}")))??;
+
+        match bytes {
+            Some(bytes) => self.store(bytes).await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl ArtworkService for LocalArtworkService {
+    async fn movie_artwork(&self, video_path: &Path) -> Result<Artwork, ArtworkError> {
+        let dir = video_path.parent().unwrap_or(Path::new(""));
+        let stem = file_stem(video_path);
+
+        let mut artwork = Artwork {
+            poster_url: self
+                .store_first(artwork_candidates(dir, &stem, POSTER_NAMES))
+                .await?,
+            backdrop_url: self
+                .store_first(artwork_candidates(dir, &stem, BACKDROP_NAMES))
+                .await?,
+        };
+
+        if artwork.poster_url.is_none() || artwork.backdrop_url.is_none() {
+            let extracted = self.extract_from_video(video_path).await?;
+            artwork.poster_url = artwork.poster_url.or(extracted.clone());
+            artwork.backdrop_url = artwork.backdrop_url.or(extracted);
+        }
+
+        Ok(artwork)
+    }
+
+    async fn show_artwork(&self, show_dir: &Path) -> Result<Artwork, ArtworkError> {
+        Ok(Artwork {
+            poster_url: self
+                .store_first(artwork_candidates(show_dir, "", POSTER_NAMES))
+                .await?,
+            backdrop_url: self
+                .store_first(artwork_candidates(show_dir, "", BACKDROP_NAMES))
+                .await?,
+        })
+    }
+
+    async fn episode_thumbnail(&self, video_path: &Path) -> Result<Option<String>, ArtworkError> {
+        let dir = video_path.parent().unwrap_or(Path::new(""));
+        let stem = file_stem(video_path);
+        let candidates = IMAGE_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{stem}-thumb.{ext}")))
+            .collect();
+
+        match self.store_first(candidates).await? {
+            Some(url) => Ok(Some(url)),
+            None => self.extract_from_video(video_path).await,
+        }
+    }
+}
+
+/// Artwork service that never finds anything, for callers that don't manage artwork.
+#[derive(Debug, Clone, Default)]
+pub struct NoOpArtworkService;
+
+#[async_trait::async_trait]
+impl ArtworkService for NoOpArtworkService {
+    async fn movie_artwork(&self, _video_path: &Path) -> Result<Artwork, ArtworkError> {
+        Ok(Artwork::default())
+    }
+
+    async fn show_artwork(&self, _show_dir: &Path) -> Result<Artwork, ArtworkError> {
+        Ok(Artwork::default())
+    }
+
+    async fn episode_thumbnail(&self, _video_path: &Path) -> Result<Option<String>, ArtworkError> {
+        Ok(None)
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Candidate image paths in order of preference: `<stem>-<name>.<ext>` (when a stem
+/// is given) before the directory-wide `<name>.<ext>`.
+fn artwork_candidates(dir: &Path, stem: &str, names: &[&str]) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if !stem.is_empty() {
+        for name in names {
+            for ext in IMAGE_EXTENSIONS {
+                candidates.push(dir.join(format!("{stem}-{name}.{ext}")));
+            }
+        }
+    }
+    for name in names {
+        for ext in IMAGE_EXTENSIONS {
+            candidates.push(dir.join(format!("{name}.{ext}")));
+        }
+    }
+    candidates
+}
+
+fn first_existing(candidates: Vec<PathBuf>) -> Option<PathBuf> {
+    candidates.into_iter().find(|p| p.is_file())
+}
+
+/// Cover art embedded in the container (MP4 `covr` atoms, Matroska image attachments)
+fn extract_embedded_cover(path: &Path) -> Result<Option<Vec<u8>>, ArtworkError> {
+    use ffmpeg_next::format::stream::Disposition;
+
+    let mut ictx =
+        ffmpeg_next::format::input(path).map_err(|e| ArtworkError::Extraction(e.to_string()))?;
+
+    let cover_index = ictx
+        .streams()
+        .find(|s| s.disposition().contains(Disposition::ATTACHED_PIC))
+        .map(|s| s.index());
+    let Some(cover_index) = cover_index else {
+        return Ok(None);
+    };
+
+    // Attached pictures are queued as the first packet of their stream
+    for (stream, packet) in ictx.packets() {
+        if stream.index() == cover_index {
+            return Ok(packet.data().map(<[u8]>::to_vec));
+        }
+    }
+    Ok(None)
+}
+
+/// Decode a representative frame and encode it as JPEG
+fn grab_frame(path: &Path) -> Result<Option<Vec<u8>>, ArtworkError> {
+    use ffmpeg_next::format::Pixel;
+    use ffmpeg_next::software::scaling::{Context as Scaler, Flags};
+    use ffmpeg_next::util::frame::video::Video;
+
+    let extraction = |e: ffmpeg_next::Error| ArtworkError::Extraction(e.to_string());
+
+    let mut ictx = ffmpeg_next::format::input(path).map_err(extraction)?;
+    let Some(stream) = ictx.streams().best(ffmpeg_next::media::Type::Video) else {
+        return Ok(None);
+    };
+    let stream_index = stream.index();
+    let mut decoder = ffmpeg_next::codec::context::Context::from_parameters(stream.parameters())
+        .and_then(|c| c.decoder().video())
+        .map_err(extraction)?;
+
+    if decoder.width() == 0 || decoder.height() == 0 {
+        return Ok(None);
+    }
+    let width = decoder.width().min(FRAME_GRAB_WIDTH);
+    let height = ((decoder.height() as u64 * width as u64) / decoder.width() as u64).max(1) as u32;
+
+    let target = (ictx.duration() as f64 * FRAME_GRAB_POSITION) as i64;
+    if target > 0 {
+        // Seeking is best-effort; decoding from the start still yields a frame
+        let _ = ictx.seek(target, ..target);
+    }
+
+    let mut scaler = Scaler::get(
+        decoder.format(),
+        decoder.width(),
+        decoder.height(),
+        Pixel::RGB24,
+        width,
+        height,
+        Flags::BILINEAR,
+    )
+    .map_err(extraction)?;
+
+    let mut decoded = Video::empty();
+    for (stream, packet) in ictx.packets() {
+        if stream.index() != stream_index || decoder.send_packet(&packet).is_err() {
+            continue;
+        }
+        if decoder.receive_frame(&mut decoded).is_ok() {
+            let mut rgb = Video::empty();
+            scaler.run(&decoded, &mut rgb).map_err(extraction)?;
+            return encode_rgb_frame(&rgb, width, height).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+fn encode_rgb_frame(
+    frame: &ffmpeg_next::util::frame::video::Video,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, ArtworkError> {
+    let row_bytes = width as usize * 3;
+    let stride = frame.stride(0);
+    let data = frame.data(0);
+
+    let mut pixels = Vec::with_capacity(row_bytes * height as usize);
+    for row in 0..height as usize {
+        pixels.extend_from_slice(&data[row * stride..row * stride + row_bytes]);
+    }
+
+    let image = image::RgbImage::from_raw(width, height, pixels)
+        .ok_or_else(|| ArtworkError::Extraction("frame buffer too small".into()))?;
+    let mut out = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut out, 85).encode_image(&image)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn png_bytes(width: u32, height: u32, color: [u8; 3]) -> Vec<u8> {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb(color));
+        let mut out = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut out, image::ImageFormat::Png).unwrap();
+        out.into_inner()
+    }
+
+    #[test]
+    fn test_artwork_id_is_content_addressed() {
+        let a = png_bytes(2, 2, [255, 0, 0]);
+        let b = png_bytes(2, 2, [0, 255, 0]);
+        assert_eq!(artwork_id(&a), artwork_id(&a.clone()));
+        assert_ne!(artwork_id(&a), artwork_id(&b));
+        assert!(is_valid_artwork_id(&artwork_id(&a)));
+    }
+
+    #[test]
+    fn test_is_valid_artwork_id_rejects_paths() {
+        assert!(is_valid_artwork_id("0123456789abcdef"));
+        assert!(!is_valid_artwork_id("0123456789ABCDEF"));
+        assert!(!is_valid_artwork_id("../../etc/passwd"));
+        assert!(!is_valid_artwork_id("0123456789abcde"));
+    }
+
+    #[test]
+    fn test_artwork_candidates_prefer_stem_specific_files() {
+        let candidates = artwork_candidates(Path::new("/m"), "Heat", POSTER_NAMES);
+        assert_eq!(candidates[0], PathBuf::from("/m/Heat-poster.jpg"));
+        let first_generic = candidates
+            .iter()
+            .position(|p| p == Path::new("/m/poster.jpg"))
+            .unwrap();
+        assert!(
+            candidates
+                .iter()
+                .take(first_generic)
+                .all(|p| p.to_string_lossy().contains("Heat-"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_show_artwork_discovers_poster_and_fanart() {
+        let media = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        let poster = png_bytes(2, 3, [10, 20, 30]);
+        let fanart = png_bytes(3, 2, [40, 50, 60]);
+        std::fs::write(media.path().join("folder.png"), &poster).unwrap();
+        std::fs::write(media.path().join("fanart.jpg"), &fanart).unwrap();
+
+        let service = LocalArtworkService::new(cache.path().to_path_buf());
+        let artwork = service.show_artwork(media.path()).await.unwrap();
+
+        let poster_id = artwork_id(&poster);
+        let fanart_id = artwork_id(&fanart);
+        assert_eq!(artwork.poster_url, Some(artwork_url(&poster_id)));
+        assert_eq!(artwork.backdrop_url, Some(artwork_url(&fanart_id)));
+        assert_eq!(
+            std::fs::read(artwork_path(cache.path(), &poster_id)).unwrap(),
+            poster
+        );
+        assert!(artwork_path(cache.path(), &fanart_id).exists());
+    }
+
+    #[tokio::test]
+    async fn test_identical_images_are_stored_once() {
+        let media = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        let bytes = png_bytes(4, 4, [1, 2, 3]);
+        std::fs::write(media.path().join("poster.jpg"), &bytes).unwrap();
+        std::fs::write(media.path().join("fanart.jpg"), &bytes).unwrap();
+
+        let service = LocalArtworkService::new(cache.path().to_path_buf());
+        let artwork = service.show_artwork(media.path()).await.unwrap();
+
+        assert_eq!(artwork.poster_url, artwork.backdrop_url);
+        let stored = std::fs::read_dir(cache.path().join(ARTWORK_DIR))
+            .unwrap()
+            .count();
+        assert_eq!(stored, 1);
+    }
+
+    #[tokio::test]
+    async fn test_episode_thumbnail_from_thumb_file() {
+        let media = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        let thumb = png_bytes(16, 9, [7, 8, 9]);
+        std::fs::write(media.path().join("Show.S01E01-thumb.jpg"), &thumb).unwrap();
+
+        let service = LocalArtworkService::new(cache.path().to_path_buf());
+        let url = service
+            .episode_thumbnail(&media.path().join("Show.S01E01.mkv"))
+            .await
+            .unwrap();
+
+        assert_eq!(url, Some(artwork_url(&artwork_id(&thumb))));
+    }
+
+    #[tokio::test]
+    async fn test_non_image_artwork_is_rejected() {
+        let media = TempDir::new().unwrap();
+        let cache = TempDir::new().unwrap();
+        std::fs::write(media.path().join("poster.jpg"), b"not an image").unwrap();
+
+        let service = LocalArtworkService::new(cache.path().to_path_buf());
+        let result = service.show_artwork(media.path()).await;
+
+        assert!(matches!(result, Err(ArtworkError::UnsupportedImage(_))));
+        assert!(!cache.path().join(ARTWORK_DIR).exists());
+    }
+}
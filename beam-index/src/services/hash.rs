@@ -5,17 +5,20 @@ use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::utils::hash::compute_hash;
+use crate::utils::hash::{HashStrategy, compute_hash_with, compute_partial_hash};
 
 #[derive(Debug, Clone)]
 pub struct HashConfig {
     pub num_threads: usize,
+    /// Strategy used for newly computed hashes
+    pub strategy: HashStrategy,
 }
 
 impl Default for HashConfig {
     fn default() -> Self {
         Self {
             num_threads: num_cpus::get_physical(),
+            strategy: HashStrategy::Full,
         }
     }
 }
@@ -24,14 +27,21 @@ impl Default for HashConfig {
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
 #[async_trait::async_trait]
 pub trait HashService: Send + Sync + std::fmt::Debug {
+    /// Strategy used by [`HashService::hash_sync`] and [`HashService::hash_async`]
+    fn strategy(&self) -> HashStrategy;
     fn hash_sync(&self, path: &Path) -> io::Result<u64>;
     async fn hash_async(&self, path: PathBuf) -> io::Result<u64>;
+    /// Hash with a specific strategy, e.g. to check a hash stored under another strategy
+    async fn hash_with_async(&self, path: PathBuf, strategy: HashStrategy) -> io::Result<u64>;
+    /// Cheap hash of the file's size, head and tail. See [`compute_partial_hash`].
+    async fn partial_hash_async(&self, path: PathBuf) -> io::Result<u64>;
 }
 
 /// A service that manages file hashing operations using a dedicated Rayon thread pool.
 #[derive(Debug, Clone)]
 pub struct LocalHashService {
     thread_pool: Arc<ThreadPool>,
+    strategy: HashStrategy,
 }
 
 impl Default for LocalHashService {
@@ -54,22 +64,32 @@ impl LocalHashService {
             .build()
             .expect("Failed to build hash service thread pool");
 
-        tracing::info!("Initialized hash thread pool with {} threads", num_threads);
+        tracing::info!(
+            "Initialized hash thread pool with {} threads ({} hashing)",
+            num_threads,
+            config.strategy
+        );
 
         Self {
             thread_pool: Arc::new(thread_pool),
+            strategy: config.strategy,
         }
     }
 }
 
 #[async_trait::async_trait]
 impl HashService for LocalHashService {
+    fn strategy(&self) -> HashStrategy {
+        self.strategy
+    }
+
     fn hash_sync(&self, path: &Path) -> io::Result<u64> {
         let path = path.to_path_buf();
+        let strategy = self.strategy;
         let (tx, rx) = std::sync::mpsc::channel();
 
         self.thread_pool.spawn(move || {
-            let result = compute_hash(&path);
+            let result = compute_hash_with(&path, strategy);
             let _ = tx.send(result);
         });
 
@@ -77,13 +97,17 @@ impl HashService for LocalHashService {
     }
 
     async fn hash_async(&self, path: PathBuf) -> io::Result<u64> {
+        self.hash_with_async(path, self.strategy).await
+    }
+
+    async fn hash_with_async(&self, path: PathBuf, strategy: HashStrategy) -> io::Result<u64> {
         let thread_pool = self.thread_pool.clone();
 
         tokio::task::spawn_blocking(move || {
             let (tx, rx) = std::sync::mpsc::channel();
 
             thread_pool.spawn(move || {
-                let result = compute_hash(&path);
+                let result = compute_hash_with(&path, strategy);
                 let _ = tx.send(result);
             });
 
@@ -92,6 +116,13 @@ impl HashService for LocalHashService {
         .await
         .map_err(io::Error::other)?
     }
+
+    async fn partial_hash_async(&self, path: PathBuf) -> io::Result<u64> {
+        // Only a couple of small reads, so no need to queue behind full hashes
+        tokio::task::spawn_blocking(move || compute_partial_hash(&path))
+            .await
+            .map_err(io::Error::other)?
+    }
 }
 
 #[cfg(test)]
@@ -140,4 +171,101 @@ mod tests {
 
         assert_eq!(hash_sync, hash_async);
     }
+
+    #[tokio::test]
+    async fn test_partial_hash_covers_head_tail_and_size() {
+        let chunk = crate::utils::hash::PARTIAL_HASH_CHUNK_SIZE as usize;
+        let service = LocalHashService::default();
+        let partial_hash = |data: Vec<u8>| {
+            let service = service.clone();
+            async move {
+                let mut temp_file = NamedTempFile::new().unwrap();
+                temp_file.write_all(&data).unwrap();
+                temp_file.flush().unwrap();
+                service
+                    .partial_hash_async(temp_file.path().to_path_buf())
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let data = vec![7u8; chunk * 4];
+        let base = partial_hash(data.clone()).await;
+
+        let mut middle = data.clone();
+        middle[chunk * 2] = 0;
+        assert_eq!(partial_hash(middle).await, base, "middle is not sampled");
+
+        let mut tail = data.clone();
+        tail[chunk * 4 - 1] = 0;
+        assert_ne!(partial_hash(tail).await, base);
+
+        let mut longer = data;
+        longer.push(7);
+        assert_ne!(partial_hash(longer).await, base);
+    }
+
+    #[tokio::test]
+    async fn test_sampled_and_head_strategies() {
+        let chunk = crate::utils::hash::SAMPLE_CHUNK_SIZE as usize;
+        let service = LocalHashService::default();
+        let hash = |data: &[u8], strategy: HashStrategy| {
+            let service = service.clone();
+            let data = data.to_vec();
+            async move {
+                let mut temp_file = NamedTempFile::new().unwrap();
+                temp_file.write_all(&data).unwrap();
+                temp_file.flush().unwrap();
+                service
+                    .hash_with_async(temp_file.path().to_path_buf(), strategy)
+                    .await
+                    .unwrap()
+            }
+        };
+        let sampled = HashStrategy::Sampled { chunks: 1 };
+        let head = HashStrategy::Head { megabytes: 1 };
+
+        // Head, middle and tail chunks are sampled from a 9-chunk file
+        let data: Vec<u8> = (0..chunk * 9).map(|i| (i % 251) as u8).collect();
+        let base = hash(&data, sampled).await;
+        assert_ne!(base, hash(&data, HashStrategy::Full).await);
+
+        let mut unsampled = data.clone();
+        unsampled[chunk * 2] ^= 0xff;
+        assert_eq!(hash(&unsampled, sampled).await, base);
+        assert_ne!(
+            hash(&unsampled, HashStrategy::Full).await,
+            hash(&data, HashStrategy::Full).await
+        );
+
+        let mut middle = data.clone();
+        middle[chunk * 4] ^= 0xff;
+        assert_ne!(hash(&middle, sampled).await, base);
+
+        let mut tail = data.clone();
+        tail[chunk * 9 - 1] ^= 0xff;
+        assert_ne!(hash(&tail, sampled).await, base);
+
+        // Only the first MiB (and the size) counts for a head hash
+        let head_base = hash(&data, head).await;
+        assert_eq!(hash(&tail, head).await, head_base);
+        let mut start = data.clone();
+        start[0] ^= 0xff;
+        assert_ne!(hash(&start, head).await, head_base);
+        assert_ne!(hash(&data[..chunk * 8], head).await, head_base);
+    }
+
+    #[test]
+    fn test_hash_strategy_round_trip() {
+        for strategy in [
+            HashStrategy::Full,
+            HashStrategy::Sampled { chunks: 8 },
+            HashStrategy::Head { megabytes: 64 },
+        ] {
+            assert_eq!(strategy.to_string().parse::<HashStrategy>(), Ok(strategy));
+        }
+        assert!("sampled".parse::<HashStrategy>().is_err());
+        assert!("head:0".parse::<HashStrategy>().is_err());
+        assert!("full:1".parse::<HashStrategy>().is_err());
+    }
 }
@@ -12,21 +12,40 @@ use uuid::Uuid;
 use walkdir::WalkDir;
 
 use crate::services::admin_log::AdminLogService;
+use crate::services::artwork::{Artwork, ArtworkService};
 use crate::services::hash::HashService;
 use crate::services::media_info::MediaInfoService;
+use crate::services::nfo::{NfoKind, NfoMetadata, NfoService};
 use crate::services::notification::{AdminEvent, EventCategory, NotificationService};
 use crate::utils::metadata::{StreamMetadata, VideoFileMetadata};
+use beam_domain::models::Library;
 use beam_domain::models::admin_log::{AdminLogCategory, AdminLogLevel};
-use beam_domain::models::file::{FileStatus, MediaFileContent, UpdateMediaFile};
+use beam_domain::models::file::{FileStatus, MediaFile, MediaFileContent, UpdateMediaFile};
 use beam_domain::repositories::{
     FileRepository, LibraryRepository, MediaStreamRepository, MovieRepository, ShowRepository,
 };
+use beam_domain::utils::hash::HashStrategy;
 
 static EPISODE_REGEX: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(?i)S(\d+)E(\d+)").expect("valid regex"));
 
+/// Version markers in a movie file name: a Plex-style `{edition-...}` tag, a known edition
+/// after ` - ` or in brackets, or a resolution tag.
+static MOVIE_VERSION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    const EDITIONS: &str = r"director'?s[\s.]cut|extended(?:[\s.](?:edition|cut))?|theatrical(?:[\s.]cut)?|unrated|uncut|remastered|imax|final[\s.]cut|special[\s.]edition|ultimate[\s.]edition";
+    Regex::new(&format!(
+        r"(?i)\s*\{{edition-(?P<tagged>[^}}]+)\}}|\s*[\[(](?P<bracketed>{EDITIONS})[\])]|\s+-\s+(?P<dashed>{EDITIONS})\b|(?:\s+-)?[\s.]*[\[(]?\b(?P<quality>2160p|4k|uhd|1080p|720p|480p)\b[\])]?"
+    ))
+    .expect("valid regex")
+});
+
+/// Season folders below a show root, e.g. `Season 1`, `S01` or `Specials`
+static SEASON_DIR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:(?:season|series|s)[\s._-]*\d+|specials)$").expect("valid regex")
+});
+
 // TODO: See if these can be improved. Ensure logic can detect all of them properly
-const KNOWN_VIDEO_EXTENSIONS: &[&str] = &[
+pub(crate) const KNOWN_VIDEO_EXTENSIONS: &[&str] = &[
     "mp4", "mkv", "avi", "mov", "webm", "m4v", "ts", "m2ts", "flv", "wmv", "3gp", "ogv", "mpg",
     "mpeg",
 ];
@@ -43,15 +62,60 @@ pub enum IndexError {
     PathNotFound(String),
 }
 
+/// Outcome of [`LocalIndexService::verify_file_hash`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashCheck {
+    /// The file still matches its stored hash
+    Match,
+    /// The file matched its sampled hash, which was replaced by this full hash
+    Upgraded { hash: u64 },
+    /// The file's content no longer matches its stored hash
+    Mismatch { expected: u64, actual: u64 },
+}
+
+/// Options for [`IndexService::verify_library`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyOptions {
+    /// Check at most this many files, least recently verified first. `None` checks all.
+    pub limit: Option<u32>,
+    /// Replace sampled hashes that still match with full hashes
+    pub upgrade_hashes: bool,
+    /// Also decode each file with FFmpeg. Much slower than hashing alone.
+    pub check_decode: bool,
+}
+
+/// Outcome of [`LocalIndexService::run_verification`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifyReport {
+    pub files_checked: u32,
+    /// Files whose content no longer matches their stored hash
+    pub mismatched: u32,
+    /// Files that could not be read
+    pub unreadable: u32,
+    /// Files that hashed correctly but failed to decode
+    pub decode_errors: u32,
+    /// Sampled hashes replaced by full hashes
+    pub hashes_upgraded: u32,
+}
+
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
 #[async_trait::async_trait]
 pub trait IndexService: Send + Sync + std::fmt::Debug {
     /// Scan a library for new/changed/removed files.
     /// Returns the count of newly added files.
     async fn scan_library(&self, library_id: String) -> Result<u32, IndexError>;
+
+    /// Start rehashing a library's files in the background to detect silent corruption.
+    /// Returns once the library is found; progress, each problem and the final summary are
+    /// recorded in the admin log and published as events.
+    async fn verify_library(
+        &self,
+        library_id: String,
+        options: VerifyOptions,
+    ) -> Result<(), IndexError>;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LocalIndexService {
     library_repo: Arc<dyn LibraryRepository>,
     file_repo: Arc<dyn FileRepository>,
@@ -60,6 +124,8 @@ pub struct LocalIndexService {
     stream_repo: Arc<dyn MediaStreamRepository>,
     hash_service: Arc<dyn HashService>,
     media_info_service: Arc<dyn MediaInfoService>,
+    nfo_service: Arc<dyn NfoService>,
+    artwork_service: Arc<dyn ArtworkService>,
     notification_service: Arc<dyn NotificationService>,
     admin_log: Arc<dyn AdminLogService>,
 }
@@ -74,6 +140,8 @@ impl LocalIndexService {
         stream_repo: Arc<dyn MediaStreamRepository>,
         hash_service: Arc<dyn HashService>,
         media_info_service: Arc<dyn MediaInfoService>,
+        nfo_service: Arc<dyn NfoService>,
+        artwork_service: Arc<dyn ArtworkService>,
         notification_service: Arc<dyn NotificationService>,
         admin_log: Arc<dyn AdminLogService>,
     ) -> Self {
@@ -85,11 +153,52 @@ impl LocalIndexService {
             stream_repo,
             hash_service,
             media_info_service,
+            nfo_service,
+            artwork_service,
             notification_service,
             admin_log,
         }
     }
 
+    /// Rehash a file with the strategy its stored hash was computed with and compare. With
+    /// `upgrade`, a matching non-full hash is replaced by a full hash so later checks cover
+    /// every byte.
+    pub async fn verify_file_hash(
+        &self,
+        file: &MediaFile,
+        upgrade: bool,
+    ) -> Result<HashCheck, IndexError> {
+        let actual = self
+            .hash_service
+            .hash_with_async(file.path.clone(), file.hash_strategy)
+            .await
+            .map_err(|e| IndexError::PathNotFound(format!("Hash failed: {}", e)))?;
+        if actual != file.hash {
+            return Ok(HashCheck::Mismatch {
+                expected: file.hash,
+                actual,
+            });
+        }
+        if !upgrade || file.hash_strategy == HashStrategy::Full {
+            return Ok(HashCheck::Match);
+        }
+
+        let hash = self
+            .hash_service
+            .hash_with_async(file.path.clone(), HashStrategy::Full)
+            .await
+            .map_err(|e| IndexError::PathNotFound(format!("Hash failed: {}", e)))?;
+        self.file_repo
+            .update(UpdateMediaFile {
+                id: file.id,
+                hash: Some(hash),
+                hash_strategy: Some(HashStrategy::Full),
+                ..Default::default()
+            })
+            .await?;
+        Ok(HashCheck::Upgraded { hash })
+    }
+
     /// Helper to extract and insert media streams for a file
     pub(crate) async fn insert_media_streams(
         &self,
@@ -167,7 +276,10 @@ impl LocalIndexService {
         lib_uuid: Uuid,
         duration: Duration,
     ) -> Result<MediaFileContent, IndexError> {
-        use beam_domain::models::{CreateEpisode, CreateMovie, CreateMovieEntry, MediaFileContent};
+        use beam_domain::models::{
+            CreateEpisode, CreateMovie, CreateMovieEntry, MediaFileContent, UpdateEpisode,
+            UpdateMovie, UpdateShow,
+        };
 
         let file_stem = path
             .file_stem()
@@ -179,19 +291,95 @@ impl LocalIndexService {
             let season_num: u32 = captures[1].parse().unwrap_or(1);
             let episode_num: i32 = captures[2].parse().unwrap_or(1);
 
-            // Show title guess: Parent directory name
-            let show_title = path
-                .parent()
+            // The show root holds tvshow.nfo and show artwork. Episodes sit in it directly
+            // or one level below in a season folder.
+            let show_dir = path.parent();
+            let show_root = show_dir.map(|dir| match dir.file_name() {
+                Some(name) if SEASON_DIR_REGEX.is_match(&name.to_string_lossy()) => {
+                    dir.parent().unwrap_or(dir)
+                }
+                _ => dir,
+            });
+
+            // Show title guess: show root directory name
+            let show_title = show_root
                 .and_then(|p| p.file_name())
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_else(|| "Unknown Show".to_string());
 
-            // Find or create show using repository
-            let show = match self.show_repo.find_by_title(&show_title).await? {
+            let show_dirs: Vec<&Path> = show_dir
+                .into_iter()
+                .chain(show_dir.and_then(|d| d.parent()))
+                .collect();
+            let mut show_nfo = None;
+            for dir in &show_dirs {
+                show_nfo = self.read_nfo(NfoKind::Show, dir).await;
+                if show_nfo.is_some() {
+                    break;
+                }
+            }
+            // The NFO's original title becomes the show title, so look it up by that
+            let show_title = show_nfo
+                .as_ref()
+                .and_then(|nfo| nfo.original_title.clone().or(nfo.title.clone()))
+                .unwrap_or(show_title);
+
+            // Episodes already indexed under the show root identify the show even after
+            // its title was edited; the title only matters for a show's first episode
+            let existing = match show_root.filter(|dir| !dir.as_os_str().is_empty()) {
+                Some(dir) => self.find_show_in_dir(dir).await?,
+                None => None,
+            };
+            let mut show = match existing {
                 Some(s) => s,
-                None => self.show_repo.create(show_title.clone()).await?,
+                None => match self.show_repo.find_by_title(&show_title).await? {
+                    Some(s) => s,
+                    None => self.show_repo.create(show_title.clone()).await?,
+                },
             };
 
+            if let Some(nfo) = &show_nfo {
+                let update = nfo.show_update(show.id).without_locked(&show.locked_fields);
+                show = self.show_repo.update(update).await?;
+                if !nfo.genres.is_empty() {
+                    self.show_repo
+                        .set_genres(show.id, nfo.genres.clone())
+                        .await?;
+                }
+            }
+
+            if show.poster_url.is_none() || show.backdrop_url.is_none() {
+                // Like tvshow.nfo, show artwork may sit above a season folder
+                for dir in &show_dirs {
+                    let artwork = match self.artwork_service.show_artwork(dir).await {
+                        Ok(artwork) => artwork,
+                        Err(e) => {
+                            warn!("Failed to load artwork from {}: {}", dir.display(), e);
+                            continue;
+                        }
+                    };
+                    let update = UpdateShow {
+                        id: show.id,
+                        poster_url: artwork.poster_url.filter(|_| show.poster_url.is_none()),
+                        backdrop_url: artwork.backdrop_url.filter(|_| show.backdrop_url.is_none()),
+                        ..Default::default()
+                    }
+                    .without_locked(&show.locked_fields);
+                    if !update.changed_fields().is_empty() {
+                        show = self.show_repo.update(update).await?;
+                        break;
+                    }
+                }
+            }
+
+            if let Some(dir) =
+                show_root.filter(|_| show_nfo.is_none() && self.nfo_service.export_enabled())
+            {
+                let genres = self.show_repo.find_genres(show.id).await?;
+                self.export_nfo(NfoKind::Show, dir, NfoMetadata::from_show(&show, genres))
+                    .await;
+            }
+
             // Ensure library-show association exists
             self.show_repo
                 .ensure_library_association(lib_uuid, show.id)
@@ -210,17 +398,58 @@ impl LocalIndexService {
                 title: file_stem.to_string(),
                 runtime: Some(duration),
             };
-            let episode = self.show_repo.create_episode(create_episode).await?;
+            let mut episode = self.show_repo.create_episode(create_episode).await?;
+
+            let episode_nfo = self.read_nfo(NfoKind::Episode, path).await;
+            if let Some(nfo) = &episode_nfo {
+                episode = self
+                    .show_repo
+                    .update_episode(
+                        nfo.episode_update(episode.id)
+                            .without_locked(&episode.locked_fields),
+                    )
+                    .await?;
+            }
+
+            if episode.thumbnail_url.is_none() {
+                match self.artwork_service.episode_thumbnail(path).await {
+                    Ok(Some(thumbnail_url)) => {
+                        let update = UpdateEpisode {
+                            id: episode.id,
+                            thumbnail_url: Some(thumbnail_url),
+                            ..Default::default()
+                        }
+                        .without_locked(&episode.locked_fields);
+                        if !update.changed_fields().is_empty() {
+                            episode = self.show_repo.update_episode(update).await?;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to load thumbnail for {}: {}", path.display(), e),
+                }
+            }
+
+            if episode_nfo.is_none() && self.nfo_service.export_enabled() {
+                let nfo = NfoMetadata::from_episode(&episode, season.season_number);
+                self.export_nfo(NfoKind::Episode, path, nfo).await;
+            }
 
             Ok(MediaFileContent::Episode {
                 episode_id: episode.id,
             })
         } else {
             // IT IS A MOVIE
-            let movie_title = file_stem.to_string();
+            // Versions of a movie (editions, resolutions) share a title once their markers
+            // are stripped, so they group under one movie with an entry per version
+            let (file_title, edition) = parse_movie_version(&file_stem);
+            let movie_nfo = self.read_nfo(NfoKind::Movie, path).await;
+            let movie_title = movie_nfo
+                .as_ref()
+                .and_then(|nfo| nfo.original_title.clone().or(nfo.title.clone()))
+                .unwrap_or(file_title);
 
             // Find or create movie using repository
-            let movie = match self.movie_repo.find_by_title(&movie_title).await? {
+            let mut movie = match self.movie_repo.find_by_title(&movie_title).await? {
                 Some(m) => m,
                 None => {
                     let create_movie = CreateMovie {
@@ -231,19 +460,73 @@ impl LocalIndexService {
                 }
             };
 
+            if let Some(nfo) = &movie_nfo {
+                let update = nfo
+                    .movie_update(movie.id)
+                    .without_locked(&movie.locked_fields);
+                movie = self.movie_repo.update(update).await?;
+                if !nfo.genres.is_empty() {
+                    self.movie_repo
+                        .set_genres(movie.id, nfo.genres.clone())
+                        .await?;
+                }
+            }
+
+            if movie.poster_url.is_none() || movie.backdrop_url.is_none() {
+                let artwork = match self.artwork_service.movie_artwork(path).await {
+                    Ok(artwork) => artwork,
+                    Err(e) => {
+                        warn!("Failed to load artwork for {}: {}", path.display(), e);
+                        Artwork::default()
+                    }
+                };
+                let update = UpdateMovie {
+                    id: movie.id,
+                    poster_url: artwork.poster_url.filter(|_| movie.poster_url.is_none()),
+                    backdrop_url: artwork
+                        .backdrop_url
+                        .filter(|_| movie.backdrop_url.is_none()),
+                    ..Default::default()
+                }
+                .without_locked(&movie.locked_fields);
+                if !update.changed_fields().is_empty() {
+                    movie = self.movie_repo.update(update).await?;
+                }
+            }
+
+            if movie_nfo.is_none() && self.nfo_service.export_enabled() {
+                let genres = self.movie_repo.find_genres(movie.id).await?;
+                self.export_nfo(
+                    NfoKind::Movie,
+                    path,
+                    NfoMetadata::from_movie(&movie, genres),
+                )
+                .await;
+            }
+
             // Ensure library-movie association exists
             self.movie_repo
                 .ensure_library_association(lib_uuid, movie.id)
                 .await?;
 
-            // Create movie entry
-            let create_entry = CreateMovieEntry {
-                library_id: lib_uuid,
-                movie_id: movie.id,
-                edition: None,
-                is_primary: true,
+            // Reuse this version's entry, or create one. The first version becomes primary.
+            let entries = self.movie_repo.find_entries_by_movie_id(movie.id).await?;
+            let existing = entries
+                .iter()
+                .find(|e| e.library_id == lib_uuid && e.edition == edition)
+                .cloned();
+            let entry = match existing {
+                Some(entry) => entry,
+                None => {
+                    let create_entry = CreateMovieEntry {
+                        library_id: lib_uuid,
+                        movie_id: movie.id,
+                        edition,
+                        is_primary: entries.is_empty(),
+                    };
+                    self.movie_repo.create_entry(create_entry).await?
+                }
             };
-            let entry = self.movie_repo.create_entry(create_entry).await?;
 
             Ok(MediaFileContent::Movie {
                 movie_entry_id: entry.id,
@@ -251,6 +534,255 @@ impl LocalIndexService {
         }
     }
 
+    /// Show of an episode file already indexed under `dir`
+    async fn find_show_in_dir(
+        &self,
+        dir: &Path,
+    ) -> Result<Option<beam_domain::models::Show>, IndexError> {
+        let file = self
+            .file_repo
+            .find_episode_file_in_dir(&dir.to_string_lossy())
+            .await?;
+        let Some(MediaFileContent::Episode { episode_id }) = file.and_then(|f| f.content) else {
+            return Ok(None);
+        };
+        let Some(episode) = self.show_repo.find_episode_by_id(episode_id).await? else {
+            return Ok(None);
+        };
+        let Some(season) = self.show_repo.find_season_by_id(episode.season_id).await? else {
+            return Ok(None);
+        };
+        Ok(self.show_repo.find_by_id(season.show_id).await?)
+    }
+
+    /// Read the NFO sidecar for `media_path`. Unreadable or malformed files are logged
+    /// and treated as absent so a stray NFO never blocks indexing.
+    async fn read_nfo(&self, kind: NfoKind, media_path: &Path) -> Option<NfoMetadata> {
+        match self.nfo_service.read(kind, media_path).await {
+            Ok(nfo) => nfo,
+            Err(e) => {
+                warn!("Ignoring NFO for {}: {}", media_path.display(), e);
+                None
+            }
+        }
+    }
+
+    /// Export Beam's metadata to an NFO sidecar. Existing NFO files are left untouched.
+    async fn export_nfo(&self, kind: NfoKind, media_path: &Path, metadata: NfoMetadata) {
+        if let Err(e) = self.nfo_service.write(kind, media_path, &metadata).await {
+            warn!("Failed to write NFO for {}: {}", media_path.display(), e);
+        }
+    }
+
+    /// Re-probe a file whose content changed since it was indexed: recompute its hashes,
+    /// replace its streams and refresh duration and container. Its movie or episode link
+    /// is kept. Bumping `scanned_at` also marks cached remuxes of the file as stale.
+    async fn reprocess_changed_file(
+        &self,
+        file: &MediaFile,
+        size_bytes: u64,
+    ) -> Result<(), IndexError> {
+        let path = file.path.as_path();
+        let is_known_video = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| KNOWN_VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if !is_known_video {
+            self.file_repo
+                .update(UpdateMediaFile {
+                    id: file.id,
+                    size_bytes: Some(size_bytes),
+                    scanned_at: Some(chrono::Utc::now()),
+                    ..Default::default()
+                })
+                .await?;
+            return Ok(());
+        }
+
+        let metadata = match self.media_info_service.get_video_metadata(path).await {
+            Ok(m) => m,
+            Err(e) => {
+                // Stay flagged so the next scan retries the probe
+                warn!("Failed to extract metadata for {}: {}", path.display(), e);
+                self.file_repo
+                    .update(UpdateMediaFile {
+                        id: file.id,
+                        size_bytes: Some(size_bytes),
+                        status: Some(FileStatus::Changed),
+                        ..Default::default()
+                    })
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let hash = self
+            .hash_service
+            .hash_async(path.to_path_buf())
+            .await
+            .map_err(|e| IndexError::PathNotFound(format!("Hash failed: {}", e)))?;
+        let partial_hash = match self
+            .hash_service
+            .partial_hash_async(path.to_path_buf())
+            .await
+        {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                warn!(
+                    "Failed to compute partial hash for {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        };
+
+        self.stream_repo.delete_by_file_id(file.id).await?;
+        self.insert_media_streams(file.id, &metadata).await?;
+
+        self.file_repo
+            .update(UpdateMediaFile {
+                id: file.id,
+                hash: Some(hash),
+                hash_strategy: Some(self.hash_service.strategy()),
+                partial_hash,
+                size_bytes: Some(metadata.file_size),
+                mime_type: Some(format!("video/{}", metadata.format_name)),
+                duration: Some(Duration::from_secs_f64(metadata.duration_seconds())),
+                container_format: Some(metadata.format_name.clone()),
+                status: Some(FileStatus::Known),
+                scanned_at: Some(chrono::Utc::now()),
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Log, publish and record a file that failed to index
+    async fn report_file_failure(&self, library: &Library, path: &Path, e: &IndexError) {
+        error!("Failed to process file {}: {}", path.display(), e);
+        self.notification_service.publish(AdminEvent::warning(
+            EventCategory::LibraryScan,
+            format!("Failed to process file '{}': {}", path.display(), e),
+            Some(library.id.to_string()),
+            Some(library.name.clone()),
+        ));
+        let _ = self
+            .admin_log
+            .log(
+                AdminLogLevel::Warning,
+                AdminLogCategory::LibraryScan,
+                format!("Failed to process file: {}", path.display()),
+                Some(serde_json::json!({
+                    "library_id": library.id.to_string(),
+                    "path": path.display().to_string(),
+                    "error": e.to_string()
+                })),
+            )
+            .await;
+    }
+
+    /// Log, publish and record a file that failed an integrity check
+    async fn report_integrity_issue(
+        &self,
+        library: &Library,
+        file: &MediaFile,
+        level: AdminLogLevel,
+        problem: &str,
+        extra: serde_json::Value,
+    ) {
+        let message = format!("{}: '{}'", problem, file.path.display());
+        warn!("Integrity check: {}", message);
+        let (library_id, library_name) = (Some(library.id.to_string()), Some(library.name.clone()));
+        self.notification_service.publish(match level {
+            AdminLogLevel::Error => {
+                AdminEvent::error(EventCategory::Integrity, &message, library_id, library_name)
+            }
+            _ => AdminEvent::warning(EventCategory::Integrity, &message, library_id, library_name),
+        });
+
+        let mut details = serde_json::json!({
+            "library_id": library.id.to_string(),
+            "file_id": file.id.to_string(),
+            "path": file.path.display().to_string(),
+        });
+        if let (Some(details), serde_json::Value::Object(extra)) = (details.as_object_mut(), extra)
+        {
+            details.extend(extra);
+        }
+        let _ = self
+            .admin_log
+            .log(level, AdminLogCategory::Integrity, message, Some(details))
+            .await;
+    }
+
+    /// Match a new path against the files that vanished during this scan. Candidates must
+    /// share the file size, then the partial hash, and finally the full XXH3 (computed with
+    /// each candidate's own strategy), so the full hash is only computed when a move is
+    /// likely. The matched file is taken out of `vanished` and returned with the new path's
+    /// partial hash.
+    async fn find_moved_file(
+        &self,
+        path: &Path,
+        vanished: &mut Vec<MediaFile>,
+    ) -> Option<(MediaFile, u64)> {
+        let size = std::fs::metadata(path).ok()?.len();
+        // Unhashed (unknown) files carry no history worth preserving
+        let same_size = |f: &MediaFile| f.hash != 0 && f.size_bytes == size;
+        if !vanished.iter().any(same_size) {
+            return None;
+        }
+
+        let partial_hash = match self
+            .hash_service
+            .partial_hash_async(path.to_path_buf())
+            .await
+        {
+            Ok(hash) => hash,
+            Err(e) => {
+                warn!("Failed to hash file {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        // Files indexed before partial hashes existed fall through to the full hash
+        let candidate =
+            |f: &MediaFile| same_size(f) && f.partial_hash.is_none_or(|h| h == partial_hash);
+        if !vanished.iter().any(candidate) {
+            return None;
+        }
+
+        let mut hashes: Vec<(HashStrategy, u64)> = Vec::new();
+        for index in 0..vanished.len() {
+            let file = &vanished[index];
+            if !candidate(file) {
+                continue;
+            }
+            let hash = match hashes.iter().find(|(s, _)| *s == file.hash_strategy) {
+                Some((_, hash)) => *hash,
+                None => {
+                    let hash = match self
+                        .hash_service
+                        .hash_with_async(path.to_path_buf(), file.hash_strategy)
+                        .await
+                    {
+                        Ok(hash) => hash,
+                        Err(e) => {
+                            warn!("Failed to hash file {}: {}", path.display(), e);
+                            return None;
+                        }
+                    };
+                    hashes.push((file.hash_strategy, hash));
+                    hash
+                }
+            };
+            if file.hash == hash {
+                return Some((vanished.swap_remove(index), partial_hash));
+            }
+        }
+        None
+    }
+
     /// Process a NEW file to add it to the library
     async fn process_new_file(&self, path: &Path, lib_uuid: Uuid) -> Result<bool, IndexError> {
         use beam_domain::models::CreateMediaFile;
@@ -275,6 +807,8 @@ impl LocalIndexService {
                 library_id: lib_uuid,
                 path: path.to_path_buf(),
                 hash: 0,
+                hash_strategy: HashStrategy::Full,
+                partial_hash: None,
                 size_bytes: metadata.len(),
                 mime_type: None,
                 duration: None,
@@ -297,6 +831,8 @@ impl LocalIndexService {
                     library_id: lib_uuid,
                     path: path.to_path_buf(),
                     hash: 0,
+                    hash_strategy: HashStrategy::Full,
+                    partial_hash: None,
                     size_bytes: fs_meta.len(),
                     mime_type: None,
                     duration: None,
@@ -318,6 +854,22 @@ impl LocalIndexService {
                 IndexError::PathNotFound(format!("Hash failed: {}", e))
             })?;
 
+        let partial_hash = match self
+            .hash_service
+            .partial_hash_async(path.to_path_buf())
+            .await
+        {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                warn!(
+                    "Failed to compute partial hash for {}: {}",
+                    path.display(),
+                    e
+                );
+                None
+            }
+        };
+
         // Classify content
         let duration = Duration::from_secs_f64(metadata.duration_seconds());
         let content = self
@@ -329,6 +881,8 @@ impl LocalIndexService {
             library_id: lib_uuid,
             path: path.to_path_buf(),
             hash: hash_value,
+            hash_strategy: self.hash_service.strategy(),
+            partial_hash,
             size_bytes: metadata.file_size,
             mime_type: Some(format!("video/{}", metadata.format_name)),
             duration: Some(duration),
@@ -426,8 +980,10 @@ impl IndexService for LocalIndexService {
         info!("Found {} existing files in DB", existing_map.len());
 
         let mut added_count = 0;
+        let mut changed_count = 0;
+        let mut new_paths = Vec::new();
 
-        // Phase 2 & 3: Walk FS, compare with DB, add new files
+        // Phase 2: Walk FS, compare with DB
         for entry in WalkDir::new(&library.root_path)
             .into_iter()
             .filter_map(|e| e.ok())
@@ -438,63 +994,91 @@ impl IndexService for LocalIndexService {
             }
 
             if let Some(existing_file) = existing_map.remove(&path) {
-                // File exists in DB. Check if changed (size).
+                // File exists in DB. Check if changed (size, or modified since last probed).
                 let metadata = match std::fs::metadata(&path) {
                     Ok(m) => m,
                     Err(_) => continue,
                 };
-
-                if metadata.len() != existing_file.size_bytes {
+                let modified_since_scan = metadata
+                    .modified()
+                    .map(chrono::DateTime::<chrono::Utc>::from)
+                    .is_ok_and(|modified| modified > existing_file.scanned_at);
+
+                if metadata.len() != existing_file.size_bytes
+                    || modified_since_scan
+                    || existing_file.status == FileStatus::Changed
+                {
                     info!("File changed: {}", path.display());
-                    if existing_file.status != FileStatus::Changed {
-                        self.file_repo
-                            .update(UpdateMediaFile {
-                                id: existing_file.id,
-                                hash: None,
-                                size_bytes: Some(metadata.len()),
-                                mime_type: None,
-                                duration: None,
-                                container_format: None,
-                                content: None,
-                                status: Some(FileStatus::Changed),
-                            })
-                            .await?;
+                    match self
+                        .reprocess_changed_file(&existing_file, metadata.len())
+                        .await
+                    {
+                        Ok(()) => changed_count += 1,
+                        Err(e) => {
+                            self.report_file_failure(&library, &path, &e).await;
+                        }
                     }
                 }
             } else {
-                // New file
-                match self.process_new_file(&path, lib_uuid).await {
-                    Ok(true) => added_count += 1,
-                    Ok(false) => {}
-                    Err(e) => {
-                        error!("Failed to process file {}: {}", path.display(), e);
-                        self.notification_service.publish(AdminEvent::warning(
-                            EventCategory::LibraryScan,
-                            format!("Failed to process file '{}': {}", path.display(), e),
-                            Some(lib_uuid.to_string()),
-                            Some(library.name.clone()),
-                        ));
-                        let _ = self
-                            .admin_log
-                            .log(
-                                AdminLogLevel::Warning,
-                                AdminLogCategory::LibraryScan,
-                                format!("Failed to process file: {}", path.display()),
-                                Some(serde_json::json!({
-                                    "library_id": library_id,
-                                    "path": path.display().to_string(),
-                                    "error": e.to_string()
-                                })),
-                            )
-                            .await;
-                    }
-                }
+                new_paths.push(path);
+            }
+        }
+
+        // Phase 3: A new path whose content matches a vanished file is a move or rename.
+        // Updating the record in place keeps its links, manual edits and cache entries.
+        let mut vanished: Vec<MediaFile> = existing_map.into_values().collect();
+        let mut moved_count = 0;
+        for path in new_paths {
+            if let Some((file, partial_hash)) = self.find_moved_file(&path, &mut vanished).await {
+                self.file_repo
+                    .update(UpdateMediaFile {
+                        id: file.id,
+                        path: Some(path.clone()),
+                        partial_hash: Some(partial_hash),
+                        ..Default::default()
+                    })
+                    .await?;
+                moved_count += 1;
+
+                info!("File moved: {} -> {}", file.path.display(), path.display());
+                self.notification_service.publish(AdminEvent::info(
+                    EventCategory::LibraryScan,
+                    format!(
+                        "File moved: '{}' -> '{}'",
+                        file.path.display(),
+                        path.display()
+                    ),
+                    Some(lib_uuid.to_string()),
+                    Some(library.name.clone()),
+                ));
+                let _ = self
+                    .admin_log
+                    .log(
+                        AdminLogLevel::Info,
+                        AdminLogCategory::LibraryScan,
+                        format!("File moved: {}", path.display()),
+                        Some(serde_json::json!({
+                            "library_id": library_id,
+                            "file_id": file.id,
+                            "from": file.path.display().to_string(),
+                            "to": path.display().to_string(),
+                        })),
+                    )
+                    .await;
+                continue;
+            }
+
+            // New file
+            match self.process_new_file(&path, lib_uuid).await {
+                Ok(true) => added_count += 1,
+                Ok(false) => {}
+                Err(e) => self.report_file_failure(&library, &path, &e).await,
             }
         }
 
         // Phase 4: Remove files that are in DB but not on FS
-        let removed_count = existing_map.len();
-        let to_remove: Vec<Uuid> = existing_map.values().map(|f| f.id).collect();
+        let removed_count = vanished.len();
+        let to_remove: Vec<Uuid> = vanished.iter().map(|f| f.id).collect();
         if !to_remove.is_empty() {
             info!("Removing {} missing files from library", to_remove.len());
             self.file_repo.delete_by_ids(to_remove).await?;
@@ -509,15 +1093,15 @@ impl IndexService for LocalIndexService {
             .await?;
 
         info!(
-            "Scan complete. Added: {}, Removed: {}, Total: {}",
-            added_count, removed_count, total_files
+            "Scan complete. Added: {}, Changed: {}, Moved: {}, Removed: {}, Total: {}",
+            added_count, changed_count, moved_count, removed_count, total_files
         );
 
         self.notification_service.publish(AdminEvent::info(
             EventCategory::LibraryScan,
             format!(
-                "Library scan complete for '{}': added {}, removed {}, total {}",
-                library.name, added_count, removed_count, total_files
+                "Library scan complete for '{}': added {}, changed {}, moved {}, removed {}, total {}",
+                library.name, added_count, changed_count, moved_count, removed_count, total_files
             ),
             Some(lib_uuid.to_string()),
             Some(library.name.clone()),
@@ -528,12 +1112,19 @@ impl IndexService for LocalIndexService {
                 AdminLogLevel::Info,
                 AdminLogCategory::LibraryScan,
                 format!(
-                    "Library scan completed: \"{}\" — {} added, {} removed, {} total",
-                    library.name, added_count, removed_count, total_files
+                    "Library scan completed: \"{}\" — {} added, {} changed, {} moved, {} removed, {} total",
+                    library.name,
+                    added_count,
+                    changed_count,
+                    moved_count,
+                    removed_count,
+                    total_files
                 ),
                 Some(serde_json::json!({
                     "library_id": library_id,
                     "added": added_count,
+                    "changed": changed_count,
+                    "moved": moved_count,
                     "removed": removed_count,
                     "total": total_files,
                 })),
@@ -542,74 +1133,357 @@ impl IndexService for LocalIndexService {
 
         Ok(added_count)
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::services::admin_log::LocalAdminLogService;
-    use crate::services::admin_log::NoOpAdminLogService;
-    use crate::services::hash::MockHashService;
-    use crate::services::media_info::MockMediaInfoService;
-    use crate::services::notification::EventLevel;
-    use crate::services::notification::InMemoryNotificationService;
-    use crate::utils::color::{
-        ChromaLocation, ColorPrimaries, ColorRange, ColorSpace, ColorTransferCharacteristic,
-        PixelFormat,
-    };
-    use crate::utils::format::{ChannelLayout, Disposition, SampleFormat};
-    use crate::utils::media::{CodecId, Discard};
-    use crate::utils::metadata::MetadataError;
-    use crate::utils::metadata::StreamMetadata as UtilStreamMetadata;
-    use crate::utils::metadata::{
-        AudioMetadata, AudioStreamMetadata as UtilAudioStream,
-        SubtitleStreamMetadata as UtilSubtitleStream, VideoFileMetadata, VideoMetadata,
-        VideoStreamMetadata as UtilVideoStream,
-    };
-    use beam_domain::models::{CreateLibrary, Library, MediaFile};
-    use beam_domain::repositories::AdminLogRepository;
-    use beam_domain::repositories::admin_log::in_memory::InMemoryAdminLogRepository;
-    use beam_domain::repositories::file::MockFileRepository;
-    use beam_domain::repositories::file::in_memory::InMemoryFileRepository;
-    use beam_domain::repositories::library::MockLibraryRepository;
-    use beam_domain::repositories::library::in_memory::InMemoryLibraryRepository;
-    use beam_domain::repositories::movie::MockMovieRepository;
-    use beam_domain::repositories::movie::in_memory::InMemoryMovieRepository;
-    use beam_domain::repositories::show::MockShowRepository;
-    use beam_domain::repositories::show::in_memory::InMemoryShowRepository;
-    use beam_domain::repositories::stream::MockMediaStreamRepository;
-    use beam_domain::repositories::stream::in_memory::InMemoryMediaStreamRepository;
-    use num::rational::Ratio;
-    use std::path::PathBuf;
-    use tempfile::TempDir;
 
-    // ─── helpers ─────────────────────────────────────────────────────────────
+    async fn verify_library(
+        &self,
+        library_id: String,
+        options: VerifyOptions,
+    ) -> Result<(), IndexError> {
+        let lib_uuid = Uuid::parse_str(&library_id).map_err(|_| IndexError::InvalidId)?;
+        let library = self
+            .library_repo
+            .find_by_id(lib_uuid)
+            .await?
+            .ok_or(IndexError::LibraryNotFound)?;
 
-    fn make_classify_service() -> (
-        LocalIndexService,
-        Arc<InMemoryMovieRepository>,
-        Arc<InMemoryShowRepository>,
-    ) {
-        let movie_repo = Arc::new(InMemoryMovieRepository::default());
-        let show_repo = Arc::new(InMemoryShowRepository::default());
-        let service = LocalIndexService::new(
-            Arc::new(InMemoryLibraryRepository::default()),
-            Arc::new(InMemoryFileRepository::default()),
-            movie_repo.clone(),
-            show_repo.clone(),
-            Arc::new(InMemoryMediaStreamRepository::default()),
-            Arc::new(MockHashService::new()),
-            Arc::new(MockMediaInfoService::new()),
-            Arc::new(InMemoryNotificationService::new()),
-            Arc::new(NoOpAdminLogService),
-        );
-        (service, movie_repo, show_repo)
+        // Hashing a whole library can take hours, so don't hold the caller's request open
+        let service = self.clone();
+        tokio::spawn(async move {
+            let (library_id, library_name) = (library.id.to_string(), library.name.clone());
+            if let Err(e) = service.run_verification(library, options).await {
+                error!("Integrity check failed for {}: {:?}", library_name, e);
+                service.notification_service.publish(AdminEvent::error(
+                    EventCategory::Integrity,
+                    format!("Integrity check failed for '{}': {}", library_name, e),
+                    Some(library_id.clone()),
+                    Some(library_name.clone()),
+                ));
+                let _ = service
+                    .admin_log
+                    .log(
+                        AdminLogLevel::Error,
+                        AdminLogCategory::Integrity,
+                        format!("Integrity check failed: \"{}\"", library_name),
+                        Some(serde_json::json!({
+                            "library_id": library_id,
+                            "error": e.to_string(),
+                        })),
+                    )
+                    .await;
+            }
+        });
+        Ok(())
     }
+}
 
-    fn make_service_with_stream_repo(
-        stream_repo: Arc<InMemoryMediaStreamRepository>,
-    ) -> LocalIndexService {
-        LocalIndexService::new(
+impl LocalIndexService {
+    /// Rehash a library's files and compare them against their stored hashes. Each problem
+    /// is recorded in the admin log and published as an event; files that pass get their
+    /// `verified_at` bumped.
+    pub async fn run_verification(
+        &self,
+        library: Library,
+        options: VerifyOptions,
+    ) -> Result<VerifyReport, IndexError> {
+        let lib_uuid = library.id;
+        let library_id = lib_uuid.to_string();
+
+        // Unknown files were never hashed. Never-verified files sort first.
+        let mut files: Vec<MediaFile> = self
+            .file_repo
+            .find_all_by_library(lib_uuid)
+            .await?
+            .into_iter()
+            .filter(|f| f.hash != 0)
+            .collect();
+        files.sort_by_key(|f| f.verified_at);
+        if let Some(limit) = options.limit {
+            files.truncate(limit as usize);
+        }
+
+        info!(
+            "Verifying {} files in library: {}",
+            files.len(),
+            library.name
+        );
+        self.notification_service.publish(AdminEvent::info(
+            EventCategory::Integrity,
+            format!(
+                "Integrity check started for '{}' ({} files)",
+                library.name,
+                files.len()
+            ),
+            Some(lib_uuid.to_string()),
+            Some(library.name.clone()),
+        ));
+        let _ = self
+            .admin_log
+            .log(
+                AdminLogLevel::Info,
+                AdminLogCategory::Integrity,
+                format!("Integrity check started: \"{}\"", library.name),
+                Some(serde_json::json!({
+                    "library_id": library_id,
+                    "files": files.len(),
+                    "upgrade_hashes": options.upgrade_hashes,
+                    "check_decode": options.check_decode,
+                })),
+            )
+            .await;
+
+        let mut report = VerifyReport::default();
+        for file in &files {
+            report.files_checked += 1;
+
+            let check = match self.verify_file_hash(file, options.upgrade_hashes).await {
+                Ok(check) => check,
+                Err(IndexError::Db(e)) => return Err(e.into()),
+                Err(e) => {
+                    report.unreadable += 1;
+                    self.report_integrity_issue(
+                        &library,
+                        file,
+                        AdminLogLevel::Warning,
+                        "File unreadable",
+                        serde_json::json!({ "error": e.to_string() }),
+                    )
+                    .await;
+                    continue;
+                }
+            };
+            match check {
+                HashCheck::Match => {}
+                HashCheck::Upgraded { .. } => report.hashes_upgraded += 1,
+                HashCheck::Mismatch { expected, actual } => {
+                    report.mismatched += 1;
+                    self.report_integrity_issue(
+                        &library,
+                        file,
+                        AdminLogLevel::Error,
+                        "Hash mismatch",
+                        serde_json::json!({
+                            "expected": format!("{:016x}", expected),
+                            "actual": format!("{:016x}", actual),
+                            "hash_strategy": file.hash_strategy.to_string(),
+                        }),
+                    )
+                    .await;
+                    continue;
+                }
+            }
+
+            if options.check_decode
+                && let Err(e) = self.media_info_service.check_decode(&file.path).await
+            {
+                report.decode_errors += 1;
+                self.report_integrity_issue(
+                    &library,
+                    file,
+                    AdminLogLevel::Error,
+                    "Decode error",
+                    serde_json::json!({ "error": e.to_string() }),
+                )
+                .await;
+                continue;
+            }
+
+            self.file_repo
+                .update(UpdateMediaFile {
+                    id: file.id,
+                    verified_at: Some(chrono::Utc::now()),
+                    ..Default::default()
+                })
+                .await?;
+        }
+
+        let problems = report.mismatched + report.unreadable + report.decode_errors;
+        info!(
+            "Integrity check complete. Checked: {}, Mismatched: {}, Unreadable: {}, Decode errors: {}, Upgraded: {}",
+            report.files_checked,
+            report.mismatched,
+            report.unreadable,
+            report.decode_errors,
+            report.hashes_upgraded
+        );
+        let summary = format!(
+            "{} checked, {} mismatched, {} unreadable, {} decode errors",
+            report.files_checked, report.mismatched, report.unreadable, report.decode_errors
+        );
+        let message = format!(
+            "Integrity check complete for '{}': {}",
+            library.name, summary
+        );
+        let (event_library_id, library_name) =
+            (Some(lib_uuid.to_string()), Some(library.name.clone()));
+        let event = if problems == 0 {
+            AdminEvent::info(
+                EventCategory::Integrity,
+                message,
+                event_library_id,
+                library_name,
+            )
+        } else {
+            AdminEvent::warning(
+                EventCategory::Integrity,
+                message,
+                event_library_id,
+                library_name,
+            )
+        };
+        let level = if problems == 0 {
+            AdminLogLevel::Info
+        } else {
+            AdminLogLevel::Warning
+        };
+        self.notification_service.publish(event);
+        let _ = self
+            .admin_log
+            .log(
+                level,
+                AdminLogCategory::Integrity,
+                format!(
+                    "Integrity check completed: \"{}\" — {}",
+                    library.name, summary
+                ),
+                Some(serde_json::json!({
+                    "library_id": library_id,
+                    "checked": report.files_checked,
+                    "mismatched": report.mismatched,
+                    "unreadable": report.unreadable,
+                    "decode_errors": report.decode_errors,
+                    "upgraded": report.hashes_upgraded,
+                })),
+            )
+            .await;
+
+        Ok(report)
+    }
+}
+
+/// Split a movie file stem into its title and version label, so that e.g.
+/// `Heat (1995) - Director's Cut - 2160p` and `Heat (1995) [1080p]` both have the title
+/// `Heat (1995)`, with versions `Director's Cut (4K)` and `1080p`.
+fn parse_movie_version(stem: &str) -> (String, Option<String>) {
+    let mut edition = None;
+    let mut quality = None;
+    for captures in MOVIE_VERSION_REGEX.captures_iter(stem) {
+        if let Some(tagged) = captures.name("tagged") {
+            edition = Some(tagged.as_str().trim().to_string());
+        } else if let Some(known) = captures
+            .name("bracketed")
+            .or_else(|| captures.name("dashed"))
+        {
+            edition = Some(edition_label(known.as_str()).to_string());
+        } else if let Some(tag) = captures.name("quality") {
+            quality = Some(match tag.as_str().to_ascii_lowercase().as_str() {
+                "2160p" | "4k" | "uhd" => "4K".to_string(),
+                other => other.to_string(),
+            });
+        }
+    }
+
+    let title = MOVIE_VERSION_REGEX.replace_all(stem, "");
+    let title = title.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '.' | '_'));
+    let title = if title.is_empty() { stem } else { title };
+
+    let version = match (edition, quality) {
+        (Some(edition), Some(quality)) => Some(format!("{edition} ({quality})")),
+        (edition, quality) => edition.or(quality),
+    };
+    (title.to_string(), version)
+}
+
+/// Canonical label for an edition matched by [`MOVIE_VERSION_REGEX`]
+fn edition_label(matched: &str) -> &'static str {
+    let matched = matched.to_ascii_lowercase();
+    match matched
+        .split(|c: char| c.is_whitespace() || c == '.')
+        .next()
+    {
+        Some("director's" | "directors") => "Director's Cut",
+        Some("extended") => "Extended",
+        Some("theatrical") => "Theatrical",
+        Some("unrated") => "Unrated",
+        Some("uncut") => "Uncut",
+        Some("remastered") => "Remastered",
+        Some("imax") => "IMAX",
+        Some("final") => "Final Cut",
+        Some("special") => "Special Edition",
+        Some("ultimate") => "Ultimate Edition",
+        _ => "Alternate",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::admin_log::LocalAdminLogService;
+    use crate::services::admin_log::NoOpAdminLogService;
+    use crate::services::artwork::{Artwork, MockArtworkService, NoOpArtworkService};
+    use crate::services::hash::MockHashService;
+    use crate::services::media_info::MockMediaInfoService;
+    use crate::services::nfo::LocalNfoService;
+    use crate::services::notification::EventLevel;
+    use crate::services::notification::InMemoryNotificationService;
+    use crate::utils::color::{
+        ChromaLocation, ColorPrimaries, ColorRange, ColorSpace, ColorTransferCharacteristic,
+        PixelFormat,
+    };
+    use crate::utils::format::{ChannelLayout, Disposition, SampleFormat};
+    use crate::utils::media::{CodecId, Discard};
+    use crate::utils::metadata::MetadataError;
+    use crate::utils::metadata::StreamMetadata as UtilStreamMetadata;
+    use crate::utils::metadata::{
+        AudioMetadata, AudioStreamMetadata as UtilAudioStream,
+        SubtitleStreamMetadata as UtilSubtitleStream, VideoFileMetadata, VideoMetadata,
+        VideoStreamMetadata as UtilVideoStream,
+    };
+    use beam_domain::models::{AdminLogFilter, CreateLibrary, CreateMediaFile, Library, MediaFile};
+    use beam_domain::repositories::AdminLogRepository;
+    use beam_domain::repositories::admin_log::in_memory::InMemoryAdminLogRepository;
+    use beam_domain::repositories::file::MockFileRepository;
+    use beam_domain::repositories::file::in_memory::InMemoryFileRepository;
+    use beam_domain::repositories::library::MockLibraryRepository;
+    use beam_domain::repositories::library::in_memory::InMemoryLibraryRepository;
+    use beam_domain::repositories::movie::MockMovieRepository;
+    use beam_domain::repositories::movie::in_memory::InMemoryMovieRepository;
+    use beam_domain::repositories::show::MockShowRepository;
+    use beam_domain::repositories::show::in_memory::InMemoryShowRepository;
+    use beam_domain::repositories::stream::MockMediaStreamRepository;
+    use beam_domain::repositories::stream::in_memory::InMemoryMediaStreamRepository;
+    use num::rational::Ratio;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    // ─── helpers ─────────────────────────────────────────────────────────────
+
+    fn make_classify_service() -> (
+        LocalIndexService,
+        Arc<InMemoryMovieRepository>,
+        Arc<InMemoryShowRepository>,
+    ) {
+        let movie_repo = Arc::new(InMemoryMovieRepository::default());
+        let show_repo = Arc::new(InMemoryShowRepository::default());
+        let service = LocalIndexService::new(
+            Arc::new(InMemoryLibraryRepository::default()),
+            Arc::new(InMemoryFileRepository::default()),
+            movie_repo.clone(),
+            show_repo.clone(),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        (service, movie_repo, show_repo)
+    }
+
+    fn make_service_with_stream_repo(
+        stream_repo: Arc<InMemoryMediaStreamRepository>,
+    ) -> LocalIndexService {
+        LocalIndexService::new(
             Arc::new(MockLibraryRepository::new()),
             Arc::new(MockFileRepository::new()),
             Arc::new(MockMovieRepository::new()),
@@ -617,6 +1491,8 @@ mod tests {
             stream_repo,
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         )
@@ -985,6 +1861,8 @@ mod tests {
             Arc::new(mock_stream_repo),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1153,6 +2031,73 @@ mod tests {
         assert_eq!(shows.len(), 1, "show must not be duplicated");
     }
 
+    #[tokio::test]
+    async fn test_classify_episode_reuses_renamed_show_of_same_folder() {
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let show_repo = Arc::new(InMemoryShowRepository::default());
+        let service = LocalIndexService::new(
+            Arc::new(InMemoryLibraryRepository::default()),
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            show_repo.clone(),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        let lib_id = Uuid::new_v4();
+        let first = PathBuf::from("/media/The Office/Season 1/The.Office.S01E01.mkv");
+
+        let content = service
+            .classify_media_content(&first, lib_id, Duration::from_secs(1300))
+            .await
+            .unwrap();
+        file_repo
+            .create(CreateMediaFile {
+                library_id: lib_id,
+                path: first,
+                hash: 1,
+                hash_strategy: HashStrategy::Full,
+                partial_hash: None,
+                size_bytes: 1,
+                mime_type: None,
+                duration: None,
+                container_format: None,
+                content: Some(content),
+                status: FileStatus::Known,
+            })
+            .await
+            .unwrap();
+
+        // An admin renames the show; the folder keeps its old name
+        let show_id = *show_repo.shows.lock().unwrap().keys().next().unwrap();
+        show_repo
+            .update(beam_domain::models::UpdateShow {
+                id: show_id,
+                title: Some("The Office (US)".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        service
+            .classify_media_content(
+                &PathBuf::from("/media/The Office/Season 2/The.Office.S02E01.mkv"),
+                lib_id,
+                Duration::from_secs(1300),
+            )
+            .await
+            .unwrap();
+
+        let shows: Vec<_> = show_repo.shows.lock().unwrap().values().cloned().collect();
+        assert_eq!(shows.len(), 1, "show must not be duplicated");
+        assert_eq!(shows[0].title, "The Office (US)");
+        assert_eq!(show_repo.seasons.lock().unwrap().len(), 2);
+    }
+
     #[tokio::test]
     async fn test_classify_episode_new_season_created() {
         let (service, _, show_repo) = make_classify_service();
@@ -1303,7 +2248,7 @@ mod tests {
             .collect();
         assert_eq!(movies.len(), 1, "movie must not be duplicated");
 
-        // Two distinct entries should exist (one per file path)
+        // Both files are the same version, so they share one primary entry
         let entries: Vec<_> = movie_repo
             .entries
             .lock()
@@ -1311,10 +2256,83 @@ mod tests {
             .values()
             .cloned()
             .collect();
-        assert_eq!(entries.len(), 2);
-        for entry in &entries {
-            assert!(entry.is_primary);
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].is_primary);
+    }
+
+    #[tokio::test]
+    async fn test_classify_movie_versions_grouped_by_edition() {
+        let (service, movie_repo, _) = make_classify_service();
+        let lib_id = Uuid::new_v4();
+        let duration = Duration::from_secs(7200);
+
+        let mut entry_ids = Vec::new();
+        for name in [
+            "Heat (1995) - 1080p.mkv",
+            "Heat (1995) - Director's Cut - 2160p.mkv",
+            "Heat (1995) {edition-Theatrical}.mkv",
+        ] {
+            let content = service
+                .classify_media_content(&PathBuf::from("/media").join(name), lib_id, duration)
+                .await
+                .unwrap();
+            match content {
+                MediaFileContent::Movie { movie_entry_id } => entry_ids.push(movie_entry_id),
+                other => panic!("expected a movie, got {other:?}"),
+            }
         }
+
+        let movies: Vec<_> = movie_repo
+            .movies
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        assert_eq!(movies.len(), 1);
+        assert_eq!(movies[0].title, "Heat (1995)");
+
+        let entries = movie_repo.entries.lock().unwrap();
+        let versions: Vec<_> = entry_ids
+            .iter()
+            .map(|id| (entries[id].edition.clone(), entries[id].is_primary))
+            .collect();
+        assert_eq!(
+            versions,
+            vec![
+                (Some("1080p".to_string()), true),
+                (Some("Director's Cut (4K)".to_string()), false),
+                (Some("Theatrical".to_string()), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_movie_version() {
+        assert_eq!(parse_movie_version("Avatar"), ("Avatar".to_string(), None));
+        assert_eq!(
+            parse_movie_version("Blade Runner (1982) [Final Cut] [2160p]"),
+            (
+                "Blade Runner (1982)".to_string(),
+                Some("Final Cut (4K)".to_string())
+            )
+        );
+        assert_eq!(
+            parse_movie_version("Aliens.1986.Extended.Edition.720p"),
+            (
+                "Aliens.1986.Extended.Edition".to_string(),
+                Some("720p".to_string())
+            )
+        );
+        // Edition words that are part of the title are kept
+        assert_eq!(
+            parse_movie_version("Uncut Gems (2019)"),
+            ("Uncut Gems (2019)".to_string(), None)
+        );
+        assert_eq!(
+            parse_movie_version("1080p"),
+            ("1080p".to_string(), Some("1080p".to_string()))
+        );
     }
 
     // ─── classify_media_content: edge cases ───────────────────────────────────
@@ -1367,43 +2385,360 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_process_file_movie_success() {
-        let mock_library_repo = MockLibraryRepository::new();
-        let mut mock_file_repo = MockFileRepository::new();
-        let mut mock_movie_repo = MockMovieRepository::new();
-        let mock_show_repo = MockShowRepository::new();
-        let mut mock_stream_repo = MockMediaStreamRepository::new();
-        let mut mock_hash_service = MockHashService::new();
-        let mut mock_media_info_service = MockMediaInfoService::new();
+    async fn test_classify_movie_applies_nfo() {
+        let (service, movie_repo, _) = make_classify_service();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("amelie.2001.mkv");
+        std::fs::write(
+            path.with_extension("nfo"),
+            r#"<movie>
+  <title>Amélie</title>
+  <plot>A shy waitress decides to change the lives of those around her.</plot>
+  <year>2001</year>
+  <genre>Comedy</genre>
+  <genre>Romance</genre>
+  <ratings><rating name="imdb"><value>8.3</value></rating></ratings>
+  <uniqueid type="imdb">tt0211915</uniqueid>
+  <uniqueid type="tmdb">194</uniqueid>
+</movie>"#,
+        )
+        .unwrap();
 
-        let temp_dir = TempDir::new().unwrap();
-        let path = temp_dir.path().join("movies/Avatar.mp4");
-        let lib_id = Uuid::new_v4();
+        service
+            .classify_media_content(&path, Uuid::new_v4(), Duration::from_secs(7200))
+            .await
+            .unwrap();
 
-        mock_media_info_service
-            .expect_get_video_metadata()
-            .times(1)
-            .returning(|_| {
-                Ok(VideoFileMetadata {
-                    file_path: PathBuf::from("test"),
-                    metadata: Default::default(),
-                    best_video_stream: Some(0),
-                    best_audio_stream: Some(1),
-                    best_subtitle_stream: None,
-                    duration: 1000000,
-                    streams: vec![],
-                    format_name: "mp4".to_string(),
-                    format_long_name: "MPEG-4".to_string(),
-                    file_size: 1024,
-                    bit_rate: 1000,
-                    probe_score: 100,
-                })
+        let movies: Vec<_> = movie_repo
+            .movies
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        assert_eq!(movies.len(), 1);
+        let movie = &movies[0];
+        assert_eq!(movie.title, "Amélie");
+        assert_eq!(
+            movie.description.as_deref(),
+            Some("A shy waitress decides to change the lives of those around her.")
+        );
+        assert_eq!(movie.year, Some(2001));
+        assert_eq!(movie.rating_imdb, Some(8.3));
+        assert_eq!(movie.imdb_id.as_deref(), Some("tt0211915"));
+        assert_eq!(movie.tmdb_id, Some(194));
+        assert_eq!(
+            movie_repo.find_genres(movie.id).await.unwrap(),
+            vec!["Comedy", "Romance"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classify_movie_nfo_skips_locked_fields() {
+        let (service, movie_repo, _) = make_classify_service();
+        let movie = movie_repo
+            .create(beam_domain::models::CreateMovie {
+                title: "Heat".to_string(),
+                runtime: None,
+            })
+            .await
+            .unwrap();
+        movie_repo
+            .update(beam_domain::models::UpdateMovie {
+                id: movie.id,
+                description: Some("Edited by hand".to_string()),
+                locked_fields: Some(vec!["description".to_string()]),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Heat.mkv");
+        std::fs::write(
+            path.with_extension("nfo"),
+            "<movie><title>Heat</title><plot>From the NFO</plot><year>1995</year></movie>",
+        )
+        .unwrap();
+
+        service
+            .classify_media_content(&path, Uuid::new_v4(), Duration::from_secs(10200))
+            .await
+            .unwrap();
+
+        let movie = movie_repo.find_by_id(movie.id).await.unwrap().unwrap();
+        assert_eq!(movie.description.as_deref(), Some("Edited by hand"));
+        assert_eq!(movie.year, Some(1995));
+    }
+
+    #[tokio::test]
+    async fn test_classify_episode_applies_show_and_episode_nfo() {
+        let (service, _, show_repo) = make_classify_service();
+        let dir = TempDir::new().unwrap();
+        let season_dir = dir.path().join("firefly").join("Season 1");
+        std::fs::create_dir_all(&season_dir).unwrap();
+        std::fs::write(
+            dir.path().join("firefly").join("tvshow.nfo"),
+            "<tvshow><title>Firefly</title><year>2002</year><genre>Sci-Fi</genre>\
+             <uniqueid type=\"tvdb\">78874</uniqueid></tvshow>",
+        )
+        .unwrap();
+        let path = season_dir.join("Firefly.S01E01.mkv");
+        std::fs::write(
+            path.with_extension("nfo"),
+            "<episodedetails><title>Serenity</title><aired>2002-12-20</aired></episodedetails>",
+        )
+        .unwrap();
+
+        service
+            .classify_media_content(&path, Uuid::new_v4(), Duration::from_secs(5400))
+            .await
+            .unwrap();
+
+        let shows: Vec<_> = show_repo.shows.lock().unwrap().values().cloned().collect();
+        assert_eq!(shows.len(), 1);
+        assert_eq!(shows[0].title, "Firefly");
+        assert_eq!(shows[0].year, Some(2002));
+        assert_eq!(shows[0].tvdb_id, Some(78874));
+        assert_eq!(
+            show_repo.find_genres(shows[0].id).await.unwrap(),
+            vec!["Sci-Fi"]
+        );
+
+        let episodes: Vec<_> = show_repo
+            .episodes
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].title, "Serenity");
+        assert_eq!(episodes[0].air_date.as_deref(), Some("2002-12-20"));
+    }
+
+    #[tokio::test]
+    async fn test_classify_episodes_sharing_nfo_with_original_title_reuse_show() {
+        let (service, _, show_repo) = make_classify_service();
+        let dir = TempDir::new().unwrap();
+        let show_dir = dir.path().join("Haus des Geldes");
+        std::fs::create_dir_all(&show_dir).unwrap();
+        std::fs::write(
+            show_dir.join("tvshow.nfo"),
+            "<tvshow><title>Money Heist</title>\
+             <originaltitle>La casa de papel</originaltitle></tvshow>",
+        )
+        .unwrap();
+
+        for name in ["La.Casa.De.Papel.S01E01.mkv", "La.Casa.De.Papel.S01E02.mkv"] {
+            service
+                .classify_media_content(
+                    &show_dir.join(name),
+                    Uuid::new_v4(),
+                    Duration::from_secs(3000),
+                )
+                .await
+                .unwrap();
+        }
+
+        let shows: Vec<_> = show_repo.shows.lock().unwrap().values().cloned().collect();
+        assert_eq!(shows.len(), 1, "show must not be duplicated");
+        assert_eq!(shows[0].title, "La casa de papel");
+        assert_eq!(shows[0].title_localized.as_deref(), Some("Money Heist"));
+    }
+
+    #[tokio::test]
+    async fn test_classify_episode_exports_show_nfo_to_show_root() {
+        let show_repo = Arc::new(InMemoryShowRepository::default());
+        let service = LocalIndexService::new(
+            Arc::new(InMemoryLibraryRepository::default()),
+            Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryMovieRepository::default()),
+            show_repo.clone(),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::new(true)),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        let dir = TempDir::new().unwrap();
+        let show_dir = dir.path().join("Firefly");
+        for season in ["Season 1", "Season 2"] {
+            let season_dir = show_dir.join(season);
+            std::fs::create_dir_all(&season_dir).unwrap();
+            service
+                .classify_media_content(
+                    &season_dir.join("Firefly.S01E01.mkv"),
+                    Uuid::new_v4(),
+                    Duration::from_secs(3000),
+                )
+                .await
+                .unwrap();
+        }
+
+        let nfo = std::fs::read_to_string(show_dir.join("tvshow.nfo")).unwrap();
+        assert!(nfo.contains("<title>Firefly</title>"));
+        assert!(!show_dir.join("Season 1").join("tvshow.nfo").exists());
+        assert!(!show_dir.join("Season 2").join("tvshow.nfo").exists());
+        assert_eq!(show_repo.shows.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_classify_movie_exports_nfo_when_enabled() {
+        let movie_repo = Arc::new(InMemoryMovieRepository::default());
+        let service = LocalIndexService::new(
+            Arc::new(InMemoryLibraryRepository::default()),
+            Arc::new(InMemoryFileRepository::default()),
+            movie_repo.clone(),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::new(true)),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("Heat.mkv");
+
+        service
+            .classify_media_content(&path, Uuid::new_v4(), Duration::from_secs(10200))
+            .await
+            .unwrap();
+
+        let nfo = std::fs::read_to_string(path.with_extension("nfo")).unwrap();
+        assert!(nfo.contains("<movie>"));
+        assert!(nfo.contains("<title>Heat</title>"));
+        assert!(nfo.contains("<runtime>170</runtime>"));
+    }
+
+    #[tokio::test]
+    async fn test_classify_fills_missing_artwork() {
+        let movie_repo = Arc::new(InMemoryMovieRepository::default());
+        let show_repo = Arc::new(InMemoryShowRepository::default());
+        let mut mock_artwork = MockArtworkService::new();
+        mock_artwork.expect_movie_artwork().times(1).returning(|_| {
+            Ok(Artwork {
+                poster_url: Some("/v1/images/00000000000000aa".to_string()),
+                backdrop_url: Some("/v1/images/00000000000000bb".to_string()),
+            })
+        });
+        mock_artwork
+            .expect_show_artwork()
+            .returning(|_| Ok(Artwork::default()));
+        mock_artwork
+            .expect_episode_thumbnail()
+            .times(1)
+            .returning(|_| Ok(Some("/v1/images/00000000000000cc".to_string())));
+        let service = LocalIndexService::new(
+            Arc::new(InMemoryLibraryRepository::default()),
+            Arc::new(InMemoryFileRepository::default()),
+            movie_repo.clone(),
+            show_repo.clone(),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(mock_artwork),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        let dir = TempDir::new().unwrap();
+
+        service
+            .classify_media_content(
+                &dir.path().join("Heat.mkv"),
+                Uuid::new_v4(),
+                Duration::from_secs(10200),
+            )
+            .await
+            .unwrap();
+        service
+            .classify_media_content(
+                &dir.path().join("Firefly.S01E01.mkv"),
+                Uuid::new_v4(),
+                Duration::from_secs(2600),
+            )
+            .await
+            .unwrap();
+
+        let movie = movie_repo
+            .movies
+            .lock()
+            .unwrap()
+            .values()
+            .next()
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            movie.poster_url.as_deref(),
+            Some("/v1/images/00000000000000aa")
+        );
+        assert_eq!(
+            movie.backdrop_url.as_deref(),
+            Some("/v1/images/00000000000000bb")
+        );
+        let episode = show_repo
+            .episodes
+            .lock()
+            .unwrap()
+            .values()
+            .next()
+            .cloned()
+            .unwrap();
+        assert_eq!(
+            episode.thumbnail_url.as_deref(),
+            Some("/v1/images/00000000000000cc")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_process_file_movie_success() {
+        let mock_library_repo = MockLibraryRepository::new();
+        let mut mock_file_repo = MockFileRepository::new();
+        let mut mock_movie_repo = MockMovieRepository::new();
+        let mock_show_repo = MockShowRepository::new();
+        let mut mock_stream_repo = MockMediaStreamRepository::new();
+        let mut mock_hash_service = MockHashService::new();
+        let mut mock_media_info_service = MockMediaInfoService::new();
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("movies/Avatar.mp4");
+        let lib_id = Uuid::new_v4();
+
+        mock_media_info_service
+            .expect_get_video_metadata()
+            .times(1)
+            .returning(|_| {
+                Ok(VideoFileMetadata {
+                    file_path: PathBuf::from("test"),
+                    metadata: Default::default(),
+                    best_video_stream: Some(0),
+                    best_audio_stream: Some(1),
+                    best_subtitle_stream: None,
+                    duration: 1000000,
+                    streams: vec![],
+                    format_name: "mp4".to_string(),
+                    format_long_name: "MPEG-4".to_string(),
+                    file_size: 1024,
+                    bit_rate: 1000,
+                    probe_score: 100,
+                })
             });
 
         mock_hash_service
             .expect_hash_async()
             .times(1)
             .returning(|_| Ok(12345));
+        mock_hash_service
+            .expect_partial_hash_async()
+            .times(1)
+            .returning(|_| Ok(1));
+        mock_hash_service
+            .expect_strategy()
+            .return_const(HashStrategy::Full);
 
         let movie_id = Uuid::new_v4();
         mock_movie_repo
@@ -1429,6 +2764,7 @@ mod tests {
                     tvdb_id: None,
                     rating_tmdb: None,
                     rating_imdb: None,
+                    locked_fields: vec![],
                     created_at: chrono::Utc::now(),
                     updated_at: chrono::Utc::now(),
                 })
@@ -1437,6 +2773,10 @@ mod tests {
             .expect_ensure_library_association()
             .times(1)
             .returning(|_, _| Ok(()));
+        mock_movie_repo
+            .expect_find_entries_by_movie_id()
+            .times(1)
+            .returning(|_| Ok(vec![]));
 
         let entry_id = Uuid::new_v4();
         mock_movie_repo
@@ -1460,6 +2800,8 @@ mod tests {
                 library_id: Uuid::new_v4(),
                 path: PathBuf::from("test"),
                 hash: 12345,
+                hash_strategy: HashStrategy::Full,
+                partial_hash: None,
                 size_bytes: 1024,
                 mime_type: Some("video/mp4".to_string()),
                 duration: None,
@@ -1468,7 +2810,9 @@ mod tests {
                     movie_entry_id: entry_id,
                 }),
                 status: FileStatus::Known,
+                is_primary: true,
                 scanned_at: chrono::Utc::now(),
+                verified_at: None,
                 updated_at: chrono::Utc::now(),
             })
         });
@@ -1486,6 +2830,8 @@ mod tests {
             Arc::new(mock_stream_repo),
             Arc::new(mock_hash_service),
             Arc::new(mock_media_info_service),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1535,16 +2881,28 @@ mod tests {
             .expect_hash_async()
             .times(1)
             .returning(|_| Ok(67890));
+        mock_hash_service
+            .expect_partial_hash_async()
+            .times(1)
+            .returning(|_| Ok(1));
+        mock_hash_service
+            .expect_strategy()
+            .return_const(HashStrategy::Full);
 
         let show_id = Uuid::new_v4();
+        mock_file_repo
+            .expect_find_episode_file_in_dir()
+            .times(1)
+            .returning(|_| Ok(None));
         mock_show_repo
             .expect_find_by_title()
+            .withf(|title| title == "The Show")
             .times(1)
             .returning(|_| Ok(None));
         mock_show_repo.expect_create().times(1).returning(move |_| {
             Ok(beam_domain::models::Show {
                 id: show_id,
-                title: "Season 1".to_string(),
+                title: "The Show".to_string(),
                 title_localized: None,
                 description: None,
                 year: None,
@@ -1553,6 +2911,7 @@ mod tests {
                 tmdb_id: None,
                 imdb_id: None,
                 tvdb_id: None,
+                locked_fields: vec![],
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
             })
@@ -1574,6 +2933,7 @@ mod tests {
                     poster_url: None,
                     first_aired: None,
                     last_aired: None,
+                    locked_fields: vec![],
                 })
             });
 
@@ -1591,6 +2951,7 @@ mod tests {
                     air_date: None,
                     runtime: None,
                     thumbnail_url: None,
+                    locked_fields: vec![],
                     created_at: chrono::Utc::now(),
                 })
             });
@@ -1602,13 +2963,17 @@ mod tests {
                 library_id: Uuid::new_v4(),
                 path: PathBuf::from("test"),
                 hash: 67890,
+                hash_strategy: HashStrategy::Full,
+                partial_hash: None,
                 size_bytes: 500 * 1024 * 1024,
                 mime_type: Some("video/x-matroska".to_string()),
                 duration: None,
                 container_format: None,
                 content: Some(beam_domain::models::MediaFileContent::Episode { episode_id }),
                 status: FileStatus::Known,
+                is_primary: true,
                 scanned_at: chrono::Utc::now(),
+                verified_at: None,
                 updated_at: chrono::Utc::now(),
             })
         });
@@ -1626,6 +2991,8 @@ mod tests {
             Arc::new(mock_stream_repo),
             Arc::new(mock_hash_service),
             Arc::new(mock_media_info_service),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1685,6 +3052,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1711,6 +3080,11 @@ mod tests {
             .expect_hash_async()
             .times(1)
             .returning(|_| Ok(12345));
+        mock_hash
+            .expect_partial_hash_async()
+            .times(1)
+            .returning(|_| Ok(1));
+        mock_hash.expect_strategy().return_const(HashStrategy::Full);
 
         let mut mock_media_info = MockMediaInfoService::new();
         mock_media_info
@@ -1726,6 +3100,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(mock_hash),
             Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1756,6 +3132,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1784,6 +3162,11 @@ mod tests {
             .expect_hash_async()
             .times(3)
             .returning(|_| Ok(99999));
+        mock_hash
+            .expect_partial_hash_async()
+            .times(3)
+            .returning(|_| Ok(1));
+        mock_hash.expect_strategy().return_const(HashStrategy::Full);
 
         let mut mock_media_info = MockMediaInfoService::new();
         mock_media_info
@@ -1799,6 +3182,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(mock_hash),
             Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1810,56 +3195,215 @@ mod tests {
         assert_eq!(files.len(), 3);
     }
 
-    #[tokio::test]
-    async fn test_scan_library_changed_file() {
-        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
-        let file_repo = Arc::new(InMemoryFileRepository::default());
-        let dir = TempDir::new().unwrap();
-        let library = make_library_in_tempdir(&lib_repo, &dir).await;
-
-        // Create a real file on disk (16 bytes)
-        let file_path = dir.path().join("movie.mp4");
-        std::fs::write(&file_path, b"new content size").unwrap();
+    /// Seed a known video record at `path` with one stale stream, scanned at `scanned_at`
+    fn seed_changed_video(
+        file_repo: &InMemoryFileRepository,
+        stream_repo: &InMemoryMediaStreamRepository,
+        library_id: Uuid,
+        path: PathBuf,
+        size_bytes: u64,
+        scanned_at: chrono::DateTime<chrono::Utc>,
+    ) -> MediaFile {
+        use beam_domain::models::{
+            MediaStream, StreamMetadata, StreamType, SubtitleStreamMetadata,
+        };
 
-        // Seed the file repo with the same path but a different size
         let existing = MediaFile {
             id: Uuid::new_v4(),
-            library_id: library.id,
-            path: file_path.clone(),
+            library_id,
+            path,
             hash: 12345,
-            size_bytes: 999, // deliberately wrong size
+            hash_strategy: HashStrategy::Full,
+            partial_hash: None,
+            size_bytes,
             mime_type: Some("video/mp4".to_string()),
             duration: None,
             container_format: None,
             content: None,
             status: FileStatus::Known,
-            scanned_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
+            is_primary: true,
+            scanned_at,
+            verified_at: None,
+            updated_at: scanned_at,
         };
         file_repo
             .files
             .lock()
             .unwrap()
             .insert(existing.id, existing.clone());
+        stream_repo.streams.lock().unwrap().insert(
+            existing.id,
+            vec![MediaStream {
+                id: Uuid::new_v4(),
+                file_id: existing.id,
+                index: 0,
+                stream_type: StreamType::Subtitle,
+                codec: "subrip".to_string(),
+                metadata: StreamMetadata::Subtitle(SubtitleStreamMetadata {
+                    language: None,
+                    title: None,
+                    is_default: false,
+                    is_forced: false,
+                }),
+            }],
+        );
+        existing
+    }
 
-        let service = LocalIndexService::new(
-            lib_repo.clone(),
-            file_repo.clone(),
+    fn make_reprocess_service(
+        lib_repo: Arc<InMemoryLibraryRepository>,
+        file_repo: Arc<InMemoryFileRepository>,
+        stream_repo: Arc<InMemoryMediaStreamRepository>,
+    ) -> LocalIndexService {
+        let mut mock_hash = MockHashService::new();
+        mock_hash
+            .expect_hash_async()
+            .times(1)
+            .returning(|_| Ok(777));
+        mock_hash
+            .expect_partial_hash_async()
+            .times(1)
+            .returning(|_| Ok(7));
+        mock_hash.expect_strategy().return_const(HashStrategy::Full);
+
+        let mut mock_media_info = MockMediaInfoService::new();
+        mock_media_info
+            .expect_get_video_metadata()
+            .times(1)
+            .returning(|_| {
+                Ok(VideoFileMetadata {
+                    file_size: 16,
+                    format_name: "matroska".to_string(),
+                    ..make_video_metadata()
+                })
+            });
+
+        LocalIndexService::new(
+            lib_repo,
+            file_repo,
             Arc::new(InMemoryMovieRepository::default()),
             Arc::new(InMemoryShowRepository::default()),
-            Arc::new(InMemoryMediaStreamRepository::default()),
-            Arc::new(MockHashService::new()),
-            Arc::new(MockMediaInfoService::new()),
+            stream_repo,
+            Arc::new(mock_hash),
+            Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_reprocesses_changed_file() {
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let stream_repo = Arc::new(InMemoryMediaStreamRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        // Create a real file on disk (16 bytes)
+        let file_path = dir.path().join("movie.mp4");
+        std::fs::write(&file_path, b"new content size").unwrap();
+
+        // Seed the file repo with the same path but a different size
+        let existing = seed_changed_video(
+            &file_repo,
+            &stream_repo,
+            library.id,
+            file_path.clone(),
+            999, // deliberately wrong size
+            chrono::Utc::now(),
         );
 
+        let service = make_reprocess_service(lib_repo, file_repo.clone(), stream_repo.clone());
         let result = service.scan_library(library.id.to_string()).await;
         assert_eq!(result.unwrap(), 0); // no new files added
 
         let files = file_repo.find_all_by_library(library.id).await.unwrap();
         assert_eq!(files.len(), 1);
-        assert_eq!(files[0].status, FileStatus::Changed);
+        assert_eq!(files[0].id, existing.id);
+        assert_eq!(files[0].status, FileStatus::Known);
+        assert_eq!(files[0].hash, 777);
+        assert_eq!(files[0].partial_hash, Some(7));
+        assert_eq!(files[0].size_bytes, 16);
+        assert_eq!(files[0].container_format.as_deref(), Some("matroska"));
+        assert!(files[0].scanned_at > existing.scanned_at);
+        // The stale stream is replaced by the (empty) re-probed set
+        assert!(
+            stream_repo
+                .find_by_file_id(existing.id)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_reprocesses_same_size_modified_file() {
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let stream_repo = Arc::new(InMemoryMediaStreamRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let file_path = dir.path().join("movie.mkv");
+        std::fs::write(&file_path, b"new content size").unwrap();
+
+        // Same size, but last probed before the file was written
+        let existing = seed_changed_video(
+            &file_repo,
+            &stream_repo,
+            library.id,
+            file_path,
+            16,
+            chrono::Utc::now() - chrono::Duration::hours(1),
+        );
+
+        let service = make_reprocess_service(lib_repo, file_repo.clone(), stream_repo);
+        service.scan_library(library.id.to_string()).await.unwrap();
+
+        let files = file_repo.find_all_by_library(library.id).await.unwrap();
+        assert_eq!(files[0].hash, 777);
+        assert!(files[0].scanned_at > existing.scanned_at);
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_unchanged_file_not_reprocessed() {
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let stream_repo = Arc::new(InMemoryMediaStreamRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let file_path = dir.path().join("movie.mkv");
+        std::fs::write(&file_path, b"new content size").unwrap();
+        let existing = seed_changed_video(
+            &file_repo,
+            &stream_repo,
+            library.id,
+            file_path,
+            16,
+            chrono::Utc::now() + chrono::Duration::hours(1),
+        );
+
+        // Mocks without expectations: any probe or hash would panic
+        let service = LocalIndexService::new(
+            lib_repo,
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            stream_repo,
+            Arc::new(MockHashService::new()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+        service.scan_library(library.id.to_string()).await.unwrap();
+
+        let files = file_repo.find_all_by_library(library.id).await.unwrap();
+        assert_eq!(files[0].hash, existing.hash);
     }
 
     #[tokio::test]
@@ -1875,13 +3419,17 @@ mod tests {
             library_id: library.id,
             path: dir.path().join("ghost.mp4"),
             hash: 0,
+            hash_strategy: HashStrategy::Full,
+            partial_hash: None,
             size_bytes: 1024,
             mime_type: None,
             duration: None,
             container_format: None,
             content: None,
             status: FileStatus::Known,
+            is_primary: true,
             scanned_at: chrono::Utc::now(),
+            verified_at: None,
             updated_at: chrono::Utc::now(),
         };
         file_repo
@@ -1898,6 +3446,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -1910,6 +3460,378 @@ mod tests {
         assert!(files.is_empty());
     }
 
+    /// Seed a hashed video record at `path` whose content matches `bytes`
+    fn seed_hashed_file(
+        file_repo: &InMemoryFileRepository,
+        library_id: Uuid,
+        path: PathBuf,
+        bytes: &[u8],
+    ) -> MediaFile {
+        let file = MediaFile {
+            id: Uuid::new_v4(),
+            library_id,
+            path,
+            hash: xxhash_rust::xxh3::xxh3_64(bytes),
+            hash_strategy: HashStrategy::Full,
+            partial_hash: None,
+            size_bytes: bytes.len() as u64,
+            mime_type: Some("video/matroska".to_string()),
+            duration: None,
+            container_format: None,
+            content: None,
+            status: FileStatus::Known,
+            is_primary: true,
+            scanned_at: chrono::Utc::now(),
+            verified_at: None,
+            updated_at: chrono::Utc::now(),
+        };
+        file_repo
+            .files
+            .lock()
+            .unwrap()
+            .insert(file.id, file.clone());
+        file
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_detects_moved_file() {
+        use crate::services::hash::LocalHashService;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let notifications = Arc::new(InMemoryNotificationService::new());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let bytes = b"not really a movie";
+        let original = seed_hashed_file(
+            &file_repo,
+            library.id,
+            dir.path().join("Movies/Heat.mkv"),
+            bytes,
+        );
+        let new_path = dir.path().join("Renamed/Heat (1995).mkv");
+        std::fs::create_dir_all(new_path.parent().unwrap()).unwrap();
+        std::fs::write(&new_path, bytes).unwrap();
+
+        // Media info is never probed: the file is recognised, not re-added
+        let service = LocalIndexService::new(
+            lib_repo.clone(),
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            notifications.clone(),
+            Arc::new(NoOpAdminLogService),
+        );
+
+        let added = service.scan_library(library.id.to_string()).await.unwrap();
+        assert_eq!(added, 0);
+
+        let files = file_repo.find_all_by_library(library.id).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].id, original.id);
+        assert_eq!(files[0].path, new_path);
+        assert!(files[0].partial_hash.is_some());
+        assert!(
+            notifications
+                .published_events()
+                .iter()
+                .any(|e| e.message.starts_with("File moved"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scan_library_same_size_different_content_is_not_a_move() {
+        use crate::services::hash::LocalHashService;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let original =
+            seed_hashed_file(&file_repo, library.id, dir.path().join("old.txt"), b"aaaa");
+        let new_path = dir.path().join("new.txt");
+        std::fs::write(&new_path, b"bbbb").unwrap();
+
+        let service = LocalIndexService::new(
+            lib_repo.clone(),
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+
+        let added = service.scan_library(library.id.to_string()).await.unwrap();
+        assert_eq!(added, 1);
+
+        let files = file_repo.find_all_by_library(library.id).await.unwrap();
+        assert_eq!(files.len(), 1);
+        assert_ne!(files[0].id, original.id);
+        assert_eq!(files[0].path, new_path);
+    }
+
+    #[tokio::test]
+    async fn test_verify_file_hash_upgrades_sampled_hash() {
+        use crate::services::hash::LocalHashService;
+        use crate::utils::hash::compute_hash_with;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let bytes = b"sampled movie";
+        let path = dir.path().join("movie.mkv");
+        std::fs::write(&path, bytes).unwrap();
+        let sampled = HashStrategy::Sampled { chunks: 4 };
+        let mut file = seed_hashed_file(&file_repo, library.id, path.clone(), bytes);
+        file.hash = compute_hash_with(&path, sampled).unwrap();
+        file.hash_strategy = sampled;
+        file_repo
+            .files
+            .lock()
+            .unwrap()
+            .insert(file.id, file.clone());
+
+        let service = LocalIndexService::new(
+            lib_repo,
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+
+        let check = service.verify_file_hash(&file, false).await.unwrap();
+        assert_eq!(check, HashCheck::Match);
+        let stored = file_repo.find_by_id(file.id).await.unwrap().unwrap();
+        assert_eq!(stored.hash_strategy, sampled);
+
+        let check = service.verify_file_hash(&file, true).await.unwrap();
+        let full = xxhash_rust::xxh3::xxh3_64(bytes);
+        assert_eq!(check, HashCheck::Upgraded { hash: full });
+        let stored = file_repo.find_by_id(file.id).await.unwrap().unwrap();
+        assert_eq!(stored.hash_strategy, HashStrategy::Full);
+        assert_eq!(stored.hash, full);
+
+        std::fs::write(&path, b"corrupted movie").unwrap();
+        let check = service.verify_file_hash(&stored, true).await.unwrap();
+        assert!(matches!(check, HashCheck::Mismatch { expected, .. } if expected == full));
+    }
+
+    #[tokio::test]
+    async fn test_verify_library_reports_problems() {
+        use crate::services::hash::LocalHashService;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let notifications = Arc::new(InMemoryNotificationService::new());
+        let admin_log_repo = Arc::new(InMemoryAdminLogRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let intact_path = dir.path().join("intact.mkv");
+        std::fs::write(&intact_path, b"intact").unwrap();
+        let intact = seed_hashed_file(&file_repo, library.id, intact_path, b"intact");
+        let rotten_path = dir.path().join("rotten.mkv");
+        std::fs::write(&rotten_path, b"rotted").unwrap();
+        let rotten = seed_hashed_file(&file_repo, library.id, rotten_path, b"pristine");
+        let missing = seed_hashed_file(
+            &file_repo,
+            library.id,
+            dir.path().join("missing.mkv"),
+            b"gone",
+        );
+
+        let service = LocalIndexService::new(
+            lib_repo,
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            notifications.clone(),
+            Arc::new(LocalAdminLogService::new(
+                admin_log_repo.clone() as Arc<dyn AdminLogRepository>
+            )),
+        );
+
+        let report = service
+            .run_verification(library, VerifyOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            VerifyReport {
+                files_checked: 3,
+                mismatched: 1,
+                unreadable: 1,
+                ..Default::default()
+            }
+        );
+
+        let verified_at = |id| {
+            let files = file_repo.files.lock().unwrap();
+            files[&id].verified_at
+        };
+        assert!(verified_at(intact.id).is_some());
+        assert!(verified_at(rotten.id).is_none());
+        assert!(verified_at(missing.id).is_none());
+
+        let logs = admin_log_repo
+            .list(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
+        assert!(logs.iter().any(|l| {
+            l.level == AdminLogLevel::Error
+                && l.category == AdminLogCategory::Integrity
+                && l.message.starts_with("Hash mismatch")
+        }));
+        assert!(logs.iter().any(|l| {
+            l.level == AdminLogLevel::Warning
+                && l.category == AdminLogCategory::Integrity
+                && l.message.starts_with("File unreadable")
+        }));
+        assert!(notifications.published_events().iter().any(|e| {
+            matches!(e.category, EventCategory::Integrity) && e.message.contains("rotten.mkv")
+        }));
+    }
+
+    #[tokio::test]
+    async fn test_verify_library_incremental_with_decode_check() {
+        use crate::services::hash::LocalHashService;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+
+        let checked_path = dir.path().join("checked.mkv");
+        std::fs::write(&checked_path, b"checked").unwrap();
+        let mut checked = seed_hashed_file(&file_repo, library.id, checked_path, b"checked");
+        checked.verified_at = Some(chrono::Utc::now());
+        file_repo
+            .files
+            .lock()
+            .unwrap()
+            .insert(checked.id, checked.clone());
+        let fresh_path = dir.path().join("fresh.mkv");
+        std::fs::write(&fresh_path, b"fresh").unwrap();
+        let fresh = seed_hashed_file(&file_repo, library.id, fresh_path.clone(), b"fresh");
+
+        // Only the never-verified file is decoded
+        let mut mock_media_info = MockMediaInfoService::new();
+        mock_media_info
+            .expect_check_decode()
+            .withf(move |path| path == fresh_path)
+            .times(1)
+            .returning(|_| Err(MetadataError::InvalidMetadata("corrupt frame".to_string())));
+
+        let service = LocalIndexService::new(
+            lib_repo,
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(NoOpAdminLogService),
+        );
+
+        let options = VerifyOptions {
+            limit: Some(1),
+            check_decode: true,
+            ..Default::default()
+        };
+        let report = service.run_verification(library, options).await.unwrap();
+        assert_eq!(report.files_checked, 1);
+        assert_eq!(report.decode_errors, 1);
+
+        let files = file_repo.files.lock().unwrap();
+        assert!(files[&fresh.id].verified_at.is_none());
+        assert_eq!(files[&checked.id].verified_at, checked.verified_at);
+    }
+
+    #[tokio::test]
+    async fn test_verify_library_runs_in_background() {
+        use crate::services::hash::LocalHashService;
+
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let file_repo = Arc::new(InMemoryFileRepository::default());
+        let notifications = Arc::new(InMemoryNotificationService::new());
+        let dir = TempDir::new().unwrap();
+        let library = make_library_in_tempdir(&lib_repo, &dir).await;
+        let path = dir.path().join("intact.mkv");
+        std::fs::write(&path, b"intact").unwrap();
+        let intact = seed_hashed_file(&file_repo, library.id, path, b"intact");
+
+        let service = LocalIndexService::new(
+            lib_repo,
+            file_repo.clone(),
+            Arc::new(InMemoryMovieRepository::default()),
+            Arc::new(InMemoryShowRepository::default()),
+            Arc::new(InMemoryMediaStreamRepository::default()),
+            Arc::new(LocalHashService::default()),
+            Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
+            notifications.clone(),
+            Arc::new(NoOpAdminLogService),
+        );
+
+        let missing = service
+            .verify_library(Uuid::new_v4().to_string(), VerifyOptions::default())
+            .await;
+        assert!(matches!(missing, Err(IndexError::LibraryNotFound)));
+
+        service
+            .verify_library(library.id.to_string(), VerifyOptions::default())
+            .await
+            .unwrap();
+
+        // The summary event arrives once the background check finishes
+        let completed = async {
+            while !notifications
+                .published_events()
+                .iter()
+                .any(|e| e.message.starts_with("Integrity check complete"))
+            {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+        tokio::time::timeout(Duration::from_secs(5), completed)
+            .await
+            .expect("integrity check did not finish");
+        assert!(
+            file_repo.files.lock().unwrap()[&intact.id]
+                .verified_at
+                .is_some()
+        );
+    }
+
     #[tokio::test]
     async fn test_scan_library_invalid_root_path() {
         let lib_repo = Arc::new(InMemoryLibraryRepository::default());
@@ -1946,6 +3868,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             notification_svc.clone(),
             admin_log_svc,
         );
@@ -1960,7 +3884,10 @@ mod tests {
         }));
 
         // Admin log must also record an error-level LibraryScan entry
-        let logs = admin_log_repo.list(10, 0).await.unwrap();
+        let logs = admin_log_repo
+            .list(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
         assert!(logs.iter().any(|l| {
             l.level == AdminLogLevel::Error && l.category == AdminLogCategory::LibraryScan
         }));
@@ -1992,6 +3919,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -2042,6 +3971,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(mock_hash),
             Arc::new(mock_media_info),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             notification_svc.clone(),
             admin_log_svc,
         );
@@ -2058,7 +3989,10 @@ mod tests {
         }));
 
         // Admin log must also have a warning entry mentioning the failed file path
-        let logs = admin_log_repo.list(10, 0).await.unwrap();
+        let logs = admin_log_repo
+            .list(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
         let file_path_str = file_path.display().to_string();
         assert!(logs.iter().any(|l| {
             l.level == AdminLogLevel::Warning
@@ -2088,6 +4022,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(NoOpAdminLogService),
         );
@@ -2118,6 +4054,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             notification_svc.clone(),
             admin_log_svc,
         );
@@ -2133,7 +4071,10 @@ mod tests {
         }));
 
         // Admin log must have a "scan started" entry
-        let logs = admin_log_repo.list(10, 0).await.unwrap();
+        let logs = admin_log_repo
+            .list(&AdminLogFilter::default(), 10, 0)
+            .await
+            .unwrap();
         assert!(!logs.is_empty());
         assert!(logs.iter().any(|l| {
             l.level == AdminLogLevel::Info
@@ -2171,13 +4112,17 @@ mod tests {
             library_id: library.id,
             path: stays_path.clone(),
             hash: 0,
+            hash_strategy: HashStrategy::Full,
+            partial_hash: None,
             size_bytes: 5,
             mime_type: None,
             duration: None,
             container_format: None,
             content: None,
             status: FileStatus::Known,
+            is_primary: true,
             scanned_at: chrono::Utc::now(),
+            verified_at: None,
             updated_at: chrono::Utc::now(),
         };
         file_repo.files.lock().unwrap().insert(file_a.id, file_a);
@@ -2189,13 +4134,17 @@ mod tests {
             library_id: library.id,
             path: phantom_path,
             hash: 0,
+            hash_strategy: HashStrategy::Full,
+            partial_hash: None,
             size_bytes: 100,
             mime_type: None,
             duration: None,
             container_format: None,
             content: None,
             status: FileStatus::Known,
+            is_primary: true,
             scanned_at: chrono::Utc::now(),
+            verified_at: None,
             updated_at: chrono::Utc::now(),
         };
         file_repo.files.lock().unwrap().insert(file_b.id, file_b);
@@ -2212,6 +4161,8 @@ mod tests {
             Arc::new(InMemoryMediaStreamRepository::default()),
             Arc::new(MockHashService::new()),
             Arc::new(MockMediaInfoService::new()),
+            Arc::new(LocalNfoService::default()),
+            Arc::new(NoOpArtworkService),
             Arc::new(InMemoryNotificationService::new()),
             admin_log_svc,
         );
@@ -2220,7 +4171,10 @@ mod tests {
         assert_eq!(added, 1);
 
         // Admin log completion entry must record added=1, removed=1 in its JSON details
-        let logs = admin_log_repo.list(100, 0).await.unwrap();
+        let logs = admin_log_repo
+            .list(&AdminLogFilter::default(), 100, 0)
+            .await
+            .unwrap();
         let completion = logs
             .iter()
             .find(|l| l.message.contains("scan completed"))
@@ -2,7 +2,7 @@ use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
 
-use crate::utils::metadata::{MetadataError, VideoFileMetadata};
+use crate::utils::metadata::{MetadataError, VideoFileMetadata, check_decode};
 
 /// Service for extracting media information from files.
 #[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
@@ -10,6 +10,10 @@ use crate::utils::metadata::{MetadataError, VideoFileMetadata};
 pub trait MediaInfoService: Send + Sync + std::fmt::Debug {
     /// Extract video metadata from a file path
     async fn get_video_metadata(&self, path: &Path) -> Result<VideoFileMetadata, MetadataError>;
+
+    /// Decode the file's main video and audio streams, failing on the first error.
+    /// See [`check_decode`].
+    async fn check_decode(&self, path: &Path) -> Result<(), MetadataError>;
 }
 
 #[derive(Debug, Clone, Default)]
@@ -28,9 +32,13 @@ impl LocalMediaInfoService {
     }
 }
 
-#[async_trait::async_trait]
-impl MediaInfoService for LocalMediaInfoService {
-    async fn get_video_metadata(&self, path: &Path) -> Result<VideoFileMetadata, MetadataError> {
+impl LocalMediaInfoService {
+    /// Run a blocking FFmpeg task, respecting the concurrency limit
+    async fn run_blocking<T: Send + 'static>(
+        &self,
+        path: &Path,
+        task: fn(&Path) -> Result<T, MetadataError>,
+    ) -> Result<T, MetadataError> {
         let _permit = if let Some(sem) = &self.semaphore {
             Some(sem.acquire().await.map_err(|e| {
                 MetadataError::UnknownError(format!("Failed to acquire semaphore: {e}"))
@@ -40,8 +48,19 @@ impl MediaInfoService for LocalMediaInfoService {
         };
 
         let path_buf = path.to_path_buf();
-        tokio::task::spawn_blocking(move || VideoFileMetadata::from_path(&path_buf))
+        tokio::task::spawn_blocking(move || task(&path_buf))
             .await
             .map_err(|e| MetadataError::UnknownError(format!("Blocking task join error: {e}")))?
     }
 }
+
+#[async_trait::async_trait]
+impl MediaInfoService for LocalMediaInfoService {
+    async fn get_video_metadata(&self, path: &Path) -> Result<VideoFileMetadata, MetadataError> {
+        self.run_blocking(path, VideoFileMetadata::from_path).await
+    }
+
+    async fn check_decode(&self, path: &Path) -> Result<(), MetadataError> {
+        self.run_blocking(path, check_decode).await
+    }
+}
@@ -1,15 +1,19 @@
 pub mod admin_log;
+pub mod artwork;
 pub mod hash;
 pub mod index;
 pub mod media_info;
+pub mod nfo;
 pub mod notification;
 
 pub use admin_log::{AdminLogService, LocalAdminLogService, NoOpAdminLogService};
+pub use artwork::{ArtworkService, LocalArtworkService, NoOpArtworkService};
 pub use hash::{HashConfig, HashService, LocalHashService};
 #[cfg(any(test, feature = "test-utils"))]
 pub use index::MockIndexService;
 pub use index::{IndexError, IndexService, LocalIndexService};
 pub use media_info::{LocalMediaInfoService, MediaInfoService};
+pub use nfo::{LocalNfoService, NfoService};
 pub use notification::{
     AdminEvent, EventCategory, EventLevel, InMemoryNotificationService, LocalNotificationService,
     NotificationService,
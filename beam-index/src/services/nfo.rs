@@ -0,0 +1,775 @@
+//! Kodi-style `.nfo` metadata sidecars.
+//!
+//! Supports the `<movie>`, `<tvshow>` and `<episodedetails>` documents written by
+//! Kodi, Jellyfin and tinyMediaManager so libraries can be described fully offline.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Datelike, NaiveDate};
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use serde::Deserialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+use beam_domain::models::{Episode, Movie, Show, UpdateEpisode, UpdateMovie, UpdateShow};
+
+use crate::services::index::KNOWN_VIDEO_EXTENSIONS;
+
+/// File name Kodi uses for the show-level NFO in the show's root directory
+pub const TVSHOW_NFO: &str = "tvshow.nfo";
+/// Directory-level fallback NFO for movies stored one per folder
+pub const MOVIE_NFO: &str = "movie.nfo";
+
+#[derive(Debug, Error)]
+pub enum NfoError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid NFO document {0}: {1}")]
+    Parse(PathBuf, String),
+}
+
+/// The kind of item an NFO document describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NfoKind {
+    Movie,
+    Show,
+    Episode,
+}
+
+impl NfoKind {
+    /// Root element of the XML document
+    fn root_element(&self) -> &'static str {
+        match self {
+            NfoKind::Movie => "movie",
+            NfoKind::Show => "tvshow",
+            NfoKind::Episode => "episodedetails",
+        }
+    }
+
+    /// Candidate NFO paths for a media path, in order of preference.
+    ///
+    /// For movies and episodes `media_path` is the video file; for shows it is the
+    /// show directory. `movie.nfo` only counts when the movie is the folder's only
+    /// video, since it can't say which of several movies it describes.
+    async fn candidates(&self, media_path: &Path) -> std::io::Result<Vec<PathBuf>> {
+        Ok(match self {
+            NfoKind::Movie => {
+                let mut paths = vec![media_path.with_extension("nfo")];
+                if let Some(dir) = media_path.parent()
+                    && holds_single_video(dir).await?
+                {
+                    paths.push(dir.join(MOVIE_NFO));
+                }
+                paths
+            }
+            NfoKind::Show => vec![media_path.join(TVSHOW_NFO)],
+            NfoKind::Episode => vec![media_path.with_extension("nfo")],
+        })
+    }
+}
+
+/// Whether `dir` contains exactly one video file
+async fn holds_single_video(dir: &Path) -> std::io::Result<bool> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let mut videos = 0;
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        let is_video = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| KNOWN_VIDEO_EXTENSIONS.contains(&ext.to_lowercase().as_str()));
+        if is_video && entry.file_type().await?.is_file() {
+            videos += 1;
+            if videos > 1 {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(videos == 1)
+}
+
+/// Name of the first element of an XML document, if it has one
+fn document_root(xml: &str) -> Result<Option<String>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => {
+                return Ok(Some(
+                    String::from_utf8_lossy(e.name().as_ref()).into_owned(),
+                ));
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// Metadata carried by an NFO document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NfoMetadata {
+    pub title: Option<String>,
+    pub original_title: Option<String>,
+    pub plot: Option<String>,
+    pub year: Option<u32>,
+    /// Release date for movies, first air date for shows and episodes
+    pub premiered: Option<NaiveDate>,
+    pub runtime: Option<Duration>,
+    pub genres: Vec<String>,
+    pub rating_tmdb: Option<f32>,
+    pub rating_imdb: Option<f32>,
+    pub tmdb_id: Option<u32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+}
+
+impl NfoMetadata {
+    /// Parse an NFO document describing a `kind` item. Unknown elements are ignored,
+    /// but a document for another kind of item, e.g. a `<tvshow>` read as a movie, is
+    /// rejected.
+    pub fn parse(kind: NfoKind, xml: &str) -> Result<Self, quick_xml::DeError> {
+        if let Some(root) = document_root(xml)?
+            && root != kind.root_element()
+        {
+            return Err(quick_xml::DeError::Custom(format!(
+                "expected <{}> but found <{root}>",
+                kind.root_element()
+            )));
+        }
+        let doc: NfoDocument = quick_xml::de::from_str(xml)?;
+        Ok(doc.into())
+    }
+
+    /// Changes to apply to a movie. Kodi's `originaltitle` is the untranslated title,
+    /// so when it differs the NFO title is kept as the localized title.
+    pub fn movie_update(&self, id: Uuid) -> UpdateMovie {
+        let (title, title_localized) = self.split_titles();
+        UpdateMovie {
+            id,
+            title,
+            title_localized,
+            description: self.plot.clone(),
+            year: self.year.or(self.premiered.map(|d| d.year() as u32)),
+            release_date: self.premiered,
+            runtime: self.runtime,
+            tmdb_id: self.tmdb_id,
+            imdb_id: self.imdb_id.clone(),
+            tvdb_id: self.tvdb_id,
+            rating_tmdb: self.rating_tmdb,
+            rating_imdb: self.rating_imdb,
+            ..Default::default()
+        }
+    }
+
+    /// Changes to apply to a show
+    pub fn show_update(&self, id: Uuid) -> UpdateShow {
+        let (title, title_localized) = self.split_titles();
+        UpdateShow {
+            id,
+            title,
+            title_localized,
+            description: self.plot.clone(),
+            year: self.year.or(self.premiered.map(|d| d.year() as u32)),
+            tmdb_id: self.tmdb_id,
+            imdb_id: self.imdb_id.clone(),
+            tvdb_id: self.tvdb_id,
+            ..Default::default()
+        }
+    }
+
+    /// Changes to apply to an episode
+    pub fn episode_update(&self, id: Uuid) -> UpdateEpisode {
+        UpdateEpisode {
+            id,
+            title: self.title.clone(),
+            description: self.plot.clone(),
+            air_date: self.premiered,
+            runtime: self.runtime,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_movie(movie: &Movie, genres: Vec<String>) -> Self {
+        Self {
+            title: Some(movie.title_localized.clone().unwrap_or(movie.title.clone())),
+            original_title: Some(movie.title.clone()),
+            plot: movie.description.clone(),
+            year: movie.year,
+            premiered: movie.release_date,
+            runtime: movie.runtime,
+            genres,
+            rating_tmdb: movie.rating_tmdb,
+            rating_imdb: movie.rating_imdb,
+            tmdb_id: movie.tmdb_id,
+            imdb_id: movie.imdb_id.clone(),
+            tvdb_id: movie.tvdb_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_show(show: &Show, genres: Vec<String>) -> Self {
+        Self {
+            title: Some(show.title_localized.clone().unwrap_or(show.title.clone())),
+            original_title: Some(show.title.clone()),
+            plot: show.description.clone(),
+            year: show.year,
+            genres,
+            tmdb_id: show.tmdb_id,
+            imdb_id: show.imdb_id.clone(),
+            tvdb_id: show.tvdb_id,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_episode(episode: &Episode, season_number: u32) -> Self {
+        Self {
+            title: Some(episode.title.clone()),
+            plot: episode.description.clone(),
+            premiered: episode
+                .air_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok()),
+            runtime: episode.runtime,
+            season: Some(season_number),
+            episode: Some(episode.episode_number),
+            ..Default::default()
+        }
+    }
+
+    /// Render as an NFO document for the given kind
+    pub fn to_xml(&self, kind: NfoKind) -> String {
+        let mut out =
+            String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n");
+        out.push_str(&format!("<{}>\n", kind.root_element()));
+
+        let mut element = |name: &str, value: &str| {
+            out.push_str(&format!("  <{name}>{}</{name}>\n", escape(value)));
+        };
+
+        if let Some(title) = &self.title {
+            element("title", title);
+        }
+        if let Some(original_title) = &self.original_title {
+            element("originaltitle", original_title);
+        }
+        if let Some(season) = self.season {
+            element("season", &season.to_string());
+        }
+        if let Some(episode) = self.episode {
+            element("episode", &episode.to_string());
+        }
+        if let Some(plot) = &self.plot {
+            element("plot", plot);
+        }
+        if let Some(year) = self.year {
+            element("year", &year.to_string());
+        }
+        if let Some(premiered) = self.premiered {
+            let name = match kind {
+                NfoKind::Episode => "aired",
+                _ => "premiered",
+            };
+            element(name, &premiered.format("%Y-%m-%d").to_string());
+        }
+        if let Some(runtime) = self.runtime {
+            element("runtime", &(runtime.as_secs() / 60).to_string());
+        }
+        for genre in &self.genres {
+            element("genre", genre);
+        }
+
+        let ratings: Vec<(&str, f32)> =
+            [("themoviedb", self.rating_tmdb), ("imdb", self.rating_imdb)]
+                .into_iter()
+                .filter_map(|(name, rating)| rating.map(|r| (name, r)))
+                .collect();
+        if !ratings.is_empty() {
+            out.push_str("  <ratings>\n");
+            for (name, value) in ratings {
+                out.push_str(&format!(
+                    "    <rating name=\"{name}\" max=\"10\">\n      <value>{value:.1}</value>\n    </rating>\n"
+                ));
+            }
+            out.push_str("  </ratings>\n");
+        }
+
+        let ids = [
+            ("imdb", self.imdb_id.clone()),
+            ("tmdb", self.tmdb_id.map(|id| id.to_string())),
+            ("tvdb", self.tvdb_id.map(|id| id.to_string())),
+        ];
+        for (kind, id) in ids {
+            if let Some(id) = id {
+                out.push_str(&format!(
+                    "  <uniqueid type=\"{kind}\">{}</uniqueid>\n",
+                    escape(&id)
+                ));
+            }
+        }
+
+        out.push_str(&format!("</{}>\n", kind.root_element()));
+        out
+    }
+
+    fn split_titles(&self) -> (Option<String>, Option<String>) {
+        match (&self.original_title, &self.title) {
+            (Some(original), Some(title)) if original != title => {
+                (Some(original.clone()), Some(title.clone()))
+            }
+            (Some(original), _) => (Some(original.clone()), None),
+            (None, title) => (title.clone(), None),
+        }
+    }
+}
+
+/// Service for reading and (optionally) writing NFO sidecar files.
+#[cfg_attr(any(test, feature = "test-utils"), mockall::automock)]
+#[async_trait::async_trait]
+pub trait NfoService: Send + Sync + std::fmt::Debug {
+    /// Read the NFO describing `media_path`, if one exists.
+    ///
+    /// For movies and episodes `media_path` is the video file; for shows it is the
+    /// show directory.
+    async fn read(&self, kind: NfoKind, media_path: &Path)
+    -> Result<Option<NfoMetadata>, NfoError>;
+
+    /// Whether Beam's metadata should be exported to NFO files
+    fn export_enabled(&self) -> bool;
+
+    /// Write an NFO for `media_path` unless one already exists or export is disabled.
+    /// Returns `true` if a file was written.
+    async fn write(
+        &self,
+        kind: NfoKind,
+        media_path: &Path,
+        metadata: &NfoMetadata,
+    ) -> Result<bool, NfoError>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LocalNfoService {
+    /// Write NFO files for items that don't have one yet
+    export: bool,
+}
+
+impl LocalNfoService {
+    pub fn new(export: bool) -> Self {
+        Self { export }
+    }
+}
+
+#[async_trait::async_trait]
+impl NfoService for LocalNfoService {
+    async fn read(
+        &self,
+        kind: NfoKind,
+        media_path: &Path,
+    ) -> Result<Option<NfoMetadata>, NfoError> {
+        for candidate in kind.candidates(media_path).await? {
+            let xml = match tokio::fs::read_to_string(&candidate).await {
+                Ok(xml) => xml,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let metadata = NfoMetadata::parse(kind, &xml)
+                .map_err(|e| NfoError::Parse(candidate.clone(), e.to_string()))?;
+            return Ok(Some(metadata));
+        }
+        Ok(None)
+    }
+
+    fn export_enabled(&self) -> bool {
+        self.export
+    }
+
+    async fn write(
+        &self,
+        kind: NfoKind,
+        media_path: &Path,
+        metadata: &NfoMetadata,
+    ) -> Result<bool, NfoError> {
+        if !self.export {
+            return Ok(false);
+        }
+
+        let candidates = kind.candidates(media_path).await?;
+        for candidate in &candidates {
+            if tokio::fs::try_exists(candidate).await? {
+                return Ok(false);
+            }
+        }
+
+        let target = &candidates[0];
+        tokio::fs::write(target, metadata.to_xml(kind)).await?;
+        Ok(true)
+    }
+}
+
+// ─── XML document model ──────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NfoDocument {
+    title: Option<String>,
+    originaltitle: Option<String>,
+    plot: Option<String>,
+    outline: Option<String>,
+    year: Option<String>,
+    premiered: Option<String>,
+    aired: Option<String>,
+    runtime: Option<String>,
+    #[serde(rename = "genre")]
+    genres: Vec<String>,
+    ratings: Option<NfoRatings>,
+    /// Legacy single rating, assumed to be IMDb-scaled (0-10)
+    rating: Option<String>,
+    #[serde(rename = "uniqueid")]
+    unique_ids: Vec<NfoUniqueId>,
+    /// Legacy identifier: an IMDb id for movies, a TVDB id for shows
+    id: Option<String>,
+    imdbid: Option<String>,
+    tmdbid: Option<String>,
+    tvdbid: Option<String>,
+    season: Option<String>,
+    episode: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NfoRatings {
+    #[serde(rename = "rating")]
+    ratings: Vec<NfoRating>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NfoRating {
+    #[serde(rename = "@name")]
+    name: Option<String>,
+    value: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NfoUniqueId {
+    #[serde(rename = "@type")]
+    kind: Option<String>,
+    #[serde(rename = "$text")]
+    value: Option<String>,
+}
+
+/// Trim a text node, treating blank values as absent
+fn text(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn parse_date(value: Option<String>) -> Option<NaiveDate> {
+    text(value).and_then(|d| NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok())
+}
+
+impl From<NfoDocument> for NfoMetadata {
+    fn from(doc: NfoDocument) -> Self {
+        let mut metadata = NfoMetadata {
+            title: text(doc.title),
+            original_title: text(doc.originaltitle),
+            plot: text(doc.plot).or(text(doc.outline)),
+            year: text(doc.year).and_then(|y| y.parse().ok()),
+            premiered: parse_date(doc.premiered).or(parse_date(doc.aired)),
+            runtime: text(doc.runtime)
+                .and_then(|r| r.parse::<u64>().ok())
+                .filter(|mins| *mins > 0)
+                .map(|mins| Duration::from_secs(mins * 60)),
+            genres: doc
+                .genres
+                .into_iter()
+                .filter_map(|g| text(Some(g)))
+                .collect(),
+            season: text(doc.season).and_then(|s| s.parse().ok()),
+            episode: text(doc.episode).and_then(|e| e.parse().ok()),
+            imdb_id: text(doc.imdbid),
+            tmdb_id: text(doc.tmdbid).and_then(|id| id.parse().ok()),
+            tvdb_id: text(doc.tvdbid).and_then(|id| id.parse().ok()),
+            ..Default::default()
+        };
+
+        for rating in doc.ratings.map(|r| r.ratings).unwrap_or_default() {
+            let value = text(rating.value).and_then(|v| v.parse::<f32>().ok());
+            match text(rating.name).as_deref() {
+                Some("imdb") => metadata.rating_imdb = value,
+                Some("themoviedb" | "tmdb") => metadata.rating_tmdb = value,
+                _ => {}
+            }
+        }
+        if metadata.rating_imdb.is_none() && metadata.rating_tmdb.is_none() {
+            metadata.rating_imdb = text(doc.rating).and_then(|v| v.parse().ok());
+        }
+
+        for unique_id in doc.unique_ids {
+            let Some(value) = text(unique_id.value) else {
+                continue;
+            };
+            match text(unique_id.kind).as_deref() {
+                Some("imdb") => metadata.imdb_id = Some(value),
+                Some("tmdb") => metadata.tmdb_id = value.parse().ok().or(metadata.tmdb_id),
+                Some("tvdb") => metadata.tvdb_id = value.parse().ok().or(metadata.tvdb_id),
+                _ => {}
+            }
+        }
+
+        if let Some(id) = text(doc.id) {
+            if id.starts_with("tt") {
+                metadata.imdb_id.get_or_insert(id);
+            } else if let Ok(tvdb) = id.parse() {
+                metadata.tvdb_id.get_or_insert(tvdb);
+            }
+        }
+
+        metadata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MOVIE_NFO_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<movie>
+  <title>Le Fabuleux Destin d'Amélie Poulain</title>
+  <originaltitle>Amélie</originaltitle>
+  <ratings>
+    <rating name="imdb" max="10" default="true">
+      <value>8.3</value>
+      <votes>780000</votes>
+    </rating>
+    <rating name="themoviedb" max="10">
+      <value>7.9</value>
+    </rating>
+  </ratings>
+  <plot>Amélie is an innocent and naive girl in Paris.</plot>
+  <runtime>122</runtime>
+  <uniqueid type="imdb" default="true">tt0211915</uniqueid>
+  <genre>Comedy</genre>
+  <genre>Romance</genre>
+  <uniqueid type="tmdb">194</uniqueid>
+  <premiered>2001-04-25</premiered>
+  <actor><name>Audrey Tautou</name></actor>
+</movie>
+"#;
+
+    #[test]
+    fn test_parse_movie_nfo() {
+        let nfo = NfoMetadata::parse(NfoKind::Movie, MOVIE_NFO_XML).unwrap();
+        assert_eq!(
+            nfo.title.as_deref(),
+            Some("Le Fabuleux Destin d'Amélie Poulain")
+        );
+        assert_eq!(nfo.original_title.as_deref(), Some("Amélie"));
+        assert_eq!(
+            nfo.plot.as_deref(),
+            Some("Amélie is an innocent and naive girl in Paris.")
+        );
+        assert_eq!(nfo.runtime, Some(Duration::from_secs(122 * 60)));
+        assert_eq!(nfo.genres, vec!["Comedy", "Romance"]);
+        assert_eq!(nfo.rating_imdb, Some(8.3));
+        assert_eq!(nfo.rating_tmdb, Some(7.9));
+        assert_eq!(nfo.imdb_id.as_deref(), Some("tt0211915"));
+        assert_eq!(nfo.tmdb_id, Some(194));
+        assert_eq!(nfo.premiered, NaiveDate::from_ymd_opt(2001, 4, 25));
+    }
+
+    #[test]
+    fn test_parse_legacy_fields() {
+        let xml = r#"<tvshow>
+  <title>Firefly</title>
+  <year>2002</year>
+  <rating>9.0</rating>
+  <id>78874</id>
+</tvshow>"#;
+        let nfo = NfoMetadata::parse(NfoKind::Show, xml).unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Firefly"));
+        assert_eq!(nfo.year, Some(2002));
+        assert_eq!(nfo.rating_imdb, Some(9.0));
+        assert_eq!(nfo.tvdb_id, Some(78874));
+        assert_eq!(nfo.imdb_id, None);
+    }
+
+    #[test]
+    fn test_parse_episode_nfo() {
+        let xml = r#"<episodedetails>
+  <title>Serenity</title>
+  <season>1</season>
+  <episode>1</episode>
+  <aired>2002-12-20</aired>
+  <plot>  </plot>
+</episodedetails>"#;
+        let nfo = NfoMetadata::parse(NfoKind::Episode, xml).unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Serenity"));
+        assert_eq!(nfo.season, Some(1));
+        assert_eq!(nfo.episode, Some(1));
+        assert_eq!(nfo.premiered, NaiveDate::from_ymd_opt(2002, 12, 20));
+        assert_eq!(nfo.plot, None);
+    }
+
+    #[test]
+    fn test_movie_update_keeps_localized_title() {
+        let nfo = NfoMetadata::parse(NfoKind::Movie, MOVIE_NFO_XML).unwrap();
+        let id = Uuid::new_v4();
+        let update = nfo.movie_update(id);
+        assert_eq!(update.id, id);
+        assert_eq!(update.title.as_deref(), Some("Amélie"));
+        assert_eq!(
+            update.title_localized.as_deref(),
+            Some("Le Fabuleux Destin d'Amélie Poulain")
+        );
+        assert_eq!(update.year, Some(2001));
+    }
+
+    #[test]
+    fn test_xml_round_trip() {
+        let nfo = NfoMetadata {
+            title: Some("Tom & Jerry <The Movie>".to_string()),
+            original_title: Some("Tom & Jerry <The Movie>".to_string()),
+            plot: Some("Cat chases mouse.".to_string()),
+            year: Some(1992),
+            premiered: NaiveDate::from_ymd_opt(1992, 7, 30),
+            runtime: Some(Duration::from_secs(84 * 60)),
+            genres: vec!["Animation".to_string(), "Family".to_string()],
+            rating_tmdb: Some(6.1),
+            rating_imdb: Some(5.4),
+            tmdb_id: Some(18283),
+            imdb_id: Some("tt0105598".to_string()),
+            tvdb_id: None,
+            season: None,
+            episode: None,
+        };
+        let xml = nfo.to_xml(NfoKind::Movie);
+        assert!(xml.contains("<movie>"));
+        assert_eq!(NfoMetadata::parse(NfoKind::Movie, &xml).unwrap(), nfo);
+    }
+
+    #[tokio::test]
+    async fn test_read_prefers_file_nfo_over_movie_nfo() {
+        let dir = TempDir::new().unwrap();
+        let video = dir.path().join("Amelie (2001).mkv");
+        std::fs::write(&video, b"").unwrap();
+        std::fs::write(
+            dir.path().join(MOVIE_NFO),
+            "<movie><title>Fallback</title></movie>",
+        )
+        .unwrap();
+
+        let service = LocalNfoService::default();
+        let nfo = service.read(NfoKind::Movie, &video).await.unwrap().unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Fallback"));
+
+        std::fs::write(
+            video.with_extension("nfo"),
+            "<movie><title>Amélie</title></movie>",
+        )
+        .unwrap();
+        let nfo = service.read(NfoKind::Movie, &video).await.unwrap().unwrap();
+        assert_eq!(nfo.title.as_deref(), Some("Amélie"));
+    }
+
+    #[tokio::test]
+    async fn test_read_ignores_movie_nfo_shared_by_several_videos() {
+        let dir = TempDir::new().unwrap();
+        let heat = dir.path().join("Heat (1995).mkv");
+        std::fs::write(&heat, b"").unwrap();
+        std::fs::write(dir.path().join("Ronin (1998).mp4"), b"").unwrap();
+        std::fs::write(
+            dir.path().join(MOVIE_NFO),
+            "<movie><title>Heat</title></movie>",
+        )
+        .unwrap();
+
+        let service = LocalNfoService::default();
+        assert!(service.read(NfoKind::Movie, &heat).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_parse_rejects_other_kinds() {
+        let xml = "<tvshow><title>Firefly</title></tvshow>";
+        assert!(NfoMetadata::parse(NfoKind::Movie, xml).is_err());
+        assert!(NfoMetadata::parse(NfoKind::Episode, xml).is_err());
+        assert!(NfoMetadata::parse(NfoKind::Show, xml).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_read_missing_and_invalid() {
+        let dir = TempDir::new().unwrap();
+        let video = dir.path().join("S01E01.mkv");
+        let service = LocalNfoService::default();
+
+        assert!(
+            service
+                .read(NfoKind::Episode, &video)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        std::fs::write(
+            video.with_extension("nfo"),
+            "https://www.imdb.com/title/tt0303461/",
+        )
+        .unwrap();
+        assert!(matches!(
+            service.read(NfoKind::Episode, &video).await,
+            Err(NfoError::Parse(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_write_is_opt_in_and_never_overwrites() {
+        let dir = TempDir::new().unwrap();
+        let nfo = NfoMetadata {
+            title: Some("Firefly".to_string()),
+            ..Default::default()
+        };
+
+        let disabled = LocalNfoService::default();
+        assert!(
+            !disabled
+                .write(NfoKind::Show, dir.path(), &nfo)
+                .await
+                .unwrap()
+        );
+        assert!(!dir.path().join(TVSHOW_NFO).exists());
+
+        let enabled = LocalNfoService::new(true);
+        assert!(
+            enabled
+                .write(NfoKind::Show, dir.path(), &nfo)
+                .await
+                .unwrap()
+        );
+        let written = std::fs::read_to_string(dir.path().join(TVSHOW_NFO)).unwrap();
+        assert!(written.contains("<tvshow>"));
+        assert!(written.contains("<title>Firefly</title>"));
+
+        let other = NfoMetadata {
+            title: Some("Serenity".to_string()),
+            ..Default::default()
+        };
+        assert!(
+            !enabled
+                .write(NfoKind::Show, dir.path(), &other)
+                .await
+                .unwrap()
+        );
+        let unchanged = std::fs::read_to_string(dir.path().join(TVSHOW_NFO)).unwrap();
+        assert_eq!(written, unchanged);
+    }
+}
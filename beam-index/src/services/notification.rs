@@ -20,6 +20,7 @@ pub enum EventLevel {
 pub enum EventCategory {
     LibraryScan,
     System,
+    Integrity,
 }
 
 #[derive(Clone, Debug, async_graphql::SimpleObject)]
@@ -5,6 +5,19 @@ mod m20260210_000001_create_users;
 
 mod m20260212_000001_ensure_cascade;
 mod m20260222_000001_create_admin_log;
+mod m20260301_000001_add_metadata_locks;
+mod m20260310_000001_add_file_partial_hash;
+mod m20260315_000001_add_file_hash_strategy;
+mod m20260320_000001_add_file_verified_at;
+mod m20260325_000001_create_library_access;
+mod m20260401_000001_create_roles;
+mod m20260405_000001_add_user_management;
+mod m20260410_000001_add_user_profile;
+mod m20260415_000001_add_user_totp;
+mod m20260420_000001_create_user_identities;
+mod m20260501_000001_create_api_keys;
+mod m20260510_000001_create_sessions;
+mod m20260515_000001_add_user_totp_last_step;
 
 pub struct Migrator;
 
@@ -16,6 +29,19 @@ impl MigratorTrait for Migrator {
             Box::new(m20260210_000001_create_users::Migration),
             Box::new(m20260212_000001_ensure_cascade::Migration),
             Box::new(m20260222_000001_create_admin_log::Migration),
+            Box::new(m20260301_000001_add_metadata_locks::Migration),
+            Box::new(m20260310_000001_add_file_partial_hash::Migration),
+            Box::new(m20260315_000001_add_file_hash_strategy::Migration),
+            Box::new(m20260320_000001_add_file_verified_at::Migration),
+            Box::new(m20260325_000001_create_library_access::Migration),
+            Box::new(m20260401_000001_create_roles::Migration),
+            Box::new(m20260405_000001_add_user_management::Migration),
+            Box::new(m20260410_000001_add_user_profile::Migration),
+            Box::new(m20260415_000001_add_user_totp::Migration),
+            Box::new(m20260420_000001_create_user_identities::Migration),
+            Box::new(m20260501_000001_create_api_keys::Migration),
+            Box::new(m20260510_000001_create_sessions::Migration),
+            Box::new(m20260515_000001_add_user_totp_last_step::Migration),
         ]
     }
 }
@@ -0,0 +1,36 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tables whose metadata can be edited manually and locked against automated updates
+const TABLES: &[&str] = &["movies", "shows", "seasons", "episodes"];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for table in TABLES {
+            db.execute_unprepared(&format!(
+                "ALTER TABLE {table} ADD COLUMN locked_fields TEXT[] NOT NULL DEFAULT '{{}}'"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        for table in TABLES {
+            db.execute_unprepared(&format!(
+                "ALTER TABLE {table} DROP COLUMN IF EXISTS locked_fields"
+            ))
+            .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Tracks when each file last passed an integrity check, and adds an admin log category
+/// for integrity reports
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("ALTER TABLE files ADD COLUMN verified_at TIMESTAMPTZ")
+            .await?;
+        db.execute_unprepared("ALTER TYPE admin_log_category ADD VALUE IF NOT EXISTS 'integrity'")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres can't drop enum values, so 'integrity' stays on admin_log_category
+        manager
+            .get_connection()
+            .execute_unprepared("ALTER TABLE files DROP COLUMN IF EXISTS verified_at")
+            .await?;
+
+        Ok(())
+    }
+}
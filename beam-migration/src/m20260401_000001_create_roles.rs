@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Replaces `users.is_admin` with a role whose permissions live in the `roles` table.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE roles (
+                name TEXT PRIMARY KEY,
+                permissions TEXT[] NOT NULL DEFAULT '{}'
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "INSERT INTO roles (name, permissions) VALUES
+                ('admin', ARRAY['manage_users', 'manage_libraries', 'trigger_scans', 'view_logs', 'download_originals', 'transcode']),
+                ('library_manager', ARRAY['manage_libraries', 'trigger_scans', 'view_logs', 'download_originals', 'transcode']),
+                ('viewer', ARRAY['download_originals', 'transcode']),
+                ('guest', ARRAY['transcode'])",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "ALTER TABLE users ADD COLUMN role TEXT NOT NULL DEFAULT 'viewer' REFERENCES roles(name)",
+        )
+        .await?;
+
+        db.execute_unprepared("UPDATE users SET role = 'admin' WHERE is_admin")
+            .await?;
+
+        db.execute_unprepared("ALTER TABLE users DROP COLUMN is_admin")
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .await?;
+
+        db.execute_unprepared("UPDATE users SET is_admin = TRUE WHERE role = 'admin'")
+            .await?;
+
+        db.execute_unprepared("ALTER TABLE users DROP COLUMN role")
+            .await?;
+
+        db.execute_unprepared("DROP TABLE IF EXISTS roles").await?;
+
+        Ok(())
+    }
+}
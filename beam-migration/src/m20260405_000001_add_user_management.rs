@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Account state for admin user management, and invite codes for closed registration.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "ALTER TABLE users
+                ADD COLUMN disabled_at TIMESTAMPTZ,
+                ADD COLUMN password_reset_required BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TABLE invites (
+                code TEXT PRIMARY KEY,
+                role TEXT NOT NULL DEFAULT 'viewer' REFERENCES roles(name),
+                created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                expires_at TIMESTAMPTZ,
+                used_by UUID REFERENCES users(id) ON DELETE SET NULL,
+                used_at TIMESTAMPTZ
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TABLE IF EXISTS invites")
+            .await?;
+
+        db.execute_unprepared(
+            "ALTER TABLE users
+                DROP COLUMN IF EXISTS disabled_at,
+                DROP COLUMN IF EXISTS password_reset_required",
+        )
+        .await?;
+
+        Ok(())
+    }
+}
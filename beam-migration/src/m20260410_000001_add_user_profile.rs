@@ -0,0 +1,34 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Optional profile details users can edit themselves.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users
+                    ADD COLUMN display_name TEXT,
+                    ADD COLUMN avatar_url TEXT",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users
+                    DROP COLUMN IF EXISTS display_name,
+                    DROP COLUMN IF EXISTS avatar_url",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
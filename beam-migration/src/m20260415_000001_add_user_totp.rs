@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// TOTP two-factor authentication. The secret is stored encrypted and only counts once
+/// `totp_enabled_at` is set; recovery codes are stored as SHA-256 hashes.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users
+                    ADD COLUMN totp_secret TEXT,
+                    ADD COLUMN totp_enabled_at TIMESTAMPTZ,
+                    ADD COLUMN totp_recovery_codes TEXT[] NOT NULL DEFAULT '{}'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "ALTER TABLE users
+                    DROP COLUMN IF EXISTS totp_secret,
+                    DROP COLUMN IF EXISTS totp_enabled_at,
+                    DROP COLUMN IF EXISTS totp_recovery_codes",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
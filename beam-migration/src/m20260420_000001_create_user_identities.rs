@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Accounts at external identity providers (OpenID Connect) that sign in as a user,
+/// identified by the provider's `sub` claim.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE user_identities (
+                provider TEXT NOT NULL,
+                subject TEXT NOT NULL,
+                user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                PRIMARY KEY (provider, subject)
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE INDEX idx_user_identities_user_id ON user_identities (user_id)",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("DROP TABLE IF EXISTS user_identities")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Sessions, device pairings and login throttling for installs that keep them in
+/// PostgreSQL instead of Redis. Rows past `expires_at` are ignored and swept periodically.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "CREATE TABLE sessions (
+                id TEXT PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                data JSONB NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL
+            )",
+        )
+        .await?;
+        db.execute_unprepared("CREATE INDEX idx_sessions_user_id ON sessions (user_id)")
+            .await?;
+        db.execute_unprepared("CREATE INDEX idx_sessions_expires_at ON sessions (expires_at)")
+            .await?;
+
+        db.execute_unprepared(
+            "CREATE TABLE device_authorizations (
+                device_code_hash TEXT PRIMARY KEY,
+                user_code TEXT NOT NULL UNIQUE,
+                data JSONB NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL
+            )",
+        )
+        .await?;
+
+        db.execute_unprepared(
+            "CREATE TABLE login_throttles (
+                key TEXT PRIMARY KEY,
+                failures INTEGER NOT NULL DEFAULT 0,
+                failures_expire_at TIMESTAMPTZ NOT NULL,
+                blocked_until TIMESTAMPTZ
+            )",
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared("DROP TABLE IF EXISTS login_throttles")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS device_authorizations")
+            .await?;
+        db.execute_unprepared("DROP TABLE IF EXISTS sessions")
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use beam_auth::config::{RegistrationMode, SessionBackend};
 use confique::Config;
 use std::path::PathBuf;
 use thiserror::Error;
@@ -39,14 +40,48 @@ pub struct ServerConfig {
     )]
     pub database_url: String,
 
+    /// Signs stream tokens, and access tokens unless one of the key options below is set.
     #[config(env = "JWT_SECRET")]
     pub jwt_secret: String,
 
+    /// Comma-separated paths of Ed25519 or RSA private keys in PEM format, when this
+    /// server issues access tokens itself. The first signs; the rest only verify.
+    #[config(env = "JWT_SIGNING_KEYS", parse_env = confique::env::parse::list_by_comma)]
+    pub jwt_signing_keys: Option<Vec<PathBuf>>,
+
+    /// JWKS file with the public keys that verify access tokens, e.g. a copy of
+    /// beam-auth's `/.well-known/jwks.json`. Access tokens cannot be issued here.
+    #[config(env = "JWKS_FILE")]
+    pub jwks_file: Option<PathBuf>,
+
+    /// URL of the issuer's JWKS, e.g. `http://beam-auth:8001/.well-known/jwks.json`,
+    /// fetched periodically to pick up rotated keys. Access tokens cannot be issued here.
+    #[config(env = "JWKS_URL")]
+    pub jwks_url: Option<String>,
+
+    /// `redis` or `postgres`
+    #[config(env = "SESSION_BACKEND", default = "redis")]
+    pub session_backend: SessionBackend,
+
+    /// Only used with `SESSION_BACKEND=redis`
     #[config(env = "REDIS_URL", default = "redis://localhost:6379")]
     pub redis_url: String,
 
     #[config(env = "BEAM_INDEX_URL", default = "http://localhost:50051")]
     pub beam_index_url: String,
+
+    /// `open`, `invite` or `closed`. The first user can always register and becomes admin.
+    #[config(env = "REGISTRATION_MODE", default = "open")]
+    pub registration_mode: RegistrationMode,
+
+    /// Key used to encrypt TOTP secrets at rest. Falls back to `JWT_SECRET` when unset;
+    /// changing it invalidates every enrolled authenticator.
+    #[config(env = "TOTP_ENCRYPTION_KEY")]
+    pub totp_encryption_key: Option<String>,
+
+    /// Withhold admin permissions from admins until they enable two-factor authentication.
+    #[config(env = "REQUIRE_ADMIN_MFA", default = false)]
+    pub require_admin_mfa: bool,
 }
 
 impl ServerConfig {
@@ -1,5 +1,6 @@
-use uuid::Uuid;
+use beam_auth::utils::models::Permission;
 
+use crate::services::library::LibraryScope;
 use crate::state::{AppContext, AppState};
 use async_graphql::{Context, Guard, Result};
 
@@ -16,31 +17,42 @@ impl Guard for AuthGuard {
     }
 }
 
-pub struct AdminGuard;
+/// Requires the user's token to carry a permission
+pub struct PermissionGuard {
+    permission: Permission,
+}
+
+impl PermissionGuard {
+    pub fn new(permission: Permission) -> Self {
+        Self { permission }
+    }
+}
 
-impl Guard for AdminGuard {
+impl Guard for PermissionGuard {
     async fn check(&self, ctx: &Context<'_>) -> Result<()> {
         let app_ctx = ctx.data::<AppContext>().map_err(|_| "AppContext missing")?;
         let user_ctx = app_ctx
             .user_context()
             .ok_or_else(|| async_graphql::Error::new("Unauthorized"))?;
 
-        let state = ctx.data::<AppState>().map_err(|_| "AppState missing")?;
-        let user_id = Uuid::parse_str(&user_ctx.user_id)
-            .map_err(|_| async_graphql::Error::new("Invalid user ID"))?;
-
-        let user = state
-            .services
-            .user_repo
-            .find_by_id(user_id)
-            .await
-            .map_err(|e| async_graphql::Error::new(format!("Database error: {e}")))?
-            .ok_or_else(|| async_graphql::Error::new("User not found"))?;
-
-        if user.is_admin {
+        if user_ctx.has_permission(self.permission) {
             Ok(())
         } else {
-            Err("Forbidden: admin access required".into())
+            Err(format!("Forbidden: {} permission required", self.permission).into())
         }
     }
 }
+
+/// Libraries the requesting user may see
+pub async fn library_scope(ctx: &Context<'_>) -> Result<LibraryScope> {
+    let app_ctx = ctx.data::<AppContext>().map_err(|_| "AppContext missing")?;
+    let user_ctx = app_ctx
+        .user_context()
+        .ok_or_else(|| async_graphql::Error::new("Unauthorized"))?;
+    let state = ctx.data::<AppState>().map_err(|_| "AppState missing")?;
+    Ok(state
+        .services
+        .library
+        .library_scope(user_ctx.user_id)
+        .await?)
+}
@@ -13,7 +13,7 @@ mod auth_tests;
 #[cfg(test)]
 mod resolver_tests;
 
-pub use guard::{AdminGuard, AuthGuard};
+pub use guard::{AuthGuard, PermissionGuard};
 
 pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
 
@@ -1,10 +1,11 @@
 use async_graphql::*;
 
-use crate::graphql::AuthGuard;
-use crate::graphql::guard::AdminGuard;
+use crate::graphql::guard::PermissionGuard;
+use crate::models::DuplicateGroup;
 use crate::services::notification::AdminEvent;
 use crate::state::AppState;
-use beam_domain::models::admin_log::{AdminLog, AdminLogCategory, AdminLogLevel};
+use beam_auth::utils::models::Permission;
+use beam_domain::models::admin_log::{AdminLog, AdminLogCategory, AdminLogFilter, AdminLogLevel};
 
 #[derive(SimpleObject)]
 pub struct AdminLogEntry {
@@ -33,11 +34,51 @@ impl From<AdminLogLevel> for AdminLogLevelGql {
     }
 }
 
+impl From<AdminLogLevelGql> for AdminLogLevel {
+    fn from(level: AdminLogLevelGql) -> Self {
+        match level {
+            AdminLogLevelGql::Info => AdminLogLevel::Info,
+            AdminLogLevelGql::Warning => AdminLogLevel::Warning,
+            AdminLogLevelGql::Error => AdminLogLevel::Error,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+pub enum AdminLogCategoryGql {
+    LibraryScan,
+    System,
+    Auth,
+    Integrity,
+}
+
+impl From<AdminLogCategoryGql> for AdminLogCategory {
+    fn from(category: AdminLogCategoryGql) -> Self {
+        match category {
+            AdminLogCategoryGql::LibraryScan => AdminLogCategory::LibraryScan,
+            AdminLogCategoryGql::System => AdminLogCategory::System,
+            AdminLogCategoryGql::Auth => AdminLogCategory::Auth,
+            AdminLogCategoryGql::Integrity => AdminLogCategory::Integrity,
+        }
+    }
+}
+
+fn log_filter(
+    category: Option<AdminLogCategoryGql>,
+    level: Option<AdminLogLevelGql>,
+) -> AdminLogFilter {
+    AdminLogFilter {
+        level: level.map(Into::into),
+        category: category.map(Into::into),
+    }
+}
+
 fn category_to_str(cat: &AdminLogCategory) -> &'static str {
     match cat {
         AdminLogCategory::LibraryScan => "library_scan",
         AdminLogCategory::System => "system",
         AdminLogCategory::Auth => "auth",
+        AdminLogCategory::Integrity => "integrity",
     }
 }
 
@@ -60,8 +101,8 @@ pub struct AdminQuery;
 #[Object]
 impl AdminQuery {
     /// Fetch recent admin events from the in-memory event log.
-    /// Returns the most recent `limit` events (default 100, max 1000).
-    #[graphql(guard = "AuthGuard")]
+    /// Returns the most recent `limit` events (default 100, max 1000). Requires `view_logs`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ViewLogs)")]
     async fn admin_events(
         &self,
         ctx: &Context<'_>,
@@ -73,32 +114,49 @@ impl AdminQuery {
         Ok(events)
     }
 
-    /// Fetch paginated admin log entries (most recent first). Admin only.
-    #[graphql(guard = "AdminGuard")]
+    /// Fetch paginated admin log entries (most recent first), optionally only those of
+    /// one category and/or level. Requires `view_logs`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ViewLogs)")]
     async fn logs(
         &self,
         ctx: &Context<'_>,
         #[graphql(default = 50)] limit: u32,
         #[graphql(default = 0)] offset: u32,
+        category: Option<AdminLogCategoryGql>,
+        level: Option<AdminLogLevelGql>,
     ) -> Result<Vec<AdminLogEntry>> {
         let state = ctx.data::<AppState>()?;
         let logs = state
             .services
             .admin_log
-            .get_logs(limit, offset)
+            .get_logs(&log_filter(category, level), limit, offset)
             .await
             .map_err(|e| Error::new(e.to_string()))?;
         Ok(logs.into_iter().map(AdminLogEntry::from).collect())
     }
 
-    /// Total count of admin log entries. Admin only.
-    #[graphql(guard = "AdminGuard")]
-    async fn log_count(&self, ctx: &Context<'_>) -> Result<u64> {
+    /// Files holding the same content across all libraries, largest savings first.
+    /// Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn duplicates(&self, ctx: &Context<'_>) -> Result<Vec<DuplicateGroup>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.services.metadata.find_duplicates().await?)
+    }
+
+    /// Total count of admin log entries matching the same filters as `logs`.
+    /// Requires `view_logs`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ViewLogs)")]
+    async fn log_count(
+        &self,
+        ctx: &Context<'_>,
+        category: Option<AdminLogCategoryGql>,
+        level: Option<AdminLogLevelGql>,
+    ) -> Result<u64> {
         let state = ctx.data::<AppState>()?;
         let count = state
             .services
             .admin_log
-            .count()
+            .count(&log_filter(category, level))
             .await
             .map_err(|e| Error::new(e.to_string()))?;
         Ok(count)
@@ -2,9 +2,10 @@ use async_graphql::*;
 use async_stream::stream;
 use tokio::sync::broadcast::error::RecvError;
 
-use crate::graphql::AuthGuard;
+use crate::graphql::guard::PermissionGuard;
 use crate::services::notification::AdminEvent;
 use crate::state::AppState;
+use beam_auth::utils::models::Permission;
 
 #[derive(Default)]
 pub struct AdminSubscription;
@@ -13,7 +14,8 @@ pub struct AdminSubscription;
 impl AdminSubscription {
     /// Subscribe to real-time admin events.
     /// Yields events as they are published (library scans, warnings, errors, etc.).
-    #[graphql(guard = "AuthGuard")]
+    /// Requires `view_logs`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ViewLogs)")]
     async fn admin_events_watch(
         &self,
         ctx: &Context<'_>,
@@ -1,17 +1,19 @@
 use async_graphql::*;
 
-use crate::graphql::AuthGuard;
+use crate::graphql::guard::PermissionGuard;
 use crate::models::Library;
 use crate::services::metadata::MediaFilter;
 use crate::state::AppState;
+use beam_auth::utils::models::Permission;
+use beam_index::services::index::VerifyOptions;
 
 #[derive(Default)]
 pub struct LibraryMutation;
 
 #[Object]
 impl LibraryMutation {
-    /// Refresh metadata for media library
-    #[graphql(guard = "AuthGuard")]
+    /// Refresh metadata for media library. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
     async fn refresh_metadata(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
         let state = ctx.data::<AppState>()?;
         state
@@ -22,8 +24,8 @@ impl LibraryMutation {
         Ok(true)
     }
 
-    /// Create a new library
-    #[graphql(guard = "AuthGuard")]
+    /// Create a new library. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
     async fn create_library(
         &self,
         ctx: &Context<'_>,
@@ -39,16 +41,46 @@ impl LibraryMutation {
         Ok(library)
     }
 
-    /// Scan a library for new content
-    #[graphql(guard = "AuthGuard")]
+    /// Scan a library for new content. Requires `trigger_scans`.
+    #[graphql(guard = "PermissionGuard::new(Permission::TriggerScans)")]
     async fn scan_library(&self, ctx: &Context<'_>, id: ID) -> Result<u32> {
         let state = ctx.data::<AppState>()?;
         let count = state.services.library.scan_library(id.to_string()).await?;
         Ok(count)
     }
 
-    /// Delete a library and all its associated files
-    #[graphql(guard = "AuthGuard")]
+    /// Start rehashing a library's files to detect silent corruption, least recently verified
+    /// first. Runs in the background; progress, problems and the final summary are reported in
+    /// the admin log and event stream. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn verify_library(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        #[graphql(desc = "Check at most this many files")] limit: Option<u32>,
+        #[graphql(
+            desc = "Replace sampled hashes that still match with full hashes",
+            default
+        )]
+        upgrade_hashes: bool,
+        #[graphql(desc = "Also decode each file with FFmpeg (slow)", default)] check_decode: bool,
+    ) -> Result<bool> {
+        let state = ctx.data::<AppState>()?;
+        let options = VerifyOptions {
+            limit,
+            upgrade_hashes,
+            check_decode,
+        };
+        state
+            .services
+            .library
+            .verify_library(id.to_string(), options)
+            .await?;
+        Ok(true)
+    }
+
+    /// Delete a library and all its associated files. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
     async fn delete_library(&self, ctx: &Context<'_>, id: ID) -> Result<bool> {
         let state = ctx.data::<AppState>()?;
         let deleted = state
@@ -58,4 +90,39 @@ impl LibraryMutation {
             .await?;
         Ok(deleted)
     }
+
+    /// Let a user see a library. Users with `manage_libraries` see every library anyway.
+    /// Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn grant_library_access(
+        &self,
+        ctx: &Context<'_>,
+        library_id: ID,
+        user_id: ID,
+    ) -> Result<bool> {
+        let state = ctx.data::<AppState>()?;
+        state
+            .services
+            .library
+            .grant_library_access(library_id.to_string(), user_id.to_string())
+            .await?;
+        Ok(true)
+    }
+
+    /// Stop a user from seeing a library. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn revoke_library_access(
+        &self,
+        ctx: &Context<'_>,
+        library_id: ID,
+        user_id: ID,
+    ) -> Result<bool> {
+        let state = ctx.data::<AppState>()?;
+        state
+            .services
+            .library
+            .revoke_library_access(library_id.to_string(), user_id.to_string())
+            .await?;
+        Ok(true)
+    }
 }
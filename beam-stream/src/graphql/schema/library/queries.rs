@@ -1,15 +1,19 @@
 use async_graphql::*;
+use uuid::Uuid;
 
 use crate::graphql::AuthGuard;
+use crate::graphql::guard::{PermissionGuard, library_scope};
 use crate::models::{Library, LibraryFile};
+use crate::services::library::LibraryError;
 use crate::state::{AppContext, AppState};
+use beam_auth::utils::models::Permission;
 
 #[derive(Default)]
 pub struct LibraryQuery;
 
 #[Object]
 impl LibraryQuery {
-    /// Fetch list of all libraries
+    /// Fetch list of all libraries visible to the user
     #[graphql(guard = "AuthGuard")]
     async fn libraries(&self, ctx: &Context<'_>) -> Result<Vec<Library>> {
         let state = ctx.data::<AppState>()?;
@@ -31,18 +35,23 @@ impl LibraryQuery {
     #[graphql(guard = "AuthGuard")]
     async fn library_by_id(&self, ctx: &Context<'_>, id: ID) -> Result<Option<Library>> {
         let state = ctx.data::<AppState>()?;
+        let scope = library_scope(ctx).await?;
         let library = state
             .services
             .library
             .get_library_by_id(id.to_string())
             .await?;
-        Ok(library)
+        Ok(library.filter(|lib| Uuid::parse_str(&lib.id).is_ok_and(|id| scope.allows(id))))
     }
 
     /// Fetch all files within a library
     #[graphql(guard = "AuthGuard")]
     async fn library_files(&self, ctx: &Context<'_>, library_id: ID) -> Result<Vec<LibraryFile>> {
         let state = ctx.data::<AppState>()?;
+        let scope = library_scope(ctx).await?;
+        if Uuid::parse_str(&library_id).is_ok_and(|id| !scope.allows(id)) {
+            return Err(LibraryError::LibraryNotFound.into());
+        }
         let files = state
             .services
             .library
@@ -50,4 +59,16 @@ impl LibraryQuery {
             .await?;
         Ok(files)
     }
+
+    /// Users granted access to a library. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn library_access(&self, ctx: &Context<'_>, library_id: ID) -> Result<Vec<ID>> {
+        let state = ctx.data::<AppState>()?;
+        let user_ids = state
+            .services
+            .library
+            .get_library_user_ids(library_id.to_string())
+            .await?;
+        Ok(user_ids.into_iter().map(ID::from).collect())
+    }
 }
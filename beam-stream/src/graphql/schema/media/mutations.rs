@@ -1,6 +1,80 @@
+use std::time::Duration;
+
 use async_graphql::*;
+use chrono::NaiveDate;
+use uuid::Uuid;
 
 use crate::graphql::AuthGuard;
+use crate::graphql::guard::PermissionGuard;
+use crate::models::{LibraryFile, MediaMetadata};
+use crate::state::AppState;
+use beam_auth::utils::models::Permission;
+use beam_domain::models::{UpdateEpisode, UpdateMovie, UpdateSeason, UpdateShow};
+
+/// Manual edits to a movie. Omitted fields are left unchanged. Edited fields are locked
+/// against automated updates unless `lockedFields` is given, which replaces the locks.
+/// Lock names are the snake_case field names (e.g. `poster_url`).
+#[derive(InputObject, Default)]
+pub struct UpdateMovieInput {
+    pub title: Option<String>,
+    pub title_localized: Option<String>,
+    pub description: Option<String>,
+    pub year: Option<u32>,
+    pub release_date: Option<NaiveDate>,
+    /// Runtime in minutes
+    pub runtime: Option<u32>,
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+    pub tmdb_id: Option<u32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    pub locked_fields: Option<Vec<String>>,
+}
+
+/// Manual edits to a show. Locking works as for [`UpdateMovieInput`].
+#[derive(InputObject, Default)]
+pub struct UpdateShowInput {
+    pub title: Option<String>,
+    pub title_localized: Option<String>,
+    pub description: Option<String>,
+    pub year: Option<u32>,
+    pub poster_url: Option<String>,
+    pub backdrop_url: Option<String>,
+    pub tmdb_id: Option<u32>,
+    pub imdb_id: Option<String>,
+    pub tvdb_id: Option<u32>,
+    pub locked_fields: Option<Vec<String>>,
+}
+
+/// Manual edits to a season. Locking works as for [`UpdateMovieInput`].
+#[derive(InputObject, Default)]
+pub struct UpdateSeasonInput {
+    pub poster_url: Option<String>,
+    pub first_aired: Option<NaiveDate>,
+    pub last_aired: Option<NaiveDate>,
+    pub locked_fields: Option<Vec<String>>,
+}
+
+/// Manual edits to an episode. Locking works as for [`UpdateMovieInput`].
+#[derive(InputObject, Default)]
+pub struct UpdateEpisodeInput {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub air_date: Option<NaiveDate>,
+    /// Runtime in minutes
+    pub runtime: Option<u32>,
+    pub thumbnail_url: Option<String>,
+    pub locked_fields: Option<Vec<String>>,
+}
+
+fn parse_id(id: &ID) -> Result<Uuid> {
+    Uuid::parse_str(id).map_err(|_| Error::new("Invalid ID"))
+}
+
+fn minutes(runtime: Option<u32>) -> Option<Duration> {
+    runtime.map(|mins| Duration::from_secs(mins as u64 * 60))
+}
+
 #[derive(Default)]
 pub struct MediaMutation;
 
@@ -11,4 +85,186 @@ impl MediaMutation {
     async fn refresh_metadata(&self, _ctx: &Context<'_>, _id: ID) -> Result<bool> {
         todo!()
     }
+
+    /// Edit a movie's metadata. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn update_movie(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        input: UpdateMovieInput,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let update = UpdateMovie {
+            id: parse_id(&id)?,
+            title: input.title,
+            title_localized: input.title_localized,
+            description: input.description,
+            year: input.year,
+            release_date: input.release_date,
+            runtime: minutes(input.runtime),
+            poster_url: input.poster_url,
+            backdrop_url: input.backdrop_url,
+            tmdb_id: input.tmdb_id,
+            imdb_id: input.imdb_id,
+            tvdb_id: input.tvdb_id,
+            locked_fields: input.locked_fields,
+            ..Default::default()
+        };
+        Ok(state.services.metadata.update_movie(update).await?)
+    }
+
+    /// Edit a show's metadata. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn update_show(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        input: UpdateShowInput,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let update = UpdateShow {
+            id: parse_id(&id)?,
+            title: input.title,
+            title_localized: input.title_localized,
+            description: input.description,
+            year: input.year,
+            poster_url: input.poster_url,
+            backdrop_url: input.backdrop_url,
+            tmdb_id: input.tmdb_id,
+            imdb_id: input.imdb_id,
+            tvdb_id: input.tvdb_id,
+            locked_fields: input.locked_fields,
+        };
+        Ok(state.services.metadata.update_show(update).await?)
+    }
+
+    /// Edit a season's metadata, returning its show. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn update_season(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        input: UpdateSeasonInput,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let update = UpdateSeason {
+            id: parse_id(&id)?,
+            poster_url: input.poster_url,
+            first_aired: input.first_aired,
+            last_aired: input.last_aired,
+            locked_fields: input.locked_fields,
+        };
+        Ok(state.services.metadata.update_season(update).await?)
+    }
+
+    /// Edit an episode's metadata, returning its show. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn update_episode(
+        &self,
+        ctx: &Context<'_>,
+        id: ID,
+        input: UpdateEpisodeInput,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let update = UpdateEpisode {
+            id: parse_id(&id)?,
+            title: input.title,
+            description: input.description,
+            air_date: input.air_date,
+            runtime: minutes(input.runtime),
+            thumbnail_url: input.thumbnail_url,
+            locked_fields: input.locked_fields,
+        };
+        Ok(state.services.metadata.update_episode(update).await?)
+    }
+
+    /// Set the TMDB ID of a movie or show. Only the ID changes; no metadata is fetched
+    /// for it. The ID is locked so rescans keep it. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn set_tmdb_id(&self, ctx: &Context<'_>, id: ID, tmdb_id: u32) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.services.metadata.set_tmdb_id(&id, tmdb_id).await?)
+    }
+
+    /// Merge movies into `targetId` as additional versions, deleting the merged movies.
+    /// Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn merge_movies(
+        &self,
+        ctx: &Context<'_>,
+        target_id: ID,
+        movie_ids: Vec<ID>,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let source_ids = movie_ids.iter().map(parse_id).collect::<Result<_>>()?;
+        Ok(state
+            .services
+            .metadata
+            .merge_movies(parse_id(&target_id)?, source_ids)
+            .await?)
+    }
+
+    /// Split a movie version off into a new movie, returning the new movie. With `fileIds`,
+    /// only those files of the version move. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn split_movie_version(
+        &self,
+        ctx: &Context<'_>,
+        version_id: ID,
+        file_ids: Option<Vec<ID>>,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        let file_ids = file_ids
+            .map(|ids| ids.iter().map(parse_id).collect::<Result<_>>())
+            .transpose()?;
+        Ok(state
+            .services
+            .metadata
+            .split_movie_entry(parse_id(&version_id)?, file_ids)
+            .await?)
+    }
+
+    /// Make a version the one its movie plays by default. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn set_primary_movie_version(
+        &self,
+        ctx: &Context<'_>,
+        version_id: ID,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state
+            .services
+            .metadata
+            .set_primary_movie_entry(parse_id(&version_id)?)
+            .await?)
+    }
+
+    /// Set or clear the edition label of a movie version. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn set_movie_version_edition(
+        &self,
+        ctx: &Context<'_>,
+        version_id: ID,
+        edition: Option<String>,
+    ) -> Result<MediaMetadata> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state
+            .services
+            .metadata
+            .set_movie_entry_edition(parse_id(&version_id)?, edition)
+            .await?)
+    }
+
+    /// Prefer a file over its duplicates: identical copies and files of the same cut stop
+    /// being primary. Requires `manage_libraries`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageLibraries)")]
+    async fn set_primary_file(&self, ctx: &Context<'_>, file_id: ID) -> Result<LibraryFile> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state
+            .services
+            .metadata
+            .set_primary_file(parse_id(&file_id)?)
+            .await?)
+    }
 }
@@ -11,6 +11,8 @@ use crate::services::{
 };
 
 use crate::graphql::AuthGuard;
+use crate::graphql::guard::library_scope;
+use crate::services::library::LibraryScope;
 use crate::state::AppState;
 
 #[Object]
@@ -19,9 +21,18 @@ impl MediaQuery {
     #[graphql(guard = "AuthGuard")]
     async fn metadata(&self, ctx: &Context<'_>, id: ID) -> Result<Option<MediaMetadata>> {
         let state = ctx.data::<AppState>()?;
-        let media_metadata = state.services.metadata.get_media_metadata(&id).await;
+        let scope = library_scope(ctx).await?;
+        let Some(media_metadata) = state.services.metadata.get_media_metadata(&id).await else {
+            return Ok(None);
+        };
+        if scope != LibraryScope::All {
+            let library_ids = state.services.metadata.get_media_library_ids(&id).await?;
+            if !scope.allows_any(&library_ids) {
+                return Ok(None);
+            }
+        }
 
-        Ok(media_metadata)
+        Ok(Some(media_metadata))
     }
 
     /// Search/explore media with cursor-based pagination (Relay-style), sorting, and filtering
@@ -54,6 +65,7 @@ impl MediaQuery {
             year_to,
             query,
             min_rating,
+            scope: library_scope(ctx).await?,
         };
 
         let result = state
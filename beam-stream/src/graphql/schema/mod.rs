@@ -3,16 +3,18 @@ use async_graphql::*;
 use admin::{AdminQuery, AdminSubscription};
 use library::{LibraryMutation, LibraryQuery};
 use media::{MediaMutation, MediaQuery};
+use user::{UserMutation, UserQuery};
 
 pub mod admin;
 pub mod library;
 pub mod media;
+pub mod user;
 
 #[derive(MergedObject, Default)]
-pub struct QueryRoot(AdminQuery, LibraryQuery, MediaQuery);
+pub struct QueryRoot(AdminQuery, LibraryQuery, MediaQuery, UserQuery);
 
 #[derive(MergedObject, Default)]
-pub struct MutationRoot(LibraryMutation, MediaMutation);
+pub struct MutationRoot(LibraryMutation, MediaMutation, UserMutation);
 
 #[derive(MergedSubscription, Default)]
 pub struct SubscriptionRoot(AdminSubscription);
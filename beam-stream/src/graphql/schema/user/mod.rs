@@ -0,0 +1,5 @@
+mod mutations;
+mod queries;
+
+pub use mutations::UserMutation;
+pub use queries::UserQuery;
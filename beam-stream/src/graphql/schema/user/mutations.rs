@@ -0,0 +1,94 @@
+use async_graphql::*;
+
+use super::queries::{InviteGql, RoleGql, UserAccountGql};
+use crate::graphql::guard::PermissionGuard;
+use crate::state::{AppContext, AppState};
+use beam_auth::utils::models::Permission;
+
+#[derive(Default)]
+pub struct UserMutation;
+
+#[Object]
+impl UserMutation {
+    /// Create an invite code for registering an account with `role`, optionally
+    /// expiring after `expiresInHours`. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn create_invite(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "RoleGql::Viewer")] role: RoleGql,
+        expires_in_hours: Option<u32>,
+    ) -> Result<InviteGql> {
+        let state = ctx.data::<AppState>()?;
+        let app_ctx = ctx.data::<AppContext>()?;
+        let created_by = app_ctx
+            .user_context()
+            .map(|user| user.user_id)
+            .unwrap_or_default();
+        let expires_in = expires_in_hours.map(|hours| chrono::Duration::hours(hours.into()));
+        let invite = state
+            .services
+            .auth
+            .create_invite(&created_by, role.into(), expires_in)
+            .await?;
+        Ok(invite.into())
+    }
+
+    /// Delete an invite code. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn delete_invite(&self, ctx: &Context<'_>, code: String) -> Result<bool> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.services.auth.delete_invite(&code).await?)
+    }
+
+    /// Disable or re-enable an account. Disabling signs the user out everywhere.
+    /// Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn set_user_disabled(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        disabled: bool,
+    ) -> Result<UserAccountGql> {
+        let state = ctx.data::<AppState>()?;
+        let user = state
+            .services
+            .auth
+            .set_user_disabled(&user_id, disabled)
+            .await?;
+        Ok(user.into())
+    }
+
+    /// Delete an account and sign the user out everywhere. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn delete_user(&self, ctx: &Context<'_>, user_id: ID) -> Result<bool> {
+        let state = ctx.data::<AppState>()?;
+        state.services.auth.delete_user(&user_id).await?;
+        Ok(true)
+    }
+
+    /// Change an account's role, e.g. to promote it to admin. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn set_user_role(
+        &self,
+        ctx: &Context<'_>,
+        user_id: ID,
+        role: RoleGql,
+    ) -> Result<UserAccountGql> {
+        let state = ctx.data::<AppState>()?;
+        let user = state
+            .services
+            .auth
+            .set_user_role(&user_id, role.into())
+            .await?;
+        Ok(user.into())
+    }
+
+    /// Replace an account's password with a temporary one, returned here, that the user
+    /// must change after logging in. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn reset_user_password(&self, ctx: &Context<'_>, user_id: ID) -> Result<String> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.services.auth.reset_user_password(&user_id).await?)
+    }
+}
@@ -0,0 +1,115 @@
+use async_graphql::*;
+
+use crate::graphql::guard::PermissionGuard;
+use crate::state::AppState;
+use beam_auth::utils::models::{Permission, Role};
+use beam_auth::utils::service::{InviteSummary, UserAccount};
+
+#[derive(Enum, Copy, Clone, PartialEq, Eq)]
+#[graphql(name = "Role")]
+pub enum RoleGql {
+    Admin,
+    LibraryManager,
+    Viewer,
+    Guest,
+}
+
+impl From<Role> for RoleGql {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Admin => RoleGql::Admin,
+            Role::LibraryManager => RoleGql::LibraryManager,
+            Role::Viewer => RoleGql::Viewer,
+            Role::Guest => RoleGql::Guest,
+        }
+    }
+}
+
+impl From<RoleGql> for Role {
+    fn from(role: RoleGql) -> Self {
+        match role {
+            RoleGql::Admin => Role::Admin,
+            RoleGql::LibraryManager => Role::LibraryManager,
+            RoleGql::Viewer => Role::Viewer,
+            RoleGql::Guest => Role::Guest,
+        }
+    }
+}
+
+fn timestamp_to_rfc3339(secs: i64) -> String {
+    chrono::DateTime::from_timestamp(secs, 0)
+        .unwrap_or_default()
+        .to_rfc3339()
+}
+
+#[derive(SimpleObject)]
+pub struct UserAccountGql {
+    pub id: ID,
+    pub username: String,
+    pub email: String,
+    pub role: RoleGql,
+    pub disabled: bool,
+    pub password_reset_required: bool,
+    pub created_at: String,
+}
+
+impl From<UserAccount> for UserAccountGql {
+    fn from(user: UserAccount) -> Self {
+        Self {
+            id: user.id.into(),
+            username: user.username,
+            email: user.email,
+            role: user.role.into(),
+            disabled: user.disabled,
+            password_reset_required: user.password_reset_required,
+            created_at: timestamp_to_rfc3339(user.created_at),
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct InviteGql {
+    pub code: String,
+    pub role: RoleGql,
+    pub created_by: Option<ID>,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+    pub used_by: Option<ID>,
+    pub used_at: Option<String>,
+}
+
+impl From<InviteSummary> for InviteGql {
+    fn from(invite: InviteSummary) -> Self {
+        Self {
+            code: invite.code,
+            role: invite.role.into(),
+            created_by: invite.created_by.map(ID::from),
+            created_at: timestamp_to_rfc3339(invite.created_at),
+            expires_at: invite.expires_at.map(timestamp_to_rfc3339),
+            used_by: invite.used_by.map(ID::from),
+            used_at: invite.used_at.map(timestamp_to_rfc3339),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct UserQuery;
+
+#[Object]
+impl UserQuery {
+    /// All user accounts, oldest first. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<UserAccountGql>> {
+        let state = ctx.data::<AppState>()?;
+        let users = state.services.auth.list_users().await?;
+        Ok(users.into_iter().map(UserAccountGql::from).collect())
+    }
+
+    /// All invite codes, newest first. Requires `manage_users`.
+    #[graphql(guard = "PermissionGuard::new(Permission::ManageUsers)")]
+    async fn invites(&self, ctx: &Context<'_>) -> Result<Vec<InviteGql>> {
+        let state = ctx.data::<AppState>()?;
+        let invites = state.services.auth.list_invites().await?;
+        Ok(invites.into_iter().map(InviteGql::from).collect())
+    }
+}
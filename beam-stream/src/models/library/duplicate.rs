@@ -0,0 +1,40 @@
+use async_graphql::{Enum, SimpleObject};
+use salvo::oapi::ToSchema;
+use serde::Serialize;
+
+use super::LibraryFile;
+use crate::models::MediaStreamMetadata;
+
+/// How the files in a [`DuplicateGroup`] were matched
+#[derive(Clone, Copy, Debug, Serialize, ToSchema, Enum, Eq, PartialEq)]
+pub enum DuplicateKind {
+    /// Byte-identical files (same size and content hash)
+    ExactHash,
+    /// Different files of the same movie or episode with similar durations, e.g. other
+    /// qualities of the same cut
+    SameContent,
+}
+
+/// A file within a duplicate group
+#[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
+pub struct DuplicateFile {
+    pub file: LibraryFile,
+    /// Summary of the file's tracks, if it has been probed
+    pub streams: Option<MediaStreamMetadata>,
+}
+
+/// A set of files holding the same content
+#[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
+pub struct DuplicateGroup {
+    pub kind: DuplicateKind,
+    /// Movie or episode the first file belongs to
+    pub media_id: Option<String>,
+    /// Display title of that movie or episode
+    pub title: Option<String>,
+    /// Combined size of all files in bytes
+    pub total_size_bytes: i64,
+    /// Bytes freed by keeping only the first file
+    pub reclaimable_bytes: i64,
+    /// Primary files first, then largest first
+    pub files: Vec<DuplicateFile>,
+}
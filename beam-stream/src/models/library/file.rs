@@ -54,8 +54,12 @@ pub struct LibraryFile {
     pub status: FileIndexStatus,
     /// What kind of content this file represents
     pub content_type: FileContentType,
-    /// When this file was first scanned
+    /// Whether this file is preferred over duplicates of the same content
+    pub is_primary: bool,
+    /// When this file was last scanned
     pub scanned_at: DateTime<Utc>,
+    /// When this file last passed an integrity check
+    pub verified_at: Option<DateTime<Utc>>,
     /// When this file was last updated
     pub updated_at: DateTime<Utc>,
 }
@@ -67,13 +71,17 @@ impl From<beam_domain::models::MediaFile> for LibraryFile {
             library_id,
             path,
             hash: _,
+            hash_strategy: _,
+            partial_hash: _,
             size_bytes,
             mime_type,
             duration,
             container_format,
             status,
             content,
+            is_primary,
             scanned_at,
+            verified_at,
             updated_at,
         } = f;
         let content_type = match &content {
@@ -92,7 +100,9 @@ impl From<beam_domain::models::MediaFile> for LibraryFile {
             container_format,
             status: status.into(),
             content_type,
+            is_primary,
             scanned_at,
+            verified_at,
             updated_at,
         }
     }
@@ -1,3 +1,4 @@
+mod duplicate;
 mod file;
 
 use async_graphql::SimpleObject;
@@ -5,6 +6,7 @@ use chrono::{DateTime, Utc};
 use salvo::oapi::ToSchema;
 use serde::Serialize;
 
+pub use duplicate::*;
 pub use file::*;
 
 #[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
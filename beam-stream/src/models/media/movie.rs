@@ -30,9 +30,22 @@ pub struct MovieMetadata {
     pub ratings: Option<Ratings>,
     /// External identifiers to movie
     pub identifiers: Option<ExternalIdentifiers>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
 
-    /// List of unique streams associated with this movie
+    /// List of unique streams associated with this movie, primary version first
     pub streams: Vec<MediaStreamMetadata>,
     //
     // TODO: Add people involved (cast, crew, directors, writers, etc.)
 }
+
+/// One version of a movie, such as an edition or a resolution, with its own files
+#[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
+pub struct MovieVersion {
+    /// ID of the movie entry backing this version
+    pub id: String,
+    /// Edition label, e.g. "Director's Cut" or "4K"
+    pub edition: Option<String>,
+    /// Whether this is the version played by default
+    pub is_primary: bool,
+}
@@ -18,10 +18,14 @@ pub struct ShowMetadata {
     pub year: Option<u32>,
     /// List of seasons in the show
     pub seasons: Vec<SeasonMetadata>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
 }
 
 #[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
 pub struct SeasonMetadata {
+    /// Season ID
+    pub id: String,
     /// Season number
     pub season_number: u32,
     /// Show dates
@@ -40,6 +44,8 @@ pub struct SeasonMetadata {
     pub ratings: Option<Ratings>,
     /// External identifiers to show
     pub identifiers: Option<ExternalIdentifiers>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
     // Add people involved (cast, crew, directors, writers, etc.)
 }
 
@@ -53,6 +59,8 @@ pub struct ShowDates {
 
 #[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
 pub struct EpisodeMetadata {
+    /// Episode ID
+    pub id: String,
     /// Episode number within the season
     pub episode_number: u32,
     /// Title of the episode
@@ -68,5 +76,7 @@ pub struct EpisodeMetadata {
 
     /// List of unique streams associated with this episode
     pub streams: Vec<MediaStreamMetadata>,
+    /// Fields protected from automated metadata updates
+    pub locked_fields: Vec<String>,
 }
 // TODO: detect discrepancy in video file length to detected episode length
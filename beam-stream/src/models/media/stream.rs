@@ -6,7 +6,7 @@ use rust_decimal::Decimal;
 use salvo::oapi::ToSchema;
 use serde::Serialize;
 
-use super::{OutputAudioCodec, OutputSubtitleCodec, OutputVideoCodec, Resolution};
+use super::{MovieVersion, OutputAudioCodec, OutputSubtitleCodec, OutputVideoCodec, Resolution};
 
 #[derive(Clone, Debug, Serialize, ToSchema, SimpleObject)]
 pub struct MediaStreamMetadata {
@@ -16,6 +16,8 @@ pub struct MediaStreamMetadata {
     pub audio_tracks: Vec<AudioTrack>,
     /// Subtitle tracks
     pub subtitle_tracks: Vec<SubtitleTrack>,
+    /// Movie version these streams come from. Unset for episodes.
+    pub version: Option<MovieVersion>,
 }
 
 impl From<&StreamConfiguration> for MediaStreamMetadata {
@@ -61,6 +63,7 @@ impl From<&StreamConfiguration> for MediaStreamMetadata {
                     is_forced: ss.is_forced,
                 })
                 .collect(),
+            version: None,
         }
     }
 }
@@ -52,9 +52,7 @@ pub async fn graphql_handler(req: &mut Request, depot: &mut Depot, res: &mut Res
         // Get AuthService from state
         match state.services.auth.verify_token(token).await {
             Ok(user) => {
-                user_context = Some(UserContext {
-                    user_id: user.user_id,
-                });
+                user_context = Some(UserContext::from(user));
             }
             Err(e) => {
                 tracing::warn!("Failed to verify token: {}", e);
@@ -43,9 +43,7 @@ pub async fn graphql_ws_handler(req: &mut Request, depot: &mut Depot, res: &mut
                 // Resolve user context from token if present
                 let user_context = if let Some(token) = token {
                     match state.services.auth.verify_token(&token).await {
-                        Ok(user) => Some(UserContext {
-                            user_id: user.user_id,
-                        }),
+                        Ok(user) => Some(UserContext::from(user)),
                         Err(e) => {
                             tracing::warn!("WS auth token invalid: {}", e);
                             None
@@ -0,0 +1,181 @@
+use crate::services::image::{ImageError, ImageFormatRequest, ImageRequest};
+use crate::services::library::{LibraryError, LibraryScope};
+use crate::state::AppState;
+use salvo::oapi::ToResponses;
+use salvo::prelude::*;
+use tracing::error;
+
+// ── Error enums ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, ToResponses)]
+pub enum GetImageError {
+    /// Bad request
+    #[salvo(response(status_code = 400))]
+    BadRequest(String),
+    /// Missing or invalid token
+    #[salvo(response(status_code = 401))]
+    Unauthorized(String),
+    /// Image not found
+    #[salvo(response(status_code = 404))]
+    NotFound(String),
+    /// Internal server error
+    #[salvo(response(status_code = 500))]
+    InternalError(String),
+}
+
+#[async_trait]
+impl Writer for GetImageError {
+    async fn write(self, _req: &mut Request, _depot: &mut Depot, res: &mut Response) {
+        match self {
+            Self::BadRequest(msg) => {
+                res.status_code(StatusCode::BAD_REQUEST);
+                res.render(Text::Plain(msg));
+            }
+            Self::Unauthorized(msg) => {
+                res.status_code(StatusCode::UNAUTHORIZED);
+                res.render(Text::Plain(msg));
+            }
+            Self::NotFound(msg) => {
+                res.status_code(StatusCode::NOT_FOUND);
+                res.render(Text::Plain(msg));
+            }
+            Self::InternalError(msg) => {
+                res.status_code(StatusCode::INTERNAL_SERVER_ERROR);
+                res.render(Text::Plain(msg));
+            }
+        }
+    }
+}
+
+// ── Endpoints ─────────────────────────────────────────────────────────────────
+
+/// Get artwork (poster, backdrop or thumbnail), optionally resized and re-encoded
+#[endpoint(
+    tags("media"),
+    parameters(
+        ("id" = String, description = "Artwork ID"),
+        ("Authorization" = Option<String>, Header, description = "Bearer <user JWT, API key or stream token>"),
+        ("token" = Option<String>, Query, description = "Stream token for clients that cannot set headers, e.g. <img> tags"),
+        ("w" = Option<u32>, Query, description = "Maximum width in pixels, rounded up to a fixed size"),
+        ("h" = Option<u32>, Query, description = "Maximum height in pixels, rounded up to a fixed size"),
+        ("format" = Option<String>, Query, description = "Output format: jpeg (default) or webp")
+    ),
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_image(
+    req: &mut Request,
+    depot: &mut Depot,
+    res: &mut Response,
+) -> Result<(), GetImageError> {
+    let state = depot.obtain::<AppState>().unwrap();
+    let id: String = req.param::<String>("id").unwrap_or_default();
+
+    // The query string leaks into logs and referrers, so it only carries short-lived stream
+    // tokens for clients that cannot set headers; the header also takes user tokens and API keys
+    let user_id = if let Some(token) = req.query::<String>("token") {
+        state
+            .services
+            .auth
+            .verify_stream_token(&token)
+            .await
+            .map(|grant| grant.user_id)
+            .map_err(|_| GetImageError::Unauthorized("Invalid or expired token".into()))?
+    } else {
+        let token = req
+            .headers()
+            .get("Authorization")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .ok_or_else(|| GetImageError::Unauthorized("Missing token".into()))?;
+        match state.services.auth.verify_token(&token).await {
+            Ok(user) => user.user_id,
+            Err(_) => state
+                .services
+                .auth
+                .verify_stream_token(&token)
+                .await
+                .map(|grant| grant.user_id)
+                .map_err(|_| GetImageError::Unauthorized("Invalid or expired token".into()))?,
+        }
+    };
+
+    // Artwork of media in libraries the user may not see is reported as missing
+    let scope = match state.services.library.library_scope(user_id).await {
+        Ok(scope) => scope,
+        Err(LibraryError::UserNotFound) => {
+            return Err(GetImageError::Unauthorized("Unknown user".into()));
+        }
+        Err(e) => {
+            error!("Failed to look up library access: {:?}", e);
+            return Err(GetImageError::InternalError(
+                "Failed to look up library access".into(),
+            ));
+        }
+    };
+    if scope != LibraryScope::All {
+        let library_ids = state
+            .services
+            .library
+            .get_artwork_library_ids(id.clone())
+            .await
+            .map_err(|e| {
+                error!("Failed to look up libraries of image {}: {:?}", id, e);
+                GetImageError::InternalError("Failed to look up image".into())
+            })?;
+        if !scope.allows_any(&library_ids) {
+            return Err(GetImageError::NotFound("Image not found".into()));
+        }
+    }
+
+    let dimension = |name: &str| -> Result<Option<u32>, GetImageError> {
+        match req.query::<String>(name) {
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| GetImageError::BadRequest(format!("Invalid value for '{name}'"))),
+            None => Ok(None),
+        }
+    };
+    let format = match req.query::<String>("format") {
+        Some(value) => ImageFormatRequest::parse(&value)
+            .ok_or_else(|| GetImageError::BadRequest("Unsupported image format".into()))?,
+        None => ImageFormatRequest::default(),
+    };
+    let request = ImageRequest {
+        width: dimension("w")?,
+        height: dimension("h")?,
+        format,
+    };
+
+    let path = state
+        .services
+        .image
+        .render(&id, request)
+        .await
+        .map_err(|e| match e {
+            ImageError::NotFound => GetImageError::NotFound("Image not found".into()),
+            ImageError::InvalidRequest(msg) => GetImageError::BadRequest(msg),
+            e => {
+                error!("Failed to render image {}: {:?}", id, e);
+                GetImageError::InternalError("Failed to render image".into())
+            }
+        })?;
+
+    let bytes = tokio::fs::read(&path).await.map_err(|e| {
+        error!("Failed to read image {:?}: {:?}", path, e);
+        GetImageError::InternalError("Failed to read image".into())
+    })?;
+
+    res.headers_mut()
+        .insert("Content-Type", format.content_type().parse().unwrap());
+    // Artwork ids are content hashes, so a given URL never changes. Shared caches must
+    // not keep it since access depends on the user.
+    res.headers_mut().insert(
+        "Cache-Control",
+        "private, max-age=31536000, immutable".parse().unwrap(),
+    );
+    res.write_body(bytes).ok();
+
+    Ok(())
+}
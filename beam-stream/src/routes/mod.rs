@@ -1,17 +1,19 @@
 pub mod graphql;
 pub mod graphql_ws;
 pub mod health;
+pub mod image;
 pub mod stream;
 
 use salvo::prelude::*;
 
 pub use health::*;
+pub use image::*;
 pub use stream::*;
 
 use crate::graphql::AppSchema;
 use crate::state::AppState;
 
-/// REST-only sub-routes (health, stream, auth). Single source of truth used by
+/// REST-only sub-routes (health, stream, downloads, images, auth). Single source of truth used by
 /// both `create_router` and `create_docs_router` so new endpoints only need to
 /// be registered in one place.
 fn rest_routes() -> Router {
@@ -19,27 +21,35 @@ fn rest_routes() -> Router {
         .push(Router::with_path("health").get(health_check))
         .push(Router::with_path("stream/{id}/token").post(get_stream_token))
         .push(Router::with_path("stream/mp4/{id}").get(stream_mp4))
+        .push(Router::with_path("download/{id}").get(download_original))
+        .push(Router::with_path("images/{id}").get(get_image))
         .push(Router::with_path("auth").push(beam_auth::server::auth_routes()))
 }
 
 /// Create the main API router with all routes
 pub fn create_router(state: AppState, schema: AppSchema) -> Router {
-    // Note: No authorization is done at the top-level here because only `graphql` is secured with auth; other endpoints are either public or self-contained (e.g., stream token validated in the handler).
-    Router::new().hoop(affix_state::inject(state)).push(
-        Router::with_path("v1")
-            .push(rest_routes())
-            .push(
-                Router::with_path("graphql")
-                    .hoop(affix_state::inject(schema.clone()))
-                    .get(graphql::graphiql)
-                    .post(graphql::graphql_handler),
-            )
-            .push(
-                Router::with_path("graphql/ws")
-                    .hoop(affix_state::inject(schema))
-                    .get(graphql_ws::graphql_ws_handler),
-            ),
-    )
+    // Note: No authorization is done at the top-level here because only `graphql` is secured with auth; other endpoints are either public or self-contained (e.g., stream and image tokens validated in the handler).
+    // The auth routes look up the auth service on its own
+    let auth = state.services.auth.clone();
+    Router::new()
+        .hoop(affix_state::inject(state))
+        .hoop(affix_state::inject(auth))
+        .push(beam_auth::server::jwks_routes())
+        .push(
+            Router::with_path("v1")
+                .push(rest_routes())
+                .push(
+                    Router::with_path("graphql")
+                        .hoop(affix_state::inject(schema.clone()))
+                        .get(graphql::graphiql)
+                        .post(graphql::graphql_handler),
+                )
+                .push(
+                    Router::with_path("graphql/ws")
+                        .hoop(affix_state::inject(schema))
+                        .get(graphql_ws::graphql_ws_handler),
+                ),
+        )
 }
 
 /// Create a minimal router for OpenAPI documentation export.
@@ -1,9 +1,9 @@
 use async_trait::async_trait;
 use tonic::transport::Channel;
 
-use beam_index::proto::ScanLibraryRequest;
 use beam_index::proto::index_service_client::IndexServiceClient;
-use beam_index::services::index::{IndexError, IndexService};
+use beam_index::proto::{ScanLibraryRequest, VerifyLibraryRequest};
+use beam_index::services::index::{IndexError, IndexService, VerifyOptions};
 
 #[derive(Debug, Clone)]
 pub struct GrpcIndexService {
@@ -33,4 +33,22 @@ impl IndexService for GrpcIndexService {
             .map_err(|s| IndexError::PathNotFound(s.to_string()))?;
         Ok(response.into_inner().files_added)
     }
+
+    async fn verify_library(
+        &self,
+        library_id: String,
+        options: VerifyOptions,
+    ) -> Result<(), IndexError> {
+        self.client
+            .clone()
+            .verify_library(VerifyLibraryRequest {
+                library_id,
+                limit: options.limit,
+                upgrade_hashes: options.upgrade_hashes,
+                check_decode: options.check_decode,
+            })
+            .await
+            .map_err(|s| IndexError::PathNotFound(s.to_string()))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use image::ImageFormat;
+use image::imageops::FilterType;
+use thiserror::Error;
+use tracing::trace;
+
+use beam_index::services::artwork::{ARTWORK_DIR, artwork_path, is_valid_artwork_id};
+
+/// Largest dimension a resized variant may request
+pub const MAX_IMAGE_DIMENSION: u32 = 4096;
+
+/// Dimensions variants are rendered at. Requests round up to the next one so clients
+/// cannot fill the cache with a variant for every pixel size.
+pub const IMAGE_SIZES: [u32; 11] = [
+    100,
+    200,
+    300,
+    400,
+    600,
+    800,
+    1200,
+    1600,
+    2400,
+    3200,
+    MAX_IMAGE_DIMENSION,
+];
+
+#[derive(Debug, Error)]
+pub enum ImageError {
+    #[error("Image not found")]
+    NotFound,
+    #[error("Invalid image request: {0}")]
+    InvalidRequest(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Image processing error: {0}")]
+    Processing(#[from] image::ImageError),
+}
+
+/// Output encoding of a rendered image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageFormatRequest {
+    #[default]
+    Jpeg,
+    Webp,
+}
+
+impl ImageFormatRequest {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Jpeg => ImageFormat::Jpeg,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+/// Requested rendition of a cached artwork image. The image is scaled to fit within
+/// `width` x `height`, each rounded up to one of [`IMAGE_SIZES`] (preserving aspect
+/// ratio), and is never upscaled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImageRequest {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub format: ImageFormatRequest,
+}
+
+#[async_trait::async_trait]
+pub trait ImageService: Send + Sync + std::fmt::Debug {
+    /// Render a cached artwork image, returning the path of the encoded variant
+    async fn render(&self, id: &str, request: ImageRequest) -> Result<PathBuf, ImageError>;
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalImageService {
+    cache_dir: PathBuf,
+}
+
+impl LocalImageService {
+    pub fn new(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+
+    fn variant_path(&self, id: &str, request: &ImageRequest) -> PathBuf {
+        let dimension = |d: Option<u32>| d.map(|d| d.to_string()).unwrap_or_default();
+        self.cache_dir
+            .join(ARTWORK_DIR)
+            .join("resized")
+            .join(format!(
+                "{id}_{}x{}.{}",
+                dimension(request.width),
+                dimension(request.height),
+                request.format.extension()
+            ))
+    }
+}
+
+#[async_trait::async_trait]
+impl ImageService for LocalImageService {
+    async fn render(&self, id: &str, request: ImageRequest) -> Result<PathBuf, ImageError> {
+        if !is_valid_artwork_id(id) {
+            return Err(ImageError::NotFound);
+        }
+        for dimension in [request.width, request.height].into_iter().flatten() {
+            if dimension == 0 || dimension > MAX_IMAGE_DIMENSION {
+                return Err(ImageError::InvalidRequest(format!(
+                    "dimensions must be between 1 and {MAX_IMAGE_DIMENSION}"
+                )));
+            }
+        }
+
+        let snap = |dimension: u32| {
+            IMAGE_SIZES
+                .into_iter()
+                .find(|size| *size >= dimension)
+                .unwrap_or(MAX_IMAGE_DIMENSION)
+        };
+        let request = ImageRequest {
+            width: request.width.map(snap),
+            height: request.height.map(snap),
+            ..request
+        };
+
+        let source = artwork_path(&self.cache_dir, id);
+        if !tokio::fs::try_exists(&source).await? {
+            return Err(ImageError::NotFound);
+        }
+
+        let variant = self.variant_path(id, &request);
+        if tokio::fs::try_exists(&variant).await? {
+            trace!("Using cached image variant: {:?}", variant);
+            return Ok(variant);
+        }
+
+        let output = variant.clone();
+        tokio::task::spawn_blocking(move || render_variant(&source, &output, request))
+            .await
+            .map_err(|e| std::io::Error::other(format!("Blocking task join error: {e}")))??;
+
+        Ok(variant)
+    }
+}
+
+fn render_variant(source: &Path, output: &Path, request: ImageRequest) -> Result<(), ImageError> {
+    let mut image = image::ImageReader::open(source)?
+        .with_guessed_format()?
+        .decode()?;
+
+    let max_width = request.width.unwrap_or(u32::MAX).min(image.width());
+    let max_height = request.height.unwrap_or(u32::MAX).min(image.height());
+    if max_width < image.width() || max_height < image.height() {
+        image = image.resize(max_width, max_height, FilterType::Lanczos3);
+    }
+    // Both encoders only accept 8-bit input, and JPEG has no alpha channel
+    let image = match request.format {
+        ImageFormatRequest::Jpeg => image::DynamicImage::ImageRgb8(image.to_rgb8()),
+        ImageFormatRequest::Webp => image::DynamicImage::ImageRgba8(image.to_rgba8()),
+    };
+
+    let dir = output.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(dir)?;
+    // Write to a temp file of our own, then rename, so concurrent requests for the same
+    // variant never serve or clobber a partial file
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    image.write_to(
+        &mut std::io::BufWriter::new(tmp.as_file_mut()),
+        request.format.image_format(),
+    )?;
+    tmp.persist(output).map_err(|e| e.error)?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[path = "image_tests.rs"]
+mod image_tests;
@@ -0,0 +1,158 @@
+#[cfg(test)]
+mod tests {
+    use crate::services::image::{
+        ImageError, ImageFormatRequest, ImageRequest, ImageService, LocalImageService,
+    };
+    use beam_index::services::artwork::{artwork_id, artwork_path};
+    use tempfile::TempDir;
+
+    /// Store a solid-colour PNG in the artwork cache, returning its id.
+    fn store_png(cache_dir: &TempDir, width: u32, height: u32) -> String {
+        let image = image::RgbImage::from_pixel(width, height, image::Rgb([200, 100, 50]));
+        let mut bytes = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut bytes, image::ImageFormat::Png).unwrap();
+        let bytes = bytes.into_inner();
+
+        let id = artwork_id(&bytes);
+        let path = artwork_path(cache_dir.path(), &id);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+        id
+    }
+
+    #[tokio::test]
+    async fn test_render_resizes_within_bounds() {
+        let cache = TempDir::new().unwrap();
+        let id = store_png(&cache, 400, 600);
+        let service = LocalImageService::new(cache.path().to_path_buf());
+
+        let path = service
+            .render(
+                &id,
+                ImageRequest {
+                    width: Some(200),
+                    height: None,
+                    format: ImageFormatRequest::Jpeg,
+                },
+            )
+            .await
+            .unwrap();
+
+        let rendered = image::open(&path).unwrap();
+        assert_eq!((rendered.width(), rendered.height()), (200, 300));
+        assert_eq!(
+            image::ImageFormat::from_path(&path).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+    }
+
+    #[tokio::test]
+    async fn test_render_snaps_dimensions_to_fixed_sizes() {
+        let cache = TempDir::new().unwrap();
+        let id = store_png(&cache, 800, 800);
+        let service = LocalImageService::new(cache.path().to_path_buf());
+        let request = |width| ImageRequest {
+            width: Some(width),
+            height: None,
+            format: ImageFormatRequest::Jpeg,
+        };
+
+        let first = service.render(&id, request(301)).await.unwrap();
+        let second = service.render(&id, request(399)).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(image::open(&first).unwrap().width(), 400);
+    }
+
+    #[tokio::test]
+    async fn test_render_never_upscales() {
+        let cache = TempDir::new().unwrap();
+        let id = store_png(&cache, 40, 30);
+        let service = LocalImageService::new(cache.path().to_path_buf());
+
+        let path = service
+            .render(
+                &id,
+                ImageRequest {
+                    width: Some(400),
+                    height: Some(300),
+                    format: ImageFormatRequest::Webp,
+                },
+            )
+            .await
+            .unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(
+            image::guess_format(&bytes).unwrap(),
+            image::ImageFormat::WebP
+        );
+        let rendered = image::load_from_memory(&bytes).unwrap();
+        assert_eq!((rendered.width(), rendered.height()), (40, 30));
+    }
+
+    #[tokio::test]
+    async fn test_render_reuses_cached_variant() {
+        let cache = TempDir::new().unwrap();
+        let id = store_png(&cache, 100, 100);
+        let service = LocalImageService::new(cache.path().to_path_buf());
+        let request = ImageRequest {
+            width: Some(50),
+            height: Some(50),
+            format: ImageFormatRequest::Jpeg,
+        };
+
+        let first = service.render(&id, request).await.unwrap();
+        std::fs::remove_file(artwork_path(cache.path(), &id)).unwrap();
+        std::fs::write(&first, b"cached").unwrap();
+
+        // The original is gone, so only a cache hit can succeed
+        let result = service.render(&id, request).await;
+        assert!(matches!(result, Err(ImageError::NotFound)));
+
+        let id = store_png(&cache, 100, 100);
+        let second = service.render(&id, request).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(std::fs::read(&second).unwrap(), b"cached");
+    }
+
+    #[tokio::test]
+    async fn test_render_rejects_invalid_requests() {
+        let cache = TempDir::new().unwrap();
+        let id = store_png(&cache, 10, 10);
+        let service = LocalImageService::new(cache.path().to_path_buf());
+
+        let result = service
+            .render("../../etc/passwd", ImageRequest::default())
+            .await;
+        assert!(matches!(result, Err(ImageError::NotFound)));
+
+        let result = service
+            .render("0000000000000000", ImageRequest::default())
+            .await;
+        assert!(matches!(result, Err(ImageError::NotFound)));
+
+        let result = service
+            .render(
+                &id,
+                ImageRequest {
+                    width: Some(0),
+                    ..Default::default()
+                },
+            )
+            .await;
+        assert!(matches!(result, Err(ImageError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(
+            ImageFormatRequest::parse("WEBP"),
+            Some(ImageFormatRequest::Webp)
+        );
+        assert_eq!(
+            ImageFormatRequest::parse("jpg"),
+            Some(ImageFormatRequest::Jpeg)
+        );
+        assert_eq!(ImageFormatRequest::parse("gif"), None);
+    }
+}
@@ -0,0 +1,133 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use beam_auth::utils::keys::{JwkSet, KeyError, KeySource, TokenKeys};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::{debug, warn};
+
+/// How often the issuer's keys are fetched again, to pick up rotated keys
+pub const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How soon a failed fetch is retried
+const JWKS_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How often tokens naming unknown keys may trigger a fetch
+const JWKS_REFETCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Upper bound on a JWKS request, so a hung issuer cannot stall token checks
+const JWKS_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Error)]
+pub enum JwksError {
+    #[error("Failed to fetch JWKS: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Failed to read JWKS file {0}: {1}")]
+    Io(String, std::io::Error),
+    #[error("Invalid JWKS: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("Invalid key in JWKS: {0}")]
+    Keys(#[from] KeyError),
+}
+
+type Result<T> = std::result::Result<T, JwksError>;
+
+/// Fetches the public keys published at an issuer's `/.well-known/jwks.json`.
+pub async fn fetch_jwks(client: &reqwest::Client, url: &str) -> Result<JwkSet> {
+    let body = client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Reads a JWKS document, e.g. one saved from beam-auth's `/.well-known/jwks.json`.
+pub fn read_jwks(path: &Path) -> Result<JwkSet> {
+    let body = std::fs::read(path).map_err(|e| JwksError::Io(path.display().to_string(), e))?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+/// Verify-only keys that follow the JWKS at `url`.
+///
+/// The keys are fetched once before returning and then every [`JWKS_REFRESH_INTERVAL`],
+/// and again when a token names an unknown key, at most every [`JWKS_REFETCH_INTERVAL`].
+/// If the issuer cannot be reached, no token verifies until a retry succeeds.
+pub async fn remote_token_keys(url: String) -> Arc<TokenKeys> {
+    let source = Arc::new(RemoteJwks {
+        url,
+        client: reqwest::Client::builder()
+            .timeout(JWKS_TIMEOUT)
+            .build()
+            .expect("HTTP client configuration is valid"),
+        last_refetch: Mutex::new(None),
+    });
+    let keys = Arc::new(
+        TokenKeys::from_jwks(JwkSet { keys: Vec::new() })
+            .expect("empty JWKS")
+            .with_source(source.clone()),
+    );
+
+    let mut next = source.refresh(&keys).await;
+    tokio::spawn({
+        let keys = keys.clone();
+        async move {
+            loop {
+                tokio::time::sleep(next).await;
+                next = source.refresh(&keys).await;
+            }
+        }
+    });
+    keys
+}
+
+#[derive(Debug)]
+struct RemoteJwks {
+    url: String,
+    client: reqwest::Client,
+    /// When a token naming an unknown key last caused a fetch. Held during that fetch, so
+    /// concurrent requests with the new key wait for it instead of fetching again.
+    last_refetch: Mutex<Option<Instant>>,
+}
+
+impl RemoteJwks {
+    /// Replaces the keys with the issuer's current set and returns when to refresh next.
+    async fn refresh(&self, keys: &TokenKeys) -> Duration {
+        let result = match fetch_jwks(&self.client, &self.url).await {
+            Ok(jwks) => keys.set_jwks(jwks).map_err(JwksError::from),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => {
+                debug!("Refreshed access token keys from {}", self.url);
+                JWKS_REFRESH_INTERVAL
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to refresh access token keys from {}: {}",
+                    self.url, e
+                );
+                JWKS_RETRY_INTERVAL
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl KeySource for RemoteJwks {
+    async fn unknown_key(&self, keys: &TokenKeys) {
+        let mut last_refetch = self.last_refetch.lock().await;
+        if last_refetch.is_some_and(|at| at.elapsed() < JWKS_REFETCH_INTERVAL) {
+            return;
+        }
+        *last_refetch = Some(Instant::now());
+        self.refresh(keys).await;
+    }
+}
+
+#[cfg(test)]
+#[path = "jwks_tests.rs"]
+mod jwks_tests;
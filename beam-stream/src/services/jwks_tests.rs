@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use beam_auth::utils::keys::{PrivateKey, TokenKeys, TokenKind};
+    use serde::{Deserialize, Serialize};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use crate::services::jwks::{read_jwks, remote_token_keys};
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn issuer() -> TokenKeys {
+        TokenKeys::from_private_keys(&[PrivateKey::generate_ed25519()]).unwrap()
+    }
+
+    fn token(issuer: &TokenKeys) -> String {
+        issuer
+            .sign(
+                TokenKind::Access,
+                &TestClaims {
+                    sub: "user-1".to_string(),
+                    exp: (chrono::Utc::now().timestamp() + 60) as usize,
+                },
+            )
+            .unwrap()
+    }
+
+    /// Serves `body` as JSON to every request and returns the URL.
+    async fn serve_json(body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}/.well-known/jwks.json")
+    }
+
+    #[test]
+    fn test_keys_from_jwks_file_verify_issuer_tokens() {
+        let issuer = issuer();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), serde_json::to_vec(&issuer.jwks()).unwrap()).unwrap();
+
+        let keys = TokenKeys::from_jwks(read_jwks(file.path()).unwrap()).unwrap();
+        assert!(
+            keys.verify::<TestClaims>(TokenKind::Access, &token(&issuer))
+                .is_ok()
+        );
+        assert!(!keys.can_sign());
+    }
+
+    #[test]
+    fn test_read_jwks_rejects_invalid_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "not json").unwrap();
+        assert!(read_jwks(file.path()).is_err());
+        assert!(read_jwks(std::path::Path::new("/nonexistent/jwks.json")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_keys_are_fetched_from_issuer() {
+        let issuer = issuer();
+        let url = serve_json(serde_json::to_string(&issuer.jwks()).unwrap()).await;
+
+        let keys = remote_token_keys(url).await;
+        assert!(
+            keys.verify::<TestClaims>(TokenKind::Access, &token(&issuer))
+                .is_ok()
+        );
+        assert!(
+            keys.verify::<TestClaims>(TokenKind::Access, &token(&self::issuer()))
+                .is_err()
+        );
+    }
+
+    /// Serves whatever `body` holds at the time of each request and counts the requests.
+    async fn serve_rotating_json(body: Arc<Mutex<String>>, requests: Arc<AtomicUsize>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                requests.fetch_add(1, Ordering::SeqCst);
+                let body = body.lock().unwrap().clone();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{addr}/.well-known/jwks.json")
+    }
+
+    /// A token signed with a key rotated in since the last fetch triggers one refetch, and
+    /// further unknown keys don't hammer the issuer.
+    #[tokio::test]
+    async fn test_unknown_kid_refetches_keys_rate_limited() {
+        let old = issuer();
+        let body = Arc::new(Mutex::new(serde_json::to_string(&old.jwks()).unwrap()));
+        let requests = Arc::new(AtomicUsize::new(0));
+        let url = serve_rotating_json(body.clone(), requests.clone()).await;
+        let keys = remote_token_keys(url).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let rotated = issuer();
+        *body.lock().unwrap() = serde_json::to_string(&rotated.jwks()).unwrap();
+        let rotated_token = token(&rotated);
+        keys.refresh_for(&rotated_token).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        assert!(
+            keys.verify::<TestClaims>(TokenKind::Access, &rotated_token)
+                .is_ok()
+        );
+
+        // Known keys and repeated unknown ones within the interval don't fetch again
+        keys.refresh_for(&rotated_token).await;
+        keys.refresh_for(&token(&issuer())).await;
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_issuer_verifies_nothing() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/jwks.json", listener.local_addr().unwrap());
+        drop(listener);
+
+        let keys = remote_token_keys(url).await;
+        assert!(keys.jwks().keys.is_empty());
+        assert!(
+            keys.verify::<TestClaims>(TokenKind::Access, &token(&issuer()))
+                .is_err()
+        );
+    }
+}
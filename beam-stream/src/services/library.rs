@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -8,8 +9,11 @@ use uuid::Uuid;
 
 use crate::models::{Library, LibraryFile};
 use crate::services::notification::{AdminEvent, EventCategory, NotificationService};
+use beam_auth::utils::models::Permission;
+use beam_auth::utils::repository::UserRepository;
 use beam_domain::models::Library as DomainLibrary;
-use beam_index::services::index::{IndexError, IndexService};
+use beam_index::services::artwork::artwork_url;
+use beam_index::services::index::{IndexError, IndexService, VerifyOptions};
 
 pub trait PathValidator: Send + Sync + std::fmt::Debug {
     /// Validates that `requested` is within `root`, returning the canonical absolute path.
@@ -108,12 +112,59 @@ impl PathValidator for InMemoryPathValidator {
     }
 }
 
+/// Libraries a user may see
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LibraryScope {
+    /// Every library. Admins see everything.
+    All,
+    /// Only the libraries the user has been granted access to
+    Only(HashSet<Uuid>),
+}
+
+impl LibraryScope {
+    pub fn allows(&self, library_id: Uuid) -> bool {
+        match self {
+            LibraryScope::All => true,
+            LibraryScope::Only(ids) => ids.contains(&library_id),
+        }
+    }
+
+    /// Whether any of the given libraries is visible, e.g. for media shared across
+    /// libraries
+    pub fn allows_any(&self, library_ids: &[Uuid]) -> bool {
+        library_ids.iter().any(|id| self.allows(*id))
+    }
+}
+
 #[async_trait::async_trait]
 pub trait LibraryService: Send + Sync + std::fmt::Debug {
-    /// Get all libraries by user ID
-    /// Returns None if user is not found
+    /// Get all libraries visible to a user
+    /// Returns `UserNotFound` if user is not found
     async fn get_libraries(&self, user_id: String) -> Result<Vec<Library>, LibraryError>;
 
+    /// Libraries a user may see
+    async fn library_scope(&self, user_id: String) -> Result<LibraryScope, LibraryError>;
+
+    /// Libraries holding media that uses an artwork image
+    async fn get_artwork_library_ids(&self, artwork_id: String) -> Result<Vec<Uuid>, LibraryError>;
+
+    /// Users granted access to a library
+    async fn get_library_user_ids(&self, library_id: String) -> Result<Vec<String>, LibraryError>;
+
+    /// Grant a user access to a library
+    async fn grant_library_access(
+        &self,
+        library_id: String,
+        user_id: String,
+    ) -> Result<(), LibraryError>;
+
+    /// Revoke a user's access to a library
+    async fn revoke_library_access(
+        &self,
+        library_id: String,
+        user_id: String,
+    ) -> Result<(), LibraryError>;
+
     /// Get a single library by ID
     async fn get_library_by_id(&self, library_id: String) -> Result<Option<Library>, LibraryError>;
 
@@ -134,6 +185,13 @@ pub trait LibraryService: Send + Sync + std::fmt::Debug {
     /// Scan a library for new content
     async fn scan_library(&self, library_id: String) -> Result<u32, LibraryError>;
 
+    /// Start checking a library's files against their stored hashes in the background
+    async fn verify_library(
+        &self,
+        library_id: String,
+        options: VerifyOptions,
+    ) -> Result<(), LibraryError>;
+
     /// Delete a library by ID
     async fn delete_library(&self, library_id: String) -> Result<bool, LibraryError>;
 }
@@ -142,6 +200,7 @@ pub trait LibraryService: Send + Sync + std::fmt::Debug {
 pub struct LocalLibraryService {
     library_repo: Arc<dyn beam_domain::repositories::LibraryRepository>,
     file_repo: Arc<dyn beam_domain::repositories::FileRepository>,
+    user_repo: Arc<dyn UserRepository>,
     video_dir: PathBuf,
     notification_service: Arc<dyn NotificationService>,
     index_service: Arc<dyn IndexService>,
@@ -152,6 +211,7 @@ impl LocalLibraryService {
     pub fn new(
         library_repo: Arc<dyn beam_domain::repositories::LibraryRepository>,
         file_repo: Arc<dyn beam_domain::repositories::FileRepository>,
+        user_repo: Arc<dyn UserRepository>,
         video_dir: PathBuf,
         notification_service: Arc<dyn NotificationService>,
         index_service: Arc<dyn IndexService>,
@@ -160,21 +220,43 @@ impl LocalLibraryService {
         LocalLibraryService {
             library_repo,
             file_repo,
+            user_repo,
             video_dir,
             notification_service,
             index_service,
             path_validator,
         }
     }
+
+    async fn find_library(&self, library_id: &str) -> Result<DomainLibrary, LibraryError> {
+        let lib_uuid = Uuid::parse_str(library_id).map_err(|_| LibraryError::InvalidId)?;
+        self.library_repo
+            .find_by_id(lib_uuid)
+            .await?
+            .ok_or(LibraryError::LibraryNotFound)
+    }
+
+    async fn find_user_id(&self, user_id: &str) -> Result<Uuid, LibraryError> {
+        let user_uuid = Uuid::parse_str(user_id).map_err(|_| LibraryError::UserNotFound)?;
+        self.user_repo
+            .find_by_id(user_uuid)
+            .await?
+            .map(|user| user.id)
+            .ok_or(LibraryError::UserNotFound)
+    }
 }
 
 #[async_trait::async_trait]
 impl LibraryService for LocalLibraryService {
-    async fn get_libraries(&self, _user_id: String) -> Result<Vec<Library>, LibraryError> {
+    async fn get_libraries(&self, user_id: String) -> Result<Vec<Library>, LibraryError> {
+        let scope = self.library_scope(user_id).await?;
         let domain_libraries = self.library_repo.find_all().await?;
 
         let mut result = Vec::new();
-        for lib in domain_libraries {
+        for lib in domain_libraries
+            .into_iter()
+            .filter(|lib| scope.allows(lib.id))
+        {
             let DomainLibrary {
                 id,
                 name,
@@ -202,6 +284,56 @@ impl LibraryService for LocalLibraryService {
         Ok(result)
     }
 
+    async fn library_scope(&self, user_id: String) -> Result<LibraryScope, LibraryError> {
+        let user_uuid = Uuid::parse_str(&user_id).map_err(|_| LibraryError::UserNotFound)?;
+        let user = self
+            .user_repo
+            .find_by_id(user_uuid)
+            .await?
+            .ok_or(LibraryError::UserNotFound)?;
+        let permissions = self.user_repo.find_role_permissions(user.role).await?;
+        if permissions.contains(&Permission::ManageLibraries) {
+            return Ok(LibraryScope::All);
+        }
+        let ids = self.library_repo.find_ids_by_user(user.id).await?;
+        Ok(LibraryScope::Only(ids.into_iter().collect()))
+    }
+
+    async fn get_artwork_library_ids(&self, artwork_id: String) -> Result<Vec<Uuid>, LibraryError> {
+        Ok(self
+            .library_repo
+            .find_ids_by_artwork(&artwork_url(&artwork_id))
+            .await?)
+    }
+
+    async fn get_library_user_ids(&self, library_id: String) -> Result<Vec<String>, LibraryError> {
+        let library = self.find_library(&library_id).await?;
+        let user_ids = self.library_repo.find_user_ids(library.id).await?;
+        Ok(user_ids.into_iter().map(|id| id.to_string()).collect())
+    }
+
+    async fn grant_library_access(
+        &self,
+        library_id: String,
+        user_id: String,
+    ) -> Result<(), LibraryError> {
+        let library = self.find_library(&library_id).await?;
+        let user_id = self.find_user_id(&user_id).await?;
+        self.library_repo.grant_access(library.id, user_id).await?;
+        Ok(())
+    }
+
+    async fn revoke_library_access(
+        &self,
+        library_id: String,
+        user_id: String,
+    ) -> Result<(), LibraryError> {
+        let library = self.find_library(&library_id).await?;
+        let user_id = self.find_user_id(&user_id).await?;
+        self.library_repo.revoke_access(library.id, user_id).await?;
+        Ok(())
+    }
+
     async fn get_library_by_id(&self, library_id: String) -> Result<Option<Library>, LibraryError> {
         let lib_uuid = Uuid::parse_str(&library_id).map_err(|_| LibraryError::InvalidId)?;
         let library = self.library_repo.find_by_id(lib_uuid).await?;
@@ -300,6 +432,17 @@ impl LibraryService for LocalLibraryService {
             .map_err(LibraryError::from)
     }
 
+    async fn verify_library(
+        &self,
+        library_id: String,
+        options: VerifyOptions,
+    ) -> Result<(), LibraryError> {
+        self.index_service
+            .verify_library(library_id, options)
+            .await
+            .map_err(LibraryError::from)
+    }
+
     async fn delete_library(&self, library_id: String) -> Result<bool, LibraryError> {
         let lib_uuid = Uuid::parse_str(&library_id).map_err(|_| LibraryError::InvalidId)?;
 
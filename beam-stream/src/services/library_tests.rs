@@ -4,6 +4,9 @@ mod tests {
         InMemoryPathValidator, LibraryError, LibraryService, LocalLibraryService,
     };
     use crate::services::notification::{InMemoryNotificationService, NotificationService};
+    use beam_auth::utils::models::{CreateUser, Role};
+    use beam_auth::utils::repository::UserRepository;
+    use beam_auth::utils::repository::in_memory::InMemoryUserRepository;
     use beam_domain::models::{FileStatus, Library as DomainLibrary, MediaFile};
     use beam_domain::repositories::file::MockFileRepository;
     use beam_domain::repositories::file::in_memory::InMemoryFileRepository;
@@ -26,6 +29,7 @@ mod tests {
         LocalLibraryService::new(
             Arc::new(mock_library_repo),
             Arc::new(mock_file_repo),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(mock_index_service),
@@ -33,6 +37,20 @@ mod tests {
         )
     }
 
+    async fn seed_user(user_repo: &InMemoryUserRepository, username: &str, role: Role) -> String {
+        user_repo
+            .create(CreateUser {
+                username: username.to_string(),
+                email: format!("{}@example.com", username),
+                password_hash: "hash".to_string(),
+                role,
+            })
+            .await
+            .unwrap()
+            .id
+            .to_string()
+    }
+
     fn make_domain_library(id: Uuid, name: &str) -> DomainLibrary {
         DomainLibrary {
             id,
@@ -53,13 +71,17 @@ mod tests {
             library_id,
             path: PathBuf::from("/media/videos/test.mp4"),
             hash: 0,
+            hash_strategy: Default::default(),
+            partial_hash: None,
             size_bytes: 1024,
             mime_type: Some("video/mp4".to_string()),
             duration: None,
             container_format: Some("mp4".to_string()),
             content: None,
             status: FileStatus::Known,
+            is_primary: true,
             scanned_at: chrono::Utc::now(),
+            verified_at: None,
             updated_at: chrono::Utc::now(),
         }
     }
@@ -156,6 +178,7 @@ mod tests {
         let service = LocalLibraryService::new(
             lib_repo,
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             notif as Arc<dyn NotificationService>,
             Arc::new(MockIndexService::new()),
@@ -188,6 +211,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -211,6 +235,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -234,6 +259,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -253,6 +279,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -279,6 +306,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(mock_library_repo),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -297,16 +325,19 @@ mod tests {
     #[tokio::test]
     async fn test_get_libraries_empty_repo_returns_empty_vec() {
         let video_dir = PathBuf::from("/media/videos");
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let admin_id = seed_user(&user_repo, "admin", Role::Admin).await;
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            user_repo,
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
             Arc::new(InMemoryPathValidator::success(video_dir)),
         );
 
-        let result = service.get_libraries("user1".to_string()).await;
+        let result = service.get_libraries(admin_id).await;
 
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 0);
@@ -315,6 +346,8 @@ mod tests {
     #[tokio::test]
     async fn test_get_libraries_returns_all_libraries_with_correct_file_counts() {
         let video_dir = PathBuf::from("/media/videos");
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let admin_id = seed_user(&user_repo, "admin", Role::Admin).await;
         let lib_repo = Arc::new(InMemoryLibraryRepository::default());
         let id1 = Uuid::new_v4();
         let id2 = Uuid::new_v4();
@@ -335,13 +368,14 @@ mod tests {
         let service = LocalLibraryService::new(
             lib_repo,
             Arc::new(InMemoryFileRepository::default()),
+            user_repo,
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
             Arc::new(InMemoryPathValidator::success(video_dir)),
         );
 
-        let result = service.get_libraries("user1".to_string()).await;
+        let result = service.get_libraries(admin_id).await;
 
         assert!(result.is_ok());
         let libs = result.unwrap();
@@ -355,6 +389,8 @@ mod tests {
     #[tokio::test]
     async fn test_get_libraries_repo_find_all_db_error_returns_db_error() {
         let video_dir = PathBuf::from("/media/videos");
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let admin_id = seed_user(&user_repo, "admin", Role::Admin).await;
 
         let mut mock_library_repo = MockLibraryRepository::new();
         mock_library_repo
@@ -365,19 +401,22 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(mock_library_repo),
             Arc::new(InMemoryFileRepository::default()),
+            user_repo,
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
             Arc::new(InMemoryPathValidator::success(video_dir)),
         );
 
-        let result = service.get_libraries("user1".to_string()).await;
+        let result = service.get_libraries(admin_id).await;
         assert!(matches!(result, Err(LibraryError::Db(_))));
     }
 
     #[tokio::test]
     async fn test_get_libraries_count_files_db_error_propagates() {
         let video_dir = PathBuf::from("/media/videos");
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let admin_id = seed_user(&user_repo, "admin", Role::Admin).await;
         let lib_id = Uuid::new_v4();
 
         let mut mock_library_repo = MockLibraryRepository::new();
@@ -393,16 +432,101 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(mock_library_repo),
             Arc::new(InMemoryFileRepository::default()),
+            user_repo,
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
             Arc::new(InMemoryPathValidator::success(video_dir)),
         );
 
-        let result = service.get_libraries("user1".to_string()).await;
+        let result = service.get_libraries(admin_id).await;
         assert!(matches!(result, Err(LibraryError::Db(_))));
     }
 
+    #[tokio::test]
+    async fn test_get_libraries_regular_user_sees_granted_libraries_only() {
+        let video_dir = PathBuf::from("/media/videos");
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let user_repo = Arc::new(InMemoryUserRepository::default());
+        let alice_id = seed_user(&user_repo, "alice", Role::Viewer).await;
+        let (granted, hidden) = (Uuid::new_v4(), Uuid::new_v4());
+        for (id, name) in [(granted, "Movies"), (hidden, "Private")] {
+            lib_repo
+                .libraries
+                .lock()
+                .unwrap()
+                .insert(id, make_domain_library(id, name));
+        }
+        let service = LocalLibraryService::new(
+            lib_repo,
+            Arc::new(InMemoryFileRepository::default()),
+            user_repo,
+            video_dir.clone(),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(MockIndexService::new()),
+            Arc::new(InMemoryPathValidator::success(video_dir)),
+        );
+
+        assert!(
+            service
+                .get_libraries(alice_id.clone())
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        service
+            .grant_library_access(granted.to_string(), alice_id.clone())
+            .await
+            .unwrap();
+        let libs = service.get_libraries(alice_id.clone()).await.unwrap();
+        assert_eq!(libs.len(), 1);
+        assert_eq!(libs[0].id, granted.to_string());
+        assert_eq!(
+            service
+                .get_library_user_ids(granted.to_string())
+                .await
+                .unwrap(),
+            vec![alice_id.clone()]
+        );
+
+        service
+            .revoke_library_access(granted.to_string(), alice_id.clone())
+            .await
+            .unwrap();
+        assert!(service.get_libraries(alice_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_library_access_unknown_user_returns_user_not_found() {
+        let video_dir = PathBuf::from("/media/videos");
+        let lib_repo = Arc::new(InMemoryLibraryRepository::default());
+        let lib_id = Uuid::new_v4();
+        lib_repo
+            .libraries
+            .lock()
+            .unwrap()
+            .insert(lib_id, make_domain_library(lib_id, "Movies"));
+        let service = LocalLibraryService::new(
+            lib_repo,
+            Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
+            video_dir.clone(),
+            Arc::new(InMemoryNotificationService::new()),
+            Arc::new(MockIndexService::new()),
+            Arc::new(InMemoryPathValidator::success(video_dir)),
+        );
+        let unknown = Uuid::new_v4().to_string();
+
+        let result = service.get_libraries(unknown.clone()).await;
+        assert!(matches!(result, Err(LibraryError::UserNotFound)));
+
+        let result = service
+            .grant_library_access(lib_id.to_string(), unknown)
+            .await;
+        assert!(matches!(result, Err(LibraryError::UserNotFound)));
+    }
+
     // ── get_library_by_id ─────────────────────────────────────────────────────────
 
     #[tokio::test]
@@ -421,6 +545,7 @@ mod tests {
         let service = LocalLibraryService::new(
             lib_repo,
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -444,6 +569,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -462,6 +588,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -501,6 +628,7 @@ mod tests {
         let service = LocalLibraryService::new(
             lib_repo,
             file_repo,
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -521,6 +649,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -538,6 +667,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -568,6 +698,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             file_repo,
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -588,6 +719,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -606,6 +738,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(InMemoryLibraryRepository::default()),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -631,6 +764,7 @@ mod tests {
         let service = LocalLibraryService::new(
             Arc::new(mock_library_repo),
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             Arc::new(InMemoryNotificationService::new()),
             Arc::new(MockIndexService::new()),
@@ -660,6 +794,7 @@ mod tests {
         let service = LocalLibraryService::new(
             lib_repo,
             Arc::new(InMemoryFileRepository::default()),
+            Arc::new(InMemoryUserRepository::default()),
             video_dir.clone(),
             notif as Arc<dyn NotificationService>,
             Arc::new(MockIndexService::new()),
@@ -1,22 +1,36 @@
 use async_graphql::{Enum, SimpleObject};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tracing::warn;
 use uuid::Uuid;
 
 use crate::models::{
-    EpisodeMetadata, ExternalIdentifiers, MediaMetadata, MovieMetadata, Ratings, SeasonMetadata,
-    ShowDates, ShowMetadata, Title,
+    DuplicateFile, DuplicateGroup, DuplicateKind, EpisodeMetadata, ExternalIdentifiers,
+    LibraryFile, MediaMetadata, MovieMetadata, MovieVersion, Ratings, SeasonMetadata, ShowDates,
+    ShowMetadata, Title,
+};
+use crate::services::library::LibraryScope;
+use beam_domain::models::{
+    CreateMovie, CreateMovieEntry, MediaFile, MediaFileContent, Season, UpdateEpisode,
+    UpdateMediaFile, UpdateMovie, UpdateMovieEntry, UpdateSeason, UpdateShow,
 };
 use beam_domain::repositories::{
     FileRepository, MediaStreamRepository, MovieRepository, ShowRepository,
 };
+use beam_domain::utils::hash::HashStrategy;
 
 #[async_trait::async_trait]
 pub trait MetadataService: Send + Sync + std::fmt::Debug {
     /// Get media metadata by media ID
     async fn get_media_metadata(&self, media_id: &str) -> Option<MediaMetadata>;
 
+    /// Libraries a movie or show belongs to
+    async fn get_media_library_ids(&self, media_id: &str) -> Result<Vec<Uuid>, MetadataError>;
+
     /// Search/explore media with cursor-based pagination, sorting, and filtering
     #[allow(clippy::too_many_arguments)]
     async fn search_media(
@@ -32,6 +46,75 @@ pub trait MetadataService: Send + Sync + std::fmt::Debug {
 
     /// Refresh metadata for by media filter
     async fn refresh_metadata(&self, filter: MediaFilter) -> Result<(), MetadataError>;
+
+    /// Manually edit a movie. Edited fields are locked against automated updates
+    /// unless `locked_fields` is given, in which case it replaces the movie's locks.
+    async fn update_movie(&self, update: UpdateMovie) -> Result<MediaMetadata, MetadataError>;
+
+    /// Manually edit a show, locking edited fields like [`Self::update_movie`]
+    async fn update_show(&self, update: UpdateShow) -> Result<MediaMetadata, MetadataError>;
+
+    /// Manually edit a season, returning the metadata of its show
+    async fn update_season(&self, update: UpdateSeason) -> Result<MediaMetadata, MetadataError>;
+
+    /// Manually edit an episode, returning the metadata of its show
+    async fn update_episode(&self, update: UpdateEpisode) -> Result<MediaMetadata, MetadataError>;
+
+    /// Set the TMDB ID of a movie or show and lock it against rescans. Only the ID
+    /// changes; titles, artwork and the rest are left as they are.
+    async fn set_tmdb_id(
+        &self,
+        media_id: &str,
+        tmdb_id: u32,
+    ) -> Result<MediaMetadata, MetadataError>;
+
+    /// Merge other movies into `target_id` as additional versions. The merged versions are
+    /// not primary, and versions matching an existing edition join that version's files.
+    /// The merged movies are deleted.
+    async fn merge_movies(
+        &self,
+        target_id: Uuid,
+        source_ids: Vec<Uuid>,
+    ) -> Result<MediaMetadata, MetadataError>;
+
+    /// Split a wrongly merged version off into a new movie, returning the new movie.
+    /// With `file_ids`, only those files of the version move.
+    async fn split_movie_entry(
+        &self,
+        entry_id: Uuid,
+        file_ids: Option<Vec<Uuid>>,
+    ) -> Result<MediaMetadata, MetadataError>;
+
+    /// Make a version the one its movie plays by default
+    async fn set_primary_movie_entry(&self, entry_id: Uuid)
+    -> Result<MediaMetadata, MetadataError>;
+
+    /// Set or clear the edition label of a movie version
+    async fn set_movie_entry_edition(
+        &self,
+        entry_id: Uuid,
+        edition: Option<String>,
+    ) -> Result<MediaMetadata, MetadataError>;
+
+    /// Find sets of files holding the same content: byte-identical copies, and different
+    /// files of the same movie or episode with similar durations. Largest savings first.
+    async fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>, MetadataError>;
+
+    /// Prefer a file over its duplicates. Identical copies and files of the same cut of
+    /// the same movie or episode stop being primary.
+    async fn set_primary_file(&self, file_id: Uuid) -> Result<LibraryFile, MetadataError>;
+}
+
+/// The movie or episode a file belongs to, for matching duplicates across versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum ContentKey {
+    Movie(Uuid),
+    /// Every episode file gets its own episode row, so episodes are matched by number
+    Episode {
+        show_id: Uuid,
+        season: u32,
+        episode: u32,
+    },
 }
 
 /// Database-backed metadata service
@@ -64,24 +147,26 @@ impl DbMetadataService {
         movie: beam_domain::models::Movie,
     ) -> Result<MediaMetadata, MetadataError> {
         // Get all movie entries, then files for each, then streams
-        let entries = self
+        let mut entries = self
             .movie_repo
             .find_entries_by_movie_id(movie.id)
             .await
             .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        entries.sort_by_key(|e| (!e.is_primary, e.created_at));
 
         let mut streams = Vec::new();
         let mut duration: Option<f64> = None;
 
         for entry in &entries {
-            let files = self
+            let mut files = self
                 .file_repo
                 .find_by_movie_entry_id(entry.id)
                 .await
                 .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+            files.sort_by_key(|f| !f.is_primary);
 
             for file in &files {
-                // Use the first file's duration as the movie duration
+                // Use the primary version's first file for the movie duration
                 if duration.is_none() {
                     duration = file.duration.map(|d| d.as_secs_f64());
                 }
@@ -94,8 +179,13 @@ impl DbMetadataService {
 
                 if !file_streams.is_empty() {
                     // Build a StreamConfiguration from the file's streams and convert
-                    let stream_meta =
+                    let mut stream_meta =
                         build_media_stream_metadata_from_domain_streams(&file_streams);
+                    stream_meta.version = Some(MovieVersion {
+                        id: entry.id.to_string(),
+                        edition: entry.edition.clone(),
+                        is_primary: entry.is_primary,
+                    });
                     streams.push(stream_meta);
                 }
             }
@@ -112,6 +202,12 @@ impl DbMetadataService {
                 None
             };
 
+        let genres = self
+            .movie_repo
+            .find_genres(movie.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
         let ratings = movie.rating_tmdb.map(|r| Ratings {
             tmdb: Some((r * 10.0) as u32),
         });
@@ -134,13 +230,285 @@ impl DbMetadataService {
             duration,
             poster_url: movie.poster_url.clone(),
             backdrop_url: movie.backdrop_url.clone(),
-            genres: vec![],
+            genres,
             ratings,
             identifiers,
+            locked_fields: movie.locked_fields.clone(),
             streams,
         }))
     }
 
+    async fn movie_metadata_by_id(&self, movie_id: Uuid) -> Result<MediaMetadata, MetadataError> {
+        let movie = self
+            .movie_repo
+            .find_by_id(movie_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+        self.build_movie_metadata(movie).await
+    }
+
+    async fn find_movie_entry(
+        &self,
+        entry_id: Uuid,
+    ) -> Result<beam_domain::models::MovieEntry, MetadataError> {
+        self.movie_repo
+            .find_entry_by_id(entry_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)
+    }
+
+    /// Re-point files at another movie entry
+    async fn move_files(&self, file_ids: &[Uuid], entry_id: Uuid) -> Result<(), MetadataError> {
+        for &id in file_ids {
+            self.file_repo
+                .update(UpdateMediaFile {
+                    id,
+                    content: Some(MediaFileContent::Movie {
+                        movie_entry_id: entry_id,
+                    }),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Promote the oldest entry of a movie if none of its entries is primary
+    async fn ensure_primary_entry(&self, movie_id: Uuid) -> Result<(), MetadataError> {
+        let entries = self
+            .movie_repo
+            .find_entries_by_movie_id(movie_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        if entries.iter().any(|e| e.is_primary) {
+            return Ok(());
+        }
+        if let Some(oldest) = entries.iter().min_by_key(|e| e.created_at) {
+            self.movie_repo
+                .set_primary_entry(oldest.id)
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    async fn movie_in_scope(&self, movie_id: Uuid, scope: &LibraryScope) -> bool {
+        if *scope == LibraryScope::All {
+            return true;
+        }
+        match self.movie_repo.find_library_ids(movie_id).await {
+            Ok(ids) => scope.allows_any(&ids),
+            Err(e) => {
+                warn!("Failed to look up libraries of movie {}: {}", movie_id, e);
+                false
+            }
+        }
+    }
+
+    async fn show_in_scope(&self, show_id: Uuid, scope: &LibraryScope) -> bool {
+        if *scope == LibraryScope::All {
+            return true;
+        }
+        match self.show_repo.find_library_ids(show_id).await {
+            Ok(ids) => scope.allows_any(&ids),
+            Err(e) => {
+                warn!("Failed to look up libraries of show {}: {}", show_id, e);
+                false
+            }
+        }
+    }
+
+    /// Map every movie entry to its movie
+    /// The movie or episode each linked file belongs to, by file id
+    async fn content_keys(
+        &self,
+        files: &[MediaFile],
+    ) -> Result<HashMap<Uuid, ContentKey>, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        let mut entry_movies: HashMap<Uuid, Option<Uuid>> = HashMap::new();
+        let mut seasons: HashMap<Uuid, Option<Season>> = HashMap::new();
+        let mut keys = HashMap::new();
+        for file in files {
+            let key = match &file.content {
+                None => None,
+                Some(MediaFileContent::Movie { movie_entry_id }) => {
+                    if let Entry::Vacant(slot) = entry_movies.entry(*movie_entry_id) {
+                        let entry = self
+                            .movie_repo
+                            .find_entry_by_id(*movie_entry_id)
+                            .await
+                            .map_err(internal)?;
+                        slot.insert(entry.map(|e| e.movie_id));
+                    }
+                    entry_movies[movie_entry_id].map(ContentKey::Movie)
+                }
+                Some(MediaFileContent::Episode { episode_id }) => {
+                    let Some(episode) = self
+                        .show_repo
+                        .find_episode_by_id(*episode_id)
+                        .await
+                        .map_err(internal)?
+                    else {
+                        continue;
+                    };
+                    if let Entry::Vacant(slot) = seasons.entry(episode.season_id) {
+                        let season = self
+                            .show_repo
+                            .find_season_by_id(episode.season_id)
+                            .await
+                            .map_err(internal)?;
+                        slot.insert(season);
+                    }
+                    seasons[&episode.season_id]
+                        .as_ref()
+                        .map(|season| ContentKey::Episode {
+                            show_id: season.show_id,
+                            season: season.season_number,
+                            episode: episode.episode_number,
+                        })
+                }
+            };
+            if let Some(key) = key {
+                keys.insert(file.id, key);
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Every file of a movie, or of an episode across its duplicate episode rows
+    async fn content_files(&self, key: ContentKey) -> Result<Vec<MediaFile>, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        let mut files = Vec::new();
+        match key {
+            ContentKey::Movie(movie_id) => {
+                let entries = self
+                    .movie_repo
+                    .find_entries_by_movie_id(movie_id)
+                    .await
+                    .map_err(internal)?;
+                for entry in entries {
+                    files.extend(
+                        self.file_repo
+                            .find_by_movie_entry_id(entry.id)
+                            .await
+                            .map_err(internal)?,
+                    );
+                }
+            }
+            ContentKey::Episode {
+                show_id,
+                season,
+                episode,
+            } => {
+                let seasons = self
+                    .show_repo
+                    .find_seasons_by_show_id(show_id)
+                    .await
+                    .map_err(internal)?;
+                for s in seasons.into_iter().filter(|s| s.season_number == season) {
+                    let episodes = self
+                        .show_repo
+                        .find_episodes_by_season_id(s.id)
+                        .await
+                        .map_err(internal)?;
+                    for e in episodes.into_iter().filter(|e| e.episode_number == episode) {
+                        files.extend(
+                            self.file_repo
+                                .find_by_episode_id(e.id)
+                                .await
+                                .map_err(internal)?,
+                        );
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Display title of a movie, or `Show S01E02` for an episode
+    async fn content_title(&self, key: ContentKey) -> Result<Option<String>, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        match key {
+            ContentKey::Movie(id) => Ok(self
+                .movie_repo
+                .find_by_id(id)
+                .await
+                .map_err(internal)?
+                .map(|m| m.title)),
+            ContentKey::Episode {
+                show_id,
+                season,
+                episode,
+            } => Ok(self
+                .show_repo
+                .find_by_id(show_id)
+                .await
+                .map_err(internal)?
+                .map(|show| format!("{} S{:02}E{:02}", show.title, season, episode))),
+        }
+    }
+
+    async fn build_duplicate_group(
+        &self,
+        kind: DuplicateKind,
+        mut files: Vec<&MediaFile>,
+        keys: &HashMap<Uuid, ContentKey>,
+    ) -> Result<DuplicateGroup, MetadataError> {
+        files.sort_by_key(|f| (!f.is_primary, Reverse(f.size_bytes)));
+        let total_size_bytes: u64 = files.iter().map(|f| f.size_bytes).sum();
+        let kept = files.first().map_or(0, |f| f.size_bytes);
+
+        let key = files.first().and_then(|f| keys.get(&f.id).copied());
+        let title = match key {
+            Some(key) => self.content_title(key).await?,
+            None => None,
+        };
+        // Episode groups point at the kept file's episode
+        let media_id = match (key, files.first().and_then(|f| f.content.as_ref())) {
+            (Some(ContentKey::Movie(id)), _) => Some(id.to_string()),
+            (Some(ContentKey::Episode { .. }), Some(MediaFileContent::Episode { episode_id })) => {
+                Some(episode_id.to_string())
+            }
+            _ => None,
+        };
+
+        let mut duplicates = Vec::with_capacity(files.len());
+        for file in files {
+            let streams = self
+                .stream_repo
+                .find_by_file_id(file.id)
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+            duplicates.push(DuplicateFile {
+                file: LibraryFile::from(file.clone()),
+                streams: (!streams.is_empty())
+                    .then(|| build_media_stream_metadata_from_domain_streams(&streams)),
+            });
+        }
+
+        Ok(DuplicateGroup {
+            kind,
+            media_id,
+            title,
+            total_size_bytes: total_size_bytes as i64,
+            reclaimable_bytes: (total_size_bytes - kept) as i64,
+            files: duplicates,
+        })
+    }
+
+    async fn show_metadata_by_id(&self, show_id: Uuid) -> Result<MediaMetadata, MetadataError> {
+        let show = self
+            .show_repo
+            .find_by_id(show_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+        self.build_show_metadata(show).await
+    }
+
     /// Build MediaMetadata for a show by its DB model
     async fn build_show_metadata(
         &self,
@@ -162,11 +530,12 @@ impl DbMetadataService {
 
             let mut episodes = Vec::new();
             for ep in episodes_domain {
-                let files = self
+                let mut files = self
                     .file_repo
                     .find_by_episode_id(ep.id)
                     .await
                     .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+                files.sort_by_key(|f| !f.is_primary);
 
                 let duration = files
                     .first()
@@ -187,6 +556,7 @@ impl DbMetadataService {
                 }
 
                 episodes.push(EpisodeMetadata {
+                    id: ep.id.to_string(),
                     episode_number: ep.episode_number,
                     title: ep.title,
                     description: ep.description,
@@ -194,6 +564,7 @@ impl DbMetadataService {
                     thumbnail_url: ep.thumbnail_url,
                     duration,
                     streams: ep_streams,
+                    locked_fields: ep.locked_fields,
                 });
             }
 
@@ -213,6 +584,7 @@ impl DbMetadataService {
             };
 
             seasons.push(SeasonMetadata {
+                id: season.id.to_string(),
                 season_number: season.season_number,
                 dates,
                 episode_runtime: None,
@@ -221,6 +593,7 @@ impl DbMetadataService {
                 genres: vec![],
                 ratings: None,
                 identifiers: None,
+                locked_fields: season.locked_fields,
             });
         }
 
@@ -233,6 +606,7 @@ impl DbMetadataService {
             description: show.description.clone(),
             year: show.year,
             seasons,
+            locked_fields: show.locked_fields.clone(),
         }))
     }
 }
@@ -294,6 +668,7 @@ fn build_media_stream_metadata_from_domain_streams(
         video_tracks,
         audio_tracks,
         subtitle_tracks,
+        version: None,
     }
 }
 
@@ -372,6 +747,31 @@ impl MetadataService for DbMetadataService {
         None
     }
 
+    async fn get_media_library_ids(&self, media_id: &str) -> Result<Vec<Uuid>, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        let id = Uuid::parse_str(media_id).map_err(|_| MetadataError::MediaNotFound)?;
+
+        if self
+            .movie_repo
+            .find_by_id(id)
+            .await
+            .map_err(internal)?
+            .is_some()
+        {
+            return self.movie_repo.find_library_ids(id).await.map_err(internal);
+        }
+        if self
+            .show_repo
+            .find_by_id(id)
+            .await
+            .map_err(internal)?
+            .is_some()
+        {
+            return self.show_repo.find_library_ids(id).await.map_err(internal);
+        }
+        Err(MetadataError::MediaNotFound)
+    }
+
     #[allow(clippy::too_many_arguments)]
     async fn search_media(
         &self,
@@ -427,6 +827,9 @@ impl MetadataService for DbMetadataService {
                                 continue;
                             }
                         }
+                        if !self.movie_in_scope(id, &filters.scope).await {
+                            continue;
+                        }
                         // Build lightweight metadata (no deep stream loading for search)
                         let metadata = MediaMetadata::Movie(MovieMetadata {
                             title: Title {
@@ -457,6 +860,7 @@ impl MetadataService for DbMetadataService {
                             } else {
                                 None
                             },
+                            locked_fields: movie.locked_fields,
                             streams: vec![],
                         });
                         items.push(MediaItem::Movie {
@@ -501,6 +905,9 @@ impl MetadataService for DbMetadataService {
                         {
                             continue;
                         }
+                        if !self.show_in_scope(id, &filters.scope).await {
+                            continue;
+                        }
                         let metadata = MediaMetadata::Show(ShowMetadata {
                             title: Title {
                                 original: show.title.clone(),
@@ -510,6 +917,7 @@ impl MetadataService for DbMetadataService {
                             description: show.description.clone(),
                             year: show.year,
                             seasons: vec![],
+                            locked_fields: show.locked_fields,
                         });
                         items.push(MediaItem::Show {
                             id,
@@ -587,14 +995,526 @@ impl MetadataService for DbMetadataService {
     async fn refresh_metadata(&self, _filter: MediaFilter) -> Result<(), MetadataError> {
         // Metadata refresh (re-enriching from external APIs or ffmpeg) is a future
         // enhancement. Currently the indexer populates basic metadata on scan.
+        // Refreshes must apply their changes via `Update*::without_locked`.
         Ok(())
     }
+
+    async fn update_movie(&self, mut update: UpdateMovie) -> Result<MediaMetadata, MetadataError> {
+        let movie = self
+            .movie_repo
+            .find_by_id(update.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+
+        update.locked_fields = Some(locks_after_edit(
+            &movie.locked_fields,
+            update.changed_fields(),
+            update.locked_fields.take(),
+            UpdateMovie::LOCKABLE_FIELDS,
+        )?);
+        let movie = self
+            .movie_repo
+            .update(update)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        self.build_movie_metadata(movie).await
+    }
+
+    async fn update_show(&self, mut update: UpdateShow) -> Result<MediaMetadata, MetadataError> {
+        let show = self
+            .show_repo
+            .find_by_id(update.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+
+        update.locked_fields = Some(locks_after_edit(
+            &show.locked_fields,
+            update.changed_fields(),
+            update.locked_fields.take(),
+            UpdateShow::LOCKABLE_FIELDS,
+        )?);
+        let show = self
+            .show_repo
+            .update(update)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        self.build_show_metadata(show).await
+    }
+
+    async fn update_season(
+        &self,
+        mut update: UpdateSeason,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let season = self
+            .show_repo
+            .find_season_by_id(update.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+
+        update.locked_fields = Some(locks_after_edit(
+            &season.locked_fields,
+            update.changed_fields(),
+            update.locked_fields.take(),
+            UpdateSeason::LOCKABLE_FIELDS,
+        )?);
+        self.show_repo
+            .update_season(update)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        self.show_metadata_by_id(season.show_id).await
+    }
+
+    async fn update_episode(
+        &self,
+        mut update: UpdateEpisode,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let episode = self
+            .show_repo
+            .find_episode_by_id(update.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+        let season = self
+            .show_repo
+            .find_season_by_id(episode.season_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+
+        update.locked_fields = Some(locks_after_edit(
+            &episode.locked_fields,
+            update.changed_fields(),
+            update.locked_fields.take(),
+            UpdateEpisode::LOCKABLE_FIELDS,
+        )?);
+        self.show_repo
+            .update_episode(update)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        self.show_metadata_by_id(season.show_id).await
+    }
+
+    async fn set_tmdb_id(
+        &self,
+        media_id: &str,
+        tmdb_id: u32,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let id = Uuid::parse_str(media_id).map_err(|_| MetadataError::MediaNotFound)?;
+
+        let is_movie = self
+            .movie_repo
+            .find_by_id(id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .is_some();
+
+        // Going through the manual-edit path locks `tmdb_id`, so a rescan can't undo it
+        if is_movie {
+            self.update_movie(UpdateMovie {
+                id,
+                tmdb_id: Some(tmdb_id),
+                ..Default::default()
+            })
+            .await?;
+        } else {
+            self.update_show(UpdateShow {
+                id,
+                tmdb_id: Some(tmdb_id),
+                ..Default::default()
+            })
+            .await?;
+        }
+
+        self.get_media_metadata(media_id)
+            .await
+            .ok_or(MetadataError::MediaNotFound)
+    }
+
+    async fn merge_movies(
+        &self,
+        target_id: Uuid,
+        source_ids: Vec<Uuid>,
+    ) -> Result<MediaMetadata, MetadataError> {
+        if source_ids.is_empty() || source_ids.contains(&target_id) {
+            return Err(MetadataError::InvalidInput(
+                "Merge needs at least one movie other than the target".to_string(),
+            ));
+        }
+        self.movie_repo
+            .find_by_id(target_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+        for &source_id in &source_ids {
+            self.movie_repo
+                .find_by_id(source_id)
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?
+                .ok_or(MetadataError::MediaNotFound)?;
+        }
+
+        let mut target_entries = self
+            .movie_repo
+            .find_entries_by_movie_id(target_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        for source_id in source_ids {
+            let source_entries = self
+                .movie_repo
+                .find_entries_by_movie_id(source_id)
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+            for entry in source_entries {
+                self.movie_repo
+                    .ensure_library_association(entry.library_id, target_id)
+                    .await
+                    .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+                // Entries are unique per library and edition, so a matching version absorbs
+                // the files instead
+                let matching = target_entries
+                    .iter()
+                    .find(|t| t.library_id == entry.library_id && t.edition == entry.edition);
+                if let Some(matching) = matching {
+                    let files = self
+                        .file_repo
+                        .find_by_movie_entry_id(entry.id)
+                        .await
+                        .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+                    let file_ids: Vec<Uuid> = files.iter().map(|f| f.id).collect();
+                    self.move_files(&file_ids, matching.id).await?;
+                } else {
+                    let moved = self
+                        .movie_repo
+                        .update_entry(UpdateMovieEntry {
+                            id: entry.id,
+                            movie_id: Some(target_id),
+                            is_primary: Some(false),
+                            ..Default::default()
+                        })
+                        .await
+                        .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+                    target_entries.push(moved);
+                }
+            }
+
+            self.movie_repo
+                .delete(source_id)
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        }
+
+        self.ensure_primary_entry(target_id).await?;
+        self.movie_metadata_by_id(target_id).await
+    }
+
+    async fn split_movie_entry(
+        &self,
+        entry_id: Uuid,
+        file_ids: Option<Vec<Uuid>>,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let entry = self.find_movie_entry(entry_id).await?;
+        let movie = self
+            .movie_repo
+            .find_by_id(entry.movie_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .ok_or(MetadataError::MediaNotFound)?;
+
+        let entry_files: Vec<Uuid> = self
+            .file_repo
+            .find_by_movie_entry_id(entry.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?
+            .into_iter()
+            .map(|f| f.id)
+            .collect();
+        if let Some(ids) = &file_ids {
+            if ids.is_empty() {
+                return Err(MetadataError::InvalidInput(
+                    "No files given to split".to_string(),
+                ));
+            }
+            if let Some(id) = ids.iter().find(|id| !entry_files.contains(id)) {
+                return Err(MetadataError::InvalidInput(format!(
+                    "File {id} does not belong to this version"
+                )));
+            }
+        }
+        let partial = file_ids
+            .as_ref()
+            .is_some_and(|ids| entry_files.iter().any(|id| !ids.contains(id)));
+
+        // The new movie starts as a copy of the title so it can be renamed or matched after
+        let new_movie = self
+            .movie_repo
+            .create(CreateMovie {
+                title: movie.title.clone(),
+                runtime: movie.runtime,
+            })
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        self.movie_repo
+            .ensure_library_association(entry.library_id, new_movie.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+
+        if partial {
+            let new_entry = self
+                .movie_repo
+                .create_entry(CreateMovieEntry {
+                    library_id: entry.library_id,
+                    movie_id: new_movie.id,
+                    edition: entry.edition.clone(),
+                    is_primary: true,
+                })
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+            self.move_files(file_ids.as_deref().unwrap_or_default(), new_entry.id)
+                .await?;
+        } else {
+            self.movie_repo
+                .update_entry(UpdateMovieEntry {
+                    id: entry.id,
+                    movie_id: Some(new_movie.id),
+                    is_primary: Some(true),
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+            self.ensure_primary_entry(movie.id).await?;
+        }
+
+        self.build_movie_metadata(new_movie).await
+    }
+
+    async fn set_primary_movie_entry(
+        &self,
+        entry_id: Uuid,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let entry = self.find_movie_entry(entry_id).await?;
+        self.movie_repo
+            .set_primary_entry(entry.id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        self.movie_metadata_by_id(entry.movie_id).await
+    }
+
+    async fn set_movie_entry_edition(
+        &self,
+        entry_id: Uuid,
+        edition: Option<String>,
+    ) -> Result<MediaMetadata, MetadataError> {
+        let entry = self.find_movie_entry(entry_id).await?;
+        let edition = edition
+            .map(|e| e.trim().to_string())
+            .filter(|e| !e.is_empty());
+
+        let siblings = self
+            .movie_repo
+            .find_entries_by_movie_id(entry.movie_id)
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        if siblings
+            .iter()
+            .any(|e| e.id != entry.id && e.library_id == entry.library_id && e.edition == edition)
+        {
+            return Err(MetadataError::InvalidInput(
+                "Another version of this movie already has that edition".to_string(),
+            ));
+        }
+
+        self.movie_repo
+            .update_entry(UpdateMovieEntry {
+                id: entry.id,
+                edition: Some(edition),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| MetadataError::InternalError(e.to_string()))?;
+        self.movie_metadata_by_id(entry.movie_id).await
+    }
+
+    async fn find_duplicates(&self) -> Result<Vec<DuplicateGroup>, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        let mut files = self
+            .file_repo
+            .find_with_shared_hash()
+            .await
+            .map_err(internal)?;
+        let mut seen: HashSet<Uuid> = files.iter().map(|f| f.id).collect();
+        let shared_content = self
+            .file_repo
+            .find_with_shared_content()
+            .await
+            .map_err(internal)?;
+        files.extend(shared_content.into_iter().filter(|f| seen.insert(f.id)));
+        let keys = self.content_keys(&files).await?;
+
+        let mut by_hash: HashMap<_, Vec<&MediaFile>> = HashMap::new();
+        let mut by_content: HashMap<ContentKey, Vec<(Duration, &MediaFile)>> = HashMap::new();
+        for file in &files {
+            if let Some(key) = hash_key(file) {
+                by_hash.entry(key).or_default().push(file);
+            }
+            if let (Some(key), Some(duration)) = (keys.get(&file.id).copied(), file.duration) {
+                by_content.entry(key).or_default().push((duration, file));
+            }
+        }
+
+        let mut matches: Vec<(DuplicateKind, Vec<&MediaFile>)> = by_hash
+            .into_values()
+            .filter(|files| files.len() > 1)
+            .map(|files| (DuplicateKind::ExactHash, files))
+            .collect();
+
+        // Cluster each movie's or episode's files by duration, so different cuts stay apart
+        for mut candidates in by_content.into_values() {
+            candidates.sort_by_key(|(duration, _)| *duration);
+            let mut clusters: Vec<(Duration, Vec<&MediaFile>)> = Vec::new();
+            for (duration, file) in candidates {
+                match clusters.last_mut() {
+                    Some((start, cluster))
+                        if (duration - *start).as_secs_f64()
+                            <= duration_tolerance(start.as_secs_f64()) =>
+                    {
+                        cluster.push(file)
+                    }
+                    _ => clusters.push((duration, vec![file])),
+                }
+            }
+            matches.extend(
+                clusters
+                    .into_iter()
+                    .map(|(_, cluster)| cluster)
+                    // Clusters of identical copies are already reported by hash
+                    .filter(|cluster| {
+                        cluster.len() > 1
+                            && cluster.iter().any(|f| {
+                                hash_key(f).is_none() || hash_key(f) != hash_key(cluster[0])
+                            })
+                    })
+                    .map(|cluster| (DuplicateKind::SameContent, cluster)),
+            );
+        }
+
+        let mut groups = Vec::with_capacity(matches.len());
+        for (kind, files) in matches {
+            groups.push(self.build_duplicate_group(kind, files, &keys).await?);
+        }
+        groups.sort_by_key(|g| Reverse(g.reclaimable_bytes));
+        Ok(groups)
+    }
+
+    async fn set_primary_file(&self, file_id: Uuid) -> Result<LibraryFile, MetadataError> {
+        let internal = |e: sea_orm::DbErr| MetadataError::InternalError(e.to_string());
+        let file = self
+            .file_repo
+            .find_by_id(file_id)
+            .await
+            .map_err(internal)?
+            .ok_or(MetadataError::MediaNotFound)?;
+        let key = self
+            .content_keys(std::slice::from_ref(&file))
+            .await?
+            .remove(&file.id);
+
+        let mut duplicates = match hash_key(&file) {
+            Some((hash, strategy, size)) => self
+                .file_repo
+                .find_by_hash(hash, strategy, size)
+                .await
+                .map_err(internal)?,
+            None => Vec::new(),
+        };
+        if let Some(key) = key {
+            let same_cut = |sibling: &MediaFile| match (file.duration, sibling.duration) {
+                (Some(a), Some(b)) => {
+                    a.abs_diff(b).as_secs_f64() <= duration_tolerance(a.min(b).as_secs_f64())
+                }
+                _ => false,
+            };
+            let siblings = self.content_files(key).await?;
+            duplicates.extend(siblings.into_iter().filter(|s| same_cut(s)));
+        }
+
+        let mut demoted = HashSet::new();
+        for sibling in duplicates {
+            if sibling.id != file.id && sibling.is_primary && demoted.insert(sibling.id) {
+                self.file_repo
+                    .update(UpdateMediaFile {
+                        id: sibling.id,
+                        is_primary: Some(false),
+                        ..Default::default()
+                    })
+                    .await
+                    .map_err(internal)?;
+            }
+        }
+
+        let file = self
+            .file_repo
+            .update(UpdateMediaFile {
+                id: file.id,
+                is_primary: Some(true),
+                ..Default::default()
+            })
+            .await
+            .map_err(internal)?;
+        Ok(file.into())
+    }
+}
+
+/// Identity of a file's content. Unhashed files have none.
+fn hash_key(file: &MediaFile) -> Option<(u64, HashStrategy, u64)> {
+    (file.hash != 0).then_some((file.hash, file.hash_strategy, file.size_bytes))
+}
+
+/// Largest duration difference between files of the same cut: 1% of the runtime, but at
+/// least 30 seconds
+fn duration_tolerance(secs: f64) -> f64 {
+    (secs * 0.01).max(30.0)
+}
+
+/// Locks to store after a manual edit. An explicit lock list replaces the current locks;
+/// otherwise every edited field is added so later scans keep the manual value.
+fn locks_after_edit(
+    current: &[String],
+    changed: Vec<String>,
+    explicit: Option<Vec<String>>,
+    lockable: &[&str],
+) -> Result<Vec<String>, MetadataError> {
+    let mut locks = match explicit {
+        Some(locks) => {
+            if let Some(unknown) = locks.iter().find(|f| !lockable.contains(&f.as_str())) {
+                return Err(MetadataError::InvalidInput(format!(
+                    "Unknown lockable field '{unknown}'"
+                )));
+            }
+            locks
+        }
+        None => current.iter().cloned().chain(changed).collect(),
+    };
+    locks.sort();
+    locks.dedup();
+    Ok(locks)
 }
 
 #[derive(Debug, Error)]
 pub enum MetadataError {
     #[error("Media not found")]
     MediaNotFound,
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
     #[error("Internal metadata service error: {0}")]
     InternalError(String),
 }
@@ -651,6 +1571,8 @@ pub struct MediaSearchFilters {
     pub year_to: Option<u32>,
     pub query: Option<String>,
     pub min_rating: Option<u32>,
+    /// Only media in libraries the user may see
+    pub scope: LibraryScope,
 }
 
 /// Relay-style connection for media search results
@@ -9,9 +9,10 @@ mod tests {
 
     use uuid::Uuid;
 
+    use crate::services::library::LibraryScope;
     use crate::services::metadata::{
-        DbMetadataService, MediaFilter, MediaSearchFilters, MediaSortField, MetadataService,
-        SortOrder,
+        DbMetadataService, MediaFilter, MediaSearchFilters, MediaSortField, MetadataError,
+        MetadataService, SortOrder,
     };
     use beam_domain::models::movie::Movie;
     use beam_domain::models::{Episode, MediaFile, MediaFileContent, MovieEntry, Season, Show};
@@ -40,6 +41,7 @@ mod tests {
             tvdb_id: None,
             rating_tmdb: None,
             rating_imdb: None,
+            locked_fields: vec![],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         }
@@ -52,13 +54,17 @@ mod tests {
             library_id,
             path: PathBuf::from("/media/test.mp4"),
             hash: 0,
+            hash_strategy: Default::default(),
+            partial_hash: None,
             size_bytes: 1024,
             mime_type: Some("video/mp4".to_string()),
             duration: Some(Duration::from_secs(7200)),
             container_format: Some("mp4".to_string()),
             content: Some(content),
             status: beam_domain::models::FileStatus::Known,
+            is_primary: true,
             scanned_at: chrono::Utc::now(),
+            verified_at: None,
             updated_at: chrono::Utc::now(),
         }
     }
@@ -162,6 +168,7 @@ mod tests {
             tmdb_id: None,
             imdb_id: None,
             tvdb_id: None,
+            locked_fields: vec![],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -176,6 +183,7 @@ mod tests {
             poster_url: None,
             first_aired: None,
             last_aired: None,
+            locked_fields: vec![],
         };
         let season_id = season.id;
         show_repo.seasons.lock().unwrap().insert(season.id, season);
@@ -189,6 +197,7 @@ mod tests {
             air_date: None,
             runtime: None,
             thumbnail_url: None,
+            locked_fields: vec![],
             created_at: chrono::Utc::now(),
         };
         show_repo.episodes.lock().unwrap().insert(ep.id, ep);
@@ -233,6 +242,7 @@ mod tests {
             tmdb_id: None,
             imdb_id: None,
             tvdb_id: None,
+            locked_fields: vec![],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -261,6 +271,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -293,6 +304,7 @@ mod tests {
             tmdb_id: None,
             imdb_id: None,
             tvdb_id: None,
+            locked_fields: vec![],
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -321,6 +333,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -361,6 +374,7 @@ mod tests {
                     year_to: None,
                     query: Some("blade".to_string()),
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -403,6 +417,7 @@ mod tests {
                     year_to: Some(2021),
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -445,6 +460,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -471,6 +487,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -513,6 +530,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -548,6 +566,7 @@ mod tests {
                     year_to: None,
                     query: None,
                     min_rating: None,
+                    scope: LibraryScope::All,
                 },
             )
             .await;
@@ -556,4 +575,516 @@ mod tests {
         assert!(!conn.page_info.has_next_page);
         assert!(!conn.page_info.has_previous_page);
     }
+
+    // ---------------------------------------------------------------------------
+    // Movie versions
+    // ---------------------------------------------------------------------------
+
+    struct VersionFixture {
+        movie_repo: Arc<InMemoryMovieRepository>,
+        show_repo: Arc<InMemoryShowRepository>,
+        file_repo: Arc<InMemoryFileRepository>,
+        stream_repo: Arc<InMemoryMediaStreamRepository>,
+        library_id: Uuid,
+    }
+
+    impl VersionFixture {
+        fn new() -> Self {
+            Self {
+                movie_repo: Arc::new(InMemoryMovieRepository::default()),
+                show_repo: Arc::new(InMemoryShowRepository::default()),
+                file_repo: Arc::new(InMemoryFileRepository::default()),
+                stream_repo: Arc::new(InMemoryMediaStreamRepository::default()),
+                library_id: Uuid::new_v4(),
+            }
+        }
+
+        fn service(&self) -> DbMetadataService {
+            DbMetadataService::new(
+                self.movie_repo.clone(),
+                self.show_repo.clone(),
+                self.file_repo.clone(),
+                self.stream_repo.clone(),
+            )
+        }
+
+        fn add_movie(&self, title: &str) -> Uuid {
+            let movie = make_movie(title, None);
+            let id = movie.id;
+            self.movie_repo.movies.lock().unwrap().insert(id, movie);
+            id
+        }
+
+        /// Add a version with one file carrying a single subtitle stream
+        fn add_version(
+            &self,
+            movie_id: Uuid,
+            edition: Option<&str>,
+            is_primary: bool,
+            age_secs: i64,
+        ) -> (Uuid, Uuid) {
+            use beam_domain::models::stream::{
+                MediaStream, StreamMetadata, StreamType, SubtitleStreamMetadata,
+            };
+
+            let entry = MovieEntry {
+                id: Uuid::new_v4(),
+                library_id: self.library_id,
+                movie_id,
+                edition: edition.map(str::to_string),
+                is_primary,
+                created_at: chrono::Utc::now() - chrono::Duration::seconds(age_secs),
+            };
+            let entry_id = entry.id;
+            self.movie_repo
+                .entries
+                .lock()
+                .unwrap()
+                .insert(entry_id, entry);
+
+            let file = make_media_file(
+                self.library_id,
+                MediaFileContent::Movie {
+                    movie_entry_id: entry_id,
+                },
+            );
+            let file_id = file.id;
+            self.file_repo.files.lock().unwrap().insert(file_id, file);
+            self.stream_repo.streams.lock().unwrap().insert(
+                file_id,
+                vec![MediaStream {
+                    id: Uuid::new_v4(),
+                    file_id,
+                    index: 0,
+                    stream_type: StreamType::Subtitle,
+                    codec: "subrip".to_string(),
+                    metadata: StreamMetadata::Subtitle(SubtitleStreamMetadata {
+                        language: None,
+                        title: None,
+                        is_default: false,
+                        is_forced: false,
+                    }),
+                }],
+            );
+            (entry_id, file_id)
+        }
+
+        /// Add a version without files
+        fn add_movie_entry(&self, movie_id: Uuid) -> Uuid {
+            let entry = MovieEntry {
+                id: Uuid::new_v4(),
+                library_id: self.library_id,
+                movie_id,
+                edition: None,
+                is_primary: true,
+                created_at: chrono::Utc::now(),
+            };
+            let id = entry.id;
+            self.movie_repo.entries.lock().unwrap().insert(id, entry);
+            id
+        }
+
+        fn entry(&self, id: Uuid) -> MovieEntry {
+            self.movie_repo.entries.lock().unwrap()[&id].clone()
+        }
+    }
+
+    fn versions(metadata: crate::models::MediaMetadata) -> Vec<(Option<String>, bool)> {
+        match metadata {
+            crate::models::MediaMetadata::Movie(m) => m
+                .streams
+                .into_iter()
+                .map(|s| {
+                    let version = s.version.expect("movie streams are labelled");
+                    (version.edition, version.is_primary)
+                })
+                .collect(),
+            _ => panic!("Expected Movie metadata"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_movie_streams_labelled_primary_version_first() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        fixture.add_version(movie_id, Some("1080p"), false, 60);
+        fixture.add_version(movie_id, Some("4K"), true, 0);
+
+        let metadata = fixture
+            .service()
+            .get_media_metadata(&movie_id.to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            versions(metadata),
+            vec![
+                (Some("4K".to_string()), true),
+                (Some("1080p".to_string()), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_movies_adds_versions_and_deletes_sources() {
+        let fixture = VersionFixture::new();
+        let target_id = fixture.add_movie("Heat");
+        let source_id = fixture.add_movie("Heat (1995)");
+        let (target_entry, _) = fixture.add_version(target_id, None, true, 60);
+        let (cut_entry, _) = fixture.add_version(source_id, Some("Director's Cut"), true, 0);
+        let (_, duplicate_file) = fixture.add_version(source_id, None, false, 0);
+
+        let metadata = fixture
+            .service()
+            .merge_movies(target_id, vec![source_id])
+            .await
+            .unwrap();
+
+        assert!(
+            !fixture
+                .movie_repo
+                .movies
+                .lock()
+                .unwrap()
+                .contains_key(&source_id)
+        );
+        let cut = fixture.entry(cut_entry);
+        assert_eq!(cut.movie_id, target_id);
+        assert!(!cut.is_primary);
+        // The source's edition-less version joins the target's matching version
+        let duplicate = fixture.file_repo.files.lock().unwrap()[&duplicate_file].clone();
+        assert!(matches!(
+            duplicate.content,
+            Some(MediaFileContent::Movie { movie_entry_id }) if movie_entry_id == target_entry
+        ));
+        assert_eq!(
+            versions(metadata),
+            vec![
+                (None, true),
+                (None, true),
+                (Some("Director's Cut".to_string()), false),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_merge_movies_rejects_self_merge() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+
+        let result = fixture
+            .service()
+            .merge_movies(movie_id, vec![movie_id])
+            .await;
+
+        assert!(matches!(result, Err(MetadataError::InvalidInput(_))));
+    }
+
+    #[tokio::test]
+    async fn test_split_movie_entry_moves_version_to_new_movie() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Dune");
+        let (old_entry, _) = fixture.add_version(movie_id, Some("1984"), true, 0);
+        let (other_entry, _) = fixture.add_version(movie_id, Some("2021"), false, 60);
+
+        let metadata = fixture
+            .service()
+            .split_movie_entry(old_entry, None)
+            .await
+            .unwrap();
+
+        let moved = fixture.entry(old_entry);
+        assert_ne!(moved.movie_id, movie_id);
+        assert!(moved.is_primary);
+        // The remaining version is promoted
+        assert!(fixture.entry(other_entry).is_primary);
+        assert_eq!(versions(metadata), vec![(Some("1984".to_string()), true)]);
+    }
+
+    #[tokio::test]
+    async fn test_split_movie_entry_moves_selected_files() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Dune");
+        let (entry_id, kept_file) = fixture.add_version(movie_id, None, true, 0);
+        let stray = make_media_file(
+            fixture.library_id,
+            MediaFileContent::Movie {
+                movie_entry_id: entry_id,
+            },
+        );
+        let stray_id = stray.id;
+        fixture
+            .file_repo
+            .files
+            .lock()
+            .unwrap()
+            .insert(stray_id, stray);
+        let service = fixture.service();
+
+        let result = service
+            .split_movie_entry(entry_id, Some(vec![Uuid::new_v4()]))
+            .await;
+        assert!(matches!(result, Err(MetadataError::InvalidInput(_))));
+
+        service
+            .split_movie_entry(entry_id, Some(vec![stray_id]))
+            .await
+            .unwrap();
+
+        let files = fixture.file_repo.files.lock().unwrap();
+        assert!(matches!(
+            files[&kept_file].content,
+            Some(MediaFileContent::Movie { movie_entry_id }) if movie_entry_id == entry_id
+        ));
+        let Some(MediaFileContent::Movie { movie_entry_id }) = files[&stray_id].content else {
+            panic!("stray file must stay a movie file");
+        };
+        let new_entry = fixture.entry(movie_entry_id);
+        assert_ne!(new_entry.movie_id, movie_id);
+        assert!(new_entry.is_primary);
+        assert!(fixture.entry(entry_id).is_primary);
+    }
+
+    #[tokio::test]
+    async fn test_set_primary_movie_entry_demotes_others() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        let (first, _) = fixture.add_version(movie_id, Some("1080p"), true, 60);
+        let (second, _) = fixture.add_version(movie_id, Some("4K"), false, 0);
+
+        let metadata = fixture
+            .service()
+            .set_primary_movie_entry(second)
+            .await
+            .unwrap();
+
+        assert!(!fixture.entry(first).is_primary);
+        assert!(fixture.entry(second).is_primary);
+        assert_eq!(versions(metadata)[0], (Some("4K".to_string()), true));
+    }
+
+    #[tokio::test]
+    async fn test_set_movie_entry_edition() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        let (first, _) = fixture.add_version(movie_id, Some("Theatrical"), true, 60);
+        let (second, _) = fixture.add_version(movie_id, None, false, 0);
+        let service = fixture.service();
+
+        let result = service
+            .set_movie_entry_edition(second, Some(" Theatrical ".to_string()))
+            .await;
+        assert!(matches!(result, Err(MetadataError::InvalidInput(_))));
+
+        service
+            .set_movie_entry_edition(second, Some("Extended".to_string()))
+            .await
+            .unwrap();
+        service
+            .set_movie_entry_edition(first, Some("  ".to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(fixture.entry(second).edition.as_deref(), Some("Extended"));
+        assert_eq!(fixture.entry(first).edition, None);
+    }
+
+    // ---------------------------------------------------------------------------
+    // Duplicates
+    // ---------------------------------------------------------------------------
+
+    impl VersionFixture {
+        /// Add another file to a version
+        fn add_file(&self, entry_id: Uuid, hash: u64, size_bytes: u64, minutes: u64) -> Uuid {
+            let mut file = make_media_file(
+                self.library_id,
+                MediaFileContent::Movie {
+                    movie_entry_id: entry_id,
+                },
+            );
+            file.hash = hash;
+            file.size_bytes = size_bytes;
+            file.duration = Some(Duration::from_secs(minutes * 60));
+            let id = file.id;
+            self.file_repo.files.lock().unwrap().insert(id, file);
+            id
+        }
+
+        /// Add a season with one episode and one file, the way the indexer creates a row
+        /// per episode file
+        fn add_episode_file(&self, show_id: Uuid, episode_number: u32, hash: u64) -> Uuid {
+            let season = Season {
+                id: Uuid::new_v4(),
+                show_id,
+                season_number: 1,
+                poster_url: None,
+                first_aired: None,
+                last_aired: None,
+                locked_fields: vec![],
+            };
+            let episode = Episode {
+                id: Uuid::new_v4(),
+                season_id: season.id,
+                episode_number,
+                title: format!("Episode {episode_number}"),
+                description: None,
+                air_date: None,
+                runtime: None,
+                thumbnail_url: None,
+                locked_fields: vec![],
+                created_at: chrono::Utc::now(),
+            };
+            let mut file = make_media_file(
+                self.library_id,
+                MediaFileContent::Episode {
+                    episode_id: episode.id,
+                },
+            );
+            file.hash = hash;
+            file.duration = Some(Duration::from_secs(45 * 60));
+            let id = file.id;
+            self.file_repo.files.lock().unwrap().insert(id, file);
+            self.show_repo
+                .episodes
+                .lock()
+                .unwrap()
+                .insert(episode.id, episode);
+            self.show_repo
+                .seasons
+                .lock()
+                .unwrap()
+                .insert(season.id, season);
+            id
+        }
+
+        fn add_show(&self, title: &str) -> Uuid {
+            let show = Show {
+                id: Uuid::new_v4(),
+                title: title.to_string(),
+                title_localized: None,
+                description: None,
+                year: None,
+                poster_url: None,
+                backdrop_url: None,
+                tmdb_id: None,
+                imdb_id: None,
+                tvdb_id: None,
+                locked_fields: vec![],
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            let id = show.id;
+            self.show_repo.shows.lock().unwrap().insert(id, show);
+            id
+        }
+
+        fn is_primary(&self, file_id: Uuid) -> bool {
+            self.file_repo.files.lock().unwrap()[&file_id].is_primary
+        }
+    }
+
+    fn file_ids(group: &crate::models::DuplicateGroup) -> Vec<String> {
+        group.files.iter().map(|f| f.file.id.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_groups_identical_copies() {
+        use crate::models::DuplicateKind;
+
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        let entry = fixture.add_movie_entry(movie_id);
+        let small = fixture.add_file(entry, 7, 1_000, 120);
+        let large = fixture.add_file(entry, 7, 1_000, 120);
+        fixture.add_file(entry, 8, 5_000, 170);
+
+        let groups = fixture.service().find_duplicates().await.unwrap();
+
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.kind, DuplicateKind::ExactHash);
+        assert_eq!(group.title.as_deref(), Some("Heat"));
+        assert_eq!(group.media_id, Some(movie_id.to_string()));
+        assert_eq!(group.total_size_bytes, 2_000);
+        assert_eq!(group.reclaimable_bytes, 1_000);
+        let mut ids = file_ids(group);
+        ids.sort();
+        let mut expected = vec![small.to_string(), large.to_string()];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_matches_encodes_across_versions() {
+        use crate::models::DuplicateKind;
+
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        let first = fixture.add_movie_entry(movie_id);
+        let second = fixture.add_movie_entry(movie_id);
+        let encode = fixture.add_file(first, 1, 4_000, 170);
+        let remux = fixture.add_file(second, 2, 30_000, 171);
+        // The extended cut is a different movie as far as duplicates go
+        fixture.add_file(second, 3, 35_000, 190);
+
+        let groups = fixture.service().find_duplicates().await.unwrap();
+
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.kind, DuplicateKind::SameContent);
+        // All files are primary, so the largest one is the copy to keep
+        assert_eq!(file_ids(group), vec![remux.to_string(), encode.to_string()]);
+        assert_eq!(group.reclaimable_bytes, 4_000);
+    }
+
+    #[tokio::test]
+    async fn test_set_primary_file_demotes_duplicates() {
+        let fixture = VersionFixture::new();
+        let movie_id = fixture.add_movie("Heat");
+        let entry = fixture.add_movie_entry(movie_id);
+        let encode = fixture.add_file(entry, 1, 4_000, 170);
+        let remux = fixture.add_file(entry, 2, 30_000, 171);
+        let extended = fixture.add_file(entry, 3, 35_000, 190);
+        let service = fixture.service();
+
+        let file = service.set_primary_file(encode).await.unwrap();
+
+        assert!(file.is_primary);
+        assert!(!fixture.is_primary(remux));
+        assert!(fixture.is_primary(extended));
+        let groups = service.find_duplicates().await.unwrap();
+        assert_eq!(file_ids(&groups[0])[0], encode.to_string());
+        assert_eq!(groups[0].reclaimable_bytes, 30_000);
+
+        let result = service.set_primary_file(Uuid::new_v4()).await;
+        assert!(matches!(result, Err(MetadataError::MediaNotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_matches_episode_files_by_number() {
+        use crate::models::DuplicateKind;
+
+        let fixture = VersionFixture::new();
+        let show_id = fixture.add_show("The Wire");
+        let encode = fixture.add_episode_file(show_id, 3, 1);
+        let remux = fixture.add_episode_file(show_id, 3, 2);
+        fixture.add_episode_file(show_id, 4, 3);
+        let service = fixture.service();
+
+        let groups = service.find_duplicates().await.unwrap();
+
+        assert_eq!(groups.len(), 1);
+        let group = &groups[0];
+        assert_eq!(group.kind, DuplicateKind::SameContent);
+        assert_eq!(group.title.as_deref(), Some("The Wire S01E03"));
+        let mut ids = file_ids(group);
+        ids.sort();
+        let mut expected = vec![encode.to_string(), remux.to_string()];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        service.set_primary_file(remux).await.unwrap();
+        assert!(!fixture.is_primary(encode));
+        assert!(fixture.is_primary(remux));
+    }
 }
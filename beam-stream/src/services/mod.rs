@@ -1,6 +1,8 @@
 pub mod admin_log;
 pub mod grpc_index;
 pub mod hash;
+pub mod image;
+pub mod jwks;
 pub mod library;
 pub mod media_info;
 pub mod metadata;
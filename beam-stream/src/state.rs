@@ -2,10 +2,15 @@ use sea_orm::DatabaseConnection;
 use std::ops::Deref;
 use std::sync::Arc;
 
+use beam_auth::config::SessionBackend;
 use beam_auth::utils::{
+    admin_log::SqlAdminLogSink,
+    keys::TokenKeys,
+    models::{Permission, Role},
+    rate_limit::{LoginRateLimiter, RedisLoginRateLimiter, SqlLoginRateLimiter},
     repository::{SqlUserRepository, UserRepository},
-    service::{AuthService, LocalAuthService},
-    session_store::RedisSessionStore,
+    service::{AuthService, AuthenticatedUser, LocalAuthService},
+    session_store::{RedisSessionStore, SessionStore, SqlSessionStore},
 };
 use beam_index::services::index::IndexService;
 
@@ -15,6 +20,8 @@ use crate::{
         GrpcIndexService,
         admin_log::{AdminLogService, LocalAdminLogService},
         hash::{HashConfig, HashService, LocalHashService},
+        image::{ImageService, LocalImageService},
+        jwks::{read_jwks, remote_token_keys},
         library::{LibraryService, LocalLibraryService, OsPathValidator},
         metadata::{DbMetadataService, MetadataService},
         notification::{LocalNotificationService, NotificationService},
@@ -52,6 +59,25 @@ impl Deref for AppState {
 #[derive(Clone, Debug)]
 pub struct UserContext {
     pub user_id: String,
+    pub role: Role,
+    /// Permissions carried by the access token
+    pub permissions: Vec<Permission>,
+}
+
+impl UserContext {
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
+}
+
+impl From<AuthenticatedUser> for UserContext {
+    fn from(user: AuthenticatedUser) -> Self {
+        Self {
+            user_id: user.user_id,
+            role: user.role,
+            permissions: user.permissions,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -74,6 +100,7 @@ impl AppContext {
 pub struct AppServices {
     pub auth: Arc<dyn AuthService>,
     pub hash: Arc<dyn HashService>,
+    pub image: Arc<dyn ImageService>,
     pub library: Arc<dyn LibraryService>,
     pub metadata: Arc<dyn MetadataService>,
     pub transcode: Arc<dyn TranscodeService>,
@@ -82,6 +109,26 @@ pub struct AppServices {
     pub user_repo: Arc<dyn UserRepository>,
 }
 
+/// Keys for access tokens, when they are not signed with the JWT secret: this server's
+/// own signing keys, or the issuer's public keys from a file or URL.
+async fn token_keys(config: &ServerConfig) -> eyre::Result<Option<Arc<TokenKeys>>> {
+    if let Some(paths) = &config.jwt_signing_keys {
+        let keys = TokenKeys::from_private_key_files(paths)
+            .map_err(|e| eyre::eyre!("Failed to load JWT signing keys: {}", e))?;
+        return Ok(Some(Arc::new(keys)));
+    }
+    if let Some(path) = &config.jwks_file {
+        let keys = read_jwks(path)
+            .and_then(|jwks| Ok(TokenKeys::from_jwks(jwks)?))
+            .map_err(|e| eyre::eyre!("Failed to load JWKS file: {}", e))?;
+        return Ok(Some(Arc::new(keys)));
+    }
+    if let Some(url) = &config.jwks_url {
+        return Ok(Some(remote_token_keys(url.clone()).await));
+    }
+    Ok(None)
+}
+
 impl AppServices {
     pub async fn new(config: &ServerConfig, db: DatabaseConnection) -> eyre::Result<Self> {
         let hash_config = HashConfig::default();
@@ -115,18 +162,37 @@ impl AppServices {
         ));
         let transcode_service = Arc::new(LocalTranscodeService::new(mp4_generator));
 
-        // Initialize Redis session store
-        let session_store = Arc::new(
-            RedisSessionStore::new(&config.redis_url)
-                .await
-                .expect("Failed to connect to Redis"),
-        );
-
-        let auth_service = Arc::new(LocalAuthService::new(
-            user_repo.clone(),
-            session_store,
-            config.jwt_secret.clone(),
-        ));
+        let (session_store, login_limiter): (Arc<dyn SessionStore>, Arc<dyn LoginRateLimiter>) =
+            match config.session_backend {
+                SessionBackend::Redis => {
+                    let session_store = RedisSessionStore::new(&config.redis_url)
+                        .await
+                        .map_err(|e| eyre::eyre!("Failed to connect to Redis: {}", e))?;
+                    let login_limiter = RedisLoginRateLimiter::new(session_store.pool());
+                    (Arc::new(session_store), Arc::new(login_limiter))
+                }
+                SessionBackend::Postgres => {
+                    let session_store = Arc::new(SqlSessionStore::new(db.clone()));
+                    session_store.spawn_sweeper();
+                    let login_limiter = Arc::new(SqlLoginRateLimiter::new(db.clone()));
+                    login_limiter.spawn_sweeper();
+                    (session_store, login_limiter)
+                }
+            };
+
+        let mut auth_service =
+            LocalAuthService::new(user_repo.clone(), session_store, config.jwt_secret.clone())
+                .with_registration_mode(config.registration_mode)
+                .with_require_admin_mfa(config.require_admin_mfa)
+                .with_login_rate_limiter(login_limiter)
+                .with_admin_log(Arc::new(SqlAdminLogSink::new(db.clone())));
+        if let Some(key) = &config.totp_encryption_key {
+            auth_service = auth_service.with_totp_encryption_key(key);
+        }
+        if let Some(token_keys) = token_keys(config).await? {
+            auth_service = auth_service.with_token_keys(token_keys);
+        }
+        let auth_service = Arc::new(auth_service);
 
         let admin_log_service: Arc<dyn AdminLogService> =
             Arc::new(LocalAdminLogService::new(admin_log_repo));
@@ -140,9 +206,11 @@ impl AppServices {
         Ok(Self {
             auth: auth_service,
             hash: hash_service.clone() as Arc<dyn HashService>,
+            image: Arc::new(LocalImageService::new(config.cache_dir.clone())),
             library: Arc::new(LocalLibraryService::new(
                 library_repo,
                 file_repo.clone(),
+                user_repo.clone(),
                 config.video_dir.clone(),
                 notification_service.clone(),
                 index_service,